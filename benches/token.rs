@@ -0,0 +1,90 @@
+//! Timings and allocations of handling tokens
+//!
+//! Run with and without the `small-token` feature to compare:
+//!
+//! ```text
+//! cargo bench --bench token -- --nocapture
+//! cargo bench --bench token --features small-token -- --nocapture
+//! ```
+//!
+//! Every bench prints the amount of heap allocations made per operation next
+//! to its timing. The allocations are counted by the global allocator of this
+//! bench binary, for the thread of the bench only.
+
+#![feature(test)]
+
+extern crate datatypes;
+extern crate serde_json;
+extern crate test;
+
+use datatypes::valid::token::Token;
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::cell::Cell;
+use test::{black_box, Bencher};
+
+/// A allocator which counts the allocations made by each thread
+struct CountingAllocator;
+
+thread_local! {
+    // Const initialized, so that the counter itself never allocates
+    static ALLOCATIONS: Cell<usize> = const { Cell::new(0) };
+}
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        // The counter is gone while a thread is torn down
+        let _ = ALLOCATIONS.try_with(|count| count.set(count.get() + 1));
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+}
+
+#[global_allocator]
+static ALLOCATOR: CountingAllocator = CountingAllocator;
+
+/// A token of a typical length (32 random bytes encoded as base64)
+const HEADER: &str = "Bearer q83vEjRWeJq7zN7/ASNFZ4mrze8BI0VniavN7wEjRWc=";
+const JSON: &str = r#""q83vEjRWeJq7zN7/ASNFZ4mrze8BI0VniavN7wEjRWc=""#;
+
+/// The amount of operations the allocations are averaged over
+const OPS: usize = 1_000;
+
+/// Print the amount of allocations which `op` makes per call
+fn report_allocations<T, F: FnMut() -> T>(name: &str, mut op: F) {
+    let before = ALLOCATIONS.with(Cell::get);
+    for _ in 0..OPS {
+        drop(black_box(op()));
+    }
+    let count = ALLOCATIONS.with(Cell::get) - before;
+    println!(
+        "{}: {:.2} allocation(s) per op",
+        name,
+        count as f64 / OPS as f64
+    );
+}
+
+#[bench]
+fn from_bearer(b: &mut Bencher) {
+    report_allocations("from_bearer", || {
+        Token::from_bearer(black_box(HEADER)).unwrap()
+    });
+    b.iter(|| Token::from_bearer(black_box(HEADER)).unwrap());
+}
+
+#[bench]
+fn deserialize(b: &mut Bencher) {
+    report_allocations("deserialize", || {
+        serde_json::from_str::<Token>(black_box(JSON)).unwrap()
+    });
+    b.iter(|| serde_json::from_str::<Token>(black_box(JSON)).unwrap());
+}
+
+#[bench]
+fn clone(b: &mut Bencher) {
+    let token = Token::from_bearer(HEADER).unwrap();
+    report_allocations("clone", || black_box(&token).clone());
+    b.iter(|| black_box(&token).clone());
+}
@@ -0,0 +1,36 @@
+// Generated by `cargo run --bin export_ts --features ts-export`, do not edit
+
+export type AuthRequest =
+  | { payload: AuthPayload; type: "AUTHENTICATE" }
+  | { payload: EmptyPayloadStrict | null; type: "DEAUTHENTICATE" }
+  | { payload: RegisterUserPayload; type: "REGISTER" }
+  | { payload: TwoFactorPayload; type: "SUBMIT_TWO_FACTOR" }
+  | { payload: EmptyPayloadStrict | null; type: "WHO_AM_I" };
+
+export type AuthPayload = {
+  password: PlainPassword;
+  username: Username;
+};
+
+export type Email = string;
+
+export type EmptyPayloadStrict = {};
+
+export type PlainPassword = string;
+
+export type RegisterUserPayload = {
+  email: Email;
+  password: PlainPassword;
+  username: Username;
+};
+
+export type Token = string;
+
+export type TwoFactorCode = string;
+
+export type TwoFactorPayload = {
+  challenge: Token;
+  code: TwoFactorCode;
+};
+
+export type Username = string;
@@ -0,0 +1,13 @@
+#!/bin/sh
+# Check that the crate builds for the browser, and run the tests of the
+# JavaScript bindings if wasm-pack is installed
+set -e
+
+cd "$(dirname "$0")/.."
+
+rustup target add wasm32-unknown-unknown
+cargo check --target wasm32-unknown-unknown --no-default-features --features wasm-bindgen
+
+if command -v wasm-pack > /dev/null; then
+    wasm-pack test --node -- --no-default-features --features wasm-bindgen
+fi
@@ -0,0 +1,281 @@
+//! Extracting a `TokenPayload` from a request in actix-web
+//!
+//! The token is read from the `Authorization` header (with the `Bearer`
+//! scheme) or, if the request has no such header, from the session cookie.
+//! The inner payload is read from the json body with `parse_payload`, so a
+//! payload which is invalid is rejected with a `ParseError` which names the
+//! field.
+//!
+//! A request without a token (or with a token which is malformed) fails with
+//! `401 Unauthorized` and `ResponseError::Unauthenticated`, a body which is
+//! larger than the limit of the `TokenPayloadConfig` fails with
+//! `413 Payload Too Large`.
+//!
+//! # Example usage
+//!
+//! ```
+//! # use actix_web::{App, HttpResponse};
+//! # use datatypes::admin::requests::UserIdRefPayload;
+//! # use datatypes::payloads::TokenPayload;
+//! fn unban(payload: TokenPayload<UserIdRefPayload>) -> HttpResponse {
+//!     HttpResponse::Ok().finish()
+//! }
+//!
+//! let app = App::new().resource("/unban", |r| {
+//!     r.with_config(unban, |cfg| {
+//!         cfg.0.limit(4096).cookie("sid");
+//!     })
+//! });
+//! ```
+
+use crate::error::{ErrorCode, ParseError, ParseErrorKind, ResponseError};
+use crate::payloads::{parse_payload, TokenPayload};
+use crate::valid::token::{Token, TokenParseError, DEFAULT_SESSION_COOKIE};
+use actix_web::error::PayloadError;
+use actix_web::http::{header, StatusCode};
+use actix_web::{FromRequest, HttpMessage, HttpRequest, HttpResponse};
+use futures::future::{self, Future};
+use serde::de::DeserializeOwned;
+use std::str;
+
+/// The default limit of the size of a body, in bytes
+pub const DEFAULT_BODY_LIMIT: usize = 262_144;
+
+/// The configuration of the `TokenPayload` extractor
+#[derive(PartialEq, Eq, Clone, Debug)]
+pub struct TokenPayloadConfig {
+    limit: usize,
+    cookie: String,
+}
+
+impl TokenPayloadConfig {
+    /// Set the largest size of a body, in bytes
+    pub fn limit(&mut self, limit: usize) -> &mut Self {
+        self.limit = limit;
+        self
+    }
+
+    /// Set the name of the cookie which contains the token of a session
+    pub fn cookie(&mut self, name: impl Into<String>) -> &mut Self {
+        self.cookie = name.into();
+        self
+    }
+}
+
+impl Default for TokenPayloadConfig {
+    fn default() -> Self {
+        TokenPayloadConfig {
+            limit: DEFAULT_BODY_LIMIT,
+            cookie: DEFAULT_SESSION_COOKIE.to_owned(),
+        }
+    }
+}
+
+/// Why a `TokenPayload` could not be extracted from a request
+#[derive(Fail, PartialEq, Debug)]
+pub enum ExtractError {
+    #[fail(display = "missing token")]
+    MissingToken,
+    #[fail(display = "malformed token")]
+    InvalidToken(TokenParseError),
+    #[fail(display = "payload is larger than {} bytes", _0)]
+    PayloadTooLarge(usize),
+    #[fail(display = "payload could not be read")]
+    UnreadableBody,
+    #[fail(display = "{}", _0)]
+    Payload(ParseError),
+}
+
+impl actix_web::ResponseError for ExtractError {
+    fn error_response(&self) -> HttpResponse {
+        match self {
+            ExtractError::MissingToken | ExtractError::InvalidToken(_) => {
+                let status = status(ErrorCode::Unauthenticated);
+                HttpResponse::build(status).json(ResponseError::Unauthenticated)
+            }
+            ExtractError::PayloadTooLarge(_) => HttpResponse::new(StatusCode::PAYLOAD_TOO_LARGE),
+            ExtractError::UnreadableBody => HttpResponse::new(StatusCode::BAD_REQUEST),
+            ExtractError::Payload(e) => e.error_response(),
+        }
+    }
+}
+
+impl actix_web::ResponseError for ParseError {
+    fn error_response(&self) -> HttpResponse {
+        HttpResponse::build(status(ErrorCode::InvalidPayload)).json(self)
+    }
+}
+
+/// The status of a response with the given code
+fn status(code: ErrorCode) -> StatusCode {
+    StatusCode::from_u16(code.http_status()).unwrap_or(StatusCode::INTERNAL_SERVER_ERROR)
+}
+
+/// Read the token of a request, from its `Authorization` header or else from
+/// the cookie named `cookie`
+///
+/// A header which is present but not a valid bearer token is an error, even
+/// if the request also has the cookie.
+fn request_token<S>(req: &HttpRequest<S>, cookie: &str) -> Result<Token, ExtractError> {
+    if let Some(value) = req.headers().get(header::AUTHORIZATION) {
+        let value = value
+            .to_str()
+            .map_err(|_| ExtractError::InvalidToken(TokenParseError::InvalidToken))?;
+        return Token::from_bearer(value).map_err(ExtractError::InvalidToken);
+    }
+    match req.cookie(cookie) {
+        Some(cookie) => Token::from_b64token(cookie.value()).map_err(ExtractError::InvalidToken),
+        None => Err(ExtractError::MissingToken),
+    }
+}
+
+/// Parse the inner payload from the bytes of a body
+fn body_payload<T>(body: &[u8]) -> Result<T, ExtractError>
+where
+    T: DeserializeOwned,
+{
+    let json = str::from_utf8(body).map_err(|e| {
+        ExtractError::Payload(ParseError {
+            request_type: None,
+            field: None,
+            kind: ParseErrorKind::InvalidJson {
+                message: e.to_string(),
+            },
+        })
+    })?;
+    parse_payload(json).map_err(ExtractError::Payload)
+}
+
+impl<S, T> FromRequest<S> for TokenPayload<T>
+where
+    S: 'static,
+    T: DeserializeOwned + 'static,
+{
+    type Config = TokenPayloadConfig;
+    type Result = Box<dyn Future<Item = Self, Error = actix_web::Error>>;
+
+    fn from_request(req: &HttpRequest<S>, cfg: &Self::Config) -> Self::Result {
+        let token = match request_token(req, &cfg.cookie) {
+            Ok(token) => token,
+            Err(e) => return Box::new(future::err(e.into())),
+        };
+        let limit = cfg.limit;
+        let payload = req
+            .body()
+            .limit(limit)
+            .map_err(move |e| match e {
+                PayloadError::Overflow => ExtractError::PayloadTooLarge(limit),
+                _ => ExtractError::UnreadableBody,
+            })
+            .and_then(|body| body_payload(&body))
+            .map(move |inner| TokenPayload::new(inner, token))
+            .map_err(actix_web::Error::from);
+        Box::new(payload)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::admin::requests::UserIdRefPayload;
+    use crate::valid::ids::UserId;
+    use actix_web::http::Cookie;
+    use actix_web::test::TestRequest;
+
+    const BODY: &str = r#"{ "id": 3 }"#;
+
+    fn extract(req: TestRequest<()>) -> Result<TokenPayload<UserIdRefPayload>, actix_web::Error> {
+        extract_with(req, &TokenPayloadConfig::default())
+    }
+
+    fn extract_with(
+        req: TestRequest<()>,
+        cfg: &TokenPayloadConfig,
+    ) -> Result<TokenPayload<UserIdRefPayload>, actix_web::Error> {
+        TokenPayload::from_request(&req.finish(), cfg).wait()
+    }
+
+    fn status_of(e: &actix_web::Error) -> StatusCode {
+        e.as_response_error().error_response().status()
+    }
+
+    #[test]
+    fn token_from_header() {
+        let req = TestRequest::with_header("Authorization", "Bearer abc").set_payload(BODY);
+        let payload = extract(req).unwrap();
+        assert_eq!(payload.token(), &Token::new("abc"));
+        assert_eq!(payload.id, UserId::from(3));
+    }
+
+    #[test]
+    fn token_from_cookie() {
+        let req = TestRequest::default()
+            .cookie(Cookie::new(DEFAULT_SESSION_COOKIE, "abc"))
+            .set_payload(BODY);
+        assert_eq!(extract(req).unwrap().token(), &Token::new("abc"));
+
+        let mut cfg = TokenPayloadConfig::default();
+        cfg.cookie("sid");
+        let req = TestRequest::default()
+            .cookie(Cookie::new("sid", "xyz"))
+            .set_payload(BODY);
+        assert_eq!(extract_with(req, &cfg).unwrap().token(), &Token::new("xyz"));
+    }
+
+    #[test]
+    fn header_is_preferred_over_cookie() {
+        let req = TestRequest::with_header("Authorization", "Bearer abc")
+            .cookie(Cookie::new(DEFAULT_SESSION_COOKIE, "xyz"))
+            .set_payload(BODY);
+        assert_eq!(extract(req).unwrap().token(), &Token::new("abc"));
+    }
+
+    #[test]
+    fn missing_token() {
+        let e = extract(TestRequest::default().set_payload(BODY)).unwrap_err();
+        assert_eq!(status_of(&e), StatusCode::UNAUTHORIZED);
+        assert_eq!(
+            e.as_fail().downcast_ref::<ExtractError>(),
+            Some(&ExtractError::MissingToken)
+        );
+    }
+
+    #[test]
+    fn malformed_token() {
+        let req = TestRequest::with_header("Authorization", "Basic abc").set_payload(BODY);
+        let e = extract(req).unwrap_err();
+        assert_eq!(status_of(&e), StatusCode::UNAUTHORIZED);
+
+        let req = TestRequest::default()
+            .cookie(Cookie::new(DEFAULT_SESSION_COOKIE, "not a token"))
+            .set_payload(BODY);
+        let e = extract(req).unwrap_err();
+        assert_eq!(
+            e.as_fail().downcast_ref::<ExtractError>(),
+            Some(&ExtractError::InvalidToken(TokenParseError::InvalidToken))
+        );
+    }
+
+    #[test]
+    fn invalid_payload() {
+        let req = TestRequest::with_header("Authorization", "Bearer abc")
+            .set_payload(r#"{ "id": "three" }"#);
+        let e = extract(req).unwrap_err();
+        assert_eq!(status_of(&e), StatusCode::BAD_REQUEST);
+        match e.as_fail().downcast_ref::<ExtractError>() {
+            Some(ExtractError::Payload(e)) => {
+                assert_eq!(e.field.as_ref().map(String::as_str), Some("id"))
+            }
+            e => panic!("{:?}", e),
+        }
+    }
+
+    #[test]
+    fn payload_over_the_limit() {
+        let mut cfg = TokenPayloadConfig::default();
+        cfg.limit(4);
+        let req = TestRequest::with_header("Authorization", "Bearer abc").set_payload(BODY);
+        let e = extract_with(req, &cfg).unwrap_err();
+        assert_eq!(status_of(&e), StatusCode::PAYLOAD_TOO_LARGE);
+    }
+}
@@ -1,4 +1,8 @@
 //! Admin requests and responses to and from the service
 
+pub mod moderation;
 pub mod requests;
 pub mod responses;
+
+#[cfg(feature = "hmac")]
+mod signing;
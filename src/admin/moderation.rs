@@ -0,0 +1,202 @@
+//! The moderation log, which records the actions taken by moderators and admins
+
+use chrono::{DateTime, Utc};
+use crate::admin::requests::AdminRequest;
+use crate::content::requests::{ContentRef, ContentRequest};
+use crate::valid::ids::{CategoryId, UserId};
+use std::net::IpAddr;
+
+/// A entry in the moderation log
+#[derive(Serialize, Deserialize, PartialEq, Clone, Debug)]
+#[cfg_attr(feature = "schemars", derive(JsonSchema))]
+#[cfg_attr(feature = "utoipa", derive(ToSchema))]
+#[serde(rename_all = "snake_case")]
+pub struct ModAction {
+    pub actor: UserId,
+    pub target: ModTarget,
+    pub kind: ModActionKind,
+    #[serde(with = "crate::timestamp::seconds")]
+    #[cfg_attr(feature = "schemars", schemars(with = "chrono::DateTime<chrono::Utc>"))]
+    pub at: DateTime<Utc>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reason: Option<String>,
+}
+
+/// What a moderation action was applied to
+#[derive(Serialize, Deserialize, PartialEq, Clone, Copy, Debug)]
+#[cfg_attr(feature = "schemars", derive(JsonSchema))]
+#[serde(
+    tag = "type",
+    content = "payload",
+    rename_all = "SCREAMING_SNAKE_CASE"
+)]
+pub enum ModTarget {
+    Content(ContentRef),
+    Category(CategoryId),
+    User(UserId),
+    Ip(IpAddr),
+}
+
+// `IpAddr` has no `ToSchema`, so the variants are written out by hand
+#[cfg(feature = "utoipa")]
+impl utoipa::PartialSchema for ModTarget {
+    fn schema() -> utoipa::openapi::RefOr<utoipa::openapi::schema::Schema> {
+        use utoipa::PartialSchema;
+        crate::openapi::tagged(vec![
+            ("CONTENT", ContentRef::schema()),
+            ("CATEGORY", CategoryId::schema()),
+            ("USER", UserId::schema()),
+            ("IP", crate::openapi::ip_addr()),
+        ])
+    }
+}
+
+#[cfg(feature = "utoipa")]
+impl utoipa::ToSchema for ModTarget {
+    fn name() -> std::borrow::Cow<'static, str> {
+        std::borrow::Cow::Borrowed("ModTarget")
+    }
+}
+
+#[derive(Serialize, Deserialize, PartialEq, Eq, Clone, Copy, Debug)]
+#[cfg_attr(feature = "schemars", derive(JsonSchema))]
+#[cfg_attr(feature = "utoipa", derive(ToSchema))]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum ModActionKind {
+    DeleteThread,
+    RestoreThread,
+    DeleteComment,
+    RestoreComment,
+    HideCategory,
+    RestoreCategory,
+    PinThread,
+    UnpinThread,
+    BanUser,
+    UnbanUser,
+    BanIp,
+    UnbanIp,
+    ChangeRole,
+}
+
+impl ModAction {
+    /// Make a log entry for a admin request
+    ///
+    /// Returns `None` if the request doesn't change anything (e.g. a listing).
+    pub fn from_admin_request(
+        actor: UserId,
+        request: &AdminRequest,
+        at: DateTime<Utc>,
+        reason: Option<String>,
+    ) -> Option<ModAction> {
+        use self::ModActionKind::*;
+        let (target, kind) = match request {
+            AdminRequest::BanIp(p) => (ModTarget::Ip(p.ip), BanIp),
+            AdminRequest::UnbanIp(p) => (ModTarget::Ip(p.ip), UnbanIp),
+            AdminRequest::BanUser(p) => (ModTarget::User(p.id), BanUser),
+            AdminRequest::UnbanUser(p) => (ModTarget::User(p.id), UnbanUser),
+            AdminRequest::SetUserRole(p) => (ModTarget::User(p.id), ChangeRole),
+            AdminRequest::ListBans(_) | AdminRequest::ListReports(_) => return None,
+        };
+        Some(ModAction {
+            actor,
+            target,
+            kind,
+            at,
+            reason,
+        })
+    }
+
+    /// Make a log entry for a content request
+    ///
+    /// Returns `None` if the request isn't a moderation action.
+    pub fn from_content_request(
+        actor: UserId,
+        request: &ContentRequest,
+        at: DateTime<Utc>,
+        reason: Option<String>,
+    ) -> Option<ModAction> {
+        use self::ModActionKind::*;
+        let (target, kind) = match request {
+            ContentRequest::HideThread(p) => (
+                ModTarget::Content(ContentRef::Thread(p.id)),
+                if p.hide { DeleteThread } else { RestoreThread },
+            ),
+            ContentRequest::HideComment(p) => (
+                ModTarget::Content(ContentRef::Comment(p.id)),
+                if p.hide { DeleteComment } else { RestoreComment },
+            ),
+            ContentRequest::HideCategory(p) => (
+                ModTarget::Category(p.id),
+                if p.hide { HideCategory } else { RestoreCategory },
+            ),
+            ContentRequest::SetThreadPinned(p) => (
+                ModTarget::Content(ContentRef::Thread(p.thread_id)),
+                if p.pinned { PinThread } else { UnpinThread },
+            ),
+            _ => return None,
+        };
+        Some(ModAction {
+            actor,
+            target,
+            kind,
+            at,
+            reason,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::admin::requests::BanUserPayload;
+    use crate::content::requests::HideThreadPayload;
+    use crate::payloads::PaginationPayload;
+    use chrono::TimeZone;
+
+    #[test]
+    fn mod_action_from_ban_user() {
+        let at = Utc.timestamp(1_540_000_000, 0);
+        let request = AdminRequest::BanUser(BanUserPayload {
+            id: UserId::from(13),
+            duration: None,
+            country: None,
+        });
+        let action = ModAction::from_admin_request(
+            UserId::from(1),
+            &request,
+            at,
+            Some("spam".to_owned()),
+        );
+        let expt = ModAction {
+            actor: UserId::from(1),
+            target: ModTarget::User(UserId::from(13)),
+            kind: ModActionKind::BanUser,
+            at,
+            reason: Some("spam".to_owned()),
+        };
+        assert_eq!(action, Some(expt));
+    }
+
+    #[test]
+    fn mod_action_from_hide_thread() {
+        let at = Utc.timestamp(1_540_000_000, 0);
+        let request = ContentRequest::HideThread(HideThreadPayload {
+            id: 4.into(),
+            user_id: None,
+            hide: true,
+        });
+        let action = ModAction::from_content_request(UserId::from(1), &request, at, None).unwrap();
+        assert_eq!(action.kind, ModActionKind::DeleteThread);
+        assert_eq!(action.target, ModTarget::Content(ContentRef::Thread(4.into())));
+    }
+
+    #[test]
+    fn no_mod_action_from_listings() {
+        let at = Utc.timestamp(1_540_000_000, 0);
+        let request = AdminRequest::ListReports(PaginationPayload::default());
+        assert_eq!(
+            ModAction::from_admin_request(UserId::from(1), &request, at, None),
+            None
+        );
+    }
+}
@@ -1,21 +1,450 @@
 //! The requests a admin can send to the service
 
 use crate::auth::requests::SetUserRolePayload;
+use crate::error::{Error, ParseError};
+use crate::payloads::{
+    from_json_lenient, parse_request, parse_request_value, PaginationPayload, TokenPayload,
+};
+use crate::valid::country::CountryCode;
+use crate::valid::duration::HumanDuration;
+use crate::valid::ids::UserId;
+use crate::valid::ip::parse_client_ip;
+use crate::valid::ValidationError;
+use std::convert::TryFrom;
 use std::net::IpAddr;
 
-#[derive(Serialize, Deserialize, Debug)]
+/// A admin request together with the token of the admin who sent it
+pub type TokenAdminRequest = TokenPayload<AdminRequest>;
+
+#[derive(Serialize, Deserialize, PartialEq, Debug)]
+#[cfg_attr(feature = "schemars", derive(JsonSchema))]
+#[cfg_attr(feature = "utoipa", derive(ToSchema))]
 #[serde(
     tag = "type",
     content = "payload",
     rename_all = "SCREAMING_SNAKE_CASE"
 )]
 pub enum AdminRequest {
-    BanIp(IpAddrPayload),
+    BanIp(BanIpPayload),
     UnbanIp(IpAddrPayload),
+    BanUser(BanUserPayload),
+    UnbanUser(UserIdRefPayload),
     SetUserRole(SetUserRolePayload),
+    ListBans(ListBansPayload),
+    ListReports(PaginationPayload),
+}
+
+impl AdminRequest {
+    /// The type tags of the requests
+    pub const TAGS: &'static [&'static str] = &[
+        "BAN_IP",
+        "UNBAN_IP",
+        "BAN_USER",
+        "UNBAN_USER",
+        "SET_USER_ROLE",
+        "LIST_BANS",
+        "LIST_REPORTS",
+    ];
+
+    /// Parse a request from json, where the type tag is matched
+    /// case-insensitively (see `payloads::from_json_lenient`)
+    pub fn from_json_lenient(json: &str) -> Result<Self, Error> {
+        from_json_lenient(json, Self::TAGS)
+    }
+
+    /// Parse a request from json, where a error names the type of the request
+    /// and the field which was wrong (see `payloads::parse_request`)
+    pub fn parse_json(json: &str) -> Result<Self, ParseError> {
+        parse_request(json, Self::TAGS)
+    }
+}
+
+/// Convert json which has already been parsed (e.g. by a middleware), without
+/// serializing it again
+impl TryFrom<serde_json::Value> for AdminRequest {
+    type Error = ParseError;
+    fn try_from(value: serde_json::Value) -> Result<Self, Self::Error> {
+        parse_request_value(value, Self::TAGS)
+    }
+}
+
+impl TryFrom<serde_json::Map<String, serde_json::Value>> for AdminRequest {
+    type Error = ParseError;
+    fn try_from(map: serde_json::Map<String, serde_json::Value>) -> Result<Self, Self::Error> {
+        AdminRequest::try_from(serde_json::Value::Object(map))
+    }
 }
 
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, PartialEq, Debug)]
+#[cfg_attr(feature = "schemars", derive(JsonSchema))]
+#[cfg_attr(feature = "utoipa", derive(ToSchema))]
+#[serde(rename_all = "snake_case", deny_unknown_fields)]
 pub struct IpAddrPayload {
+    #[serde(with = "crate::valid::ip::as_string")]
+    #[cfg_attr(feature = "schemars", schemars(with = "std::net::IpAddr"))]
+    #[cfg_attr(feature = "utoipa", schema(value_type = String, format = "ip"))]
+    pub ip: IpAddr,
+}
+
+impl IpAddrPayload {
+    /// Make a payload from a `X-Forwarded-For` style string
+    pub fn from_client_ip(s: &str) -> Result<Self, ValidationError> {
+        parse_client_ip(s).map(|ip| IpAddrPayload { ip })
+    }
+}
+
+#[derive(Serialize, Deserialize, PartialEq, Debug)]
+#[cfg_attr(feature = "schemars", derive(JsonSchema))]
+#[cfg_attr(feature = "utoipa", derive(ToSchema))]
+#[serde(rename_all = "snake_case", deny_unknown_fields)]
+pub struct UserIdRefPayload {
+    pub id: UserId,
+}
+
+/// Bans a IP address, a ban without a duration is permanent
+#[derive(Serialize, Deserialize, PartialEq, Debug)]
+#[cfg_attr(feature = "schemars", derive(JsonSchema))]
+#[cfg_attr(feature = "utoipa", derive(ToSchema))]
+#[serde(rename_all = "snake_case", deny_unknown_fields)]
+pub struct BanIpPayload {
+    #[serde(with = "crate::valid::ip::as_string")]
+    #[cfg_attr(feature = "schemars", schemars(with = "std::net::IpAddr"))]
+    #[cfg_attr(feature = "utoipa", schema(value_type = String, format = "ip"))]
     pub ip: IpAddr,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub duration: Option<HumanDuration>,
+    /// The country of the subject, as supplied by the service which
+    /// annotates bans for compliance reporting
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub country: Option<CountryCode>,
+}
+
+/// Bans a user, a ban without a duration is permanent
+#[derive(Serialize, Deserialize, PartialEq, Debug)]
+#[cfg_attr(feature = "schemars", derive(JsonSchema))]
+#[cfg_attr(feature = "utoipa", derive(ToSchema))]
+#[serde(rename_all = "snake_case", deny_unknown_fields)]
+pub struct BanUserPayload {
+    pub id: UserId,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub duration: Option<HumanDuration>,
+    /// The country of the subject, as supplied by the service which
+    /// annotates bans for compliance reporting
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub country: Option<CountryCode>,
+}
+
+/// A request for a page of the currently active bans
+#[derive(Serialize, Deserialize, PartialEq, Debug)]
+#[cfg_attr(feature = "schemars", derive(JsonSchema))]
+#[cfg_attr(feature = "utoipa", derive(ToSchema))]
+#[serde(rename_all = "snake_case", deny_unknown_fields)]
+pub struct ListBansPayload {
+    #[serde(default)]
+    pub pagination: PaginationPayload,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub filter: Option<BanFilter>,
+}
+
+/// Limits which bans are listed
+#[derive(Serialize, Deserialize, PartialEq, Clone, Copy, Debug)]
+#[cfg_attr(feature = "schemars", derive(JsonSchema))]
+#[cfg_attr(feature = "utoipa", derive(ToSchema))]
+#[serde(
+    tag = "type",
+    content = "payload",
+    rename_all = "SCREAMING_SNAKE_CASE"
+)]
+pub enum BanFilter {
+    /// Only list IP bans of the given family
+    IpFamily(IpFamily),
+    /// Only list bans of the given kind
+    Kind(BanKind),
+}
+
+#[derive(Serialize, Deserialize, PartialEq, Eq, Clone, Copy, Debug)]
+#[cfg_attr(feature = "schemars", derive(JsonSchema))]
+#[cfg_attr(feature = "utoipa", derive(ToSchema))]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum IpFamily {
+    V4,
+    V6,
+}
+
+#[derive(Serialize, Deserialize, PartialEq, Eq, Clone, Copy, Debug)]
+#[cfg_attr(feature = "schemars", derive(JsonSchema))]
+#[cfg_attr(feature = "utoipa", derive(ToSchema))]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum BanKind {
+    User,
+    Ip,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::ParseErrorKind;
+
+    #[test]
+    fn list_bans_envelope() {
+        let json = r#"{
+            "type": "LIST_BANS",
+            "payload": {
+                "pagination": { "page": 2, "per_page": 50 },
+                "filter": { "type": "IP_FAMILY", "payload": "V6" }
+            }
+        }"#;
+        let expt = AdminRequest::ListBans(ListBansPayload {
+            pagination: PaginationPayload {
+                page: 2,
+                per_page: 50,
+            },
+            filter: Some(BanFilter::IpFamily(IpFamily::V6)),
+        });
+        assert_eq!(serde_json::from_str::<AdminRequest>(json).unwrap(), expt);
+    }
+
+    #[test]
+    fn list_bans_defaults() {
+        let json = r#"{ "type": "LIST_BANS", "payload": {} }"#;
+        let expt = AdminRequest::ListBans(ListBansPayload {
+            pagination: PaginationPayload::default(),
+            filter: None,
+        });
+        assert_eq!(serde_json::from_str::<AdminRequest>(json).unwrap(), expt);
+    }
+
+    #[test]
+    fn ban_durations() {
+        let json = r#"{ "type": "BAN_USER", "payload": { "id": 3, "duration": "7d" } }"#;
+        let expt = AdminRequest::BanUser(BanUserPayload {
+            id: UserId::from(3),
+            duration: Some(HumanDuration::from_secs(7 * 24 * 60 * 60)),
+            country: None,
+        });
+        assert_eq!(serde_json::from_str::<AdminRequest>(json).unwrap(), expt);
+
+        let json = r#"{ "type": "BAN_IP", "payload": { "ip": "10.0.0.1", "duration": 3600 } }"#;
+        let expt = AdminRequest::BanIp(BanIpPayload {
+            ip: "10.0.0.1".parse().unwrap(),
+            duration: Some(HumanDuration::from_secs(3600)),
+            country: None,
+        });
+        assert_eq!(serde_json::from_str::<AdminRequest>(json).unwrap(), expt);
+        assert_eq!(
+            serde_json::to_value(&expt).unwrap()["payload"]["duration"],
+            3600
+        );
+    }
+
+    #[test]
+    fn ban_country() {
+        let json = r#"{ "type": "BAN_USER", "payload": { "id": 3, "country": "no" } }"#;
+        let expt = AdminRequest::BanUser(BanUserPayload {
+            id: UserId::from(3),
+            duration: None,
+            country: Some(CountryCode::try_from("NO").unwrap()),
+        });
+        assert_eq!(serde_json::from_str::<AdminRequest>(json).unwrap(), expt);
+        assert_eq!(
+            serde_json::to_value(&expt).unwrap()["payload"]["country"],
+            "NO"
+        );
+
+        let json = r#"{ "ip": "10.0.0.1", "country": "NOR" }"#;
+        assert!(serde_json::from_str::<BanIpPayload>(json).is_err());
+    }
+
+    #[test]
+    fn ban_without_country_omits_it() {
+        let payload = BanIpPayload {
+            ip: "10.0.0.1".parse().unwrap(),
+            duration: None,
+            country: None,
+        };
+        let json = serde_json::to_value(&payload).unwrap();
+        assert_eq!(json, serde_json::json!({ "ip": "10.0.0.1" }));
+    }
+
+    #[test]
+    fn ban_without_duration_is_permanent() {
+        let json = r#"{ "type": "BAN_IP", "payload": { "ip": "10.0.0.1" } }"#;
+        match serde_json::from_str::<AdminRequest>(json).unwrap() {
+            AdminRequest::BanIp(payload) => assert_eq!(payload.duration, None),
+            _ => panic!("expected a BanIp request"),
+        }
+    }
+
+    #[test]
+    fn unknown_fields_are_rejected() {
+        let json = r#"{ "ip": "10.0.0.1", "port": 80 }"#;
+        assert!(serde_json::from_str::<IpAddrPayload>(json).is_err());
+
+        let json = r#"{ "ip": "10.0.0.1", "duraton": "2h" }"#;
+        assert!(serde_json::from_str::<BanIpPayload>(json).is_err());
+
+        let json = r#"{ "id": 3, "reason": "spam" }"#;
+        assert!(serde_json::from_str::<BanUserPayload>(json).is_err());
+
+        let json = r#"{ "id": 3, "username": "john" }"#;
+        assert!(serde_json::from_str::<UserIdRefPayload>(json).is_err());
+
+        let json = r#"{ "pagination": { "page": 2 }, "page": 2 }"#;
+        assert!(serde_json::from_str::<ListBansPayload>(json).is_err());
+    }
+
+    #[test]
+    fn unknown_fields_are_rejected_in_token_requests() {
+        // The payload is nested inside the envelope (not flattened), so it is
+        // still strict when the request is wrapped in a `TokenPayload`
+        let json = r#"{
+            "token": "admin-token",
+            "type": "UNBAN_IP",
+            "payload": { "ip": "10.0.0.1", "port": 80 }
+        }"#;
+        assert!(serde_json::from_str::<TokenAdminRequest>(json).is_err());
+
+        let json = r#"{
+            "token": "admin-token",
+            "type": "UNBAN_IP",
+            "payload": { "ip": "10.0.0.1" }
+        }"#;
+        assert!(serde_json::from_str::<TokenAdminRequest>(json).is_ok());
+    }
+
+    #[test]
+    fn type_tags_are_matched_case_insensitively() {
+        let expt = AdminRequest::UnbanUser(UserIdRefPayload {
+            id: UserId::from(3),
+        });
+        for tag in &["UNBAN_USER", "unban_user", "UnbanUser", "Unban_User"] {
+            let json = format!(r#"{{ "type": "{}", "payload": {{ "id": 3 }} }}"#, tag);
+            let request = AdminRequest::from_json_lenient(&json).unwrap();
+            assert_eq!(request, expt, "{}", tag);
+            let json = serde_json::to_string(&request).unwrap();
+            assert_eq!(json, r#"{"type":"UNBAN_USER","payload":{"id":3}}"#);
+        }
+
+        // The tag of a flattened request is matched as well
+        let json = r#"{ "token": "admin-token", "type": "unbanUser", "payload": { "id": 3 } }"#;
+        let request: TokenAdminRequest = from_json_lenient(json, AdminRequest::TAGS).unwrap();
+        assert_eq!(*request, expt);
+    }
+
+    #[test]
+    fn from_json_value() {
+        let value = serde_json::json!({ "type": "UNBAN_USER", "payload": { "id": 3 } });
+        let expt = AdminRequest::UnbanUser(UserIdRefPayload {
+            id: UserId::from(3),
+        });
+        assert_eq!(AdminRequest::try_from(value.clone()).unwrap(), expt);
+
+        let map = value.as_object().unwrap().clone();
+        assert_eq!(AdminRequest::try_from(map).unwrap(), expt);
+    }
+
+    #[test]
+    fn from_invalid_json_value() {
+        let value = serde_json::json!({ "type": "BAN_USER", "payload": { "id": "three" } });
+        let e = AdminRequest::try_from(value.clone()).unwrap_err();
+        assert_eq!(
+            e.request_type.as_ref().map(String::as_str),
+            Some("BAN_USER")
+        );
+        assert_eq!(e.field.as_ref().map(String::as_str), Some("id"));
+        match &e.kind {
+            ParseErrorKind::InvalidValue { .. } => {}
+            kind => panic!("expected a invalid value, got {:?}", kind),
+        }
+        let map = value.as_object().unwrap().clone();
+        assert_eq!(AdminRequest::try_from(map), Err(e));
+
+        // The same error as when the request is parsed from text
+        let json = value.to_string();
+        assert_eq!(
+            AdminRequest::try_from(value).unwrap_err(),
+            AdminRequest::parse_json(&json).unwrap_err()
+        );
+    }
+
+    #[test]
+    fn parse_json() {
+        let json = r#"{ "type": "UNBAN_USER", "payload": { "id": 3 } }"#;
+        let id = UserId::from(3);
+        let expt = AdminRequest::UnbanUser(UserIdRefPayload { id });
+        assert_eq!(AdminRequest::parse_json(json), Ok(expt));
+    }
+
+    #[test]
+    fn parse_json_without_a_tag() {
+        for json in &[r#"{ "payload": { "id": 3 } }"#, r#"{ "type": 3 }"#, "[]"] {
+            let e = AdminRequest::parse_json(json).unwrap_err();
+            assert_eq!(e.request_type, None, "{}", json);
+            assert_eq!(e.kind, ParseErrorKind::MissingTag, "{}", json);
+        }
+        let e = AdminRequest::parse_json(r#"{ "type": "#).unwrap_err();
+        match e.kind {
+            ParseErrorKind::InvalidJson { .. } => {}
+            kind => panic!("expected invalid json, got {:?}", kind),
+        }
+    }
+
+    #[test]
+    fn parse_json_with_a_unknown_tag() {
+        let json = r#"{ "type": "BAN", "payload": { "id": 3 } }"#;
+        let e = AdminRequest::parse_json(json).unwrap_err();
+        assert_eq!(e.request_type, Some("BAN".to_owned()));
+        assert_eq!(e.field, None);
+        let expected: Vec<_> = AdminRequest::TAGS.iter().map(|t| t.to_string()).collect();
+        assert_eq!(e.kind, ParseErrorKind::UnknownTag { expected });
+        assert_eq!(
+            e.to_string(),
+            "unknown request type 'BAN', expected one of BAN_IP, UNBAN_IP, BAN_USER, \
+             UNBAN_USER, SET_USER_ROLE, LIST_BANS, LIST_REPORTS"
+        );
+    }
+
+    #[test]
+    fn parse_json_with_a_missing_field() {
+        let json = r#"{ "type": "BAN_IP", "payload": { "duration": "2h" } }"#;
+        let e = AdminRequest::parse_json(json).unwrap_err();
+        assert_eq!(e.request_type, Some("BAN_IP".to_owned()));
+        assert_eq!(e.field, Some("ip".to_owned()));
+        assert_eq!(e.kind, ParseErrorKind::MissingField);
+        assert_eq!(e.to_string(), "missing field 'ip' in a BAN_IP request");
+
+        let e = AdminRequest::parse_json(r#"{ "type": "BAN_IP" }"#).unwrap_err();
+        assert_eq!(e.field, None);
+        assert_eq!(e.kind, ParseErrorKind::MissingPayload);
+        assert_eq!(e.to_string(), "missing payload in a BAN_IP request");
+    }
+
+    #[test]
+    fn parse_json_with_a_invalid_field() {
+        // The payload is given before the tag
+        let json = r#"{ "payload": { "id": "three" }, "type": "BAN_USER" }"#;
+        let e = AdminRequest::parse_json(json).unwrap_err();
+        assert_eq!(e.request_type, Some("BAN_USER".to_owned()));
+        assert_eq!(e.field, Some("id".to_owned()));
+        match e.kind {
+            ParseErrorKind::InvalidValue { ref message } => {
+                assert!(message.contains("invalid type"), "{}", message)
+            }
+            ref kind => panic!("expected a invalid value, got {:?}", kind),
+        }
+        let s = e.to_string();
+        let expt = "invalid field 'id' in a BAN_USER request: ";
+        assert!(s.starts_with(expt), "{}", s);
+
+        let json = r#"{ "type": "LIST_BANS", "payload": { "pagination": { "page": "two" } } }"#;
+        let e = AdminRequest::parse_json(json).unwrap_err();
+        assert_eq!(e.field, Some("pagination.page".to_owned()));
+
+        let json = r#"{ "type": "UNBAN_USER", "payload": 3 }"#;
+        let e = AdminRequest::parse_json(json).unwrap_err();
+        assert_eq!(e.field, None);
+        let s = e.to_string();
+        let expt = "invalid payload in a UNBAN_USER request: ";
+        assert!(s.starts_with(expt), "{}", s);
+    }
 }
@@ -1,24 +1,196 @@
 //! The responses a admin will get from requests to the service
 
-#[derive(Serialize, Deserialize, Debug)]
+use chrono::naive::NaiveDateTime;
+use crate::content::requests::{ContentRef, ReportReason};
+use crate::error::ErrorCode;
+use crate::payloads::{Paginated, PaginationPayload};
+use crate::valid::country::CountryCode;
+use crate::valid::ids::UserId;
+use std::net::IpAddr;
+
+#[derive(Serialize, Deserialize, PartialEq, Debug)]
+#[cfg_attr(feature = "schemars", derive(JsonSchema))]
+#[cfg_attr(feature = "utoipa", derive(ToSchema))]
 #[serde(
     tag = "type",
     content = "payload",
     rename_all = "SCREAMING_SNAKE_CASE"
 )]
+#[cfg_attr(feature = "forward-compat", serde(remote = "Self"))]
 pub enum AdminSuccess {
     IpBanned,
     IpUnbanned,
     ChangedRole,
+    BanList(BanListResponse),
+    Reports(Vec<ReportEntry>),
+    /// A response of a type which is not known to this version of the crate
+    ///
+    /// It can't be serialized, so a unknown response can only be logged and
+    /// ignored.
+    #[cfg(feature = "forward-compat")]
+    #[serde(skip)]
+    Unknown {
+        type_tag: String,
+        payload: serde_json::Value,
+    },
+}
+
+impl AdminSuccess {
+    /// The type tags of the responses
+    pub const TAGS: &'static [&'static str] = &[
+        "IP_BANNED",
+        "IP_UNBANNED",
+        "CHANGED_ROLE",
+        "BAN_LIST",
+        "REPORTS",
+    ];
 }
 
+#[cfg(feature = "forward-compat")]
+impl_forward_compatible!(AdminSuccess);
+
 #[derive(Fail, Serialize, Deserialize, PartialEq, Clone, Copy, Debug)]
+#[cfg_attr(feature = "schemars", derive(JsonSchema))]
+#[cfg_attr(feature = "utoipa", derive(ToSchema))]
 #[serde(
     tag = "type",
     content = "payload",
     rename_all = "SCREAMING_SNAKE_CASE"
 )]
+#[non_exhaustive]
 pub enum AdminError {
     #[fail(display = "internal occured error")]
     InternalError,
 }
+
+impl AdminError {
+    /// The machine-stable code of the error
+    pub fn code(&self) -> ErrorCode {
+        match self {
+            AdminError::InternalError => ErrorCode::AdminInternalError,
+        }
+    }
+
+    /// The error which has the given code, if it is a error of this kind
+    pub fn from_code(code: ErrorCode) -> Option<Self> {
+        match code {
+            ErrorCode::AdminInternalError => Some(AdminError::InternalError),
+            _ => None,
+        }
+    }
+}
+
+/// A page of the currently active bans
+pub type BanListResponse = Paginated<BanEntry>;
+
+#[derive(Serialize, Deserialize, PartialEq, Debug)]
+#[cfg_attr(feature = "schemars", derive(JsonSchema))]
+#[cfg_attr(feature = "utoipa", derive(ToSchema))]
+#[serde(rename_all = "snake_case")]
+pub struct BanEntry {
+    pub subject: BanSubject,
+    #[serde(with = "crate::timestamp::naive_seconds")]
+    #[cfg_attr(feature = "schemars", schemars(with = "chrono::NaiveDateTime"))]
+    pub banned_at: NaiveDateTime,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub country: Option<CountryCode>,
+}
+
+/// What a ban applies to
+#[derive(Serialize, Deserialize, PartialEq, Clone, Copy, Debug)]
+#[cfg_attr(feature = "schemars", derive(JsonSchema))]
+#[serde(
+    tag = "type",
+    content = "payload",
+    rename_all = "SCREAMING_SNAKE_CASE"
+)]
+pub enum BanSubject {
+    Ip(IpAddr),
+    User(UserId),
+}
+
+// `IpAddr` has no `ToSchema`, so the variants are written out by hand
+#[cfg(feature = "utoipa")]
+impl utoipa::PartialSchema for BanSubject {
+    fn schema() -> utoipa::openapi::RefOr<utoipa::openapi::schema::Schema> {
+        use utoipa::PartialSchema;
+        crate::openapi::tagged(vec![
+            ("IP", crate::openapi::ip_addr()),
+            ("USER", UserId::schema()),
+        ])
+    }
+}
+
+#[cfg(feature = "utoipa")]
+impl utoipa::ToSchema for BanSubject {
+    fn name() -> std::borrow::Cow<'static, str> {
+        std::borrow::Cow::Borrowed("BanSubject")
+    }
+}
+
+/// A report made by a user, as seen by the moderators
+#[derive(Serialize, Deserialize, PartialEq, Debug)]
+#[cfg_attr(feature = "schemars", derive(JsonSchema))]
+#[cfg_attr(feature = "utoipa", derive(ToSchema))]
+#[serde(rename_all = "snake_case")]
+pub struct ReportEntry {
+    pub reporter: UserId,
+    pub target: ContentRef,
+    pub reason: ReportReason,
+    #[serde(with = "crate::timestamp::naive_seconds")]
+    #[cfg_attr(feature = "schemars", schemars(with = "chrono::NaiveDateTime"))]
+    pub timestamp: NaiveDateTime,
+    pub status: ReportStatus,
+}
+
+#[derive(Serialize, Deserialize, PartialEq, Eq, Clone, Copy, Debug)]
+#[cfg_attr(feature = "schemars", derive(JsonSchema))]
+#[cfg_attr(feature = "utoipa", derive(ToSchema))]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum ReportStatus {
+    Open,
+    Resolved,
+    Dismissed,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::convert::TryFrom;
+
+    #[test]
+    fn ban_list_round_trip() {
+        let timestamp = NaiveDateTime::from_timestamp(1_540_000_000, 0);
+        let response = AdminSuccess::BanList(BanListResponse::new(
+            vec![
+                BanEntry {
+                    subject: BanSubject::Ip("10.0.0.1".parse().unwrap()),
+                    banned_at: timestamp,
+                    country: Some(CountryCode::try_from("NO").unwrap()),
+                },
+                BanEntry {
+                    subject: BanSubject::User(UserId::from(42)),
+                    banned_at: timestamp,
+                    country: None,
+                },
+            ],
+            PaginationPayload::default(),
+            2,
+        ));
+        let json = serde_json::to_string(&response).unwrap();
+        assert_eq!(serde_json::from_str::<AdminSuccess>(&json).unwrap(), response);
+    }
+
+    #[test]
+    fn reports_round_trip() {
+        let response = AdminSuccess::Reports(vec![ReportEntry {
+            reporter: UserId::from(3),
+            target: ContentRef::Thread(8.into()),
+            reason: ReportReason::Harassment,
+            timestamp: NaiveDateTime::from_timestamp(1_540_000_000, 0),
+            status: ReportStatus::Open,
+        }]);
+        let json = serde_json::to_string(&response).unwrap();
+        assert_eq!(serde_json::from_str::<AdminSuccess>(&json).unwrap(), response);
+    }
+}
@@ -0,0 +1,131 @@
+//! Signing and verification of admin requests
+//!
+//! Admin requests cross a trust boundary, so the controller signs them with a
+//! shared key using HMAC-SHA256. The signature is calculated over the
+//! canonical JSON (see `codec::to_canonical_json`) of the request (including
+//! the token) and is hex encoded.
+
+use crate::admin::requests::TokenAdminRequest;
+use crate::codec::to_canonical_json;
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+
+type HmacSha256 = Hmac<Sha256>;
+
+impl TokenAdminRequest {
+    /// Sign the request with the given key
+    pub fn sign(&self, key: &[u8]) -> String {
+        let mac = self.mac(key);
+        mac.result()
+            .code()
+            .iter()
+            .map(|b| format!("{:02x}", b))
+            .collect()
+    }
+
+    /// Verify that the signature was made by someone who knows the key
+    ///
+    /// The signature is compared in constant time.
+    pub fn verify(&self, signature: &str, key: &[u8]) -> bool {
+        match decode_hex(signature) {
+            Some(signature) => self.mac(key).verify(&signature).is_ok(),
+            None => false,
+        }
+    }
+
+    fn mac(&self, key: &[u8]) -> HmacSha256 {
+        let bytes = to_canonical_json(self).expect("admin requests are always serializable");
+        let mut mac = HmacSha256::new_varkey(key).expect("HMAC accepts keys of any length");
+        mac.input(&bytes);
+        mac
+    }
+}
+
+fn decode_hex(s: &str) -> Option<Vec<u8>> {
+    // `from_str_radix` takes a leading sign, so every digit is checked first
+    if s.len() % 2 != 0 || !s.bytes().all(|b| b.is_ascii_hexdigit()) {
+        return None;
+    }
+    (0..s.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(&s[i..i + 2], 16).ok())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::admin::requests::{AdminRequest, BanIpPayload};
+    use crate::valid::token::Token;
+
+    const KEY: &[u8] = b"controller-secret";
+
+    fn request(ip: &str) -> TokenAdminRequest {
+        let inner = AdminRequest::BanIp(BanIpPayload {
+            ip: ip.parse().unwrap(),
+            duration: None,
+            country: None,
+        });
+        TokenAdminRequest::new(inner, Token::new("admin-token"))
+    }
+
+    #[test]
+    fn valid_signature() {
+        let req = request("10.0.0.1");
+        let signature = req.sign(KEY);
+        assert_eq!(signature.len(), 64);
+        assert!(req.verify(&signature, KEY));
+    }
+
+    #[test]
+    fn tampered_request() {
+        let signature = request("10.0.0.1").sign(KEY);
+        assert!(!request("10.0.0.2").verify(&signature, KEY));
+
+        let mut req = request("10.0.0.1");
+        req.set_token(Token::new("other-token"));
+        assert!(!req.verify(&signature, KEY));
+    }
+
+    #[test]
+    fn wrong_key() {
+        let req = request("10.0.0.1");
+        let signature = req.sign(KEY);
+        assert!(!req.verify(&signature, b"wrong-secret"));
+    }
+
+    #[test]
+    fn malformed_signature() {
+        let req = request("10.0.0.1");
+        assert!(!req.verify("not hex", KEY));
+        assert!(!req.verify("abc", KEY));
+    }
+
+    #[test]
+    fn hex_digits_only() {
+        assert_eq!(decode_hex("0fA0"), Some(vec![0x0f, 0xa0]));
+        assert_eq!(decode_hex("+f"), None);
+        assert_eq!(decode_hex("-f"), None);
+        assert_eq!(decode_hex("0g"), None);
+    }
+
+    #[test]
+    fn signature_is_made_over_the_canonical_json() {
+        let req = request("10.0.0.1");
+        let mut mac = HmacSha256::new_varkey(KEY).unwrap();
+        mac.input(&to_canonical_json(&req).unwrap());
+        let expt: String = mac
+            .result()
+            .code()
+            .iter()
+            .map(|b| format!("{:02x}", b))
+            .collect();
+        assert_eq!(req.sign(KEY), expt);
+
+        // The order of the fields in the json doesn't change the signature
+        let json =
+            r#"{ "payload": { "ip": "10.0.0.1" }, "type": "BAN_IP", "token": "admin-token" }"#;
+        let parsed: TokenAdminRequest = serde_json::from_str(json).unwrap();
+        assert!(parsed.verify(&expt, KEY));
+    }
+}
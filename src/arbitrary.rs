@@ -0,0 +1,1409 @@
+//! Generation of arbitrary (but valid) values of the wire types for property
+//! based tests
+//!
+//! Every type which is sent over the wire implements `proptest`'s
+//! `Arbitrary`, so services which use the crate can generate requests and
+//! responses in their own tests. Only values which are valid according to the
+//! type's own constraints are generated, e.g. a `Username` always matches the
+//! username regex and a `NewPollPayload` always has between
+//! `MIN_POLL_OPTIONS` and `MAX_POLL_OPTIONS` options.
+//!
+//! The free-form texts (like a `Title` or a `FileName`) are generated with the
+//! characters which are HTML-escaped (`&<>"'`), so a text which isn't the
+//! same after a round trip is caught.
+//!
+//! `AbsentOrEmpty` is only generated as `Empty`, as `Absent` is the absence of
+//! a payload and not a value which can be serialized.
+//!
+//! # Example usage
+//!
+//! ```
+//! # #[macro_use]
+//! # extern crate proptest;
+//! # extern crate datatypes;
+//! # use datatypes::content::requests::ContentRequest;
+//! # use proptest::prelude::*;
+//! proptest! {
+//!     #[test]
+//!     fn request_round_trip(request in any::<ContentRequest>()) {
+//!         let json = serde_json::to_string(&request).unwrap();
+//!         let decoded: ContentRequest = serde_json::from_str(&json).unwrap();
+//!         assert_eq!(decoded, request);
+//!     }
+//! }
+//! # fn main() {}
+//! ```
+
+use crate::admin::moderation::*;
+use crate::admin::requests::*;
+use crate::admin::responses::*;
+use crate::auth::requests::*;
+use crate::auth::responses::*;
+use crate::content::models::*;
+use crate::content::requests::*;
+use crate::content::responses::*;
+use crate::error::{ErrorCode, ErrorPayload, ParseError, ParseErrorKind, ResponseError, WireError};
+use crate::payloads::*;
+use crate::valid::attachment::{AttachmentRef, FileName, HexDigest, MimeType};
+use crate::valid::country::CountryCode;
+use crate::valid::duration::HumanDuration;
+use crate::valid::fields::*;
+use crate::valid::ids::*;
+use crate::valid::phone::PhoneNumber;
+use crate::valid::token::Token;
+use crate::valid::ValidationError;
+use chrono::naive::NaiveDateTime;
+use chrono::{DateTime, Utc};
+use proptest::arbitrary::{any, Arbitrary};
+use proptest::collection::vec;
+use proptest::strategy::{BoxedStrategy, Just, Strategy, Union};
+use proptest::string::string_regex;
+use std::convert::TryFrom;
+use std::fmt::Debug;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+
+/// Implement `Arbitrary` with the given strategy
+macro_rules! arbitrary {
+    ($ty:ty => $strategy:expr) => {
+        impl Arbitrary for $ty {
+            type Parameters = ();
+            type Strategy = BoxedStrategy<Self>;
+            fn arbitrary_with(_: Self::Parameters) -> Self::Strategy {
+                $strategy.boxed()
+            }
+        }
+    };
+}
+
+/// Implement `Arbitrary` for a validated string which is made from the
+/// strings matching `$regex`
+macro_rules! arbitrary_validated {
+    ($ty:ident => $regex:expr) => {
+        arbitrary!($ty => string_regex($regex)
+            .expect("invalid regex")
+            .prop_map(|s| $ty::try_from(s).expect(concat!("invalid ", stringify!($ty)))));
+    };
+}
+
+/// A strategy which picks one of `values`
+fn select<T>(values: &'static [T]) -> impl Strategy<Value = T>
+where
+    T: Clone + Debug,
+{
+    (0..values.len()).prop_map(move |i| values[i].clone())
+}
+
+/// A strategy which picks one of the error codes which `from_code` turns into
+/// a error
+fn error_code<T>(from_code: fn(ErrorCode) -> Option<T>) -> impl Strategy<Value = T>
+where
+    T: Debug,
+{
+    let codes: Vec<ErrorCode> = ErrorCode::ALL
+        .iter()
+        .cloned()
+        .filter(|code| from_code(*code).is_some())
+        .collect();
+    (0..codes.len()).prop_map(move |i| from_code(codes[i]).unwrap())
+}
+
+/// A short list of arbitrary items
+fn short_vec<T: Arbitrary + 'static>() -> impl Strategy<Value = Vec<T>> {
+    vec(any::<T>(), 0..3)
+}
+
+// Validated fields
+
+arbitrary_validated!(Username => "[a-zA-Z0-9_-]{4,10}");
+arbitrary_validated!(PlainPassword => "[a-z]{3,20}[A-Z]{3,20}[0-9]{3,20}[.@%$!]{0,4}");
+arbitrary_validated!(Description => "[a-zA-Z0-9æøå ,.!?&<>\"'-]{0,100}");
+arbitrary_validated!(CommentContent => "[a-zA-Z0-9 ,.!?&<>\"'-]{5,70}");
+arbitrary_validated!(Email => "[a-z0-9._%+-]{1,10}@[a-z0-9-]{1,10}\\.[a-z]{2,5}");
+arbitrary_validated!(QueryStr => "[a-zA-Z0-9 ]{2,30}");
+arbitrary_validated!(TwoFactorCode => "[0-9]{6}");
+arbitrary_validated!(
+    DisplayName => "[a-zA-Z0-9æøå&<>\"']([a-zA-Z0-9æøå .&<>\"'-]{0,30}[a-zA-Z0-9æøå])?"
+);
+arbitrary_validated!(
+    AvatarUrl => "https?://[a-z0-9-]{1,10}(\\.[a-z]{2,5})?(/[a-zA-Z0-9._~-]{0,10}){0,3}\
+                  (\\?[a-z]{1,5}=[a-z0-9]{1,5}(&[a-z]{1,5}=[a-z0-9]{1,5}){0,2})?"
+);
+arbitrary_validated!(
+    IdempotencyKey => "[0-9a-f]{8}-[0-9a-f]{4}-[0-9a-f]{4}-[0-9a-f]{4}-[0-9a-f]{12}"
+);
+arbitrary_validated!(
+    Title => "[a-zA-Z0-9æøå][a-zA-Z0-9æøå ,.!?&<>\"'-]{1,60}[a-zA-Z0-9æøå.!?]"
+);
+arbitrary_validated!(PhoneNumber => "\\+[1-9][0-9]{7,14}");
+arbitrary_validated!(CountryCode => "[A-Z]{2}");
+arbitrary_validated!(
+    FileName => "[a-zA-Z0-9_&'-][a-zA-Z0-9 _&<>\"'-]{0,30}(\\.[a-z0-9]{1,5})?"
+);
+arbitrary_validated!(HexDigest => "[0-9a-f]{64}");
+arbitrary_validated!(AttachmentRef => "[A-Za-z0-9_-]{22}");
+arbitrary_validated!(MimeType => "(image/(png|jpeg|gif|webp))|([a-z]{1,10}/[a-z0-9.+-]{1,20})");
+arbitrary_validated!(Cursor => "[A-Za-z0-9+/_=-]{1,64}");
+
+#[cfg(feature = "url")]
+arbitrary!(crate::valid::url::Url => string_regex(
+    "https?://[a-z0-9-]{1,10}\\.[a-z]{2,5}(/[a-z0-9._~-]{0,10}){0,3}"
+)
+    .expect("invalid regex")
+    .prop_map(|s| crate::valid::url::Url::try_from(s).expect("invalid Url")));
+
+// A token which is longer than `INLINE_TOKEN_LEN` is generated now and then
+arbitrary!(Token => string_regex("[A-Za-z0-9._~+/-]{1,80}")
+    .expect("invalid regex")
+    .prop_map(|token| Token::new(token)));
+
+arbitrary!(HumanDuration => any::<u64>().prop_map(HumanDuration::from_secs));
+
+arbitrary!(ValidationError => error_code(ValidationError::from_code));
+
+// Ids
+
+arbitrary!(CategoryId => any::<u32>().prop_map(CategoryId::from));
+arbitrary!(ThreadId => any::<u32>().prop_map(ThreadId::from));
+arbitrary!(CommentId => any::<u32>().prop_map(CommentId::from));
+arbitrary!(UserId => any::<u32>().prop_map(UserId::from));
+arbitrary!(DraftId => any::<u32>().prop_map(DraftId::from));
+arbitrary!(AttachmentId => any::<u32>().prop_map(AttachmentId::from));
+arbitrary!(PollOptionId => any::<u32>().prop_map(PollOptionId::from));
+
+impl<I> Arbitrary for IdList<I>
+where
+    I: Id + Arbitrary + Debug + 'static,
+{
+    type Parameters = ();
+    type Strategy = BoxedStrategy<Self>;
+    fn arbitrary_with(_: Self::Parameters) -> Self::Strategy {
+        vec(any::<I>(), 0..5).prop_map(IdList::new).boxed()
+    }
+}
+
+/// Both the ipv4 and ipv6 addresses
+fn ip_addr() -> impl Strategy<Value = IpAddr> {
+    let v4 = any::<u32>().prop_map(|ip| IpAddr::V4(Ipv4Addr::from(ip)));
+    let v6 = any::<[u16; 8]>().prop_map(|ip| IpAddr::V6(Ipv6Addr::from(ip)));
+    Union::new(vec![v4.boxed(), v6.boxed()])
+}
+
+/// A time between 1970 and 2096, in whole seconds as the timestamps are
+/// serialized with whole-second precision (see `timestamp`)
+fn naive_date_time() -> impl Strategy<Value = NaiveDateTime> {
+    (0..4_000_000_000i64).prop_map(|secs| NaiveDateTime::from_timestamp(secs, 0))
+}
+
+fn date_time() -> impl Strategy<Value = DateTime<Utc>> {
+    naive_date_time().prop_map(|time| DateTime::from_utc(time, Utc))
+}
+
+// Payload wrappers
+
+arbitrary!(EmptyPayloadStrict => Just(()).prop_map(|_| EmptyPayloadStrict {}));
+
+arbitrary!(PaginationPayload => (any::<u32>(), 1..=u32::max_value())
+    .prop_map(|(page, per_page)| PaginationPayload { page, per_page }));
+
+impl<T: Arbitrary + 'static> Arbitrary for CursorPage<T> {
+    type Parameters = ();
+    type Strategy = BoxedStrategy<Self>;
+    fn arbitrary_with(_: Self::Parameters) -> Self::Strategy {
+        (
+            short_vec(),
+            any::<Option<Cursor>>(),
+            any::<Option<Cursor>>(),
+        )
+            .prop_map(|(items, next, prev)| CursorPage::new(items, next, prev))
+            .boxed()
+    }
+}
+
+impl<T: Arbitrary + 'static> Arbitrary for Paginated<T> {
+    type Parameters = ();
+    type Strategy = BoxedStrategy<Self>;
+    fn arbitrary_with(_: Self::Parameters) -> Self::Strategy {
+        (
+            short_vec(),
+            any::<PaginationPayload>(),
+            any::<Option<u64>>(),
+            any::<Option<Cursor>>(),
+        )
+            .prop_map(|(items, pagination, total, next)| Paginated {
+                next,
+                ..Paginated::new(items, pagination, total)
+            })
+            .boxed()
+    }
+}
+
+impl<T> Arbitrary for Patch<T>
+where
+    T: Arbitrary + 'static,
+{
+    type Parameters = ();
+    type Strategy = BoxedStrategy<Self>;
+    fn arbitrary_with(_: Self::Parameters) -> Self::Strategy {
+        Union::new(vec![
+            Just(Patch::Absent).boxed(),
+            Just(Patch::Null).boxed(),
+            any::<T>().prop_map(Patch::Set).boxed(),
+        ])
+        .boxed()
+    }
+}
+
+impl<T, E> Arbitrary for ResponseResult<T, E>
+where
+    T: Arbitrary + 'static,
+    E: Arbitrary + 'static,
+{
+    type Parameters = ();
+    type Strategy = BoxedStrategy<Self>;
+    fn arbitrary_with(_: Self::Parameters) -> Self::Strategy {
+        Union::new(vec![
+            any::<T>().prop_map(ResponseResult::Success).boxed(),
+            any::<E>().prop_map(ResponseResult::Error).boxed(),
+        ])
+        .boxed()
+    }
+}
+
+impl<Inner: Arbitrary + 'static> Arbitrary for TokenPayload<Inner> {
+    type Parameters = ();
+    type Strategy = BoxedStrategy<Self>;
+    fn arbitrary_with(_: Self::Parameters) -> Self::Strategy {
+        (any::<Inner>(), any::<Token>())
+            .prop_map(|(inner, token)| TokenPayload::new(inner, token))
+            .boxed()
+    }
+}
+
+impl<Inner: Arbitrary + 'static> Arbitrary for UserIdPayload<Inner> {
+    type Parameters = ();
+    type Strategy = BoxedStrategy<Self>;
+    fn arbitrary_with(_: Self::Parameters) -> Self::Strategy {
+        (any::<Inner>(), any::<UserId>())
+            .prop_map(|(inner, id)| UserIdPayload::new(inner, id))
+            .boxed()
+    }
+}
+
+impl<Inner: Arbitrary + 'static> Arbitrary for TokenPayloadCompact<Inner> {
+    type Parameters = ();
+    type Strategy = BoxedStrategy<Self>;
+    fn arbitrary_with(_: Self::Parameters) -> Self::Strategy {
+        (any::<Inner>(), any::<Token>())
+            .prop_map(|(inner, token)| TokenPayloadCompact::new(inner, token))
+            .boxed()
+    }
+}
+
+impl<Inner: Arbitrary + 'static> Arbitrary for UserIdPayloadCompact<Inner> {
+    type Parameters = ();
+    type Strategy = BoxedStrategy<Self>;
+    fn arbitrary_with(_: Self::Parameters) -> Self::Strategy {
+        (any::<Inner>(), any::<UserId>())
+            .prop_map(|(inner, id)| UserIdPayloadCompact::new(inner, id))
+            .boxed()
+    }
+}
+
+impl<Inner: Arbitrary + 'static> Arbitrary for Versioned<Inner> {
+    type Parameters = ();
+    type Strategy = BoxedStrategy<Self>;
+    fn arbitrary_with(_: Self::Parameters) -> Self::Strategy {
+        (any::<Inner>(), any::<u16>())
+            .prop_map(|(inner, v)| Versioned::with_version(inner, v))
+            .boxed()
+    }
+}
+
+impl<T: Arbitrary + 'static> Arbitrary for Strict<T> {
+    type Parameters = ();
+    type Strategy = BoxedStrategy<Self>;
+    fn arbitrary_with(_: Self::Parameters) -> Self::Strategy {
+        any::<T>().prop_map(Strict::new).boxed()
+    }
+}
+
+impl<T, B> Arbitrary for BoundedVec<T, B>
+where
+    T: Arbitrary + 'static,
+    B: LengthBounds + Debug + 'static,
+{
+    type Parameters = ();
+    type Strategy = BoxedStrategy<Self>;
+    fn arbitrary_with(_: Self::Parameters) -> Self::Strategy {
+        vec(any::<T>(), B::MIN..B::MAX + 1)
+            .prop_map(|items| BoundedVec::<T, B>::try_from(items).expect("invalid BoundedVec"))
+            .boxed()
+    }
+}
+
+// Errors
+
+arbitrary!(ErrorCode => select(ErrorCode::ALL));
+
+/// The details of a error, which are never floats as they don't always
+/// survive a round trip
+fn error_details() -> impl Strategy<Value = Option<serde_json::Value>> {
+    Union::new(vec![
+        Just(None).boxed(),
+        any::<u64>()
+            .prop_map(|n| Some(serde_json::Value::from(n)))
+            .boxed(),
+        ".{0,20}"
+            .prop_map(|s| Some(serde_json::Value::from(s)))
+            .boxed(),
+    ])
+}
+
+arbitrary!(WireError => (any::<ErrorCode>(), ".{0,40}", error_details())
+    .prop_map(|(code, message, details)| WireError { code, message, details }));
+
+arbitrary!(ErrorPayload => (any::<ErrorCode>(), ".{0,40}", any::<Option<ErrorCode>>())
+    .prop_map(|(code, message, reason)| ErrorPayload { code, message, reason }));
+
+arbitrary!(ParseErrorKind => Union::new(vec![
+    ".{0,40}".prop_map(|message| ParseErrorKind::InvalidJson { message }).boxed(),
+    Just(ParseErrorKind::MissingTag).boxed(),
+    vec("[A-Z_]{1,20}", 0..8).prop_map(|expected| ParseErrorKind::UnknownTag { expected }).boxed(),
+    Just(ParseErrorKind::MissingPayload).boxed(),
+    Just(ParseErrorKind::MissingField).boxed(),
+    ".{0,40}".prop_map(|message| ParseErrorKind::InvalidValue { message }).boxed(),
+]));
+
+arbitrary!(ParseError => (
+    proptest::option::of("[A-Z_]{1,20}"),
+    proptest::option::of("[a-z_.]{1,20}"),
+    any::<ParseErrorKind>(),
+)
+    .prop_map(|(request_type, field, kind)| ParseError { request_type, field, kind }));
+
+arbitrary!(ResponseError => Union::new(vec![
+    any::<AuthError>().prop_map(ResponseError::AuthRequestError).boxed(),
+    any::<ContentError>().prop_map(ResponseError::ContentRequestError).boxed(),
+    any::<AdminError>().prop_map(ResponseError::AdminRequestError).boxed(),
+    Just(ResponseError::Unauthenticated).boxed(),
+    Just(ResponseError::Unauthorized).boxed(),
+    any::<ErrorPayload>().prop_map(ResponseError::InvalidPayload).boxed(),
+    Just(ResponseError::InternalServerError).boxed(),
+]));
+
+// Auth
+
+arbitrary!(Role => select(&[Role::Admin, Role::Moderator, Role::User]));
+
+arbitrary!(AuthPayload => (any::<Username>(), any::<PlainPassword>())
+    .prop_map(|(username, password)| AuthPayload { username, password }));
+
+arbitrary!(RegisterUserPayload => (any::<Username>(), any::<PlainPassword>(), any::<Email>())
+.prop_map(|(username, password, email)| RegisterUserPayload {
+    username,
+    password,
+    email,
+}));
+
+arbitrary!(TwoFactorPayload => (any::<Token>(), any::<TwoFactorCode>())
+    .prop_map(|(challenge, code)| TwoFactorPayload { challenge, code }));
+
+arbitrary!(SetUserRolePayload => (any::<UserId>(), any::<Role>())
+    .prop_map(|(id, role)| SetUserRolePayload { id, role }));
+
+arbitrary!(AuthRequest => Union::new(vec![
+    any::<AuthPayload>().prop_map(AuthRequest::Authenticate).boxed(),
+    any::<EmptyPayload>().prop_map(AuthRequest::Deauthenticate).boxed(),
+    any::<RegisterUserPayload>().prop_map(AuthRequest::RegisterUser).boxed(),
+    any::<TwoFactorPayload>().prop_map(AuthRequest::SubmitTwoFactor).boxed(),
+    any::<EmptyPayload>().prop_map(AuthRequest::WhoAmI).boxed(),
+]));
+
+arbitrary!(TwoFactorChallengePayload => any::<Token>()
+    .prop_map(|challenge| TwoFactorChallengePayload { challenge }));
+
+arbitrary!(WhoAmIResponse => (any::<UserId>(), any::<Username>(), any::<Role>())
+    .prop_map(|(id, username, role)| WhoAmIResponse { id, username, role }));
+
+arbitrary!(AuthSuccess => Union::new(vec![
+    Just(()).prop_map(|_| AuthSuccess::Authenticated).boxed(),
+    Just(()).prop_map(|_| AuthSuccess::Deauthenticated).boxed(),
+    Just(()).prop_map(|_| AuthSuccess::UserRegistered).boxed(),
+    any::<TwoFactorChallengePayload>().prop_map(AuthSuccess::TwoFactorRequired).boxed(),
+    any::<WhoAmIResponse>().prop_map(AuthSuccess::WhoAmI).boxed(),
+]));
+
+arbitrary!(AuthError => Union::new(vec![
+    error_code(AuthError::from_code).boxed(),
+    any::<u64>().prop_map(|retry_after| AuthError::RateLimited { retry_after }).boxed(),
+]));
+
+// Admin
+
+arbitrary!(IpAddrPayload => ip_addr().prop_map(|ip| IpAddrPayload { ip }));
+
+arbitrary!(UserIdRefPayload => any::<UserId>().prop_map(|id| UserIdRefPayload { id }));
+
+arbitrary!(BanIpPayload => (
+    ip_addr(),
+    any::<Option<HumanDuration>>(),
+    any::<Option<CountryCode>>(),
+)
+    .prop_map(|(ip, duration, country)| BanIpPayload { ip, duration, country }));
+
+arbitrary!(BanUserPayload => (
+    any::<UserId>(),
+    any::<Option<HumanDuration>>(),
+    any::<Option<CountryCode>>(),
+)
+    .prop_map(|(id, duration, country)| BanUserPayload { id, duration, country }));
+
+arbitrary!(IpFamily => select(&[IpFamily::V4, IpFamily::V6]));
+
+arbitrary!(BanKind => select(&[BanKind::User, BanKind::Ip]));
+
+arbitrary!(BanFilter => Union::new(vec![
+    any::<IpFamily>().prop_map(BanFilter::IpFamily).boxed(),
+    any::<BanKind>().prop_map(BanFilter::Kind).boxed(),
+]));
+
+arbitrary!(ListBansPayload => (any::<PaginationPayload>(), any::<Option<BanFilter>>())
+    .prop_map(|(pagination, filter)| ListBansPayload { pagination, filter }));
+
+arbitrary!(AdminRequest => Union::new(vec![
+    any::<BanIpPayload>().prop_map(AdminRequest::BanIp).boxed(),
+    any::<IpAddrPayload>().prop_map(AdminRequest::UnbanIp).boxed(),
+    any::<BanUserPayload>().prop_map(AdminRequest::BanUser).boxed(),
+    any::<UserIdRefPayload>().prop_map(AdminRequest::UnbanUser).boxed(),
+    any::<SetUserRolePayload>().prop_map(AdminRequest::SetUserRole).boxed(),
+    any::<ListBansPayload>().prop_map(AdminRequest::ListBans).boxed(),
+    any::<PaginationPayload>().prop_map(AdminRequest::ListReports).boxed(),
+]));
+
+arbitrary!(BanSubject => Union::new(vec![
+    ip_addr().prop_map(BanSubject::Ip).boxed(),
+    any::<UserId>().prop_map(BanSubject::User).boxed(),
+]));
+
+arbitrary!(BanEntry => (
+    any::<BanSubject>(),
+    naive_date_time(),
+    any::<Option<CountryCode>>(),
+)
+    .prop_map(|(subject, banned_at, country)| BanEntry { subject, banned_at, country }));
+
+arbitrary!(ReportStatus => select(&[
+    ReportStatus::Open,
+    ReportStatus::Resolved,
+    ReportStatus::Dismissed,
+]));
+
+arbitrary!(ReportEntry => (
+    any::<UserId>(),
+    any::<ContentRef>(),
+    any::<ReportReason>(),
+    naive_date_time(),
+    any::<ReportStatus>(),
+)
+    .prop_map(|(reporter, target, reason, timestamp, status)| ReportEntry {
+        reporter,
+        target,
+        reason,
+        timestamp,
+        status,
+    }));
+
+arbitrary!(AdminSuccess => Union::new(vec![
+    Just(()).prop_map(|_| AdminSuccess::IpBanned).boxed(),
+    Just(()).prop_map(|_| AdminSuccess::IpUnbanned).boxed(),
+    Just(()).prop_map(|_| AdminSuccess::ChangedRole).boxed(),
+    any::<BanListResponse>().prop_map(AdminSuccess::BanList).boxed(),
+    short_vec::<ReportEntry>().prop_map(AdminSuccess::Reports).boxed(),
+]));
+
+arbitrary!(AdminError => error_code(AdminError::from_code));
+
+arbitrary!(ModTarget => Union::new(vec![
+    any::<ContentRef>().prop_map(ModTarget::Content).boxed(),
+    any::<CategoryId>().prop_map(ModTarget::Category).boxed(),
+    any::<UserId>().prop_map(ModTarget::User).boxed(),
+    ip_addr().prop_map(ModTarget::Ip).boxed(),
+]));
+
+arbitrary!(ModActionKind => select(&[
+    ModActionKind::DeleteThread,
+    ModActionKind::RestoreThread,
+    ModActionKind::DeleteComment,
+    ModActionKind::RestoreComment,
+    ModActionKind::HideCategory,
+    ModActionKind::RestoreCategory,
+    ModActionKind::PinThread,
+    ModActionKind::UnpinThread,
+    ModActionKind::BanUser,
+    ModActionKind::UnbanUser,
+    ModActionKind::BanIp,
+    ModActionKind::UnbanIp,
+    ModActionKind::ChangeRole,
+]));
+
+arbitrary!(ModAction => (
+    any::<UserId>(),
+    any::<ModTarget>(),
+    any::<ModActionKind>(),
+    date_time(),
+    any::<Option<String>>(),
+)
+    .prop_map(|(actor, target, kind, at, reason)| ModAction {
+        actor,
+        target,
+        kind,
+        at,
+        reason,
+    }));
+
+// Content requests
+
+arbitrary!(ContentFormat => select(&[ContentFormat::PlainText, ContentFormat::Markdown]));
+
+arbitrary!(SortOrder => select(SortOrder::all()));
+
+arbitrary!(CommentSort => select(CommentSort::all()));
+
+arbitrary!(ContentRef => Union::new(vec![
+    any::<ThreadId>().prop_map(ContentRef::Thread).boxed(),
+    any::<CommentId>().prop_map(ContentRef::Comment).boxed(),
+]));
+
+arbitrary!(ContentRefPayload => any::<ContentRef>()
+    .prop_map(|target| ContentRefPayload { target }));
+
+arbitrary!(GetUserPayload => any::<UserId>().prop_map(|id| GetUserPayload { id }));
+
+arbitrary!(AddUserPayload => (any::<UserId>(), any::<Username>())
+    .prop_map(|(id, username)| AddUserPayload { id, username }));
+
+arbitrary!(EditUserPayload => (
+    any::<Option<UserId>>(),
+    any::<Patch<Description>>(),
+    any::<Patch<String>>(),
+)
+    .prop_map(|(id, description, avatar)| EditUserPayload {
+        id,
+        description,
+        avatar,
+    }));
+
+arbitrary!(UpdateProfilePayload => (
+    any::<Option<UserId>>(),
+    any::<Patch<DisplayName>>(),
+    any::<Patch<Description>>(),
+    any::<Patch<AvatarUrl>>(),
+)
+    .prop_map(|(id, display_name, bio, avatar_url)| UpdateProfilePayload {
+        id,
+        display_name,
+        bio,
+        avatar_url,
+    }));
+
+arbitrary!(GetCategoryPayload => (any::<CategoryId>(), any::<bool>())
+    .prop_map(|(id, include_hidden)| GetCategoryPayload { id, include_hidden }));
+
+arbitrary!(AddCategoryPayload => (
+    any::<Title>(),
+    any::<Description>(),
+    any::<ContentFormat>(),
+    any::<Option<IdempotencyKey>>(),
+)
+    .prop_map(|(title, description, format, idempotency_key)| AddCategoryPayload {
+        title,
+        description,
+        format,
+        idempotency_key,
+    }));
+
+arbitrary!(EditCategoryPayload => (
+    any::<CategoryId>(),
+    any::<Option<Title>>(),
+    any::<Patch<Description>>(),
+    any::<Option<ContentFormat>>(),
+)
+    .prop_map(|(id, title, description, format)| EditCategoryPayload {
+        id,
+        title,
+        description,
+        format,
+    }));
+
+arbitrary!(HideCategoryPayload => (any::<CategoryId>(), any::<bool>())
+    .prop_map(|(id, hide)| HideCategoryPayload { id, hide }));
+
+arbitrary!(GetThreadPayload => (any::<ThreadId>(), any::<bool>())
+    .prop_map(|(id, include_hidden)| GetThreadPayload { id, include_hidden }));
+
+arbitrary!(GetThreadsPayload => (
+    any::<CategoryId>(),
+    any::<bool>(),
+    any::<SortOrder>(),
+    any::<Option<UserId>>(),
+    any::<Option<u32>>(),
+    any::<Option<u32>>(),
+    any::<Option<Cursor>>(),
+)
+    .prop_map(|(id, include_hidden, sort, author, page, per_page, after)| {
+        GetThreadsPayload {
+            id,
+            include_hidden,
+            sort,
+            author,
+            page,
+            per_page,
+            after,
+        }
+    }));
+
+arbitrary!(AddThreadPayload => (
+    any::<CategoryId>(),
+    any::<Option<UserId>>(),
+    any::<Title>(),
+    any::<Description>(),
+    any::<ContentFormat>(),
+    any::<Option<IdempotencyKey>>(),
+)
+    .prop_map(|(category_id, user_id, title, description, format, idempotency_key)| {
+        AddThreadPayload {
+            category_id,
+            user_id,
+            title,
+            description,
+            format,
+            idempotency_key,
+        }
+    }));
+
+arbitrary!(EditThreadPayload => (
+    any::<ThreadId>(),
+    any::<Option<UserId>>(),
+    any::<Option<Title>>(),
+    any::<Patch<Description>>(),
+    any::<Option<ContentFormat>>(),
+)
+    .prop_map(|(id, user_id, title, description, format)| EditThreadPayload {
+        id,
+        user_id,
+        title,
+        description,
+        format,
+    }));
+
+arbitrary!(HideThreadPayload => (any::<ThreadId>(), any::<Option<UserId>>(), any::<bool>())
+    .prop_map(|(id, user_id, hide)| HideThreadPayload { id, user_id, hide }));
+
+arbitrary!(FetchThreadPayload => (any::<ThreadId>(), any::<bool>(), any::<PaginationPayload>())
+.prop_map(|(thread_id, include_hidden, comments)| FetchThreadPayload {
+    thread_id,
+    include_hidden,
+    comments,
+}));
+
+arbitrary!(ThreadRefPayload => any::<ThreadId>()
+    .prop_map(|thread_id| ThreadRefPayload { thread_id }));
+
+arbitrary!(PinScope => select(&[PinScope::Category, PinScope::Global]));
+
+arbitrary!(PinThreadPayload => (any::<ThreadId>(), any::<bool>(), any::<PinScope>())
+    .prop_map(|(thread_id, pinned, scope)| PinThreadPayload { thread_id, pinned, scope }));
+
+arbitrary!(GetCommentPayload => (any::<CommentId>(), any::<bool>())
+    .prop_map(|(id, include_hidden)| GetCommentPayload { id, include_hidden }));
+
+arbitrary!(GetCommentsPayload => (
+    any::<ThreadId>(),
+    any::<bool>(),
+    any::<CommentSort>(),
+    any::<Option<UserId>>(),
+)
+.prop_map(|(id, include_hidden, sort, author)| GetCommentsPayload {
+    id,
+    include_hidden,
+    sort,
+    author,
+}));
+
+arbitrary!(ReplyRef => (
+    any::<CommentId>(),
+    proptest::option::of(".{0,100}"),
+)
+    .prop_map(|(comment_id, excerpt)| {
+        ReplyRef::new(comment_id, excerpt).expect("invalid ReplyRef")
+    }));
+
+arbitrary!(AddCommentPayload => (
+    any::<ThreadId>(),
+    any::<Option<UserId>>(),
+    any::<Option<CommentId>>(),
+    any::<CommentContent>(),
+    any::<ContentFormat>(),
+    any::<Option<ReplyRef>>(),
+    any::<Option<IdempotencyKey>>(),
+    any::<BoundedVec<AttachmentRef, CommentAttachmentBounds>>(),
+)
+    .prop_map(
+        |(thread_id, user_id, parent_id, content, format, reply_to, idempotency_key, attachments)| {
+            AddCommentPayload {
+                thread_id,
+                user_id,
+                parent_id,
+                content,
+                format,
+                reply_to,
+                idempotency_key,
+                attachments,
+            }
+        }
+    ));
+
+arbitrary!(EditCommentPayload => (
+    any::<CommentId>(),
+    any::<Option<UserId>>(),
+    any::<CommentContent>(),
+    any::<Option<ContentFormat>>(),
+)
+    .prop_map(|(id, user_id, content, format)| EditCommentPayload {
+        id,
+        user_id,
+        content,
+        format,
+    }));
+
+arbitrary!(HideCommentPayload => (any::<CommentId>(), any::<Option<UserId>>(), any::<bool>())
+    .prop_map(|(id, user_id, hide)| HideCommentPayload { id, user_id, hide }));
+
+arbitrary!(AttachPayload => (any::<CommentId>(), any::<AttachmentId>())
+.prop_map(|(comment_id, attachment_id)| AttachPayload {
+    comment_id,
+    attachment_id,
+}));
+
+arbitrary!(ReactionKind => select(&[
+    ReactionKind::Like,
+    ReactionKind::Heart,
+    ReactionKind::Laugh,
+    ReactionKind::Confused,
+]));
+
+arbitrary!(ReactPayload => (any::<ReactionTarget>(), any::<ReactionKind>())
+    .prop_map(|(target, kind)| ReactPayload { target, kind }));
+
+arbitrary!(ReportReason => select(&[
+    ReportReason::Spam,
+    ReportReason::Harassment,
+    ReportReason::IllegalContent,
+    ReportReason::Other,
+]));
+
+// A report with the reason `Other` always has details
+arbitrary!(ReportPayload => (
+    any::<ContentRef>(),
+    any::<ReportReason>(),
+    any::<Option<Description>>(),
+)
+.prop_map(|(target, reason, details)| {
+    let has_details = details.as_ref().map_or(false, |d| !d.trim().is_empty());
+    let details = if reason == ReportReason::Other && !has_details {
+        Some(Description::try_from("Details".to_owned()).unwrap())
+    } else {
+        details
+    };
+    ReportPayload {
+        target,
+        reason,
+        details,
+    }
+}));
+
+arbitrary!(BulkDeletePayload => any::<BoundedVec<ContentRef, BulkDeleteBounds>>()
+    .prop_map(|targets| BulkDeletePayload { targets }));
+
+arbitrary!(DraftTarget => Union::new(vec![
+    any::<CategoryId>()
+        .prop_map(|category_id| DraftTarget::NewThread { category_id })
+        .boxed(),
+    (any::<ThreadId>(), any::<Option<CommentId>>())
+        .prop_map(|(thread_id, parent_id)| DraftTarget::ReplyTo {
+            thread_id,
+            parent_id,
+        })
+        .boxed(),
+]));
+
+arbitrary!(DraftPayload => (
+    any::<Option<DraftId>>(),
+    any::<DraftTarget>(),
+    any::<Option<Title>>(),
+    any::<CommentContent>(),
+    any::<u64>(),
+)
+    .prop_map(|(draft_id, target, title, content, updated_at)| DraftPayload {
+        draft_id,
+        target,
+        title,
+        content,
+        updated_at,
+    }));
+
+arbitrary!(DraftRefPayload => any::<DraftId>().prop_map(|draft_id| DraftRefPayload { draft_id }));
+
+arbitrary!(NewPollPayload => (
+    any::<ThreadId>(),
+    any::<Title>(),
+    vec(any::<Title>(), MIN_POLL_OPTIONS..MAX_POLL_OPTIONS + 1),
+    any::<bool>(),
+    any::<Option<u64>>(),
+)
+    .prop_map(|(thread_id, question, options, multiple_choice, closes_at)| {
+        NewPollPayload::new(thread_id, question, options, multiple_choice, closes_at)
+            .expect("invalid NewPollPayload")
+    }));
+
+arbitrary!(VotePayload => (any::<ThreadId>(), any::<IdList<PollOptionId>>())
+    .prop_map(|(thread_id, option_ids)| VotePayload { thread_id, option_ids }));
+
+arbitrary!(SearchPayload => (
+    any::<QueryStr>(),
+    any::<bool>(),
+    any::<SortOrder>(),
+    any::<Option<UserId>>(),
+)
+    .prop_map(|(query, include_hidden, sort, author)| SearchPayload {
+        query,
+        include_hidden,
+        sort,
+        author,
+    }));
+
+arbitrary!(GetHiddenPayload => any::<bool>()
+    .prop_map(|include_hidden| GetHiddenPayload { include_hidden }));
+
+arbitrary!(ContentRequest => Union::new(vec![
+    any::<AddUserPayload>().prop_map(ContentRequest::AddUser).boxed(),
+    any::<EditUserPayload>().prop_map(ContentRequest::EditUser).boxed(),
+    any::<UpdateProfilePayload>().prop_map(ContentRequest::UpdateProfile).boxed(),
+    any::<AddCategoryPayload>().prop_map(ContentRequest::AddCategory).boxed(),
+    any::<EditCategoryPayload>().prop_map(ContentRequest::EditCategory).boxed(),
+    any::<HideCategoryPayload>().prop_map(ContentRequest::HideCategory).boxed(),
+    any::<AddThreadPayload>().prop_map(ContentRequest::AddThread).boxed(),
+    any::<EditThreadPayload>().prop_map(ContentRequest::EditThread).boxed(),
+    any::<HideThreadPayload>().prop_map(ContentRequest::HideThread).boxed(),
+    any::<PinThreadPayload>().prop_map(ContentRequest::SetThreadPinned).boxed(),
+    any::<AddCommentPayload>().prop_map(ContentRequest::AddComment).boxed(),
+    any::<EditCommentPayload>().prop_map(ContentRequest::EditComment).boxed(),
+    any::<HideCommentPayload>().prop_map(ContentRequest::HideComment).boxed(),
+    any::<AttachPayload>().prop_map(ContentRequest::AttachToComment).boxed(),
+    any::<ReactPayload>().prop_map(ContentRequest::React).boxed(),
+    any::<ReactPayload>().prop_map(ContentRequest::RemoveReaction).boxed(),
+    any::<ReportPayload>().prop_map(ContentRequest::Report).boxed(),
+    any::<BulkDeletePayload>().prop_map(ContentRequest::BulkDelete).boxed(),
+    any::<ThreadRefPayload>().prop_map(ContentRequest::Subscribe).boxed(),
+    any::<ThreadRefPayload>().prop_map(ContentRequest::Unsubscribe).boxed(),
+    any::<PaginationPayload>().prop_map(ContentRequest::ListSubscriptions).boxed(),
+    any::<ThreadRefPayload>().prop_map(ContentRequest::Bookmark).boxed(),
+    any::<ThreadRefPayload>().prop_map(ContentRequest::Unbookmark).boxed(),
+    any::<PaginationPayload>().prop_map(ContentRequest::ListBookmarks).boxed(),
+    any::<DraftPayload>().prop_map(ContentRequest::SaveDraft).boxed(),
+    Just(ContentRequest::ListDrafts(AbsentOrEmpty::Empty)).boxed(),
+    any::<DraftRefPayload>().prop_map(ContentRequest::DeleteDraft).boxed(),
+    any::<ContentRefPayload>().prop_map(ContentRequest::GetEditHistory).boxed(),
+    any::<NewPollPayload>().prop_map(ContentRequest::CreatePoll).boxed(),
+    any::<VotePayload>().prop_map(ContentRequest::Vote).boxed(),
+    any::<FetchThreadPayload>().prop_map(ContentRequest::FetchThread).boxed(),
+]));
+
+// Content responses
+
+arbitrary!(ContentError => error_code(ContentError::from_code));
+
+arbitrary!(UserPayload => (
+    any::<UserId>(),
+    any::<Username>(),
+    any::<Option<Description>>(),
+    any::<Option<String>>(),
+)
+    .prop_map(|(id, username, description, avatar)| UserPayload {
+        id,
+        username,
+        description,
+        avatar,
+    }));
+
+arbitrary!(CategoryPayload => (
+    any::<CategoryId>(),
+    any::<Title>(),
+    any::<Description>(),
+    any::<ContentFormat>(),
+    any::<bool>(),
+)
+    .prop_map(|(id, title, description, format, hidden)| CategoryPayload {
+        id,
+        title,
+        description,
+        format,
+        hidden,
+    }));
+
+arbitrary!(ReactionCount => (any::<ReactionKind>(), any::<u32>())
+    .prop_map(|(kind, count)| ReactionCount { kind, count }));
+
+arbitrary!(PollOption => (any::<PollOptionId>(), any::<Title>(), any::<u32>())
+    .prop_map(|(id, text, votes)| PollOption { id, text, votes }));
+
+arbitrary!(PollPayload => (
+    any::<Title>(),
+    vec(any::<PollOption>(), MIN_POLL_OPTIONS..MAX_POLL_OPTIONS + 1),
+    any::<bool>(),
+    any::<Option<u64>>(),
+)
+    .prop_map(|(question, options, multiple_choice, closes_at)| {
+        PollPayload::new(question, options, multiple_choice, closes_at)
+            .expect("invalid PollPayload")
+    }));
+
+arbitrary!(ThreadPayload => (
+    (
+        any::<ThreadId>(),
+        any::<CategoryId>(),
+        any::<UserId>(),
+        any::<Title>(),
+        any::<Description>(),
+        naive_date_time(),
+    ),
+    (
+        any::<ContentFormat>(),
+        any::<bool>(),
+        any::<bool>(),
+        short_vec::<ReactionCount>(),
+        any::<Option<u64>>(),
+        any::<Option<PollPayload>>(),
+    ),
+)
+    .prop_map(|((id, category_id, user_id, title, description, timestamp), rest)| {
+        let (format, hidden, pinned, reactions, edited_at, poll) = rest;
+        ThreadPayload {
+            id,
+            category_id,
+            user_id,
+            title,
+            description,
+            format,
+            timestamp,
+            hidden,
+            pinned,
+            reactions,
+            edited_at,
+            poll,
+        }
+    }));
+
+arbitrary!(AttachmentMeta => (
+    any::<AttachmentId>(),
+    any::<FileName>(),
+    any::<MimeType>(),
+    any::<u64>(),
+    any::<HexDigest>(),
+)
+    .prop_map(|(id, filename, mime, size_bytes, sha256)| AttachmentMeta {
+        id,
+        filename,
+        mime,
+        size_bytes,
+        sha256,
+    }));
+
+arbitrary!(CommentPayload => (
+    (
+        any::<CommentId>(),
+        any::<ThreadId>(),
+        any::<Option<CommentId>>(),
+        any::<UserId>(),
+        any::<CommentContent>(),
+        any::<ContentFormat>(),
+    ),
+    (
+        naive_date_time(),
+        any::<bool>(),
+        short_vec::<ReactionCount>(),
+        any::<Option<u64>>(),
+        short_vec::<AttachmentMeta>(),
+        any::<Option<ReplyRef>>(),
+    ),
+)
+    .prop_map(|((id, thread_id, parent_id, user_id, content, format), rest)| {
+        let (timestamp, hidden, reactions, edited_at, attachments, reply_to) = rest;
+        CommentPayload {
+            id,
+            thread_id,
+            parent_id,
+            user_id,
+            content,
+            format,
+            timestamp,
+            hidden,
+            reactions,
+            edited_at,
+            attachments,
+            reply_to,
+        }
+    }));
+
+arbitrary!(ThreadPagePayload => (any::<ThreadPayload>(), any::<Paginated<CommentPayload>>())
+    .prop_map(|(thread, comments)| ThreadPagePayload { thread, comments }));
+
+arbitrary!(SubscriptionPayload => (any::<ThreadPayload>(), any::<u64>())
+.prop_map(|(thread, subscribed_at)| SubscriptionPayload {
+    thread,
+    subscribed_at,
+}));
+
+arbitrary!(BookmarkPayload => (any::<ThreadPayload>(), any::<u64>())
+.prop_map(|(thread, bookmarked_at)| BookmarkPayload {
+    thread,
+    bookmarked_at,
+}));
+
+arbitrary!(RevisionPayload => (any::<u32>(), any::<UserId>(), any::<u64>(), any::<CommentContent>())
+.prop_map(|(revision, edited_by, edited_at, content)| RevisionPayload {
+    revision,
+    edited_by,
+    edited_at,
+    content,
+}));
+
+arbitrary!(EditHistoryPayload => (any::<ContentRef>(), short_vec::<RevisionPayload>())
+    .prop_map(|(target, revisions)| EditHistoryPayload { target, revisions }));
+
+arbitrary!(SearchHit => Union::new(vec![
+    any::<CategoryPayload>().prop_map(SearchHit::Category).boxed(),
+    any::<ThreadPayload>().prop_map(SearchHit::Thread).boxed(),
+    any::<CommentPayload>().prop_map(SearchHit::Comment).boxed(),
+    any::<UserPayload>().prop_map(SearchHit::User).boxed(),
+]));
+
+arbitrary!(ContentSuccess => Union::new(vec![
+    any::<CategoryPayload>().prop_map(ContentSuccess::Category).boxed(),
+    any::<Paginated<CategoryPayload>>().prop_map(ContentSuccess::Categories).boxed(),
+    any::<ThreadState>().prop_map(ContentSuccess::Thread).boxed(),
+    any::<Paginated<ThreadState>>().prop_map(ContentSuccess::Threads).boxed(),
+    any::<ThreadPagePayload>().prop_map(ContentSuccess::ThreadPage).boxed(),
+    any::<CommentState>().prop_map(ContentSuccess::Comment).boxed(),
+    any::<Paginated<CommentState>>().prop_map(ContentSuccess::Comments).boxed(),
+    any::<UserPayload>().prop_map(ContentSuccess::User).boxed(),
+    any::<Paginated<UserPayload>>().prop_map(ContentSuccess::Users).boxed(),
+    any::<SearchResultsPayload>().prop_map(ContentSuccess::SearchResult).boxed(),
+    any::<Paginated<SubscriptionPayload>>().prop_map(ContentSuccess::Subscriptions).boxed(),
+    any::<Paginated<BookmarkPayload>>().prop_map(ContentSuccess::Bookmarks).boxed(),
+    short_vec::<DraftPayload>().prop_map(ContentSuccess::Drafts).boxed(),
+    any::<EditHistoryPayload>().prop_map(ContentSuccess::EditHistory).boxed(),
+]));
+
+// Content models
+
+arbitrary!(Thread => (
+    (
+        any::<ThreadId>(),
+        any::<CategoryId>(),
+        any::<UserId>(),
+        any::<Title>(),
+        any::<Description>(),
+        any::<ContentFormat>(),
+    ),
+    (date_time(), proptest::option::of(date_time()), any::<i64>()),
+)
+    .prop_map(|((id, category_id, author, title, body, format), rest)| {
+        let (created_at, updated_at, votes) = rest;
+        Thread {
+            id,
+            category_id,
+            author,
+            title,
+            body,
+            format,
+            created_at,
+            updated_at,
+            votes,
+        }
+    }));
+
+arbitrary!(Comment => (
+    (
+        any::<CommentId>(),
+        any::<ThreadId>(),
+        any::<Option<CommentId>>(),
+        any::<UserId>(),
+        any::<CommentContent>(),
+        any::<ContentFormat>(),
+    ),
+    (date_time(), proptest::option::of(date_time()), any::<i64>()),
+)
+    .prop_map(|((id, thread_id, parent_id, author, body, format), rest)| {
+        let (created_at, updated_at, votes) = rest;
+        Comment {
+            id,
+            thread_id,
+            parent_id,
+            author,
+            body,
+            format,
+            created_at,
+            updated_at,
+            votes,
+        }
+    }));
+
+arbitrary!(Tombstone => (
+    any::<ContentRef>(),
+    any::<UserId>(),
+    date_time(),
+    any::<Option<String>>(),
+)
+    .prop_map(|(id, deleted_by, deleted_at, reason)| Tombstone {
+        id,
+        deleted_by,
+        deleted_at,
+        reason,
+    }));
+
+impl<T: Arbitrary + 'static> Arbitrary for ContentState<T> {
+    type Parameters = ();
+    type Strategy = BoxedStrategy<Self>;
+    fn arbitrary_with(_: Self::Parameters) -> Self::Strategy {
+        Union::new(vec![
+            any::<T>().prop_map(ContentState::Live).boxed(),
+            any::<Tombstone>().prop_map(ContentState::Deleted).boxed(),
+        ])
+        .boxed()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde::de::DeserializeOwned;
+    use serde::Serialize;
+
+    /// Assert that `value` is the same after a round trip through json
+    fn round_trip<T>(value: &T)
+    where
+        T: Serialize + DeserializeOwned + PartialEq + Debug,
+    {
+        let json = serde_json::to_string(value).unwrap();
+        let decoded: T = serde_json::from_str(&json).unwrap_or_else(|e| panic!("{}: {}", e, json));
+        assert_eq!(&decoded, value, "{}", json);
+    }
+
+    /// Like `round_trip`, but for the types which don't implement `PartialEq`,
+    /// which are compared by their json instead
+    fn json_round_trip<T>(value: &T)
+    where
+        T: Serialize + DeserializeOwned,
+    {
+        let json = serde_json::to_value(value).unwrap();
+        let decoded: T =
+            serde_json::from_value(json.clone()).unwrap_or_else(|e| panic!("{}: {}", e, json));
+        assert_eq!(serde_json::to_value(&decoded).unwrap(), json);
+    }
+
+    /// Assert that the type tag of a serialized response is one of `tags`
+    fn has_known_tag<T: Serialize>(value: &T, tags: &[&str]) {
+        let json = serde_json::to_value(value).unwrap();
+        let tag = json["type"].as_str().expect("responses have a type tag");
+        assert!(tags.contains(&tag), "{}", tag);
+    }
+
+    proptest! {
+        #[test]
+        fn validated_fields(
+            username in any::<Username>(),
+            password in any::<PlainPassword>(),
+            description in any::<Description>(),
+            content in any::<CommentContent>(),
+            email in any::<Email>(),
+            query in any::<QueryStr>(),
+            code in any::<TwoFactorCode>(),
+            display_name in any::<DisplayName>(),
+        ) {
+            round_trip(&username);
+            round_trip(&password);
+            round_trip(&description);
+            round_trip(&content);
+            round_trip(&email);
+            round_trip(&query);
+            round_trip(&code);
+            round_trip(&display_name);
+        }
+
+        #[test]
+        fn other_validated_fields(
+            avatar_url in any::<AvatarUrl>(),
+            key in any::<IdempotencyKey>(),
+            title in any::<Title>(),
+            phone in any::<PhoneNumber>(),
+            file_name in any::<FileName>(),
+            digest in any::<HexDigest>(),
+            mime in any::<MimeType>(),
+            cursor in any::<Cursor>(),
+        ) {
+            round_trip(&avatar_url);
+            round_trip(&key);
+            round_trip(&title);
+            round_trip(&phone);
+            round_trip(&file_name);
+            round_trip(&digest);
+            round_trip(&mime);
+            round_trip(&cursor);
+        }
+
+        #[test]
+        fn tokens_durations_and_ids(
+            token in any::<Token>(),
+            duration in any::<HumanDuration>(),
+            id in any::<UserId>(),
+            ids in any::<IdList<PollOptionId>>(),
+            error in any::<ValidationError>(),
+        ) {
+            round_trip(&token);
+            round_trip(&duration);
+            round_trip(&id);
+            round_trip(&ids);
+            round_trip(&error);
+        }
+
+        #[test]
+        fn payload_wrappers(
+            token_payload in any::<TokenPayload<PaginationPayload>>(),
+            user_id_payload in any::<UserIdPayload<PaginationPayload>>(),
+            compact in any::<TokenPayloadCompact<PaginationPayload>>(),
+            compact_id in any::<UserIdPayloadCompact<PaginationPayload>>(),
+            versioned in any::<Versioned<PaginationPayload>>(),
+            strict in any::<Strict<TokenPayload<PaginationPayload>>>(),
+            page in any::<CursorPage<UserId>>(),
+            result in any::<ResponseResult<UserId, ErrorCode>>(),
+        ) {
+            round_trip(&token_payload);
+            round_trip(&user_id_payload);
+            round_trip(&compact);
+            round_trip(&compact_id);
+            round_trip(&versioned);
+            round_trip(&strict);
+            round_trip(&page);
+            round_trip(&result);
+        }
+
+        #[test]
+        fn errors(
+            wire in any::<WireError>(),
+            payload in any::<ErrorPayload>(),
+            response in any::<ResponseError>(),
+            parse in any::<ParseError>(),
+        ) {
+            round_trip(&wire);
+            round_trip(&payload);
+            round_trip(&response);
+            round_trip(&parse);
+        }
+
+        #[test]
+        fn auth_requests(request in any::<AuthRequest>()) {
+            json_round_trip(&request);
+        }
+
+        #[test]
+        fn auth_responses(
+            response in any::<AuthSuccess>(),
+            error in any::<AuthError>(),
+            result in any::<ResponseResult<AuthSuccess, AuthError>>(),
+        ) {
+            json_round_trip(&response);
+            has_known_tag(&response, AuthSuccess::TAGS);
+            round_trip(&error);
+            json_round_trip(&result);
+        }
+
+        #[test]
+        fn admin_requests(
+            request in any::<AdminRequest>(),
+            with_token in any::<TokenAdminRequest>(),
+        ) {
+            round_trip(&request);
+            round_trip(&with_token);
+        }
+
+        #[test]
+        fn admin_responses(
+            response in any::<AdminSuccess>(),
+            error in any::<AdminError>(),
+            action in any::<ModAction>(),
+        ) {
+            round_trip(&response);
+            has_known_tag(&response, AdminSuccess::TAGS);
+            round_trip(&error);
+            round_trip(&action);
+        }
+
+        #[test]
+        fn content_requests(request in any::<ContentRequest>()) {
+            round_trip(&request);
+        }
+
+        #[test]
+        fn content_responses(
+            response in any::<ContentSuccess>(),
+            error in any::<ContentError>(),
+        ) {
+            round_trip(&response);
+            has_known_tag(&response, ContentSuccess::TAGS);
+            round_trip(&error);
+        }
+
+        #[test]
+        fn content_queries(
+            user in any::<GetUserPayload>(),
+            category in any::<GetCategoryPayload>(),
+            thread in any::<GetThreadPayload>(),
+            threads in any::<GetThreadsPayload>(),
+            comment in any::<GetCommentPayload>(),
+            comments in any::<GetCommentsPayload>(),
+            search in any::<SearchPayload>(),
+            hidden in any::<GetHiddenPayload>(),
+        ) {
+            round_trip(&user);
+            round_trip(&category);
+            round_trip(&thread);
+            round_trip(&threads);
+            round_trip(&comment);
+            round_trip(&comments);
+            round_trip(&search);
+            round_trip(&hidden);
+        }
+
+        #[test]
+        fn content_models(
+            thread in any::<Thread>(),
+            comment in any::<Comment>(),
+            thread_state in any::<ThreadState>(),
+            comment_state in any::<CommentState>(),
+        ) {
+            round_trip(&thread);
+            round_trip(&comment);
+            round_trip(&thread_state);
+            round_trip(&comment_state);
+        }
+    }
+
+    #[cfg(feature = "url")]
+    proptest! {
+        #[test]
+        fn urls(url in any::<crate::valid::url::Url>()) {
+            round_trip(&url);
+        }
+    }
+}
@@ -0,0 +1,264 @@
+//! GraphQL scalars and inputs of the ids and validated types for
+//! async-graphql
+//!
+//! The ids are numbers like in the json of the payloads (a id is also
+//! accepted as a string of digits), and `Username`, `Email` and `Title` are
+//! strings which are validated when they are parsed. A text is resolved as it
+//! was given, a `Title` without the HTML-escaping it is stored with (see
+//! `Title::unescaped`).
+//!
+//! `AuthInput` and `RegisterUserInput` are owned mirrors of `AuthPayload` and
+//! `RegisterUserPayload`, as the inputs of async-graphql must be `'static`.
+//! The fields are plain strings which are validated when a input is converted
+//! to its payload, so a invalid field is a error with the code of the
+//! `ValidationError` in its extensions (see `ErrorExtensions`):
+//!
+//! ```json
+//! { "message": "invalid username", "extensions": { "code": "INVALID_USERNAME" } }
+//! ```
+//!
+//! The passwords are `secret`, so they are never written to the logs of a
+//! query.
+
+use crate::auth::requests::{AuthPayload, RegisterUserPayload};
+use crate::valid::fields::{Email, PlainPassword, Username};
+use crate::valid::ids::{CommentId, Id, ThreadId, UserId};
+use crate::valid::title::Title;
+use crate::valid::ValidationError;
+use async_graphql::{
+    Error, ErrorExtensions, InputObject, InputValueError, InputValueResult, Scalar, ScalarType,
+    Value,
+};
+use std::convert::TryFrom;
+use std::fmt;
+
+impl ErrorExtensions for ValidationError {
+    fn extend(&self) -> Error {
+        let code = self.code();
+        Error::new(self.to_string()).extend_with(|_, e| e.set("code", code.to_string()))
+    }
+}
+
+/// A id from a number or a string of digits
+fn id_from_value(value: &Value) -> Option<u32> {
+    match value {
+        Value::Number(n) => n.as_u64().and_then(|n| u32::try_from(n).ok()),
+        Value::String(s) => s.parse().ok(),
+        _ => None,
+    }
+}
+
+macro_rules! id_scalars {
+    ($($id:ident),*) => {
+        $(
+            #[Scalar]
+            impl ScalarType for $id {
+                fn parse(value: Value) -> InputValueResult<Self> {
+                    match id_from_value(&value) {
+                        Some(id) => Ok($id::from(id)),
+                        None => Err(InputValueError::custom(ValidationError::InvalidId)),
+                    }
+                }
+
+                fn to_value(&self) -> Value {
+                    Value::Number(self.inner().into())
+                }
+            }
+        )*
+    };
+}
+
+macro_rules! text_scalars {
+    ($($ty:ident => $validate:expr, $resolve:expr),*) => {
+        $(
+            #[Scalar]
+            impl ScalarType for $ty {
+                fn parse(value: Value) -> InputValueResult<Self> {
+                    let validate: fn(String) -> Result<$ty, ValidationError> = $validate;
+                    match value {
+                        Value::String(s) => validate(s).map_err(InputValueError::custom),
+                        value => Err(InputValueError::expected_type(value)),
+                    }
+                }
+
+                fn to_value(&self) -> Value {
+                    let resolve: fn(&$ty) -> String = $resolve;
+                    Value::String(resolve(self))
+                }
+            }
+        )*
+    };
+}
+
+id_scalars!(UserId, ThreadId, CommentId);
+
+text_scalars!(
+    Username => Username::try_from, |v| v.to_string(),
+    Email => Email::try_from, |v| v.to_string(),
+    Title => |s| Title::try_from(s.as_str()), Title::unescaped
+);
+
+/// A login, which is validated when it is converted to a `AuthPayload`
+#[derive(InputObject)]
+pub struct AuthInput {
+    pub username: String,
+    #[graphql(secret)]
+    pub password: String,
+}
+
+impl TryFrom<AuthInput> for AuthPayload {
+    type Error = ValidationError;
+    fn try_from(input: AuthInput) -> Result<Self, Self::Error> {
+        Ok(AuthPayload {
+            username: Username::try_from(input.username)?,
+            password: PlainPassword::try_from(input.password)?,
+        })
+    }
+}
+
+impl fmt::Debug for AuthInput {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("AuthInput")
+            .field("username", &self.username)
+            .field("password", &"..")
+            .finish()
+    }
+}
+
+/// A registration, which is validated when it is converted to a
+/// `RegisterUserPayload`
+#[derive(InputObject)]
+pub struct RegisterUserInput {
+    pub username: String,
+    #[graphql(secret)]
+    pub password: String,
+    pub email: String,
+}
+
+impl TryFrom<RegisterUserInput> for RegisterUserPayload {
+    type Error = ValidationError;
+    fn try_from(input: RegisterUserInput) -> Result<Self, Self::Error> {
+        Ok(RegisterUserPayload {
+            username: Username::try_from(input.username)?,
+            password: PlainPassword::try_from(input.password)?,
+            email: Email::try_from(input.email)?,
+        })
+    }
+}
+
+impl fmt::Debug for RegisterUserInput {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("RegisterUserInput")
+            .field("username", &self.username)
+            .field("password", &"..")
+            .field("email", &self.email)
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use async_graphql::{EmptySubscription, Object, Schema};
+
+    struct Query;
+
+    #[Object]
+    impl Query {
+        async fn thread(&self, id: ThreadId) -> ThreadId {
+            id
+        }
+    }
+
+    struct Mutation;
+
+    #[Object]
+    impl Mutation {
+        async fn register(&self, input: RegisterUserInput) -> async_graphql::Result<Username> {
+            let payload = RegisterUserPayload::try_from(input).map_err(|e| e.extend())?;
+            Ok(payload.username)
+        }
+    }
+
+    async fn execute(query: &str) -> serde_json::Value {
+        let schema = Schema::new(Query, Mutation, EmptySubscription);
+        serde_json::to_value(schema.execute(query).await).unwrap()
+    }
+
+    fn register(username: &str, password: &str, email: &str) -> String {
+        format!(
+            r#"mutation {{
+                register(input: {{ username: "{}", password: "{}", email: "{}" }})
+            }}"#,
+            username, password, email
+        )
+    }
+
+    #[tokio::test]
+    async fn register_with_valid_input() {
+        let res = execute(&register("john", "helloAndWelcome123", "john@example.com")).await;
+        assert_eq!(res["data"]["register"], "john");
+        assert!(res.get("errors").is_none());
+    }
+
+    #[tokio::test]
+    async fn validation_errors_have_their_code() {
+        for (query, expt) in vec![
+            (
+                register("jo", "helloAndWelcome123", "john@example.com"),
+                ValidationError::InvalidUsername,
+            ),
+            (
+                register("john", "hi", "john@example.com"),
+                ValidationError::InvalidPassword,
+            ),
+            (
+                register("john", "helloAndWelcome123", "john"),
+                ValidationError::InvalidEmail,
+            ),
+        ] {
+            let res = execute(&query).await;
+            let error = &res["errors"][0];
+            assert_eq!(error["message"], expt.to_string());
+            assert_eq!(error["extensions"]["code"], expt.code().to_string());
+        }
+    }
+
+    #[tokio::test]
+    async fn id_scalars() {
+        let res = execute("{ a: thread(id: 42), b: thread(id: \"42\") }").await;
+        assert_eq!(res["data"], serde_json::json!({ "a": 42, "b": 42 }));
+
+        let res = execute("{ thread(id: -1) }").await;
+        assert!(res["errors"][0]["message"]
+            .as_str()
+            .unwrap()
+            .contains(&ValidationError::InvalidId.to_string()));
+    }
+
+    #[test]
+    fn text_scalars() {
+        let name = Username::parse(Value::String(String::from("john"))).unwrap();
+        assert_eq!(name.to_value(), Value::String(String::from("john")));
+        assert!(Username::parse(Value::String(String::from("jo"))).is_err());
+        assert!(Email::parse(Value::Boolean(true)).is_err());
+        assert!(Title::parse(Value::String(String::from("Hi"))).is_err());
+    }
+
+    #[test]
+    fn titles_are_resolved_unescaped() {
+        let title = Title::parse(Value::String(String::from("Tom & Jerry"))).unwrap();
+        assert_eq!(&*title, "Tom &amp; Jerry");
+        assert_eq!(title.to_value(), Value::String(String::from("Tom & Jerry")));
+    }
+
+    #[test]
+    fn password_is_not_debugged() {
+        let input = AuthInput {
+            username: String::from("john"),
+            password: String::from("helloAndWelcome123"),
+        };
+        assert!(!format!("{:?}", input).contains("helloAndWelcome123"));
+        assert!(AuthPayload::try_from(input).is_ok());
+    }
+}
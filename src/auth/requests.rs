@@ -1,11 +1,26 @@
 //! The requests a user can send to the auth-service
 
 use crate::auth::responses::Role;
-use crate::payloads::EmptyPayload;
+use crate::error::{Error, ParseError};
+use crate::payloads::{from_json_lenient, parse_request, parse_request_value, EmptyPayload};
 use crate::valid::fields::*;
 use crate::valid::ids::*;
+use crate::valid::policy::ValidationPolicy;
+use crate::valid::token::Token;
+use crate::valid::{FieldError, Validate, ValidationError};
+use std::convert::TryFrom;
 
-#[derive(Serialize, Deserialize)]
+/// The requests a user can send to the auth-service
+///
+/// A login starts with `Authenticate`. If the user has not enabled two-factor
+/// authentication the service answers with `AuthSuccess::Authenticated` and
+/// the login is done. Otherwise it answers with
+/// `AuthSuccess::TwoFactorRequired`, which contains a challenge token, and the
+/// user must answer with `SubmitTwoFactor` carrying that token and the code
+/// from their device before they are authenticated.
+#[derive(Serialize, Deserialize, Debug)]
+#[cfg_attr(feature = "schemars", derive(JsonSchema))]
+#[cfg_attr(feature = "utoipa", derive(ToSchema))]
 #[serde(
     tag = "type",
     content = "payload",
@@ -14,24 +29,520 @@ use crate::valid::ids::*;
 pub enum AuthRequest {
     Authenticate(AuthPayload),
     Deauthenticate(EmptyPayload),
+    #[serde(rename = "REGISTER", alias = "REGISTER_USER")]
     RegisterUser(RegisterUserPayload),
+    SubmitTwoFactor(TwoFactorPayload),
+    /// Get the user of the current session, answered with
+    /// `AuthSuccess::WhoAmI`
+    WhoAmI(EmptyPayload),
+}
+
+impl AuthRequest {
+    /// The type tags of the requests
+    pub const TAGS: &'static [&'static str] = &[
+        "AUTHENTICATE",
+        "DEAUTHENTICATE",
+        "REGISTER",
+        "SUBMIT_TWO_FACTOR",
+        "WHO_AM_I",
+    ];
+
+    /// Parse a request from json, where the type tag is matched
+    /// case-insensitively (see `payloads::from_json_lenient`)
+    ///
+    /// The old tags (see `LEGACY_TAGS`) are matched as well.
+    pub fn from_json_lenient(json: &str) -> Result<Self, Error> {
+        let legacy = LEGACY_TAGS.iter().map(|(old, _)| *old);
+        let tags: Vec<&str> = Self::TAGS.iter().cloned().chain(legacy).collect();
+        from_json_lenient(json, &tags)
+    }
+
+    /// Parse a request from json, where a error names the type of the request
+    /// and the field which was wrong (see `payloads::parse_request`)
+    pub fn parse_json(json: &str) -> Result<Self, ParseError> {
+        parse_request(json, Self::TAGS)
+    }
+
+    /// Check every field of the request against the default
+    /// `ValidationPolicy`, and collect the fields which are not valid
+    ///
+    /// A request which was deserialized has already been checked, so this is
+    /// for the requests which were made in other ways (see
+    /// `validate_with_policy`).
+    pub fn validate(&self) -> Result<(), Vec<FieldError>> {
+        self.validate_with_policy(&ValidationPolicy::default())
+    }
+
+    /// Check every field of the request against `policy`, like a service with
+    /// stricter limits than the default does at its edge
+    ///
+    /// A request without fields to validate (like `Deauthenticate`) is always
+    /// valid.
+    pub fn validate_with_policy(&self, policy: &ValidationPolicy) -> Result<(), Vec<FieldError>> {
+        let username = |u: &Username| Username::try_new_with_policy(&**u, policy).map(|_| ());
+        let password =
+            |p: &PlainPassword| PlainPassword::try_new_with_policy(&**p, policy).map(|_| ());
+        let email = |e: &Email| Email::try_from(e.to_string()).map(|_| ());
+        let code = |c: &TwoFactorCode| TwoFactorCode::try_from((**c).to_owned()).map(|_| ());
+        let checks = match self {
+            AuthRequest::Authenticate(p) => vec![
+                ("username", username(&p.username)),
+                ("password", password(&p.password)),
+            ],
+            AuthRequest::RegisterUser(p) => vec![
+                ("username", username(&p.username)),
+                ("password", password(&p.password)),
+                ("email", email(&p.email)),
+            ],
+            AuthRequest::SubmitTwoFactor(p) => vec![("code", code(&p.code))],
+            AuthRequest::Deauthenticate(_) | AuthRequest::WhoAmI(_) => Vec::new(),
+        };
+        let errors: Vec<_> = checks
+            .into_iter()
+            .filter_map(|(field, check)| check.err().map(|error| FieldError { field, error }))
+            .collect();
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors)
+        }
+    }
 }
 
-#[derive(Serialize, Deserialize)]
+/// The errors of `AuthRequest::validate`, without the names of their fields
+///
+/// Unlike the payloads of the other requests, every field is checked again,
+/// as a auth request is often made in code (and not deserialized).
+impl Validate for AuthRequest {
+    fn validation_errors(&self) -> Vec<ValidationError> {
+        match self.validate() {
+            Ok(()) => Vec::new(),
+            Err(errors) => errors.into_iter().map(|e| e.error).collect(),
+        }
+    }
+}
+
+/// Convert json which has already been parsed (e.g. by a middleware), without
+/// serializing it again
+impl TryFrom<serde_json::Value> for AuthRequest {
+    type Error = ParseError;
+    fn try_from(value: serde_json::Value) -> Result<Self, Self::Error> {
+        parse_request_value(value, Self::TAGS)
+    }
+}
+
+impl TryFrom<serde_json::Map<String, serde_json::Value>> for AuthRequest {
+    type Error = ParseError;
+    fn try_from(map: serde_json::Map<String, serde_json::Value>) -> Result<Self, Self::Error> {
+        AuthRequest::try_from(serde_json::Value::Object(map))
+    }
+}
+
+/// The old type tags of the requests, as `(old tag, current tag)`
+///
+/// Renamed variants keep their old tags as `#[serde(alias = "...")]`, so
+/// clients which send an old tag keep working. Every alias must be listed
+/// here (and be covered by a captured request in the `wire_compat` tests), so
+/// that old tags can be gated later.
+pub const LEGACY_TAGS: &[(&str, &str)] = &[("REGISTER_USER", "REGISTER")];
+
+/// The old names of the fields of the payloads, as `(old name, current name)`
+///
+/// Like `LEGACY_TAGS`, but for the fields.
+pub const LEGACY_FIELDS: &[(&str, &str)] = &[("user", "username"), ("raw_username", "username")];
+
+// The payloads own their (validated) fields instead of borrowing from the
+// input, so they can be deserialized from any source (like
+// `serde_json::from_value` or `from_reader`) and outlive the buffer they were
+// parsed from.
+
+// Renamed fields and variants keep their old names as
+// `#[serde(alias = "...")]`. Aliases are only used when deserializing,
+// serialization always uses the current (canonical) name. The old names are
+// listed in `LEGACY_TAGS` and `LEGACY_FIELDS`.
+
+#[derive(Serialize, Deserialize, Debug)]
+#[cfg_attr(feature = "schemars", derive(JsonSchema))]
+#[cfg_attr(feature = "utoipa", derive(ToSchema))]
+#[serde(rename_all = "snake_case", deny_unknown_fields)]
 pub struct AuthPayload {
+    #[serde(alias = "user", alias = "raw_username")]
     pub username: Username,
     pub password: PlainPassword,
 }
 
-#[derive(Serialize, Deserialize)]
+impl AuthPayload {
+    /// Make a login from a registration, for logging the user in right after
+    /// it has been registered
+    ///
+    /// The username and the password of the registration are carried over.
+    pub fn from_register(registration: &RegisterUserPayload) -> Self {
+        AuthPayload {
+            username: registration.username.clone(),
+            password: registration.password.clone(),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+#[cfg_attr(feature = "schemars", derive(JsonSchema))]
+#[cfg_attr(feature = "utoipa", derive(ToSchema))]
+#[serde(rename_all = "snake_case", deny_unknown_fields)]
 pub struct RegisterUserPayload {
+    #[serde(alias = "user", alias = "raw_username")]
     pub username: Username,
     pub password: PlainPassword,
     pub email: Email,
 }
 
-#[derive(Serialize, Deserialize, Debug)]
+impl RegisterUserPayload {
+    /// Make a registration from a login attempt and the email of the user
+    ///
+    /// The username and the password of the login are carried over.
+    pub fn from_login(login: &AuthPayload, email: Email) -> Self {
+        RegisterUserPayload {
+            username: login.username.clone(),
+            password: login.password.clone(),
+            email,
+        }
+    }
+}
+
+/// The answer to a pending two-factor challenge
+#[derive(Serialize, Deserialize, PartialEq, Debug)]
+#[cfg_attr(feature = "schemars", derive(JsonSchema))]
+#[cfg_attr(feature = "utoipa", derive(ToSchema))]
+#[serde(rename_all = "snake_case")]
+pub struct TwoFactorPayload {
+    pub challenge: Token,
+    pub code: TwoFactorCode,
+}
+
+#[derive(Serialize, Deserialize, PartialEq, Debug)]
+#[cfg_attr(feature = "schemars", derive(JsonSchema))]
+#[cfg_attr(feature = "utoipa", derive(ToSchema))]
+#[serde(rename_all = "snake_case")]
 pub struct SetUserRolePayload {
     pub id: UserId,
     pub role: Role,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::ParseErrorKind;
+
+    #[test]
+    fn username_aliases_deserialize_to_the_same_value() {
+        for field in &["username", "user", "raw_username"] {
+            let json = format!(
+                r#"{{ "{}": "john", "password": "helloAndWelcome123" }}"#,
+                field
+            );
+            let payload: AuthPayload = serde_json::from_str(&json).unwrap();
+            assert_eq!(&*payload.username, "john", "field '{}'", field);
+
+            let json = format!(
+                r#"{{ "{}": "john", "password": "helloAndWelcome123", "email": "john@doe.com" }}"#,
+                field
+            );
+            let payload: RegisterUserPayload = serde_json::from_str(&json).unwrap();
+            assert_eq!(&*payload.username, "john", "field '{}'", field);
+        }
+    }
+
+    fn register(username: &str, password: &str) -> AuthRequest {
+        AuthRequest::RegisterUser(RegisterUserPayload {
+            username: Username::try_from(username.to_owned()).unwrap(),
+            password: PlainPassword::try_from(password.to_owned()).unwrap(),
+            email: Email::try_from("john@doe.com".to_owned()).unwrap(),
+        })
+    }
+
+    #[test]
+    fn valid_register_user() {
+        let request = register("john", "helloAndWelcome123");
+        assert_eq!(request.validate(), Ok(()));
+    }
+
+    #[test]
+    fn every_invalid_field_is_collected() {
+        let policy = ValidationPolicy {
+            max_username_len: 5,
+            min_password_len: 20,
+            ..ValidationPolicy::default()
+        };
+        let request = register("johnny", "helloAndWelcome123");
+        assert_eq!(request.validate(), Ok(()));
+
+        let errors = request.validate_with_policy(&policy).unwrap_err();
+        let expt = vec![
+            FieldError {
+                field: "username",
+                error: ValidationError::InvalidUsername,
+            },
+            FieldError {
+                field: "password",
+                error: ValidationError::InvalidPassword,
+            },
+        ];
+        assert_eq!(errors, expt);
+        let message = errors[0].to_string();
+        assert_eq!(message, "username: invalid (badly formatted) username");
+    }
+
+    #[test]
+    fn validation_errors_of_a_request() {
+        let request = register("johnny", "helloAndWelcome123");
+        assert_eq!(request.validation_errors(), Vec::new());
+
+        // Made under a more lenient policy than the default
+        let lenient = ValidationPolicy {
+            min_username_len: 2,
+            ..ValidationPolicy::default()
+        };
+        let request = AuthRequest::Authenticate(AuthPayload {
+            username: Username::try_new_with_policy("jo", &lenient).unwrap(),
+            password: PlainPassword::try_from("helloAndWelcome123".to_owned()).unwrap(),
+        });
+        assert_eq!(
+            request.validation_errors(),
+            vec![ValidationError::InvalidUsername]
+        );
+        assert_eq!(AuthRequest::WhoAmI(None).validation_errors(), Vec::new());
+    }
+
+    #[test]
+    fn request_without_fields_is_valid() {
+        let strict = ValidationPolicy {
+            min_username_len: 100,
+            ..ValidationPolicy::default()
+        };
+        for request in &[AuthRequest::Deauthenticate(None), AuthRequest::WhoAmI(None)] {
+            assert_eq!(request.validate(), Ok(()));
+            assert_eq!(request.validate_with_policy(&strict), Ok(()));
+        }
+    }
+
+    #[test]
+    fn username_serializes_with_canonical_name() {
+        let json = r#"{ "user": "john", "password": "helloAndWelcome123" }"#;
+        let payload: AuthPayload = serde_json::from_str(json).unwrap();
+        let value = serde_json::to_value(&payload).unwrap();
+        assert_eq!(value["username"], "john");
+        assert!(value.get("user").is_none());
+        assert!(value.get("raw_username").is_none());
+    }
+
+    #[test]
+    fn submit_two_factor_envelope() {
+        let json = r#"{
+            "type": "SUBMIT_TWO_FACTOR",
+            "payload": { "challenge": "pending-challenge", "code": "012345" }
+        }"#;
+        match serde_json::from_str::<AuthRequest>(json).unwrap() {
+            AuthRequest::SubmitTwoFactor(payload) => {
+                assert_eq!(payload.challenge, Token::new("pending-challenge"));
+                assert_eq!(&*payload.code, "012345");
+            }
+            _ => panic!("expected a SubmitTwoFactor request"),
+        }
+    }
+
+    #[test]
+    fn submit_two_factor_rejects_bad_codes() {
+        for code in &["12345", "12a456"] {
+            let json = format!(
+                r#"{{ "type": "SUBMIT_TWO_FACTOR", "payload": {{ "challenge": "c", "code": "{}" }} }}"#,
+                code
+            );
+            assert!(serde_json::from_str::<AuthRequest>(&json).is_err(), "code '{}'", code);
+        }
+    }
+
+    #[test]
+    fn unknown_fields_are_rejected() {
+        let json =
+            r#"{ "username": "john", "password": "helloAndWelcome123", "passwrod_confirm": "z" }"#;
+        assert!(serde_json::from_str::<AuthPayload>(json).is_err());
+
+        let json = r#"{
+            "username": "john",
+            "password": "helloAndWelcome123",
+            "email": "john@doe.com",
+            "password_confirm": "helloAndWelcome123"
+        }"#;
+        assert!(serde_json::from_str::<RegisterUserPayload>(json).is_err());
+    }
+
+    #[test]
+    fn unknown_fields_are_rejected_inside_envelopes() {
+        let json = r#"{
+            "type": "AUTHENTICATE",
+            "payload": { "username": "john", "password": "helloAndWelcome123", "remember": true }
+        }"#;
+        assert!(serde_json::from_str::<AuthRequest>(json).is_err());
+    }
+
+    #[test]
+    fn type_tags_are_matched_case_insensitively() {
+        let canonical = serde_json::json!({
+            "type": "REGISTER",
+            "payload": {
+                "username": "john",
+                "password": "helloAndWelcome123",
+                "email": "john@doe.com"
+            }
+        });
+        let tags = ["REGISTER", "register", "Register", "register_user"];
+        for tag in &tags {
+            let json = canonical.to_string().replace("REGISTER", tag);
+            let request = AuthRequest::from_json_lenient(&json).unwrap();
+            match request {
+                AuthRequest::RegisterUser(_) => {}
+                _ => panic!("'{}' is not a RegisterUser request", tag),
+            }
+            let json = serde_json::to_value(&request).unwrap();
+            assert_eq!(json, canonical, "{}", tag);
+        }
+
+        let json = r#"{ "type": "deauthenticate" }"#;
+        match AuthRequest::from_json_lenient(json).unwrap() {
+            AuthRequest::Deauthenticate(None) => {}
+            _ => panic!("expected a Deauthenticate request"),
+        }
+        // Only the lenient constructor accepts other cases
+        assert!(serde_json::from_str::<AuthRequest>(json).is_err());
+    }
+
+    #[test]
+    fn registration_from_login() {
+        let json = r#"{ "username": "john", "password": "helloAndWelcome123" }"#;
+        let login: AuthPayload = serde_json::from_str(json).unwrap();
+        let email = Email::try_from("john@doe.com".to_owned()).unwrap();
+
+        let payload = RegisterUserPayload::from_login(&login, email.clone());
+        assert_eq!(payload.username, login.username);
+        assert!(payload.password == login.password);
+        assert_eq!(payload.email, email);
+    }
+
+    #[test]
+    fn login_from_registration() {
+        let json =
+            r#"{ "username": "john", "password": "helloAndWelcome123", "email": "john@doe.com" }"#;
+        let registration: RegisterUserPayload = serde_json::from_str(json).unwrap();
+
+        let login = AuthPayload::from_register(&registration);
+        assert_eq!(login.username, registration.username);
+        assert!(login.password == registration.password);
+        assert_eq!(&*login.password, "helloAndWelcome123");
+    }
+
+    #[test]
+    fn legacy_tags_deserialize_to_the_current_variant() {
+        let payload =
+            r#"{ "username": "john", "password": "helloAndWelcome123", "email": "john@doe.com" }"#;
+        for (old, current) in LEGACY_TAGS {
+            let json = format!(r#"{{ "type": "{}", "payload": {} }}"#, old, payload);
+            let request: AuthRequest = serde_json::from_str(&json).unwrap();
+            let json = serde_json::to_value(&request).unwrap();
+            assert_eq!(json["type"], *current, "{}", old);
+        }
+    }
+
+    #[test]
+    fn legacy_fields_deserialize_to_the_current_field() {
+        for (old, current) in LEGACY_FIELDS {
+            let json = format!(
+                r#"{{ "{}": "john", "password": "helloAndWelcome123" }}"#,
+                old
+            );
+            let payload: AuthPayload = serde_json::from_str(&json).unwrap();
+            let json = serde_json::to_value(&payload).unwrap();
+            assert_eq!(json[*current], "john", "{}", old);
+        }
+    }
+
+    #[test]
+    fn payloads_are_deserialized_from_owned_sources() {
+        let value = serde_json::json!({
+            "username": "john",
+            "password": "helloAndWelcome123",
+            "email": "john@doe.com"
+        });
+        let payload: RegisterUserPayload = serde_json::from_value(value.clone()).unwrap();
+        assert_eq!(&*payload.username, "john");
+        assert_eq!(serde_json::to_value(&payload).unwrap(), value);
+
+        let bytes = br#"{ "username": "john", "password": "helloAndWelcome123" }"#.to_vec();
+        let payload: AuthPayload = serde_json::from_reader(bytes.as_slice()).unwrap();
+        drop(bytes);
+        assert_eq!(&*payload.username, "john");
+    }
+
+    #[test]
+    fn requests_outlive_their_input() {
+        let request: AuthRequest = {
+            let json = String::from(
+                r#"{ "type": "AUTHENTICATE", "payload": { "username": "john", "password": "helloAndWelcome123" } }"#,
+            );
+            serde_json::from_str(&json).unwrap()
+        };
+        match request {
+            AuthRequest::Authenticate(payload) => assert_eq!(&*payload.username, "john"),
+            _ => panic!("expected a Authenticate request"),
+        }
+    }
+
+    #[test]
+    fn who_am_i_envelope() {
+        let fixtures = [
+            r#"{ "type": "WHO_AM_I", "payload": {} }"#,
+            r#"{ "type": "WHO_AM_I" }"#,
+        ];
+        for json in &fixtures {
+            match serde_json::from_str::<AuthRequest>(json).unwrap() {
+                AuthRequest::WhoAmI(_) => {}
+                _ => panic!("expected a WhoAmI request: {}", json),
+            }
+        }
+        let json = r#"{ "type": "WHO_AM_I", "payload": { "id": 1 } }"#;
+        assert!(serde_json::from_str::<AuthRequest>(json).is_err());
+    }
+
+    #[test]
+    fn from_json_value() {
+        let value = serde_json::json!({
+            "type": "AUTHENTICATE",
+            "payload": { "username": "john", "password": "helloAndWelcome123" }
+        });
+        match AuthRequest::try_from(value.clone()).unwrap() {
+            AuthRequest::Authenticate(payload) => assert_eq!(&*payload.username, "john"),
+            request => panic!("expected a login, got {:?}", request),
+        }
+
+        let map = value.as_object().unwrap().clone();
+        let request = AuthRequest::try_from(map).unwrap();
+        assert_eq!(serde_json::to_value(&request).unwrap(), value);
+    }
+
+    #[test]
+    fn from_invalid_json_value() {
+        let value = serde_json::json!({ "type": "AUTHENTICATE", "payload": { "username": "jo" } });
+        let e = AuthRequest::try_from(value.clone()).unwrap_err();
+        assert_eq!(
+            e.request_type.as_ref().map(String::as_str),
+            Some("AUTHENTICATE")
+        );
+        assert_eq!(e.field.as_ref().map(String::as_str), Some("username"));
+        let map = value.as_object().unwrap().clone();
+        assert_eq!(AuthRequest::try_from(map).unwrap_err(), e);
+
+        let value = serde_json::json!({ "type": "LOG_IN" });
+        match AuthRequest::try_from(value).unwrap_err().kind {
+            ParseErrorKind::UnknownTag { .. } => {}
+            kind => panic!("expected a unknown tag, got {:?}", kind),
+        }
+    }
+}
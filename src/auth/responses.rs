@@ -1,18 +1,79 @@
 //! The responses a user will get from requests to the auth-service
 
+use crate::error::ErrorCode;
+use crate::valid::fields::Username;
+use crate::valid::ids::UserId;
+use crate::valid::token::Token;
+
 #[derive(Serialize, Deserialize, Debug)]
+#[cfg_attr(feature = "schemars", derive(JsonSchema))]
+#[cfg_attr(feature = "utoipa", derive(ToSchema))]
 #[serde(
     tag = "type",
     content = "payload",
     rename_all = "SCREAMING_SNAKE_CASE"
 )]
+#[cfg_attr(feature = "forward-compat", serde(remote = "Self"))]
 pub enum AuthSuccess {
     Authenticated,
     Deauthenticated,
     UserRegistered,
+    TwoFactorRequired(TwoFactorChallengePayload),
+    WhoAmI(WhoAmIResponse),
+    /// A response of a type which is not known to this version of the crate
+    ///
+    /// It can't be serialized, so a unknown response can only be logged and
+    /// ignored.
+    #[cfg(feature = "forward-compat")]
+    #[serde(skip)]
+    Unknown {
+        type_tag: String,
+        payload: serde_json::Value,
+    },
+}
+
+impl AuthSuccess {
+    /// The type tags of the responses
+    pub const TAGS: &'static [&'static str] = &[
+        "AUTHENTICATED",
+        "DEAUTHENTICATED",
+        "USER_REGISTERED",
+        "TWO_FACTOR_REQUIRED",
+        "WHO_AM_I",
+    ];
+}
+
+#[cfg(feature = "forward-compat")]
+impl_forward_compatible!(AuthSuccess);
+
+/// A pending two-factor challenge which must be answered with
+/// `AuthRequest::SubmitTwoFactor`
+#[derive(Serialize, Deserialize, PartialEq, Debug)]
+#[cfg_attr(feature = "schemars", derive(JsonSchema))]
+#[cfg_attr(feature = "utoipa", derive(ToSchema))]
+#[serde(rename_all = "snake_case")]
+pub struct TwoFactorChallengePayload {
+    // The challenge is sent to the client, so unlike the other tokens it
+    // isn't write-only
+    #[cfg_attr(feature = "utoipa", schema(value_type = String))]
+    pub challenge: Token,
+}
+
+/// The user of the current session, the answer to `AuthRequest::WhoAmI`
+#[derive(Serialize, Deserialize, PartialEq, Debug)]
+#[cfg_attr(feature = "schemars", derive(JsonSchema))]
+#[cfg_attr(feature = "utoipa", derive(ToSchema))]
+#[serde(rename_all = "snake_case")]
+pub struct WhoAmIResponse {
+    pub id: UserId,
+    pub username: Username,
+    pub role: Role,
 }
 
 #[derive(Fail, Serialize, Deserialize, PartialEq, Clone, Copy, Debug)]
+#[cfg_attr(feature = "schemars", derive(JsonSchema))]
+#[cfg_attr(feature = "utoipa", derive(ToSchema))]
+#[non_exhaustive]
 pub enum AuthError {
     #[fail(display = "invalid token")]
     InvalidToken,
@@ -24,10 +85,57 @@ pub enum AuthError {
     InvalidCredentials,
     #[fail(display = "user already exists")]
     ExistingUser,
+    #[fail(display = "invalid two-factor code")]
+    InvalidTwoFactorCode,
+    #[fail(display = "user does not have the required role")]
+    InsufficientRole,
+    /// Too many attempts were made, `retry_after` is in seconds
+    #[fail(display = "too many attempts, retry after {} seconds", retry_after)]
+    RateLimited { retry_after: u64 },
     #[fail(display = "internal server error")]
     InternalServerError,
 }
 
+impl AuthError {
+    /// The machine-stable code of the error
+    pub fn code(&self) -> ErrorCode {
+        match self {
+            AuthError::InvalidToken => ErrorCode::AuthInvalidToken,
+            AuthError::InvalidUsername => ErrorCode::AuthInvalidUsername,
+            AuthError::InvalidPassword => ErrorCode::AuthInvalidPassword,
+            AuthError::InvalidCredentials => ErrorCode::AuthInvalidCredentials,
+            AuthError::ExistingUser => ErrorCode::AuthExistingUser,
+            AuthError::InvalidTwoFactorCode => ErrorCode::AuthInvalidTwoFactorCode,
+            AuthError::InsufficientRole => ErrorCode::AuthInsufficientRole,
+            AuthError::RateLimited { .. } => ErrorCode::AuthRateLimited,
+            AuthError::InternalServerError => ErrorCode::AuthInternalServerError,
+        }
+    }
+
+    /// How many seconds to wait before retrying, if the error is temporary
+    pub fn retry_after(&self) -> Option<u64> {
+        match self {
+            AuthError::RateLimited { retry_after } => Some(*retry_after),
+            _ => None,
+        }
+    }
+
+    /// The error which has the given code, if it is a error of this kind
+    pub fn from_code(code: ErrorCode) -> Option<Self> {
+        match code {
+            ErrorCode::AuthInvalidToken => Some(AuthError::InvalidToken),
+            ErrorCode::AuthInvalidUsername => Some(AuthError::InvalidUsername),
+            ErrorCode::AuthInvalidPassword => Some(AuthError::InvalidPassword),
+            ErrorCode::AuthInvalidCredentials => Some(AuthError::InvalidCredentials),
+            ErrorCode::AuthExistingUser => Some(AuthError::ExistingUser),
+            ErrorCode::AuthInvalidTwoFactorCode => Some(AuthError::InvalidTwoFactorCode),
+            ErrorCode::AuthInsufficientRole => Some(AuthError::InsufficientRole),
+            ErrorCode::AuthInternalServerError => Some(AuthError::InternalServerError),
+            _ => None,
+        }
+    }
+}
+
 #[derive(Clone, Copy, Eq, PartialEq, Ord, PartialOrd, Debug)]
 pub enum Role {
     Admin = 30,
@@ -74,4 +182,36 @@ impl serde::Serialize for Role {
         let string: String = Role::into(*self);
         serializer.serialize_str(&string)
     }
-}
\ No newline at end of file
+}
+
+impl_schema!(Role => crate::schema::string().values(&["admin", "moderator", "user"]));
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::convert::TryFrom;
+
+    #[test]
+    fn who_am_i_round_trip() {
+        let payload = WhoAmIResponse {
+            id: UserId::from(42),
+            username: Username::try_from("john".to_owned()).unwrap(),
+            role: Role::Moderator,
+        };
+        let json = serde_json::to_value(&AuthSuccess::WhoAmI(payload)).unwrap();
+        let expt = serde_json::json!({
+            "type": "WHO_AM_I",
+            "payload": { "id": 42, "username": "john", "role": "moderator" }
+        });
+        assert_eq!(json, expt);
+
+        match serde_json::from_value::<AuthSuccess>(json).unwrap() {
+            AuthSuccess::WhoAmI(decoded) => {
+                assert_eq!(decoded.id, UserId::from(42));
+                assert_eq!(&*decoded.username, "john");
+                assert_eq!(decoded.role, Role::Moderator);
+            }
+            _ => panic!("expected a WhoAmI response"),
+        }
+    }
+}
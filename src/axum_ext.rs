@@ -0,0 +1,408 @@
+//! Extractors and responses for axum
+//!
+//! `Session` reads the token of a request from the `Authorization` header
+//! (with the `Bearer` scheme) or, if the request has no such header, from the
+//! session cookie. The cookie is `DEFAULT_SESSION_COOKIE`, unless a
+//! `SessionCookie` with another name is added as a extension of the router.
+//! `ValidatedJson` parses the json body with `parse_payload` and then checks
+//! the payload as a whole with `Validate`.
+//!
+//! | the request                   | the response                          |
+//! |-------------------------------|---------------------------------------|
+//! | no token, or a malformed one  | `401` and a `WireError`               |
+//! | a body which can't be parsed  | `400` and a `ParseError`              |
+//! | a payload which is invalid    | `422` and a list of `WireError`       |
+//!
+//! The errors of the crate are responses with the status of their
+//! `ErrorCode` (`ErrorCode::http_status`) and a `WireError` as the body.
+//!
+//! # Example usage
+//!
+//! ```
+//! # use axum::routing::post;
+//! # use axum::Router;
+//! # use datatypes::axum_ext::{Session, ValidatedJson};
+//! # use datatypes::content::requests::GetThreadsPayload;
+//! async fn threads(
+//!     Session(token): Session,
+//!     ValidatedJson(payload): ValidatedJson<GetThreadsPayload>,
+//! ) -> String {
+//!     payload.id.to_string()
+//! }
+//!
+//! let app: Router = Router::new().route("/threads", post(threads));
+//! ```
+
+use crate::admin::responses::AdminError;
+use crate::auth::responses::AuthError;
+use crate::content::responses::ContentError;
+use crate::error::{ErrorCode, ParseError, ParseErrorKind, WireError};
+use crate::payloads::parse_payload;
+use crate::valid::token::{Token, TokenParseError, DEFAULT_SESSION_COOKIE};
+use crate::valid::{Validate, ValidationError};
+use axum::async_trait;
+use axum::body::Bytes;
+use axum::extract::rejection::BytesRejection;
+use axum::extract::{FromRequest, FromRequestParts, Request};
+use axum::http::request::Parts;
+use axum::http::{header, HeaderMap, HeaderValue, StatusCode};
+use axum::response::{IntoResponse, Response};
+use axum::Json;
+use serde::de::DeserializeOwned;
+use std::str;
+
+/// The token of a session, read from the `Authorization` header or the
+/// session cookie of a request
+#[derive(PartialEq, Eq, Clone, Debug)]
+pub struct Session(pub Token);
+
+/// The name of the cookie which contains the token of a session
+///
+/// # Example usage
+///
+/// ```
+/// # use axum::routing::get;
+/// # use axum::{Extension, Router};
+/// # use datatypes::axum_ext::SessionCookie;
+/// let app: Router = Router::new()
+///     .route("/", get(|| async { "Hello" }))
+///     .layer(Extension(SessionCookie::new("sid")));
+/// ```
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+pub struct SessionCookie(&'static str);
+
+impl SessionCookie {
+    pub fn new(name: &'static str) -> Self {
+        SessionCookie(name)
+    }
+
+    /// The name of the cookie
+    pub fn name(&self) -> &'static str {
+        self.0
+    }
+}
+
+impl Default for SessionCookie {
+    fn default() -> Self {
+        SessionCookie(DEFAULT_SESSION_COOKIE)
+    }
+}
+
+/// Why the token of a session could not be read from a request
+#[derive(Fail, PartialEq, Eq, Clone, Copy, Debug)]
+pub enum SessionRejection {
+    #[fail(display = "missing session token")]
+    Missing,
+    #[fail(display = "malformed session token")]
+    Malformed(TokenParseError),
+}
+
+impl IntoResponse for SessionRejection {
+    fn into_response(self) -> Response {
+        WireError::new(ErrorCode::Unauthenticated, self.to_string()).into_response()
+    }
+}
+
+/// The value of the cookie named `name`, if the request has it
+fn cookie<'a>(headers: &'a HeaderMap, name: &str) -> Option<&'a str> {
+    headers
+        .get_all(header::COOKIE)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(';'))
+        .filter_map(|pair| {
+            let mut pair = pair.trim().splitn(2, '=');
+            match (pair.next(), pair.next()) {
+                (Some(key), Some(value)) if key == name => Some(value),
+                _ => None,
+            }
+        })
+        .next()
+}
+
+/// Read the token of a request, from its `Authorization` header or else from
+/// the cookie named `cookie_name`
+///
+/// A header which is present but not a valid bearer token is an error, even
+/// if the request also has the cookie.
+fn session_token(headers: &HeaderMap, cookie_name: &str) -> Result<Token, SessionRejection> {
+    if let Some(value) = headers.get(header::AUTHORIZATION) {
+        let value = value
+            .to_str()
+            .map_err(|_| SessionRejection::Malformed(TokenParseError::InvalidToken))?;
+        return Token::from_bearer(value).map_err(SessionRejection::Malformed);
+    }
+    match cookie(headers, cookie_name) {
+        Some(value) => Token::from_b64token(value).map_err(SessionRejection::Malformed),
+        None => Err(SessionRejection::Missing),
+    }
+}
+
+#[async_trait]
+impl<S> FromRequestParts<S> for Session
+where
+    S: Send + Sync,
+{
+    type Rejection = SessionRejection;
+
+    async fn from_request_parts(parts: &mut Parts, _: &S) -> Result<Self, Self::Rejection> {
+        let cookie = parts
+            .extensions
+            .get::<SessionCookie>()
+            .copied()
+            .unwrap_or_default();
+        session_token(&parts.headers, cookie.name()).map(Session)
+    }
+}
+
+/// A json body which has been parsed and validated
+#[derive(PartialEq, Debug)]
+pub struct ValidatedJson<T>(pub T);
+
+/// Why a `ValidatedJson` could not be extracted from a request
+#[derive(Debug)]
+pub enum ValidatedJsonRejection {
+    /// The body could not be read
+    Body(BytesRejection),
+    /// The body could not be parsed
+    Parse(ParseError),
+    /// The payload was parsed, but it is invalid, which is answered with the
+    /// `WireError` of every error
+    Invalid(Vec<ValidationError>),
+}
+
+impl IntoResponse for ValidatedJsonRejection {
+    fn into_response(self) -> Response {
+        match self {
+            ValidatedJsonRejection::Body(e) => e.into_response(),
+            ValidatedJsonRejection::Parse(e) => e.into_response(),
+            ValidatedJsonRejection::Invalid(errors) => {
+                let errors: Vec<_> = errors.into_iter().map(WireError::from).collect();
+                (StatusCode::UNPROCESSABLE_ENTITY, Json(errors)).into_response()
+            }
+        }
+    }
+}
+
+#[async_trait]
+impl<S, T> FromRequest<S> for ValidatedJson<T>
+where
+    S: Send + Sync,
+    T: DeserializeOwned + Validate,
+{
+    type Rejection = ValidatedJsonRejection;
+
+    async fn from_request(req: Request, state: &S) -> Result<Self, Self::Rejection> {
+        let body = Bytes::from_request(req, state)
+            .await
+            .map_err(ValidatedJsonRejection::Body)?;
+        let json = str::from_utf8(&body).map_err(|e| {
+            ValidatedJsonRejection::Parse(ParseError {
+                request_type: None,
+                field: None,
+                kind: ParseErrorKind::InvalidJson {
+                    message: e.to_string(),
+                },
+            })
+        })?;
+        let payload: T = parse_payload(json).map_err(ValidatedJsonRejection::Parse)?;
+
+        let errors = payload.validation_errors();
+        if errors.is_empty() {
+            Ok(ValidatedJson(payload))
+        } else {
+            Err(ValidatedJsonRejection::Invalid(errors))
+        }
+    }
+}
+
+/// The status of a response with the given http status code
+fn status(code: u16) -> StatusCode {
+    StatusCode::from_u16(code).unwrap_or(StatusCode::INTERNAL_SERVER_ERROR)
+}
+
+impl IntoResponse for WireError {
+    fn into_response(self) -> Response {
+        (status(self.code.http_status()), Json(self)).into_response()
+    }
+}
+
+impl IntoResponse for ParseError {
+    fn into_response(self) -> Response {
+        (status(ErrorCode::InvalidPayload.http_status()), Json(self)).into_response()
+    }
+}
+
+/// Implement `IntoResponse` for a error with a `ErrorCode`, where the body is
+/// the `WireError` of the error
+macro_rules! impl_into_response {
+    ($($error:ty),*) => {
+        $(
+            impl IntoResponse for $error {
+                fn into_response(self) -> Response {
+                    let status = status(self.code().http_status());
+                    (status, Json(WireError::from(self))).into_response()
+                }
+            }
+        )*
+    };
+}
+
+impl_into_response!(ValidationError, AdminError, ContentError);
+
+impl IntoResponse for AuthError {
+    /// A `RateLimited` error tells the time to wait in a `Retry-After` header
+    fn into_response(self) -> Response {
+        let retry_after = self.retry_after();
+        let status = status(self.code().http_status());
+        let mut response = (status, Json(WireError::from(self))).into_response();
+        if let Some(seconds) = retry_after {
+            let value = HeaderValue::from(seconds);
+            response.headers_mut().insert(header::RETRY_AFTER, value);
+        }
+        response
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::content::requests::GetThreadsPayload;
+    use axum::body::Body;
+    use axum::routing::post;
+    use axum::{Extension, Router};
+    use http_body_util::BodyExt;
+    use tower::ServiceExt;
+
+    async fn threads(
+        Session(token): Session,
+        ValidatedJson(payload): ValidatedJson<GetThreadsPayload>,
+    ) -> String {
+        format!("{} {}", token.as_ref(), payload.id)
+    }
+
+    /// Send a request with `body` and the `headers` to a router with a single
+    /// route, and get the status and the body of the response
+    async fn send(body: &str, headers: &[(header::HeaderName, &str)]) -> (StatusCode, String) {
+        send_to(None, body, headers).await
+    }
+
+    /// Like `send`, with the session cookie named by `cookie`
+    async fn send_to(
+        cookie: Option<SessionCookie>,
+        body: &str,
+        headers: &[(header::HeaderName, &str)],
+    ) -> (StatusCode, String) {
+        let mut router = Router::new().route("/threads", post(threads));
+        if let Some(cookie) = cookie {
+            router = router.layer(Extension(cookie));
+        }
+        let mut request = Request::builder()
+            .method("POST")
+            .uri("/threads")
+            .header(header::CONTENT_TYPE, "application/json");
+        for (name, value) in headers {
+            request = request.header(name, *value);
+        }
+        let request = request.body(Body::from(body.to_owned())).unwrap();
+
+        let response = router.oneshot(request).await.unwrap();
+        let status = response.status();
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        (status, String::from_utf8(body.to_vec()).unwrap())
+    }
+
+    async fn response_of(response: impl IntoResponse) -> (StatusCode, serde_json::Value) {
+        let response = response.into_response();
+        let status = response.status();
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        (status, serde_json::from_slice(&body).unwrap())
+    }
+
+    #[tokio::test]
+    async fn session_from_header() {
+        let auth = [(header::AUTHORIZATION, "Bearer abc")];
+        let (status, body) = send(r#"{ "id": 1 }"#, &auth).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body, "abc 1");
+    }
+
+    #[tokio::test]
+    async fn session_from_cookie() {
+        let cookie = [(header::COOKIE, "theme=dark; session=xyz")];
+        let (status, body) = send(r#"{ "id": 1 }"#, &cookie).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body, "xyz 1");
+    }
+
+    #[tokio::test]
+    async fn session_from_configured_cookie() {
+        let sid = Some(SessionCookie::new("sid"));
+        let cookie = [(header::COOKIE, "sid=xyz")];
+        let (status, body) = send_to(sid, r#"{ "id": 1 }"#, &cookie).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body, "xyz 1");
+
+        // The default cookie is not read when another one is configured
+        let cookie = [(header::COOKIE, "session=xyz")];
+        let (status, _) = send_to(sid, r#"{ "id": 1 }"#, &cookie).await;
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+    }
+
+    #[tokio::test]
+    async fn missing_session() {
+        let (status, body) = send(r#"{ "id": 1 }"#, &[]).await;
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+        let e: WireError = serde_json::from_str(&body).unwrap();
+        assert_eq!(e.code, ErrorCode::Unauthenticated);
+
+        let auth = [(header::AUTHORIZATION, "Basic abc")];
+        let (status, _) = send(r#"{ "id": 1 }"#, &auth).await;
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+    }
+
+    #[tokio::test]
+    async fn invalid_json() {
+        let auth = [(header::AUTHORIZATION, "Bearer abc")];
+        let (status, body) = send(r#"{ "id": 1"#, &auth).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        let e: ParseError = serde_json::from_str(&body).unwrap();
+        match e.kind {
+            ParseErrorKind::InvalidJson { .. } => {}
+            kind => panic!("{:?}", kind),
+        }
+
+        let (status, body) = send(r#"{ "id": "one" }"#, &auth).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        let e: ParseError = serde_json::from_str(&body).unwrap();
+        assert_eq!(e.field.as_ref().map(String::as_str), Some("id"));
+    }
+
+    #[tokio::test]
+    async fn failed_validation() {
+        let auth = [(header::AUTHORIZATION, "Bearer abc")];
+        let (status, body) = send(r#"{ "id": 1, "page": 2, "after": "YWJj" }"#, &auth).await;
+        assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+        let errors: Vec<WireError> = serde_json::from_str(&body).unwrap();
+        let codes: Vec<_> = errors.iter().map(|e| e.code).collect();
+        assert_eq!(codes, vec![ErrorCode::InvalidPagination]);
+    }
+
+    #[tokio::test]
+    async fn errors_into_response() {
+        let (status, body) = response_of(AuthError::RateLimited { retry_after: 30 }).await;
+        assert_eq!(status, StatusCode::TOO_MANY_REQUESTS);
+        assert_eq!(body["code"], "AUTH_RATE_LIMITED");
+
+        let response = AuthError::RateLimited { retry_after: 30 }.into_response();
+        assert_eq!(response.headers()[header::RETRY_AFTER], "30");
+
+        let (status, body) = response_of(ValidationError::InvalidTitle).await;
+        assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+        assert_eq!(body["code"], "INVALID_TITLE");
+
+        let wire = WireError::new(ErrorCode::Forbidden, "not allowed");
+        let (status, _) = response_of(wire).await;
+        assert_eq!(status, StatusCode::FORBIDDEN);
+    }
+}
@@ -0,0 +1,20 @@
+//! Writes the TypeScript definitions of the wire types to the `bindings`
+//! directory of the crate, or to the directory which is given as the first
+//! argument (see `datatypes::typescript`)
+
+use datatypes::typescript::bindings;
+use std::fs;
+use std::path::PathBuf;
+
+fn main() -> std::io::Result<()> {
+    let dir = std::env::args().nth(1).map_or_else(
+        || PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("bindings"),
+        PathBuf::from,
+    );
+    fs::create_dir_all(&dir)?;
+    for (file, definitions) in bindings() {
+        fs::write(dir.join(&file), definitions)?;
+        println!("{}", dir.join(&file).display());
+    }
+    Ok(())
+}
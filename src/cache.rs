@@ -0,0 +1,82 @@
+//! Caching of the sessions which tokens belong to
+//!
+//! The session of a token can be cached with a `CachedSession`, so it isn't
+//! looked up for every request.
+
+use crate::auth::responses::WhoAmIResponse;
+use crate::valid::duration::HumanDuration;
+use chrono::{DateTime, Duration, Utc};
+
+/// A session in a cache, which is fresh for `ttl` after it was cached
+///
+/// The entry is serializable, so it can be stored in a cache which is shared
+/// by several servers. `ttl` is serialized as seconds.
+#[derive(Serialize, Deserialize, PartialEq, Clone, Debug)]
+#[serde(rename_all = "snake_case")]
+pub struct CachedSession<S = WhoAmIResponse> {
+    pub session: S,
+    #[serde(with = "crate::timestamp::seconds")]
+    pub cached_at: DateTime<Utc>,
+    pub ttl: HumanDuration,
+}
+
+impl<S> CachedSession<S> {
+    pub fn new(session: S, cached_at: DateTime<Utc>, ttl: HumanDuration) -> Self {
+        CachedSession {
+            session,
+            cached_at,
+            ttl,
+        }
+    }
+
+    /// If the entry can still be used at `now`
+    ///
+    /// A entry with a `ttl` which is too long to be added to `cached_at` is
+    /// always fresh.
+    pub fn is_fresh(&self, now: DateTime<Utc>) -> bool {
+        let expires_at = Duration::from_std(*self.ttl)
+            .ok()
+            .and_then(|ttl| self.cached_at.checked_add_signed(ttl));
+        expires_at.map_or(true, |expires_at| now < expires_at)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn cached(ttl: u64) -> CachedSession<&'static str> {
+        let cached_at = Utc.timestamp(1_540_000_000, 0);
+        CachedSession::new("john", cached_at, HumanDuration::from_secs(ttl))
+    }
+
+    #[test]
+    fn fresh_cached_session() {
+        let entry = cached(60);
+        assert!(entry.is_fresh(entry.cached_at));
+        assert!(entry.is_fresh(entry.cached_at + Duration::seconds(59)));
+        assert!(cached(u64::max_value()).is_fresh(Utc.timestamp(4_000_000_000, 0)));
+    }
+
+    #[test]
+    fn expired_cached_session() {
+        let entry = cached(60);
+        assert!(!entry.is_fresh(entry.cached_at + Duration::seconds(60)));
+        assert!(!entry.is_fresh(entry.cached_at + Duration::days(1)));
+        assert!(!cached(0).is_fresh(entry.cached_at));
+    }
+
+    #[test]
+    fn cached_session_json() {
+        let json = serde_json::to_value(&cached(60)).unwrap();
+        let expt = serde_json::json!({
+            "session": "john",
+            "cached_at": "2018-10-20T01:46:40Z",
+            "ttl": 60,
+        });
+        assert_eq!(json, expt);
+        let entry: CachedSession<String> = serde_json::from_value(json).unwrap();
+        assert!(entry.is_fresh(entry.cached_at));
+    }
+}
@@ -0,0 +1,315 @@
+//! Encoding of payloads as CBOR
+//!
+//! CBOR is a binary, self-describing format, which means that every payload
+//! of the crate (including the tagged enums and the flattened `TokenPayload`)
+//! can be encoded with it.
+//!
+//! # Example usage
+//!
+//! ```
+//! # use datatypes::cbor::{from_cbor_slice, to_cbor_vec};
+//! # use datatypes::payloads::PaginationPayload;
+//! let payload = PaginationPayload::default();
+//! let bytes = to_cbor_vec(&payload).unwrap();
+//! assert_eq!(from_cbor_slice::<PaginationPayload>(&bytes).unwrap(), payload);
+//! ```
+
+use crate::error::Error;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
+/// Encode `value` as CBOR
+pub fn to_cbor_vec<T>(value: &T) -> Result<Vec<u8>, Error>
+where
+    T: Serialize,
+{
+    serde_cbor::to_vec(value).map_err(Error::from)
+}
+
+/// Decode a `T` from CBOR
+pub fn from_cbor_slice<T>(bytes: &[u8]) -> Result<T, Error>
+where
+    T: DeserializeOwned,
+{
+    serde_cbor::from_slice(bytes).map_err(Error::from)
+}
+
+impl From<serde_cbor::Error> for Error {
+    fn from(_: serde_cbor::Error) -> Self {
+        Error::MalformedPayload {
+            context: "invalid cbor",
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::admin::requests::{AdminRequest, TokenAdminRequest};
+    use crate::auth::requests::AuthRequest;
+    use crate::content::requests::ContentRequest;
+
+    /// Parse `fixture` as a `T`, and assert that it is the same after a round
+    /// trip through CBOR
+    ///
+    /// The json is compared as some of the requests don't implement
+    /// `PartialEq`.
+    fn round_trip<T>(fixture: &str)
+    where
+        T: Serialize + DeserializeOwned,
+    {
+        let request: T =
+            serde_json::from_str(fixture).unwrap_or_else(|e| panic!("{}: {}", e, fixture));
+        let bytes = to_cbor_vec(&request).unwrap();
+        let decoded: T = from_cbor_slice(&bytes).unwrap_or_else(|e| panic!("{}: {}", e, fixture));
+        assert_eq!(
+            serde_json::to_value(&decoded).unwrap(),
+            serde_json::to_value(&request).unwrap(),
+            "{}",
+            fixture
+        );
+        assert_eq!(to_cbor_vec(&decoded).unwrap(), bytes, "{}", fixture);
+    }
+
+    #[test]
+    fn auth_requests() {
+        let fixtures = vec![
+            r#"{
+                "type": "AUTHENTICATE",
+                "payload": { "username": "john", "password": "helloAndWelcome123" }
+            }"#,
+            r#"{ "type": "DEAUTHENTICATE", "payload": {} }"#,
+            r#"{ "type": "DEAUTHENTICATE" }"#,
+            r#"{
+                "type": "REGISTER",
+                "payload": {
+                    "username": "john",
+                    "password": "helloAndWelcome123",
+                    "email": "john@doe.com"
+                }
+            }"#,
+            r#"{
+                "type": "SUBMIT_TWO_FACTOR",
+                "payload": { "challenge": "pending-challenge", "code": "012345" }
+            }"#,
+            r#"{ "type": "WHO_AM_I", "payload": {} }"#,
+        ];
+        for fixture in fixtures {
+            round_trip::<AuthRequest>(fixture);
+        }
+    }
+
+    #[test]
+    fn admin_requests() {
+        let fixtures = vec![
+            r#"{ "type": "BAN_IP", "payload": { "ip": "10.0.0.1", "duration": "2h" } }"#,
+            r#"{ "type": "BAN_IP", "payload": { "ip": "2001:db8::1" } }"#,
+            r#"{ "type": "UNBAN_IP", "payload": { "ip": "10.0.0.1" } }"#,
+            r#"{ "type": "BAN_USER", "payload": { "id": 3, "duration": 3600 } }"#,
+            r#"{ "type": "UNBAN_USER", "payload": { "id": 3 } }"#,
+            r#"{ "type": "SET_USER_ROLE", "payload": { "id": 3, "role": "moderator" } }"#,
+            r#"{
+                "type": "LIST_BANS",
+                "payload": {
+                    "pagination": { "page": 2, "per_page": 10 },
+                    "filter": { "type": "IP_FAMILY", "payload": "V6" }
+                }
+            }"#,
+            r#"{ "type": "LIST_REPORTS", "payload": { "page": 1, "per_page": 20 } }"#,
+        ];
+        for fixture in fixtures {
+            round_trip::<AdminRequest>(fixture);
+        }
+    }
+
+    #[test]
+    fn admin_requests_with_token() {
+        // The token is flattened into the request
+        let fixtures = vec![
+            r#"{ "token": "admin-token", "type": "BAN_IP", "payload": { "ip": "10.0.0.1" } }"#,
+            r#"{ "token": "admin-token", "type": "UNBAN_USER", "payload": { "id": 3 } }"#,
+        ];
+        for fixture in fixtures {
+            round_trip::<TokenAdminRequest>(fixture);
+        }
+    }
+
+    #[test]
+    fn content_requests() {
+        let key = "123e4567-e89b-12d3-a456-426655440000";
+        let fixtures = vec![
+            r#"{ "type": "ADD_USER", "payload": { "id": 1, "username": "john" } }"#.to_owned(),
+            r#"{
+                "type": "EDIT_USER",
+                "payload": { "id": 1, "description": "About me", "avatar": "cat.png" }
+            }"#
+            .to_owned(),
+            r#"{
+                "type": "UPDATE_PROFILE",
+                "payload": { "id": 1, "display_name": "John", "bio": "About me" }
+            }"#
+            .to_owned(),
+            format!(
+                r#"{{
+                    "type": "ADD_CATEGORY",
+                    "payload": {{
+                        "title": "A category",
+                        "description": "About something",
+                        "format": "MARKDOWN",
+                        "idempotency_key": "{}"
+                    }}
+                }}"#,
+                key
+            ),
+            r#"{
+                "type": "EDIT_CATEGORY",
+                "payload": { "id": 1, "title": "A category", "format": "PLAIN_TEXT" }
+            }"#
+            .to_owned(),
+            r#"{ "type": "HIDE_CATEGORY", "payload": { "id": 1, "hide": true } }"#.to_owned(),
+            format!(
+                r#"{{
+                    "type": "ADD_THREAD",
+                    "payload": {{
+                        "category_id": 1,
+                        "user_id": 2,
+                        "title": "A thread",
+                        "description": "About something",
+                        "idempotency_key": "{}"
+                    }}
+                }}"#,
+                key
+            ),
+            r#"{
+                "type": "EDIT_THREAD",
+                "payload": { "id": 1, "user_id": 2, "title": "A thread" }
+            }"#
+            .to_owned(),
+            r#"{ "type": "HIDE_THREAD", "payload": { "id": 1, "user_id": 2, "hide": true } }"#
+                .to_owned(),
+            r#"{ "type": "SET_THREAD_PINNED", "payload": { "thread_id": 1, "pinned": true } }"#
+                .to_owned(),
+            format!(
+                r#"{{
+                    "type": "ADD_COMMENT",
+                    "payload": {{
+                        "thread_id": 1,
+                        "user_id": 2,
+                        "parent_id": 3,
+                        "content": "A comment",
+                        "format": "MARKDOWN",
+                        "reply_to": {{ "comment_id": 3, "excerpt": "Quoted" }},
+                        "idempotency_key": "{}"
+                    }}
+                }}"#,
+                key
+            ),
+            r#"{
+                "type": "EDIT_COMMENT",
+                "payload": { "id": 1, "user_id": 2, "content": "A comment" }
+            }"#
+            .to_owned(),
+            r#"{ "type": "HIDE_COMMENT", "payload": { "id": 1, "user_id": 2, "hide": true } }"#
+                .to_owned(),
+            r#"{ "type": "ATTACH_TO_COMMENT", "payload": { "comment_id": 1, "attachment_id": 2 } }"#
+                .to_owned(),
+            r#"{
+                "type": "REACT",
+                "payload": { "target": { "type": "THREAD", "id": 1 }, "kind": "LIKE" }
+            }"#
+            .to_owned(),
+            r#"{
+                "type": "REMOVE_REACTION",
+                "payload": { "target": { "type": "COMMENT", "id": 17 }, "kind": "HEART" }
+            }"#
+            .to_owned(),
+            r#"{
+                "type": "REPORT",
+                "payload": {
+                    "target": { "type": "COMMENT", "id": 4 },
+                    "reason": "OTHER",
+                    "details": "Something"
+                }
+            }"#
+            .to_owned(),
+            r#"{
+                "type": "BULK_DELETE",
+                "payload": { "targets": [{ "type": "THREAD", "id": 1 }, { "type": "COMMENT", "id": 2 }] }
+            }"#
+            .to_owned(),
+            r#"{ "type": "SUBSCRIBE", "payload": { "thread_id": 1 } }"#.to_owned(),
+            r#"{ "type": "UNSUBSCRIBE", "payload": { "thread_id": 1 } }"#.to_owned(),
+            r#"{ "type": "LIST_SUBSCRIPTIONS", "payload": { "page": 2, "per_page": 10 } }"#
+                .to_owned(),
+            r#"{ "type": "BOOKMARK", "payload": { "thread_id": 1 } }"#.to_owned(),
+            r#"{ "type": "UNBOOKMARK", "payload": { "thread_id": 1 } }"#.to_owned(),
+            r#"{ "type": "LIST_BOOKMARKS", "payload": { "page": 1, "per_page": 20 } }"#.to_owned(),
+            r#"{
+                "type": "SAVE_DRAFT",
+                "payload": {
+                    "draft_id": 1,
+                    "target": { "type": "REPLY_TO", "payload": { "thread_id": 1, "parent_id": null } },
+                    "title": null,
+                    "content": "A comment",
+                    "updated_at": 1540000000
+                }
+            }"#
+            .to_owned(),
+            r#"{ "type": "LIST_DRAFTS", "payload": {} }"#.to_owned(),
+            r#"{ "type": "DELETE_DRAFT", "payload": { "draft_id": 1 } }"#.to_owned(),
+            r#"{
+                "type": "GET_EDIT_HISTORY",
+                "payload": { "target": { "type": "COMMENT", "id": 1 } }
+            }"#
+            .to_owned(),
+            r#"{
+                "type": "CREATE_POLL",
+                "payload": {
+                    "thread_id": 1,
+                    "question": "Which option?",
+                    "options": ["First option", "Second option"],
+                    "multiple_choice": true,
+                    "closes_at": 1540000000
+                }
+            }"#
+            .to_owned(),
+            r#"{ "type": "VOTE", "payload": { "thread_id": 1, "option_ids": [1, 2] } }"#.to_owned(),
+            r#"{
+                "type": "FETCH_THREAD",
+                "payload": {
+                    "thread_id": 1,
+                    "include_hidden": false,
+                    "comments": { "page": 2, "per_page": 10 }
+                }
+            }"#
+            .to_owned(),
+        ];
+        for fixture in &fixtures {
+            round_trip::<ContentRequest>(fixture);
+        }
+    }
+
+    #[test]
+    fn requests_are_equal_after_round_trip() {
+        let json = r#"{ "type": "BAN_IP", "payload": { "ip": "10.0.0.1", "duration": "30m" } }"#;
+        let request: AdminRequest = serde_json::from_str(json).unwrap();
+        let bytes = to_cbor_vec(&request).unwrap();
+        assert_eq!(from_cbor_slice::<AdminRequest>(&bytes).unwrap(), request);
+
+        let json = r#"{ "type": "VOTE", "payload": { "thread_id": 1, "option_ids": [1] } }"#;
+        let request: ContentRequest = serde_json::from_str(json).unwrap();
+        let bytes = to_cbor_vec(&request).unwrap();
+        assert_eq!(from_cbor_slice::<ContentRequest>(&bytes).unwrap(), request);
+    }
+
+    #[test]
+    fn invalid_cbor() {
+        let e = from_cbor_slice::<AdminRequest>(&[0xff, 0x00]).unwrap_err();
+        assert_eq!(
+            e,
+            Error::MalformedPayload {
+                context: "invalid cbor"
+            }
+        );
+    }
+}
@@ -0,0 +1,637 @@
+//! Decoding and encoding of payloads in the format given by a `Content-Type`
+//!
+//! JSON is always supported, MessagePack needs the `msgpack` feature and CBOR
+//! the `cbor` feature. A content type which is not known (or whose format is
+//! not enabled) has no `WireFormat`, it is never guessed.
+//!
+//! `to_canonical_json` gives a deterministic JSON encoding, which is used
+//! where the bytes must be the same for equal values (like for signatures).
+//!
+//! # Example usage
+//!
+//! ```
+//! # use datatypes::codec::{decode, encode, WireFormat};
+//! # use datatypes::payloads::PaginationPayload;
+//! let format = WireFormat::from_content_type("application/json; charset=utf-8").unwrap();
+//! let payload = PaginationPayload::default();
+//! let bytes = encode(format, &payload).unwrap();
+//! assert_eq!(decode::<PaginationPayload>(format, &bytes).unwrap(), payload);
+//!
+//! assert_eq!(WireFormat::from_content_type("text/html"), None);
+//! ```
+
+use crate::error::Error;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
+/// A format which payloads can be sent in
+#[derive(PartialEq, Eq, Clone, Copy, Debug, Hash)]
+pub enum WireFormat {
+    Json,
+    #[cfg(feature = "msgpack")]
+    MsgPack,
+    #[cfg(feature = "cbor")]
+    Cbor,
+}
+
+impl WireFormat {
+    /// The format of a `Content-Type` header
+    ///
+    /// Parameters (like `; charset=utf-8`) are ignored, and the media type is
+    /// case-insensitive. Returns `None` if the media type is not known.
+    pub fn from_content_type(content_type: &str) -> Option<WireFormat> {
+        let media_type = content_type.split(';').next().unwrap_or("").trim();
+        match media_type.to_ascii_lowercase().as_str() {
+            "application/json" => Some(WireFormat::Json),
+            #[cfg(feature = "msgpack")]
+            "application/msgpack" | "application/x-msgpack" => Some(WireFormat::MsgPack),
+            #[cfg(feature = "cbor")]
+            "application/cbor" => Some(WireFormat::Cbor),
+            _ => None,
+        }
+    }
+
+    /// The `Content-Type` of payloads which are encoded in this format
+    pub fn content_type(self) -> &'static str {
+        match self {
+            WireFormat::Json => "application/json",
+            #[cfg(feature = "msgpack")]
+            WireFormat::MsgPack => "application/msgpack",
+            #[cfg(feature = "cbor")]
+            WireFormat::Cbor => "application/cbor",
+        }
+    }
+}
+
+/// Decode a `T` from `bytes` in the given format
+pub fn decode<T>(format: WireFormat, bytes: &[u8]) -> Result<T, Error>
+where
+    T: DeserializeOwned,
+{
+    match format {
+        WireFormat::Json => serde_json::from_slice(bytes).map_err(json_error),
+        #[cfg(feature = "msgpack")]
+        WireFormat::MsgPack => {
+            rmp_serde::from_read_ref(bytes).map_err(|_| Error::MalformedPayload {
+                context: "invalid msgpack",
+            })
+        }
+        #[cfg(feature = "cbor")]
+        WireFormat::Cbor => serde_cbor::from_slice(bytes).map_err(|_| Error::MalformedPayload {
+            context: "invalid cbor",
+        }),
+    }
+}
+
+/// Encode `value` in the given format
+///
+/// Structs are encoded as maps in every format, so that the field names are
+/// a part of the payload (like they are in JSON).
+pub fn encode<T>(format: WireFormat, value: &T) -> Result<Vec<u8>, Error>
+where
+    T: Serialize,
+{
+    match format {
+        WireFormat::Json => serde_json::to_vec(value).map_err(|_| Error::MalformedPayload {
+            context: "could not encode json",
+        }),
+        #[cfg(feature = "msgpack")]
+        WireFormat::MsgPack => {
+            rmp_serde::to_vec_named(value).map_err(|_| Error::MalformedPayload {
+                context: "could not encode msgpack",
+            })
+        }
+        #[cfg(feature = "cbor")]
+        WireFormat::Cbor => serde_cbor::to_vec(value).map_err(|_| Error::MalformedPayload {
+            context: "could not encode cbor",
+        }),
+    }
+}
+
+/// Encode `value` as canonical JSON, which is the same bytes for every equal
+/// value
+///
+/// The canonical form is used for signatures and cache keys, where the order
+/// of the fields of a struct (or of the entries of a `HashMap`) must not
+/// matter. It is normal JSON, with
+///
+/// - the keys of every object sorted by their UTF-8 bytes
+/// - no whitespace between the tokens
+/// - integers written without a fraction or exponent, and floats in the
+///   shortest form which parses back to the same float
+///
+/// Non-finite floats (NaN and the infinities) can't be represented in JSON,
+/// and are rejected instead of written as `null`.
+///
+/// # Example usage
+///
+/// ```
+/// # use datatypes::codec::to_canonical_json;
+/// let value = serde_json::json!({ "b": [1, 2.5], "a": { "d": null, "c": "x" } });
+/// let bytes = to_canonical_json(&value).unwrap();
+/// assert_eq!(bytes, br#"{"a":{"c":"x","d":null},"b":[1,2.5]}"#.to_vec());
+/// ```
+pub fn to_canonical_json<T>(value: &T) -> Result<Vec<u8>, Error>
+where
+    T: Serialize,
+{
+    let encode_error = |_| Error::MalformedPayload {
+        context: "could not encode canonical json",
+    };
+    let json = serde_json::to_value(value).map_err(encode_error)?;
+    // The value could be serialized, so a error here is a non-finite float
+    if value.serialize(FiniteFloats).is_err() {
+        return Err(Error::MalformedPayload {
+            context: "non-finite float in canonical json",
+        });
+    }
+    let mut bytes = Vec::new();
+    write_canonical(&json, &mut bytes).map_err(encode_error)?;
+    Ok(bytes)
+}
+
+fn write_canonical(value: &serde_json::Value, out: &mut Vec<u8>) -> serde_json::Result<()> {
+    use serde_json::Value;
+
+    match value {
+        Value::Object(map) => {
+            let mut entries: Vec<_> = map.iter().collect();
+            entries.sort_by(|(a, _), (b, _)| a.cmp(b));
+            out.push(b'{');
+            for (i, (key, value)) in entries.into_iter().enumerate() {
+                if i > 0 {
+                    out.push(b',');
+                }
+                serde_json::to_writer(&mut *out, key)?;
+                out.push(b':');
+                write_canonical(value, out)?;
+            }
+            out.push(b'}');
+        }
+        Value::Array(values) => {
+            out.push(b'[');
+            for (i, value) in values.iter().enumerate() {
+                if i > 0 {
+                    out.push(b',');
+                }
+                write_canonical(value, out)?;
+            }
+            out.push(b']');
+        }
+        value => serde_json::to_writer(&mut *out, value)?,
+    }
+    Ok(())
+}
+
+/// A serializer which only checks that every float is finite
+///
+/// `serde_json` writes non-finite floats as `null`, so they must be found
+/// before the value is serialized.
+#[derive(Clone, Copy)]
+struct FiniteFloats;
+
+macro_rules! accept {
+    ($($method:ident($ty:ty),)*) => {
+        $(
+            fn $method(self, _: $ty) -> Result<(), Self::Error> {
+                Ok(())
+            }
+        )*
+    };
+}
+
+impl serde::Serializer for FiniteFloats {
+    type Ok = ();
+    type Error = serde_json::Error;
+    type SerializeSeq = Self;
+    type SerializeTuple = Self;
+    type SerializeTupleStruct = Self;
+    type SerializeTupleVariant = Self;
+    type SerializeMap = Self;
+    type SerializeStruct = Self;
+    type SerializeStructVariant = Self;
+
+    accept! {
+        serialize_bool(bool),
+        serialize_i8(i8),
+        serialize_i16(i16),
+        serialize_i32(i32),
+        serialize_i64(i64),
+        serialize_u8(u8),
+        serialize_u16(u16),
+        serialize_u32(u32),
+        serialize_u64(u64),
+        serialize_char(char),
+        serialize_str(&str),
+        serialize_bytes(&[u8]),
+        serialize_unit_struct(&'static str),
+    }
+
+    fn serialize_f32(self, v: f32) -> Result<(), Self::Error> {
+        self.serialize_f64(f64::from(v))
+    }
+
+    fn serialize_f64(self, v: f64) -> Result<(), Self::Error> {
+        if v.is_finite() {
+            Ok(())
+        } else {
+            Err(serde::ser::Error::custom("non-finite float"))
+        }
+    }
+
+    fn serialize_none(self) -> Result<(), Self::Error> {
+        Ok(())
+    }
+
+    fn serialize_some<T: ?Sized + Serialize>(self, value: &T) -> Result<(), Self::Error> {
+        value.serialize(self)
+    }
+
+    fn serialize_unit(self) -> Result<(), Self::Error> {
+        Ok(())
+    }
+
+    fn serialize_unit_variant(
+        self,
+        _: &'static str,
+        _: u32,
+        _: &'static str,
+    ) -> Result<(), Self::Error> {
+        Ok(())
+    }
+
+    fn serialize_newtype_struct<T: ?Sized + Serialize>(
+        self,
+        _: &'static str,
+        value: &T,
+    ) -> Result<(), Self::Error> {
+        value.serialize(self)
+    }
+
+    fn serialize_newtype_variant<T: ?Sized + Serialize>(
+        self,
+        _: &'static str,
+        _: u32,
+        _: &'static str,
+        value: &T,
+    ) -> Result<(), Self::Error> {
+        value.serialize(self)
+    }
+
+    fn serialize_seq(self, _: Option<usize>) -> Result<Self, Self::Error> {
+        Ok(self)
+    }
+
+    fn serialize_tuple(self, _: usize) -> Result<Self, Self::Error> {
+        Ok(self)
+    }
+
+    fn serialize_tuple_struct(self, _: &'static str, _: usize) -> Result<Self, Self::Error> {
+        Ok(self)
+    }
+
+    fn serialize_tuple_variant(
+        self,
+        _: &'static str,
+        _: u32,
+        _: &'static str,
+        _: usize,
+    ) -> Result<Self, Self::Error> {
+        Ok(self)
+    }
+
+    fn serialize_map(self, _: Option<usize>) -> Result<Self, Self::Error> {
+        Ok(self)
+    }
+
+    fn serialize_struct(self, _: &'static str, _: usize) -> Result<Self, Self::Error> {
+        Ok(self)
+    }
+
+    fn serialize_struct_variant(
+        self,
+        _: &'static str,
+        _: u32,
+        _: &'static str,
+        _: usize,
+    ) -> Result<Self, Self::Error> {
+        Ok(self)
+    }
+}
+
+/// The elements of sequences and tuples
+macro_rules! check_elements {
+    ($($trait:ident::$method:ident,)*) => {
+        $(
+            impl serde::ser::$trait for FiniteFloats {
+                type Ok = ();
+                type Error = serde_json::Error;
+
+                fn $method<T: ?Sized + Serialize>(&mut self, value: &T) -> Result<(), Self::Error> {
+                    value.serialize(*self)
+                }
+
+                fn end(self) -> Result<(), Self::Error> {
+                    Ok(())
+                }
+            }
+        )*
+    };
+}
+
+check_elements! {
+    SerializeSeq::serialize_element,
+    SerializeTuple::serialize_element,
+    SerializeTupleStruct::serialize_field,
+    SerializeTupleVariant::serialize_field,
+}
+
+/// The fields of structs
+macro_rules! check_fields {
+    ($($trait:ident,)*) => {
+        $(
+            impl serde::ser::$trait for FiniteFloats {
+                type Ok = ();
+                type Error = serde_json::Error;
+
+                fn serialize_field<T: ?Sized + Serialize>(
+                    &mut self,
+                    _: &'static str,
+                    value: &T,
+                ) -> Result<(), Self::Error> {
+                    value.serialize(*self)
+                }
+
+                fn end(self) -> Result<(), Self::Error> {
+                    Ok(())
+                }
+            }
+        )*
+    };
+}
+
+check_fields! {
+    SerializeStruct,
+    SerializeStructVariant,
+}
+
+impl serde::ser::SerializeMap for FiniteFloats {
+    type Ok = ();
+    type Error = serde_json::Error;
+
+    fn serialize_key<T: ?Sized + Serialize>(&mut self, key: &T) -> Result<(), Self::Error> {
+        key.serialize(*self)
+    }
+
+    fn serialize_value<T: ?Sized + Serialize>(&mut self, value: &T) -> Result<(), Self::Error> {
+        value.serialize(*self)
+    }
+
+    fn end(self) -> Result<(), Self::Error> {
+        Ok(())
+    }
+}
+
+/// Like `From<serde_json::Error>`, but the context always mentions json
+fn json_error(e: serde_json::Error) -> Error {
+    use serde_json::error::Category;
+    let context = match e.classify() {
+        Category::Io => "json could not be read",
+        Category::Syntax => "invalid json",
+        Category::Data => "unexpected data in json",
+        Category::Eof => "unexpected end of json",
+    };
+    Error::MalformedPayload { context }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::auth::requests::AuthRequest;
+
+    const AUTHENTICATE: &str = r#"{
+        "type": "AUTHENTICATE",
+        "payload": { "username": "john", "password": "helloAndWelcome123" }
+    }"#;
+
+    /// The formats which are enabled
+    fn formats() -> Vec<WireFormat> {
+        #[allow(unused_mut)]
+        let mut formats = vec![WireFormat::Json];
+        #[cfg(feature = "msgpack")]
+        formats.push(WireFormat::MsgPack);
+        #[cfg(feature = "cbor")]
+        formats.push(WireFormat::Cbor);
+        formats
+    }
+
+    fn context(e: Error) -> &'static str {
+        match e {
+            Error::MalformedPayload { context } => context,
+            e => panic!("expected a malformed payload, got {:?}", e),
+        }
+    }
+
+    #[test]
+    fn content_types() {
+        let json = Some(WireFormat::Json);
+        assert_eq!(WireFormat::from_content_type("application/json"), json);
+        assert_eq!(WireFormat::from_content_type("Application/JSON"), json);
+        let content_type = "application/json; charset=utf-8";
+        assert_eq!(WireFormat::from_content_type(content_type), json);
+        let content_type = " application/json ;charset=utf-8";
+        assert_eq!(WireFormat::from_content_type(content_type), json);
+    }
+
+    #[test]
+    fn unknown_content_types() {
+        let content_types = [
+            "",
+            ";",
+            "text/plain",
+            "text/html; charset=utf-8",
+            "application/xml",
+            "application/jsonx",
+            "application/octet-stream",
+            "multipart/form-data; boundary=json",
+        ];
+        for content_type in &content_types {
+            assert_eq!(
+                WireFormat::from_content_type(content_type),
+                None,
+                "{}",
+                content_type
+            );
+        }
+    }
+
+    #[test]
+    fn content_type_round_trip() {
+        for format in formats() {
+            let content_type = format.content_type();
+            assert_eq!(WireFormat::from_content_type(content_type), Some(format));
+        }
+    }
+
+    #[cfg(feature = "msgpack")]
+    #[test]
+    fn msgpack_content_types() {
+        let msgpack = Some(WireFormat::MsgPack);
+        assert_eq!(
+            WireFormat::from_content_type("application/msgpack"),
+            msgpack
+        );
+        assert_eq!(
+            WireFormat::from_content_type("application/x-msgpack"),
+            msgpack
+        );
+    }
+
+    #[cfg(feature = "cbor")]
+    #[test]
+    fn cbor_content_types() {
+        let cbor = Some(WireFormat::Cbor);
+        assert_eq!(WireFormat::from_content_type("application/cbor"), cbor);
+    }
+
+    #[test]
+    fn same_request_in_every_format() {
+        let request: AuthRequest = serde_json::from_str(AUTHENTICATE).unwrap();
+        let expt = serde_json::to_value(&request).unwrap();
+
+        #[allow(unused_mut)]
+        let mut encodings = vec![(WireFormat::Json, serde_json::to_vec(&request).unwrap())];
+        #[cfg(feature = "msgpack")]
+        encodings.push((
+            WireFormat::MsgPack,
+            rmp_serde::to_vec_named(&request).unwrap(),
+        ));
+        #[cfg(feature = "cbor")]
+        encodings.push((WireFormat::Cbor, serde_cbor::to_vec(&request).unwrap()));
+
+        for (format, bytes) in encodings {
+            let decoded: AuthRequest = decode(format, &bytes).unwrap();
+            assert_eq!(
+                serde_json::to_value(&decoded).unwrap(),
+                expt,
+                "{:?}",
+                format
+            );
+        }
+    }
+
+    #[test]
+    fn encode_and_decode() {
+        let request: AuthRequest = serde_json::from_str(AUTHENTICATE).unwrap();
+        let expt = serde_json::to_value(&request).unwrap();
+        for format in formats() {
+            let bytes = encode(format, &request).unwrap();
+            let decoded: AuthRequest = decode(format, &bytes).unwrap();
+            assert_eq!(
+                serde_json::to_value(&decoded).unwrap(),
+                expt,
+                "{:?}",
+                format
+            );
+        }
+    }
+
+    #[test]
+    fn errors_name_the_format() {
+        for format in formats() {
+            let name = match format {
+                WireFormat::Json => "json",
+                #[cfg(feature = "msgpack")]
+                WireFormat::MsgPack => "msgpack",
+                #[cfg(feature = "cbor")]
+                WireFormat::Cbor => "cbor",
+            };
+            let e = decode::<AuthRequest>(format, &[0xc1, 0xff]).unwrap_err();
+            assert!(context(e).contains(name), "{:?}", format);
+        }
+        let e = decode::<AuthRequest>(WireFormat::Json, br#"{ "type": "#).unwrap_err();
+        assert_eq!(context(e), "unexpected end of json");
+        let e = decode::<AuthRequest>(WireFormat::Json, br#"{ "type": 1 }"#).unwrap_err();
+        assert_eq!(context(e), "unexpected data in json");
+    }
+
+    #[test]
+    fn canonical_json_sorts_keys() {
+        #[derive(Serialize)]
+        struct Unordered {
+            b: u32,
+            a: Vec<f64>,
+            c: Option<u32>,
+        }
+        let value = Unordered {
+            b: 1,
+            a: vec![0.5, -2.0],
+            c: None,
+        };
+        let bytes = to_canonical_json(&value).unwrap();
+        assert_eq!(bytes, br#"{"a":[0.5,-2.0],"b":1,"c":null}"#.to_vec());
+    }
+
+    #[test]
+    fn canonical_json_of_differently_ordered_maps() {
+        use std::collections::{BTreeMap, HashMap};
+
+        let mut forwards = HashMap::new();
+        for n in 0..32 {
+            forwards.insert(format!("key-{}", n), vec![n]);
+        }
+        let mut backwards = HashMap::with_capacity(128);
+        for n in (0..32).rev() {
+            backwards.insert(format!("key-{}", n), vec![n]);
+        }
+        let sorted: BTreeMap<_, _> = forwards.clone().into_iter().collect();
+
+        let bytes = to_canonical_json(&forwards).unwrap();
+        assert_eq!(to_canonical_json(&backwards).unwrap(), bytes);
+        assert_eq!(to_canonical_json(&sorted).unwrap(), bytes);
+
+        let value = serde_json::json!({ "b": { "y": 1, "x": [true] }, "a": "text" });
+        let fields = serde_json::json!({ "a": "text", "b": { "x": [true], "y": 1 } });
+        let bytes = to_canonical_json(&value).unwrap();
+        assert_eq!(to_canonical_json(&fields).unwrap(), bytes);
+        assert_eq!(bytes, br#"{"a":"text","b":{"x":[true],"y":1}}"#.to_vec());
+    }
+
+    #[test]
+    fn canonical_json_parses_back() {
+        let request: AuthRequest = serde_json::from_str(AUTHENTICATE).unwrap();
+        let bytes = to_canonical_json(&request).unwrap();
+        assert!(!bytes.contains(&b' ') && !bytes.contains(&b'\n'));
+
+        let decoded: AuthRequest = serde_json::from_slice(&bytes).unwrap();
+        assert_eq!(
+            serde_json::to_value(&decoded).unwrap(),
+            serde_json::to_value(&request).unwrap()
+        );
+        assert_eq!(to_canonical_json(&decoded).unwrap(), bytes);
+    }
+
+    #[test]
+    fn canonical_json_rejects_non_finite_floats() {
+        use std::f64;
+
+        for float in &[f64::NAN, f64::INFINITY, f64::NEG_INFINITY] {
+            let e = to_canonical_json(&vec![1.0, *float]).unwrap_err();
+            assert_eq!(context(e), "non-finite float in canonical json");
+        }
+        let mut map = std::collections::BTreeMap::new();
+        map.insert("float", Some(std::f32::NAN));
+        assert!(to_canonical_json(&map).is_err());
+        assert!(to_canonical_json(&1.5f32).is_ok());
+    }
+
+    #[test]
+    fn canonical_json_of_unserializable_values() {
+        use std::collections::BTreeMap;
+
+        let mut map = BTreeMap::new();
+        map.insert(vec![1], 1);
+        let e = to_canonical_json(&map).unwrap_err();
+        assert_eq!(context(e), "could not encode canonical json");
+    }
+}
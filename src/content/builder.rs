@@ -0,0 +1,338 @@
+//! Builders which makes it simpler to construct content requests
+//!
+//! The fields are validated when the request is built, which means that a
+//! builder can be given plain strings.
+//!
+//! # Example usage
+//!
+//! ```
+//! # use datatypes::content::requests::ContentRequest;
+//! let request = ContentRequest::create_thread()
+//!     .category(1)
+//!     .title("A new thread")
+//!     .body("With some content")
+//!     .build()
+//!     .unwrap();
+//! ```
+
+use crate::content::requests::*;
+use crate::payloads::BoundedVec;
+use crate::valid::attachment::AttachmentRef;
+use crate::valid::fields::*;
+use crate::valid::ids::*;
+use crate::valid::ValidationError;
+use std::convert::TryFrom;
+
+/// The errors which can occur when a request is built
+#[derive(Fail, PartialEq, Debug)]
+pub enum BuildError {
+    #[fail(display = "missing required field '{}'", _0)]
+    MissingField(&'static str),
+    #[fail(display = "invalid field")]
+    Invalid(#[cause] ValidationError),
+}
+
+impl From<ValidationError> for BuildError {
+    fn from(e: ValidationError) -> Self {
+        BuildError::Invalid(e)
+    }
+}
+
+impl ContentRequest {
+    /// Start building a `ContentRequest::AddThread`
+    pub fn create_thread() -> ThreadBuilder {
+        ThreadBuilder::default()
+    }
+
+    /// Start building a `ContentRequest::AddComment`
+    pub fn create_comment() -> CommentBuilder {
+        CommentBuilder::default()
+    }
+
+    /// Start building a `ContentRequest::EditComment`
+    pub fn edit_comment() -> EditCommentBuilder {
+        EditCommentBuilder::default()
+    }
+}
+
+#[derive(Default, Debug)]
+pub struct ThreadBuilder {
+    category_id: Option<CategoryId>,
+    user_id: Option<UserId>,
+    title: Option<String>,
+    body: Option<String>,
+    format: ContentFormat,
+    idempotency_key: Option<String>,
+}
+
+impl ThreadBuilder {
+    pub fn category(mut self, id: impl Into<CategoryId>) -> Self {
+        self.category_id = Some(id.into());
+        self
+    }
+
+    pub fn user(mut self, id: impl Into<UserId>) -> Self {
+        self.user_id = Some(id.into());
+        self
+    }
+
+    pub fn title(mut self, title: impl Into<String>) -> Self {
+        self.title = Some(title.into());
+        self
+    }
+
+    pub fn body(mut self, body: impl Into<String>) -> Self {
+        self.body = Some(body.into());
+        self
+    }
+
+    pub fn format(mut self, format: ContentFormat) -> Self {
+        self.format = format;
+        self
+    }
+
+    pub fn idempotency_key(mut self, key: impl Into<String>) -> Self {
+        self.idempotency_key = Some(key.into());
+        self
+    }
+
+    pub fn build(self) -> Result<ContentRequest, BuildError> {
+        let category_id = self.category_id.ok_or(BuildError::MissingField("category"))?;
+        let title = self.title.ok_or(BuildError::MissingField("title"))?;
+        let body = self.body.ok_or(BuildError::MissingField("body"))?;
+
+        Ok(ContentRequest::AddThread(AddThreadPayload {
+            category_id,
+            user_id: self.user_id,
+            title: Title::try_from(title)?,
+            description: Description::try_from(body)?,
+            format: self.format,
+            idempotency_key: self.idempotency_key.map(IdempotencyKey::try_from).transpose()?,
+        }))
+    }
+}
+
+#[derive(Default, Debug)]
+pub struct CommentBuilder {
+    thread_id: Option<ThreadId>,
+    user_id: Option<UserId>,
+    parent_id: Option<CommentId>,
+    content: Option<String>,
+    format: ContentFormat,
+    reply_to: Option<ReplyRef>,
+    idempotency_key: Option<String>,
+    attachments: Vec<String>,
+}
+
+impl CommentBuilder {
+    pub fn thread(mut self, id: impl Into<ThreadId>) -> Self {
+        self.thread_id = Some(id.into());
+        self
+    }
+
+    pub fn user(mut self, id: impl Into<UserId>) -> Self {
+        self.user_id = Some(id.into());
+        self
+    }
+
+    pub fn parent(mut self, id: impl Into<CommentId>) -> Self {
+        self.parent_id = Some(id.into());
+        self
+    }
+
+    pub fn content(mut self, content: impl Into<String>) -> Self {
+        self.content = Some(content.into());
+        self
+    }
+
+    pub fn format(mut self, format: ContentFormat) -> Self {
+        self.format = format;
+        self
+    }
+
+    pub fn reply_to(mut self, reply_to: ReplyRef) -> Self {
+        self.reply_to = Some(reply_to);
+        self
+    }
+
+    pub fn idempotency_key(mut self, key: impl Into<String>) -> Self {
+        self.idempotency_key = Some(key.into());
+        self
+    }
+
+    pub fn attachment(mut self, reference: impl Into<String>) -> Self {
+        self.attachments.push(reference.into());
+        self
+    }
+
+    pub fn build(self) -> Result<ContentRequest, BuildError> {
+        let thread_id = self.thread_id.ok_or(BuildError::MissingField("thread"))?;
+        let content = self.content.ok_or(BuildError::MissingField("content"))?;
+        let attachments = self
+            .attachments
+            .into_iter()
+            .map(AttachmentRef::try_from)
+            .collect::<Result<Vec<_>, _>>()?;
+        let attachments = BoundedVec::try_from(attachments)?;
+
+        Ok(ContentRequest::AddComment(AddCommentPayload {
+            thread_id,
+            user_id: self.user_id,
+            parent_id: self.parent_id,
+            content: CommentContent::try_from(content)?,
+            format: self.format,
+            reply_to: self.reply_to,
+            idempotency_key: self.idempotency_key.map(IdempotencyKey::try_from).transpose()?,
+            attachments,
+        }))
+    }
+}
+
+#[derive(Default, Debug)]
+pub struct EditCommentBuilder {
+    id: Option<CommentId>,
+    user_id: Option<UserId>,
+    content: Option<String>,
+    format: Option<ContentFormat>,
+}
+
+impl EditCommentBuilder {
+    pub fn comment(mut self, id: impl Into<CommentId>) -> Self {
+        self.id = Some(id.into());
+        self
+    }
+
+    pub fn user(mut self, id: impl Into<UserId>) -> Self {
+        self.user_id = Some(id.into());
+        self
+    }
+
+    pub fn content(mut self, content: impl Into<String>) -> Self {
+        self.content = Some(content.into());
+        self
+    }
+
+    pub fn format(mut self, format: ContentFormat) -> Self {
+        self.format = Some(format);
+        self
+    }
+
+    pub fn build(self) -> Result<ContentRequest, BuildError> {
+        let id = self.id.ok_or(BuildError::MissingField("comment"))?;
+        let content = self.content.ok_or(BuildError::MissingField("content"))?;
+
+        Ok(ContentRequest::EditComment(EditCommentPayload {
+            id,
+            user_id: self.user_id,
+            content: CommentContent::try_from(content)?,
+            format: self.format,
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn build_complete_thread() {
+        let request = ContentRequest::create_thread()
+            .category(1)
+            .user(2)
+            .title("A new thread")
+            .body("With some content")
+            .build()
+            .unwrap();
+
+        let expt = ContentRequest::AddThread(AddThreadPayload {
+            category_id: CategoryId::from(1),
+            user_id: Some(UserId::from(2)),
+            title: Title::try_from("A new thread".to_owned()).unwrap(),
+            description: Description::try_from("With some content".to_owned()).unwrap(),
+            format: ContentFormat::Markdown,
+            idempotency_key: None,
+        });
+        assert_eq!(request, expt);
+    }
+
+    #[test]
+    fn build_with_idempotency_key() {
+        let key = "123e4567-e89b-12d3-a456-426655440000";
+        let request = ContentRequest::create_comment()
+            .thread(1)
+            .content("With some content")
+            .idempotency_key(key)
+            .build()
+            .unwrap();
+        match request {
+            ContentRequest::AddComment(payload) => assert_eq!(
+                payload.idempotency_key,
+                Some(IdempotencyKey::try_from(key.to_owned()).unwrap())
+            ),
+            _ => panic!("expected a AddComment request"),
+        }
+
+        let res = ContentRequest::create_thread()
+            .category(1)
+            .title("A new thread")
+            .body("With some content")
+            .idempotency_key("not-a-key")
+            .build();
+        assert_eq!(
+            res,
+            Err(BuildError::Invalid(ValidationError::InvalidIdempotencyKey))
+        );
+    }
+
+    #[test]
+    fn build_thread_missing_field() {
+        let res = ContentRequest::create_thread()
+            .category(1)
+            .body("With some content")
+            .build();
+        assert_eq!(res, Err(BuildError::MissingField("title")));
+    }
+
+    #[test]
+    fn build_thread_invalid_field() {
+        let res = ContentRequest::create_thread()
+            .category(1)
+            .title("Hi")
+            .body("With some content")
+            .build();
+        assert_eq!(res, Err(BuildError::Invalid(ValidationError::InvalidTitle)));
+    }
+
+    #[test]
+    fn build_comments() {
+        let request = ContentRequest::create_comment()
+            .thread(3)
+            .parent(4)
+            .content("Hello there")
+            .format(ContentFormat::Markdown)
+            .build()
+            .unwrap();
+        match request {
+            ContentRequest::AddComment(payload) => {
+                assert_eq!(payload.thread_id, ThreadId::from(3));
+                assert_eq!(payload.parent_id, Some(CommentId::from(4)));
+                assert_eq!(payload.format, ContentFormat::Markdown);
+            }
+            _ => panic!("expected a AddComment request"),
+        }
+
+        let res = ContentRequest::edit_comment().content("Hello there").build();
+        assert_eq!(res, Err(BuildError::MissingField("comment")));
+    }
+
+    #[test]
+    fn build_comment_with_too_many_attachments() {
+        let mut builder = ContentRequest::create_comment().thread(3);
+        for _ in 0..=MAX_COMMENT_ATTACHMENTS {
+            builder = builder.attachment("AAAAAAAAAAAAAAAAAAAAAA");
+        }
+        let res = builder.content("Hello there").build();
+        let expt = BuildError::Invalid(ValidationError::InvalidListLength);
+        assert_eq!(res, Err(expt));
+    }
+}
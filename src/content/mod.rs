@@ -1,4 +1,6 @@
 //! Content requests from and responses to the web-client
 
+pub mod builder;
+pub mod models;
 pub mod requests;
 pub mod responses;
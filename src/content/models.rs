@@ -0,0 +1,563 @@
+//! Read-models of the content which are sent in responses
+//!
+//! The requests only describe what a user sends, while a model is the full
+//! object as it is stored by the content-service. The models are meant to be
+//! used as the `T` of `Paginated<T>`, while the responses of fetch requests
+//! send a `ThreadState` or `CommentState`.
+
+use chrono::{DateTime, Utc};
+use crate::content::requests::{ContentFormat, ContentRef};
+use crate::content::responses::{CategoryPayload, CommentPayload, ThreadPayload};
+use crate::valid::fields::*;
+use crate::valid::ids::*;
+use std::collections::HashMap;
+
+/// A thread, as it is stored by the content-service
+#[derive(Serialize, Deserialize, PartialEq, Clone, Debug)]
+#[cfg_attr(feature = "schemars", derive(JsonSchema))]
+#[cfg_attr(feature = "utoipa", derive(ToSchema))]
+#[serde(rename_all = "snake_case")]
+pub struct Thread {
+    pub id: ThreadId,
+    pub category_id: CategoryId,
+    pub author: UserId,
+    pub title: Title,
+    pub body: Description,
+    #[serde(default)]
+    pub format: ContentFormat,
+    #[serde(with = "crate::timestamp::seconds")]
+    #[cfg_attr(feature = "schemars", schemars(with = "chrono::DateTime<chrono::Utc>"))]
+    pub created_at: DateTime<Utc>,
+    /// When the thread was last edited, `None` if it has never been edited
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        with = "crate::timestamp::option_seconds"
+    )]
+    #[cfg_attr(
+        feature = "schemars",
+        schemars(with = "Option<chrono::DateTime<chrono::Utc>>")
+    )]
+    pub updated_at: Option<DateTime<Utc>>,
+    /// The sum of the up- and downvotes of the thread
+    #[serde(default)]
+    pub votes: i64,
+}
+
+/// A comment, as it is stored by the content-service
+#[derive(Serialize, Deserialize, PartialEq, Clone, Debug)]
+#[cfg_attr(feature = "schemars", derive(JsonSchema))]
+#[cfg_attr(feature = "utoipa", derive(ToSchema))]
+#[serde(rename_all = "snake_case")]
+pub struct Comment {
+    pub id: CommentId,
+    pub thread_id: ThreadId,
+    /// The comment which this comment is a answer to, if any
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub parent_id: Option<CommentId>,
+    pub author: UserId,
+    pub body: CommentContent,
+    #[serde(default)]
+    pub format: ContentFormat,
+    #[serde(with = "crate::timestamp::seconds")]
+    #[cfg_attr(feature = "schemars", schemars(with = "chrono::DateTime<chrono::Utc>"))]
+    pub created_at: DateTime<Utc>,
+    /// When the comment was last edited, `None` if it has never been edited
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        with = "crate::timestamp::option_seconds"
+    )]
+    #[cfg_attr(
+        feature = "schemars",
+        schemars(with = "Option<chrono::DateTime<chrono::Utc>>")
+    )]
+    pub updated_at: Option<DateTime<Utc>>,
+    /// The sum of the up- and downvotes of the comment
+    #[serde(default)]
+    pub votes: i64,
+}
+
+/// What is left of a thread or comment after it has been deleted
+///
+/// A tombstone is shown in place of the content (as "[deleted]"), so that a
+/// listing doesn't change shape and the replies to a deleted comment still
+/// have something to refer to.
+#[derive(Serialize, Deserialize, PartialEq, Clone, Debug)]
+#[cfg_attr(feature = "schemars", derive(JsonSchema))]
+#[cfg_attr(feature = "utoipa", derive(ToSchema))]
+#[serde(rename_all = "snake_case")]
+pub struct Tombstone {
+    pub id: ContentRef,
+    pub deleted_by: UserId,
+    #[serde(with = "crate::timestamp::seconds")]
+    #[cfg_attr(feature = "schemars", schemars(with = "chrono::DateTime<chrono::Utc>"))]
+    pub deleted_at: DateTime<Utc>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reason: Option<String>,
+}
+
+/// A thread or comment, which is either live or deleted
+///
+/// # Examples
+///
+/// ```json
+/// { "type": "LIVE", "payload": { "id": 4, "thread_id": 1, ... } }
+/// { "type": "DELETED", "payload": { "id": { "type": "COMMENT", "id": 4 }, ... } }
+/// ```
+#[derive(Serialize, Deserialize, PartialEq, Clone, Debug)]
+#[cfg_attr(feature = "schemars", derive(JsonSchema))]
+#[cfg_attr(feature = "utoipa", derive(ToSchema))]
+#[serde(
+    tag = "type",
+    content = "payload",
+    rename_all = "SCREAMING_SNAKE_CASE"
+)]
+pub enum ContentState<T> {
+    Live(T),
+    Deleted(Tombstone),
+}
+
+/// A thread which might have been deleted, as it is sent in the responses of
+/// fetch requests
+pub type ThreadState = ContentState<ThreadPayload>;
+
+/// A comment which might have been deleted, as it is sent in the responses of
+/// fetch requests
+pub type CommentState = ContentState<CommentPayload>;
+
+impl<T> ContentState<T> {
+    pub fn is_deleted(&self) -> bool {
+        match self {
+            ContentState::Live(_) => false,
+            ContentState::Deleted(_) => true,
+        }
+    }
+
+    /// The content, if it has not been deleted
+    pub fn live(&self) -> Option<&T> {
+        match self {
+            ContentState::Live(content) => Some(content),
+            ContentState::Deleted(_) => None,
+        }
+    }
+
+    /// The tombstone, if the content has been deleted
+    pub fn tombstone(&self) -> Option<&Tombstone> {
+        match self {
+            ContentState::Live(_) => None,
+            ContentState::Deleted(tombstone) => Some(tombstone),
+        }
+    }
+}
+
+/// A category together with its subcategories
+#[derive(Serialize, Deserialize, PartialEq, Clone, Debug)]
+#[cfg_attr(feature = "schemars", derive(JsonSchema))]
+#[cfg_attr(feature = "utoipa", derive(ToSchema))]
+#[serde(rename_all = "snake_case")]
+pub struct Category {
+    pub id: CategoryId,
+    pub title: Title,
+    pub description: Description,
+    #[serde(default)]
+    pub format: ContentFormat,
+    #[serde(default)]
+    pub hidden: bool,
+    #[serde(default)]
+    pub children: Vec<Category>,
+}
+
+impl From<CategoryPayload> for Category {
+    fn from(payload: CategoryPayload) -> Self {
+        Category {
+            id: payload.id,
+            title: payload.title,
+            description: payload.description,
+            format: payload.format,
+            hidden: payload.hidden,
+            children: Vec::new(),
+        }
+    }
+}
+
+/// Why a `CategoryTree` could not be built from a flat list of categories
+#[derive(Fail, PartialEq, Eq, Clone, Copy, Debug)]
+pub enum CategoryTreeError {
+    #[fail(display = "category {} is listed more than once", _0)]
+    DuplicateCategory(CategoryId),
+    #[fail(display = "category {} has a unknown parent {}", id, parent)]
+    UnknownParent { id: CategoryId, parent: CategoryId },
+    /// The category is a part of (or a child of) a cycle of parents
+    #[fail(display = "category {} is in a cycle of parents", _0)]
+    Cycle(CategoryId),
+}
+
+/// The nested categories of a forum
+///
+/// The tree is serialized as a list of its root categories, each with its
+/// `children`.
+///
+/// # Examples
+///
+/// ```json
+/// [{ "id": 1, "title": "A category", ..., "children": [{ "id": 2, ... }] }]
+/// ```
+#[derive(Serialize, Deserialize, PartialEq, Clone, Debug, Default)]
+#[cfg_attr(feature = "schemars", derive(JsonSchema))]
+#[cfg_attr(feature = "utoipa", derive(ToSchema))]
+#[serde(transparent)]
+pub struct CategoryTree {
+    pub roots: Vec<Category>,
+}
+
+impl CategoryTree {
+    /// Build the tree from a flat list of categories, each with the id of its
+    /// parent (`None` for a root)
+    ///
+    /// The children of a category are in the same order as in `flat`. Every
+    /// parent must be in the list, and the parents can't form a cycle.
+    pub fn from_flat(
+        flat: Vec<(CategoryPayload, Option<CategoryId>)>,
+    ) -> Result<Self, CategoryTreeError> {
+        let mut indices = HashMap::with_capacity(flat.len());
+        for (i, (category, _)) in flat.iter().enumerate() {
+            if indices.insert(category.id, i).is_some() {
+                return Err(CategoryTreeError::DuplicateCategory(category.id));
+            }
+        }
+
+        let mut roots = Vec::new();
+        let mut children: HashMap<CategoryId, Vec<usize>> = HashMap::new();
+        for (i, (category, parent)) in flat.iter().enumerate() {
+            match parent {
+                None => roots.push(i),
+                Some(parent) if indices.contains_key(parent) => {
+                    children.entry(*parent).or_default().push(i)
+                }
+                Some(parent) => {
+                    return Err(CategoryTreeError::UnknownParent {
+                        id: category.id,
+                        parent: *parent,
+                    })
+                }
+            }
+        }
+
+        // A category which can't be reached from a root is in (or below) a
+        // cycle, as every parent exists
+        let mut slots: Vec<_> = flat
+            .into_iter()
+            .map(|(category, _)| Some(Category::from(category)))
+            .collect();
+        let roots = roots
+            .into_iter()
+            .map(|i| take_subtree(i, &mut slots, &children))
+            .collect();
+        match slots.iter().flatten().next() {
+            Some(category) => Err(CategoryTreeError::Cycle(category.id)),
+            None => Ok(CategoryTree { roots }),
+        }
+    }
+
+    /// Find a category anywhere in the tree
+    pub fn find(&self, id: CategoryId) -> Option<&Category> {
+        let mut stack: Vec<&Category> = self.roots.iter().collect();
+        while let Some(category) = stack.pop() {
+            if category.id == id {
+                return Some(category);
+            }
+            stack.extend(category.children.iter());
+        }
+        None
+    }
+}
+
+/// Take the category at `i` out of `slots` together with all its children
+fn take_subtree(
+    i: usize,
+    slots: &mut Vec<Option<Category>>,
+    children: &HashMap<CategoryId, Vec<usize>>,
+) -> Category {
+    let mut category = slots[i].take().expect("a category is only in one place");
+    if let Some(indices) = children.get(&category.id) {
+        for &child in indices {
+            category.children.push(take_subtree(child, slots, children));
+        }
+    }
+    category
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::payloads::{Paginated, PaginationPayload};
+    use chrono::TimeZone;
+    use std::convert::TryFrom;
+
+    fn thread(updated_at: Option<DateTime<Utc>>) -> Thread {
+        Thread {
+            id: ThreadId::from(1),
+            category_id: CategoryId::from(2),
+            author: UserId::from(3),
+            title: Title::try_from("A thread".to_owned()).unwrap(),
+            body: Description::try_from("About something".to_owned()).unwrap(),
+            format: ContentFormat::Markdown,
+            created_at: Utc.timestamp(1_540_000_000, 0),
+            updated_at,
+            votes: 12,
+        }
+    }
+
+    fn comment(parent_id: Option<CommentId>) -> Comment {
+        Comment {
+            id: CommentId::from(4),
+            thread_id: ThreadId::from(1),
+            parent_id,
+            author: UserId::from(3),
+            body: CommentContent::try_from("A comment".to_owned()).unwrap(),
+            format: ContentFormat::Markdown,
+            created_at: Utc.timestamp(1_540_000_000, 0),
+            updated_at: Some(Utc.timestamp(1_540_000_100, 0)),
+            votes: -2,
+        }
+    }
+
+    #[test]
+    fn thread_round_trip() {
+        for thread in vec![thread(None), thread(Some(Utc.timestamp(1_540_000_100, 0)))] {
+            let json = serde_json::to_string(&thread).unwrap();
+            assert_eq!(serde_json::from_str::<Thread>(&json).unwrap(), thread);
+        }
+    }
+
+    #[test]
+    fn thread_json() {
+        let value = serde_json::to_value(&thread(None)).unwrap();
+        assert_eq!(value["author"], 3);
+        assert_eq!(value["body"], "About something");
+        assert_eq!(value["format"], "MARKDOWN");
+        assert_eq!(value["created_at"], "2018-10-20T01:46:40Z");
+        assert_eq!(value["votes"], 12);
+        assert!(value.get("updated_at").is_none());
+    }
+
+    #[test]
+    fn thread_format_defaults_to_markdown() {
+        let json = r#"{
+            "id": 1,
+            "category_id": 2,
+            "author": 3,
+            "title": "A thread",
+            "body": "About something",
+            "created_at": "2018-10-20T01:46:40Z"
+        }"#;
+        let thread: Thread = serde_json::from_str(json).unwrap();
+        assert_eq!(thread.format, ContentFormat::Markdown);
+    }
+
+    #[test]
+    fn comment_round_trip() {
+        for comment in vec![comment(None), comment(Some(CommentId::from(2)))] {
+            let json = serde_json::to_string(&comment).unwrap();
+            assert_eq!(serde_json::from_str::<Comment>(&json).unwrap(), comment);
+        }
+    }
+
+    #[test]
+    fn comment_defaults() {
+        let json = r#"{
+            "id": 4,
+            "thread_id": 1,
+            "author": 3,
+            "body": "A comment",
+            "created_at": "2018-10-20T01:46:40Z"
+        }"#;
+        let comment: Comment = serde_json::from_str(json).unwrap();
+        assert_eq!(comment.parent_id, None);
+        assert_eq!(comment.format, ContentFormat::Markdown);
+        assert_eq!(comment.updated_at, None);
+        assert_eq!(comment.votes, 0);
+    }
+
+    #[test]
+    fn paginated_models_round_trip() {
+        let page = Paginated::new(vec![thread(None)], PaginationPayload::default(), 1);
+        let json = serde_json::to_string(&page).unwrap();
+        let round_trip: Paginated<Thread> = serde_json::from_str(&json).unwrap();
+        assert_eq!(round_trip, page);
+
+        let page = Paginated::new(
+            vec![comment(None), comment(Some(CommentId::from(4)))],
+            PaginationPayload::default(),
+            2,
+        );
+        let json = serde_json::to_string(&page).unwrap();
+        let round_trip: Paginated<Comment> = serde_json::from_str(&json).unwrap();
+        assert_eq!(round_trip, page);
+    }
+
+    fn tombstone(id: ContentRef, reason: Option<&str>) -> Tombstone {
+        Tombstone {
+            id,
+            deleted_by: UserId::from(5),
+            deleted_at: Utc.timestamp(1_540_000_200, 0),
+            reason: reason.map(str::to_owned),
+        }
+    }
+
+    #[test]
+    fn thread_state_round_trip() {
+        let states = vec![
+            ContentState::Live(thread(None)),
+            ContentState::Deleted(tombstone(ContentRef::Thread(1.into()), Some("spam"))),
+            ContentState::Deleted(tombstone(ContentRef::Thread(1.into()), None)),
+        ];
+        for state in states {
+            let json = serde_json::to_string(&state).unwrap();
+            assert_eq!(
+                serde_json::from_str::<ContentState<Thread>>(&json).unwrap(),
+                state
+            );
+        }
+    }
+
+    #[test]
+    fn comment_state_round_trip() {
+        let states = vec![
+            ContentState::Live(comment(Some(CommentId::from(2)))),
+            ContentState::Deleted(tombstone(ContentRef::Comment(4.into()), Some("spam"))),
+            ContentState::Deleted(tombstone(ContentRef::Comment(4.into()), None)),
+        ];
+        for state in states {
+            let json = serde_json::to_string(&state).unwrap();
+            assert_eq!(
+                serde_json::from_str::<ContentState<Comment>>(&json).unwrap(),
+                state
+            );
+        }
+    }
+
+    #[test]
+    fn content_state_json() {
+        let state = ContentState::Live(comment(None));
+        let value = serde_json::to_value(&state).unwrap();
+        assert_eq!(value["type"], "LIVE");
+        assert_eq!(value["payload"]["body"], "A comment");
+        assert_eq!(state.live(), Some(&comment(None)));
+        assert!(!state.is_deleted());
+
+        let state: ContentState<Comment> =
+            ContentState::Deleted(tombstone(ContentRef::Comment(4.into()), None));
+        let expt = serde_json::json!({
+            "type": "DELETED",
+            "payload": {
+                "id": { "type": "COMMENT", "id": 4 },
+                "deleted_by": 5,
+                "deleted_at": "2018-10-20T01:50:00Z"
+            }
+        });
+        assert_eq!(serde_json::to_value(&state).unwrap(), expt);
+        assert!(state.is_deleted());
+        assert_eq!(state.live(), None);
+        assert_eq!(state.tombstone().unwrap().deleted_by, UserId::from(5));
+    }
+
+    #[test]
+    fn paginated_states_round_trip() {
+        let page = Paginated::new(
+            vec![
+                ContentState::Live(comment(None)),
+                ContentState::Deleted(tombstone(ContentRef::Comment(2.into()), None)),
+            ],
+            PaginationPayload::default(),
+            2,
+        );
+        let json = serde_json::to_string(&page).unwrap();
+        let round_trip: Paginated<ContentState<Comment>> = serde_json::from_str(&json).unwrap();
+        assert_eq!(round_trip, page);
+    }
+
+    fn category(id: u32) -> CategoryPayload {
+        CategoryPayload {
+            id: CategoryId::from(id),
+            title: Title::try_from(format!("Category {}", id)).unwrap(),
+            description: Description::try_from("About something".to_owned()).unwrap(),
+            format: ContentFormat::PlainText,
+            hidden: false,
+        }
+    }
+
+    fn flat(pairs: &[(u32, Option<u32>)]) -> Vec<(CategoryPayload, Option<CategoryId>)> {
+        pairs
+            .iter()
+            .map(|&(id, parent)| (category(id), parent.map(CategoryId::from)))
+            .collect()
+    }
+
+    fn ids(categories: &[Category]) -> Vec<u32> {
+        categories.iter().map(|c| *c.id).collect()
+    }
+
+    #[test]
+    fn category_tree_from_flat() {
+        // The children come before their parents on purpose
+        let pairs = [
+            (3, Some(1)),
+            (1, None),
+            (2, Some(1)),
+            (4, Some(2)),
+            (5, None),
+        ];
+        let tree = CategoryTree::from_flat(flat(&pairs)).unwrap();
+        assert_eq!(ids(&tree.roots), vec![1, 5]);
+        assert_eq!(ids(&tree.roots[0].children), vec![3, 2]);
+        assert_eq!(ids(&tree.roots[0].children[1].children), vec![4]);
+        assert!(tree.roots[1].children.is_empty());
+        assert_eq!(tree.find(CategoryId::from(4)).map(|c| *c.id), Some(4));
+        assert_eq!(tree.find(CategoryId::from(6)), None);
+    }
+
+    #[test]
+    fn category_tree_json() {
+        let tree = CategoryTree::from_flat(flat(&[(1, None), (2, Some(1))])).unwrap();
+        let value = serde_json::to_value(&tree).unwrap();
+        assert_eq!(value[0]["id"], 1);
+        assert_eq!(value[0]["children"][0]["id"], 2);
+        assert_eq!(value[0]["children"][0]["children"], serde_json::json!([]));
+
+        let json = serde_json::to_string(&tree).unwrap();
+        assert_eq!(serde_json::from_str::<CategoryTree>(&json).unwrap(), tree);
+    }
+
+    #[test]
+    fn category_tree_with_a_cycle() {
+        let pairs = [(1, None), (2, Some(3)), (3, Some(2))];
+        assert_eq!(
+            CategoryTree::from_flat(flat(&pairs)),
+            Err(CategoryTreeError::Cycle(CategoryId::from(2)))
+        );
+        let pairs = [(1, Some(1))];
+        assert_eq!(
+            CategoryTree::from_flat(flat(&pairs)),
+            Err(CategoryTreeError::Cycle(CategoryId::from(1)))
+        );
+    }
+
+    #[test]
+    fn category_tree_with_a_orphan() {
+        let pairs = [(1, None), (2, Some(7))];
+        assert_eq!(
+            CategoryTree::from_flat(flat(&pairs)),
+            Err(CategoryTreeError::UnknownParent {
+                id: CategoryId::from(2),
+                parent: CategoryId::from(7),
+            })
+        );
+        let pairs = [(1, None), (1, None)];
+        assert_eq!(
+            CategoryTree::from_flat(flat(&pairs)),
+            Err(CategoryTreeError::DuplicateCategory(CategoryId::from(1)))
+        );
+    }
+}
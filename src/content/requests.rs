@@ -1,17 +1,31 @@
 //! The requests a user can make to the content-database
 
+use crate::auth::responses::Role;
+use crate::content::responses::PollPayload;
+use crate::envelope_de::Envelope;
+use crate::error::{Error, ParseError};
+use crate::payloads::{
+    from_json_lenient, parse_request, AbsentOrEmpty, BoundedVec, Cursor, LengthBounds,
+    PaginationPayload, Patch,
+};
+use crate::valid::attachment::AttachmentRef;
 use crate::valid::fields::*;
 use crate::valid::ids::*;
+use crate::valid::{Validate, ValidationError};
 
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, PartialEq, Debug)]
+#[cfg_attr(feature = "schemars", derive(JsonSchema))]
+#[cfg_attr(feature = "utoipa", derive(ToSchema))]
 #[serde(
     tag = "type",
     content = "payload",
-    rename_all = "SCREAMING_SNAKE_CASE"
+    rename_all = "SCREAMING_SNAKE_CASE",
+    remote = "Self"
 )]
 pub enum ContentRequest {
     AddUser(AddUserPayload),
     EditUser(EditUserPayload),
+    UpdateProfile(UpdateProfilePayload),
 
     AddCategory(AddCategoryPayload),
     EditCategory(EditCategoryPayload),
@@ -20,54 +34,333 @@ pub enum ContentRequest {
     AddThread(AddThreadPayload),
     EditThread(EditThreadPayload),
     HideThread(HideThreadPayload),
+    SetThreadPinned(PinThreadPayload),
 
     AddComment(AddCommentPayload),
     EditComment(EditCommentPayload),
     HideComment(HideCommentPayload),
+    AttachToComment(AttachPayload),
+
+    React(ReactPayload),
+    RemoveReaction(ReactPayload),
+
+    Report(ReportPayload),
+    BulkDelete(BulkDeletePayload),
+
+    Subscribe(ThreadRefPayload),
+    Unsubscribe(ThreadRefPayload),
+    ListSubscriptions(PaginationPayload),
+
+    Bookmark(ThreadRefPayload),
+    Unbookmark(ThreadRefPayload),
+    ListBookmarks(PaginationPayload),
+
+    SaveDraft(DraftPayload),
+    ListDrafts(AbsentOrEmpty),
+    DeleteDraft(DraftRefPayload),
+
+    GetEditHistory(ContentRefPayload),
+
+    CreatePoll(NewPollPayload),
+    Vote(VotePayload),
+
+    FetchThread(FetchThreadPayload),
+}
+
+impl ContentRequest {
+    /// The type tags of the requests
+    pub const TAGS: &'static [&'static str] = &[
+        "ADD_USER",
+        "EDIT_USER",
+        "UPDATE_PROFILE",
+        "ADD_CATEGORY",
+        "EDIT_CATEGORY",
+        "HIDE_CATEGORY",
+        "ADD_THREAD",
+        "EDIT_THREAD",
+        "HIDE_THREAD",
+        "SET_THREAD_PINNED",
+        "ADD_COMMENT",
+        "EDIT_COMMENT",
+        "HIDE_COMMENT",
+        "ATTACH_TO_COMMENT",
+        "REACT",
+        "REMOVE_REACTION",
+        "REPORT",
+        "BULK_DELETE",
+        "SUBSCRIBE",
+        "UNSUBSCRIBE",
+        "LIST_SUBSCRIPTIONS",
+        "BOOKMARK",
+        "UNBOOKMARK",
+        "LIST_BOOKMARKS",
+        "SAVE_DRAFT",
+        "LIST_DRAFTS",
+        "DELETE_DRAFT",
+        "GET_EDIT_HISTORY",
+        "CREATE_POLL",
+        "VOTE",
+        "FETCH_THREAD",
+    ];
+
+    /// Parse a request from json, where the type tag is matched
+    /// case-insensitively (see `payloads::from_json_lenient`)
+    pub fn from_json_lenient(json: &str) -> Result<Self, Error> {
+        from_json_lenient(json, Self::TAGS)
+    }
+
+    /// Parse a request from json, where a error names the type of the request
+    /// and the field which was wrong (see `payloads::parse_request`)
+    pub fn parse_json(json: &str) -> Result<Self, ParseError> {
+        parse_request(json, Self::TAGS)
+    }
+}
+
+impl serde::Serialize for ContentRequest {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        ContentRequest::serialize(self, serializer)
+    }
+}
+
+impl<'de> serde::de::Deserialize<'de> for ContentRequest {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::de::Deserializer<'de>,
+    {
+        // Tells a `null` payload of `LIST_DRAFTS` apart from a missing one
+        ContentRequest::deserialize(Envelope(deserializer))
+    }
+}
+
+impl Validate for ContentRequest {
+    fn validation_errors(&self) -> Vec<ValidationError> {
+        match self {
+            ContentRequest::Report(payload) => payload.validation_errors(),
+            ContentRequest::SaveDraft(payload) => payload.validation_errors(),
+            _ => Vec::new(),
+        }
+    }
+}
+
+/// The format of a user submitted body (comments and descriptions)
+///
+/// A missing format is markdown (the `Default`), for every body.
+#[derive(Serialize, Deserialize, PartialEq, Eq, Clone, Copy, Debug)]
+#[cfg_attr(feature = "schemars", derive(JsonSchema))]
+#[cfg_attr(feature = "utoipa", derive(ToSchema))]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum ContentFormat {
+    PlainText,
+    Markdown,
+}
+
+impl Default for ContentFormat {
+    fn default() -> Self {
+        ContentFormat::Markdown
+    }
+}
+
+/// The order in which a listing of threads (or search hits) is returned
+///
+/// This is pure data, it is up to the service to map it to a query. A listing
+/// without a order is `NewestFirst`.
+#[derive(Serialize, Deserialize, PartialEq, Eq, Clone, Copy, Debug)]
+#[cfg_attr(feature = "schemars", derive(JsonSchema))]
+#[cfg_attr(feature = "utoipa", derive(ToSchema))]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum SortOrder {
+    NewestFirst,
+    OldestFirst,
+    RecentlyActive,
+    /// The most reacted to first
+    TopVoted,
+}
+
+impl SortOrder {
+    /// All the available orderings, so that clients can enumerate them
+    pub fn all() -> &'static [SortOrder] {
+        &[
+            SortOrder::NewestFirst,
+            SortOrder::OldestFirst,
+            SortOrder::RecentlyActive,
+            SortOrder::TopVoted,
+        ]
+    }
+}
+
+impl Default for SortOrder {
+    fn default() -> Self {
+        SortOrder::NewestFirst
+    }
+}
+
+/// The order in which the comments of a thread are returned
+///
+/// A listing without a order is `OldestFirst`, so that a thread reads from
+/// the top.
+#[derive(Serialize, Deserialize, PartialEq, Eq, Clone, Copy, Debug)]
+#[cfg_attr(feature = "schemars", derive(JsonSchema))]
+#[cfg_attr(feature = "utoipa", derive(ToSchema))]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum CommentSort {
+    OldestFirst,
+    NewestFirst,
+    /// The most reacted to first
+    TopVoted,
+}
+
+impl CommentSort {
+    /// All the available orderings, so that clients can enumerate them
+    pub fn all() -> &'static [CommentSort] {
+        &[
+            CommentSort::OldestFirst,
+            CommentSort::NewestFirst,
+            CommentSort::TopVoted,
+        ]
+    }
+}
+
+impl Default for CommentSort {
+    fn default() -> Self {
+        CommentSort::OldestFirst
+    }
+}
+
+/// A reference to a single thread or comment
+///
+/// # Examples
+///
+/// ```json
+/// { "type": "COMMENT", "id": 17 }
+/// ```
+#[derive(Serialize, Deserialize, PartialEq, Eq, Hash, Clone, Copy, Debug)]
+#[cfg_attr(feature = "schemars", derive(JsonSchema))]
+#[cfg_attr(feature = "utoipa", derive(ToSchema))]
+#[serde(
+    tag = "type",
+    content = "id",
+    rename_all = "SCREAMING_SNAKE_CASE"
+)]
+pub enum ContentRef {
+    Thread(ThreadId),
+    Comment(CommentId),
+}
+
+#[derive(Serialize, Deserialize, PartialEq, Debug)]
+#[cfg_attr(feature = "schemars", derive(JsonSchema))]
+#[cfg_attr(feature = "utoipa", derive(ToSchema))]
+#[serde(rename_all = "snake_case", deny_unknown_fields)]
+pub struct ContentRefPayload {
+    pub target: ContentRef,
 }
 
 // Users
 
 #[derive(Serialize, Deserialize, PartialEq, Debug)]
+#[cfg_attr(feature = "schemars", derive(JsonSchema))]
+#[cfg_attr(feature = "utoipa", derive(ToSchema))]
+#[serde(rename_all = "snake_case")]
 pub struct GetUserPayload {
     pub id: UserId,
 }
 
 #[derive(Serialize, Deserialize, PartialEq, Debug)]
+#[cfg_attr(feature = "schemars", derive(JsonSchema))]
+#[cfg_attr(feature = "utoipa", derive(ToSchema))]
+#[serde(rename_all = "snake_case")]
 pub struct AddUserPayload {
     pub id: UserId,
     pub username: Username,
 }
 
+/// A edit of a user
+///
+/// A field which is left out is left unchanged, while a field which is `null`
+/// is cleared (see `Patch`).
 #[derive(Serialize, Deserialize, PartialEq, Debug)]
+#[cfg_attr(feature = "schemars", derive(JsonSchema))]
+#[cfg_attr(feature = "utoipa", derive(ToSchema))]
+#[serde(rename_all = "snake_case")]
 pub struct EditUserPayload {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub id: Option<UserId>,
+    #[serde(default, skip_serializing_if = "Patch::is_absent")]
+    pub description: Patch<Description>,
+    #[serde(default, skip_serializing_if = "Patch::is_absent")]
+    pub avatar: Patch<String>,
+}
+
+/// A update of the profile of a user
+///
+/// A field which is left out is left unchanged, while a field which is `null`
+/// is cleared (see `Patch`).
+#[derive(Serialize, Deserialize, PartialEq, Debug)]
+#[cfg_attr(feature = "schemars", derive(JsonSchema))]
+#[cfg_attr(feature = "utoipa", derive(ToSchema))]
+#[serde(rename_all = "snake_case", deny_unknown_fields)]
+pub struct UpdateProfilePayload {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
     pub id: Option<UserId>,
-    pub description: Option<Description>,
-    pub avatar: Option<String>,
+    #[serde(default, skip_serializing_if = "Patch::is_absent")]
+    pub display_name: Patch<DisplayName>,
+    #[serde(default, skip_serializing_if = "Patch::is_absent")]
+    pub bio: Patch<Description>,
+    #[serde(default, skip_serializing_if = "Patch::is_absent")]
+    pub avatar_url: Patch<AvatarUrl>,
 }
 
 // Categories
 
 #[derive(Serialize, Deserialize, PartialEq, Debug)]
+#[cfg_attr(feature = "schemars", derive(JsonSchema))]
+#[cfg_attr(feature = "utoipa", derive(ToSchema))]
+#[serde(rename_all = "snake_case")]
 pub struct GetCategoryPayload {
     pub id: CategoryId,
     pub include_hidden: bool,
 }
 
 #[derive(Serialize, Deserialize, PartialEq, Debug)]
+#[cfg_attr(feature = "schemars", derive(JsonSchema))]
+#[cfg_attr(feature = "utoipa", derive(ToSchema))]
+#[serde(rename_all = "snake_case")]
 pub struct AddCategoryPayload {
     pub title: Title,
     pub description: Description,
+    #[serde(default)]
+    pub format: ContentFormat,
+    /// Lets the service recognize a retry of the same request
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub idempotency_key: Option<IdempotencyKey>,
 }
 
+/// A edit of a category
+///
+/// A title which is left out (or `null`) is left unchanged, as a category
+/// always has a title. A description which is left out is left unchanged,
+/// while a `null` description is cleared (see `Patch`).
 #[derive(Serialize, Deserialize, PartialEq, Debug)]
+#[cfg_attr(feature = "schemars", derive(JsonSchema))]
+#[cfg_attr(feature = "utoipa", derive(ToSchema))]
+#[serde(rename_all = "snake_case")]
 pub struct EditCategoryPayload {
     pub id: CategoryId,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
     pub title: Option<Title>,
-    pub description: Option<Description>,
+    #[serde(default, skip_serializing_if = "Patch::is_absent")]
+    pub description: Patch<Description>,
+    /// A format which is left out is left unchanged
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub format: Option<ContentFormat>,
 }
 
 #[derive(Serialize, Deserialize, PartialEq, Debug)]
+#[cfg_attr(feature = "schemars", derive(JsonSchema))]
+#[cfg_attr(feature = "utoipa", derive(ToSchema))]
+#[serde(rename_all = "snake_case")]
 pub struct HideCategoryPayload {
     pub id: CategoryId,
     pub hide: bool,
@@ -76,87 +369,1658 @@ pub struct HideCategoryPayload {
 // Threads
 
 #[derive(Serialize, Deserialize, PartialEq, Debug)]
+#[cfg_attr(feature = "schemars", derive(JsonSchema))]
+#[cfg_attr(feature = "utoipa", derive(ToSchema))]
+#[serde(rename_all = "snake_case")]
 pub struct GetThreadPayload {
     pub id: ThreadId,
     pub include_hidden: bool,
 }
 
+/// Lists the threads of a category
+///
+/// If `author` is set only the threads of that user within the category are
+/// listed. The threads are either paginated by `page`/`per_page` or from the
+/// cursor in `after`, but not both.
 #[derive(Serialize, Deserialize, PartialEq, Debug)]
+#[cfg_attr(feature = "schemars", derive(JsonSchema))]
+#[cfg_attr(feature = "utoipa", derive(ToSchema))]
+#[serde(rename_all = "snake_case")]
 pub struct GetThreadsPayload {
     pub id: CategoryId,
     pub include_hidden: bool,
+    #[serde(default)]
+    pub sort: SortOrder,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub author: Option<UserId>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub page: Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub per_page: Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub after: Option<Cursor>,
+}
+
+impl GetThreadsPayload {
+    /// Vertify that a cursor isn't combined with offset pagination
+    pub fn validate(&self) -> Result<(), ValidationError> {
+        let has_offset = self.page.is_some() || self.per_page.is_some();
+        if self.after.is_some() && has_offset {
+            Err(ValidationError::InvalidPagination)
+        } else {
+            Ok(())
+        }
+    }
+}
+
+impl Validate for GetThreadsPayload {
+    fn validation_errors(&self) -> Vec<ValidationError> {
+        self.validate().err().into_iter().collect()
+    }
 }
 
 #[derive(Serialize, Deserialize, PartialEq, Debug)]
+#[cfg_attr(feature = "schemars", derive(JsonSchema))]
+#[cfg_attr(feature = "utoipa", derive(ToSchema))]
+#[serde(rename_all = "snake_case")]
 pub struct AddThreadPayload {
     pub category_id: CategoryId,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
     pub user_id: Option<UserId>,
     pub title: Title,
     pub description: Description,
+    #[serde(default)]
+    pub format: ContentFormat,
+    /// Lets the service recognize a retry of the same request
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub idempotency_key: Option<IdempotencyKey>,
 }
 
+/// A edit of a thread
+///
+/// A title which is left out (or `null`) is left unchanged, as a thread
+/// always has a title. A description which is left out is left unchanged,
+/// while a `null` description is cleared (see `Patch`).
 #[derive(Serialize, Deserialize, PartialEq, Debug)]
+#[cfg_attr(feature = "schemars", derive(JsonSchema))]
+#[cfg_attr(feature = "utoipa", derive(ToSchema))]
+#[serde(rename_all = "snake_case")]
 pub struct EditThreadPayload {
     pub id: ThreadId,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
     pub user_id: Option<UserId>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
     pub title: Option<Title>,
-    pub description: Option<Description>,
+    #[serde(default, skip_serializing_if = "Patch::is_absent")]
+    pub description: Patch<Description>,
+    /// A format which is left out is left unchanged
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub format: Option<ContentFormat>,
 }
 
 #[derive(Serialize, Deserialize, PartialEq, Debug)]
+#[cfg_attr(feature = "schemars", derive(JsonSchema))]
+#[cfg_attr(feature = "utoipa", derive(ToSchema))]
+#[serde(rename_all = "snake_case")]
 pub struct HideThreadPayload {
     pub id: ThreadId,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
     pub user_id: Option<UserId>,
     pub hide: bool,
 }
 
+/// Fetches a thread together with a page of its comments
+#[derive(Serialize, Deserialize, PartialEq, Debug)]
+#[cfg_attr(feature = "schemars", derive(JsonSchema))]
+#[cfg_attr(feature = "utoipa", derive(ToSchema))]
+#[serde(rename_all = "snake_case", deny_unknown_fields)]
+pub struct FetchThreadPayload {
+    pub thread_id: ThreadId,
+    pub include_hidden: bool,
+    #[serde(default)]
+    pub comments: PaginationPayload,
+}
+
+/// A reference to a thread, used to subscribe to and bookmark threads
+#[derive(Serialize, Deserialize, PartialEq, Debug)]
+#[cfg_attr(feature = "schemars", derive(JsonSchema))]
+#[cfg_attr(feature = "utoipa", derive(ToSchema))]
+#[serde(rename_all = "snake_case", deny_unknown_fields)]
+pub struct ThreadRefPayload {
+    pub thread_id: ThreadId,
+}
+
+/// Pins (or unpins) a thread to the top of its category, or of every listing
+/// with a `Global` scope
+#[derive(Serialize, Deserialize, PartialEq, Debug)]
+#[cfg_attr(feature = "schemars", derive(JsonSchema))]
+#[cfg_attr(feature = "utoipa", derive(ToSchema))]
+#[serde(rename_all = "snake_case", deny_unknown_fields)]
+pub struct PinThreadPayload {
+    pub thread_id: ThreadId,
+    pub pinned: bool,
+    /// A scope which is left out is `PinScope::Category` (the `Default`)
+    #[serde(default)]
+    pub scope: PinScope,
+}
+
+/// Where a pinned thread is listed first
+#[derive(Serialize, Deserialize, PartialEq, Eq, Clone, Copy, Debug)]
+#[cfg_attr(feature = "schemars", derive(JsonSchema))]
+#[cfg_attr(feature = "utoipa", derive(ToSchema))]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum PinScope {
+    /// The listing of the category of the thread
+    Category,
+    /// Every listing of threads, like a announcement
+    Global,
+}
+
+impl Default for PinScope {
+    fn default() -> Self {
+        PinScope::Category
+    }
+}
+
+impl PinThreadPayload {
+    /// The lowest role which is allowed to pin threads
+    pub const REQUIRED_ROLE: Role = Role::Moderator;
+}
+
 // Comments
 
 #[derive(Serialize, Deserialize, PartialEq, Debug)]
+#[cfg_attr(feature = "schemars", derive(JsonSchema))]
+#[cfg_attr(feature = "utoipa", derive(ToSchema))]
+#[serde(rename_all = "snake_case")]
 pub struct GetCommentPayload {
     pub id: CommentId,
     pub include_hidden: bool,
 }
 
 #[derive(Serialize, Deserialize, PartialEq, Debug)]
+#[cfg_attr(feature = "schemars", derive(JsonSchema))]
+#[cfg_attr(feature = "utoipa", derive(ToSchema))]
+#[serde(rename_all = "snake_case")]
 pub struct GetCommentsPayload {
     pub id: ThreadId,
     pub include_hidden: bool,
+    #[serde(default)]
+    pub sort: CommentSort,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub author: Option<UserId>,
 }
 
 #[derive(Serialize, Deserialize, PartialEq, Debug)]
+#[cfg_attr(feature = "schemars", derive(JsonSchema))]
+#[cfg_attr(feature = "utoipa", derive(ToSchema))]
+#[serde(rename_all = "snake_case")]
 pub struct AddCommentPayload {
     pub thread_id: ThreadId,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
     pub user_id: Option<UserId>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
     pub parent_id: Option<CommentId>,
     pub content: CommentContent,
+    #[serde(default)]
+    pub format: ContentFormat,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reply_to: Option<ReplyRef>,
+    /// Lets the service recognize a retry of the same request
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub idempotency_key: Option<IdempotencyKey>,
+    /// The uploads which are attached to the comment when it is added
+    #[serde(
+        default = "no_attachments",
+        skip_serializing_if = "BoundedVec::is_empty"
+    )]
+    #[cfg_attr(
+        feature = "utoipa",
+        schema(schema_with = BoundedVec::<AttachmentRef, CommentAttachmentBounds>::openapi_schema)
+    )]
+    pub attachments: BoundedVec<AttachmentRef, CommentAttachmentBounds>,
+}
+
+/// The attachments of a comment which doesn't list any
+fn no_attachments() -> BoundedVec<AttachmentRef, CommentAttachmentBounds> {
+    BoundedVec::try_from(Vec::new()).expect("a comment can have no attachments")
+}
+
+/// The most attachments which can be added with a comment
+pub const MAX_COMMENT_ATTACHMENTS: usize = 10;
+
+/// The bounds of the attachments of a new comment, 0 to
+/// `MAX_COMMENT_ATTACHMENTS`
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+pub struct CommentAttachmentBounds;
+
+impl LengthBounds for CommentAttachmentBounds {
+    const MIN: usize = 0;
+    const MAX: usize = MAX_COMMENT_ATTACHMENTS;
+}
+
+/// The max amount of characters in a quoted excerpt
+pub const MAX_EXCERPT_LEN: usize = 280;
+
+/// A reference to the comment which a comment is a reply to, optionally with
+/// a short quote from it
+///
+/// Excerpts which are longer than `MAX_EXCERPT_LEN` characters are rejected
+/// when they are deserialized.
+#[derive(Serialize, Deserialize, PartialEq, Clone, Debug)]
+#[cfg_attr(feature = "schemars", derive(JsonSchema))]
+#[cfg_attr(feature = "utoipa", derive(ToSchema))]
+#[serde(rename_all = "snake_case", deny_unknown_fields)]
+pub struct ReplyRef {
+    pub comment_id: CommentId,
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        deserialize_with = "deserialize_excerpt"
+    )]
+    pub excerpt: Option<String>,
+}
+
+impl ReplyRef {
+    pub fn new(comment_id: CommentId, excerpt: Option<String>) -> Result<Self, ValidationError> {
+        validate_excerpt(excerpt.as_ref())?;
+        Ok(ReplyRef {
+            comment_id,
+            excerpt,
+        })
+    }
+}
+
+fn validate_excerpt(excerpt: Option<&String>) -> Result<(), ValidationError> {
+    match excerpt {
+        Some(excerpt) if excerpt.chars().count() > MAX_EXCERPT_LEN => {
+            Err(ValidationError::InvalidExcerpt)
+        }
+        _ => Ok(()),
+    }
+}
+
+fn deserialize_excerpt<'de, D>(deserializer: D) -> Result<Option<String>, D::Error>
+where
+    D: serde::de::Deserializer<'de>,
+{
+    use serde::de::Deserialize;
+    let excerpt = Option::<String>::deserialize(deserializer)?;
+    validate_excerpt(excerpt.as_ref()).map_err(serde::de::Error::custom)?;
+    Ok(excerpt)
 }
 
 #[derive(Serialize, Deserialize, PartialEq, Debug)]
+#[cfg_attr(feature = "schemars", derive(JsonSchema))]
+#[cfg_attr(feature = "utoipa", derive(ToSchema))]
+#[serde(rename_all = "snake_case")]
 pub struct EditCommentPayload {
     pub id: CommentId,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
     pub user_id: Option<UserId>,
     pub content: CommentContent,
+    /// A format which is left out is left unchanged
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub format: Option<ContentFormat>,
 }
 
 #[derive(Serialize, Deserialize, PartialEq, Debug)]
+#[cfg_attr(feature = "schemars", derive(JsonSchema))]
+#[cfg_attr(feature = "utoipa", derive(ToSchema))]
+#[serde(rename_all = "snake_case")]
 pub struct HideCommentPayload {
     pub id: CommentId,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
     pub user_id: Option<UserId>,
     pub hide: bool,
 }
 
+/// Attaches a uploaded attachment to a comment
+#[derive(Serialize, Deserialize, PartialEq, Debug)]
+#[cfg_attr(feature = "schemars", derive(JsonSchema))]
+#[cfg_attr(feature = "utoipa", derive(ToSchema))]
+#[serde(rename_all = "snake_case", deny_unknown_fields)]
+pub struct AttachPayload {
+    pub comment_id: CommentId,
+    pub attachment_id: AttachmentId,
+}
+
+// Reactions
+
+/// The kinds of reactions a user can give to a thread or a comment
+#[derive(Serialize, Deserialize, PartialEq, Eq, PartialOrd, Ord, Hash, Clone, Copy, Debug)]
+#[cfg_attr(feature = "schemars", derive(JsonSchema))]
+#[cfg_attr(feature = "utoipa", derive(ToSchema))]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum ReactionKind {
+    Like,
+    Heart,
+    Laugh,
+    Confused,
+}
+
+/// The content a reaction is given to
+pub type ReactionTarget = ContentRef;
+
+#[derive(Serialize, Deserialize, PartialEq, Debug)]
+#[cfg_attr(feature = "schemars", derive(JsonSchema))]
+#[cfg_attr(feature = "utoipa", derive(ToSchema))]
+#[serde(rename_all = "snake_case", deny_unknown_fields)]
+pub struct ReactPayload {
+    pub target: ReactionTarget,
+    pub kind: ReactionKind,
+}
+
+// Reports
+
+/// Why a user reported some content
+#[derive(Serialize, Deserialize, PartialEq, Eq, Clone, Copy, Debug)]
+#[cfg_attr(feature = "schemars", derive(JsonSchema))]
+#[cfg_attr(feature = "utoipa", derive(ToSchema))]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum ReportReason {
+    Spam,
+    Harassment,
+    IllegalContent,
+    /// Any other reason, which must be explained in the details of the report
+    Other,
+}
+
+/// A report of a thread or comment which should be looked at by a moderator
+///
+/// A report with the reason `Other` must contain details, such reports are
+/// rejected when they are deserialized.
+#[derive(Serialize, PartialEq, Debug)]
+#[cfg_attr(feature = "schemars", derive(JsonSchema))]
+#[cfg_attr(feature = "utoipa", derive(ToSchema))]
+#[serde(rename_all = "snake_case")]
+pub struct ReportPayload {
+    pub target: ContentRef,
+    pub reason: ReportReason,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub details: Option<Description>,
+}
+
+impl ReportPayload {
+    /// Vertify that the details are present if they are required by the reason
+    pub fn validate(&self) -> Result<(), ValidationError> {
+        let has_details = self
+            .details
+            .as_ref()
+            .map_or(false, |details| !details.trim().is_empty());
+        if self.reason == ReportReason::Other && !has_details {
+            Err(ValidationError::MissingReportDetails)
+        } else {
+            Ok(())
+        }
+    }
+}
+
+impl Validate for ReportPayload {
+    fn validation_errors(&self) -> Vec<ValidationError> {
+        self.validate().err().into_iter().collect()
+    }
+}
+
+impl<'de> serde::de::Deserialize<'de> for ReportPayload {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::de::Deserializer<'de>,
+    {
+        #[derive(Deserialize)]
+        #[serde(rename_all = "snake_case")]
+        struct RawReportPayload {
+            target: ContentRef,
+            reason: ReportReason,
+            #[serde(default)]
+            details: Option<Description>,
+        }
+
+        let raw = RawReportPayload::deserialize(deserializer)?;
+        let payload = ReportPayload {
+            target: raw.target,
+            reason: raw.reason,
+            details: raw.details,
+        };
+        payload.validate().map_err(serde::de::Error::custom)?;
+        Ok(payload)
+    }
+}
+
+/// The most threads and comments which can be deleted by a single request
+pub const MAX_BULK_DELETE: usize = 100;
+
+/// The bounds of the targets of a bulk delete, 1 to `MAX_BULK_DELETE`
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+pub struct BulkDeleteBounds;
+
+impl LengthBounds for BulkDeleteBounds {
+    const MIN: usize = 1;
+    const MAX: usize = MAX_BULK_DELETE;
+}
+
+/// Deletes many threads and comments at once (like when spam is cleaned up)
+///
+/// The batch can't be empty and can have at most `MAX_BULK_DELETE` targets,
+/// a batch outside of those bounds is rejected when it is deserialized.
+#[derive(Serialize, Deserialize, PartialEq, Debug)]
+#[cfg_attr(feature = "schemars", derive(JsonSchema))]
+#[cfg_attr(feature = "utoipa", derive(ToSchema))]
+#[serde(rename_all = "snake_case", deny_unknown_fields)]
+pub struct BulkDeletePayload {
+    #[cfg_attr(
+        feature = "utoipa",
+        schema(schema_with = BoundedVec::<ContentRef, BulkDeleteBounds>::openapi_schema)
+    )]
+    pub targets: BoundedVec<ContentRef, BulkDeleteBounds>,
+}
+
+// Drafts
+
+/// What a draft will become when it is posted
+#[derive(Serialize, Deserialize, PartialEq, Clone, Copy, Debug)]
+#[cfg_attr(feature = "schemars", derive(JsonSchema))]
+#[cfg_attr(feature = "utoipa", derive(ToSchema))]
+#[serde(
+    tag = "type",
+    content = "payload",
+    rename_all = "SCREAMING_SNAKE_CASE"
+)]
+pub enum DraftTarget {
+    /// A new thread in a category
+    NewThread { category_id: CategoryId },
+    /// A comment in a thread, optionally as a reply to another comment
+    ReplyTo {
+        thread_id: ThreadId,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        parent_id: Option<CommentId>,
+    },
+}
+
+/// A unsent thread or comment which is autosaved by the client
+///
+/// A draft without a id is a new draft. Only drafts of new threads can have a
+/// title, but they are allowed to leave it out while the thread is written.
+#[derive(Serialize, Deserialize, PartialEq, Debug)]
+#[cfg_attr(feature = "schemars", derive(JsonSchema))]
+#[cfg_attr(feature = "utoipa", derive(ToSchema))]
+#[serde(rename_all = "snake_case", deny_unknown_fields)]
+pub struct DraftPayload {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub draft_id: Option<DraftId>,
+    pub target: DraftTarget,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub title: Option<Title>,
+    pub content: CommentContent,
+    /// When the draft was last changed (seconds since the unix epoch)
+    pub updated_at: u64,
+}
+
+impl DraftPayload {
+    /// Vertify that only drafts of new threads have a title
+    pub fn validate(&self) -> Result<(), ValidationError> {
+        match (&self.target, &self.title) {
+            (DraftTarget::ReplyTo { .. }, Some(_)) => Err(ValidationError::InvalidDraft),
+            _ => Ok(()),
+        }
+    }
+}
+
+impl Validate for DraftPayload {
+    fn validation_errors(&self) -> Vec<ValidationError> {
+        self.validate().err().into_iter().collect()
+    }
+}
+
+#[derive(Serialize, Deserialize, PartialEq, Debug)]
+#[cfg_attr(feature = "schemars", derive(JsonSchema))]
+#[cfg_attr(feature = "utoipa", derive(ToSchema))]
+#[serde(rename_all = "snake_case", deny_unknown_fields)]
+pub struct DraftRefPayload {
+    pub draft_id: DraftId,
+}
+
+// Polls
+
+/// The least amount of options a poll can have
+pub const MIN_POLL_OPTIONS: usize = 2;
+
+/// The most amount of options a poll can have
+pub const MAX_POLL_OPTIONS: usize = 10;
+
+/// Vertify that a poll has a valid amount of options
+pub(crate) fn validate_poll_options(count: usize) -> Result<(), ValidationError> {
+    if MIN_POLL_OPTIONS <= count && count <= MAX_POLL_OPTIONS {
+        Ok(())
+    } else {
+        Err(ValidationError::InvalidPoll)
+    }
+}
+
+/// A new poll which is added to a thread
+///
+/// Polls with too few or too many options are rejected when they are
+/// deserialized.
+#[derive(Serialize, PartialEq, Debug)]
+#[cfg_attr(feature = "schemars", derive(JsonSchema))]
+#[cfg_attr(feature = "utoipa", derive(ToSchema))]
+#[serde(rename_all = "snake_case")]
+pub struct NewPollPayload {
+    pub thread_id: ThreadId,
+    pub question: Title,
+    pub options: Vec<Title>,
+    pub multiple_choice: bool,
+    /// When the poll stops accepting votes (seconds since the unix epoch)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub closes_at: Option<u64>,
+}
+
+impl NewPollPayload {
+    pub fn new(
+        thread_id: ThreadId,
+        question: Title,
+        options: Vec<Title>,
+        multiple_choice: bool,
+        closes_at: Option<u64>,
+    ) -> Result<Self, ValidationError> {
+        validate_poll_options(options.len())?;
+        Ok(NewPollPayload {
+            thread_id,
+            question,
+            options,
+            multiple_choice,
+            closes_at,
+        })
+    }
+}
+
+impl<'de> serde::de::Deserialize<'de> for NewPollPayload {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::de::Deserializer<'de>,
+    {
+        #[derive(Deserialize)]
+        #[serde(rename_all = "snake_case", deny_unknown_fields)]
+        struct RawNewPollPayload {
+            thread_id: ThreadId,
+            question: Title,
+            options: Vec<Title>,
+            multiple_choice: bool,
+            #[serde(default)]
+            closes_at: Option<u64>,
+        }
+
+        let raw = RawNewPollPayload::deserialize(deserializer)?;
+        NewPollPayload::new(
+            raw.thread_id,
+            raw.question,
+            raw.options,
+            raw.multiple_choice,
+            raw.closes_at,
+        )
+        .map_err(serde::de::Error::custom)
+    }
+}
+
+/// A vote on one or more options of the poll in a thread
+#[derive(Serialize, Deserialize, PartialEq, Debug)]
+#[cfg_attr(feature = "schemars", derive(JsonSchema))]
+#[cfg_attr(feature = "utoipa", derive(ToSchema))]
+#[serde(rename_all = "snake_case", deny_unknown_fields)]
+pub struct VotePayload {
+    pub thread_id: ThreadId,
+    pub option_ids: IdList<PollOptionId>,
+}
+
+impl VotePayload {
+    /// Vertify that the vote is valid for the given poll
+    ///
+    /// Every option must belong to the poll and can only be voted on once. A
+    /// single-choice poll must get exactly one option.
+    pub fn validate(&self, poll: &PollPayload) -> Result<(), ValidationError> {
+        let ids = &self.option_ids;
+        let valid_count = if poll.multiple_choice {
+            !ids.is_empty()
+        } else {
+            ids.len() == 1
+        };
+        let known = ids
+            .iter()
+            .all(|id| poll.options.iter().any(|option| option.id == *id));
+        let unique = ids
+            .iter()
+            .enumerate()
+            .all(|(i, id)| !ids[..i].contains(id));
+
+        if valid_count && known && unique {
+            Ok(())
+        } else {
+            Err(ValidationError::InvalidVote)
+        }
+    }
+}
+
 // Search
 
 #[derive(Serialize, Deserialize, PartialEq, Debug)]
+#[cfg_attr(feature = "schemars", derive(JsonSchema))]
+#[cfg_attr(feature = "utoipa", derive(ToSchema))]
+#[serde(rename_all = "snake_case")]
 pub struct SearchPayload {
     pub query: QueryStr,
+    /// A search leaves out the hidden threads, unless it is told otherwise
+    #[serde(default)]
     pub include_hidden: bool,
+    #[serde(default)]
+    pub sort: SortOrder,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub author: Option<UserId>,
 }
 
 // Hidden
 
 #[derive(Serialize, Deserialize, PartialEq, Debug)]
+#[cfg_attr(feature = "schemars", derive(JsonSchema))]
+#[cfg_attr(feature = "utoipa", derive(ToSchema))]
+#[serde(rename_all = "snake_case")]
 pub struct GetHiddenPayload {
     pub include_hidden: bool,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::content::responses::PollOption;
+    use std::convert::TryFrom;
+
+    #[test]
+    fn content_format_defaults_to_markdown() {
+        let json = r#"{ "thread_id": 1, "user_id": 2, "parent_id": null, "content": "Hello there" }"#;
+        let payload: AddCommentPayload = serde_json::from_str(json).unwrap();
+        assert_eq!(payload.format, ContentFormat::Markdown);
+        assert_eq!(ContentFormat::default(), ContentFormat::Markdown);
+    }
+
+    #[test]
+    fn content_format_explicit_values() {
+        for (name, expt) in vec![
+            ("PLAIN_TEXT", ContentFormat::PlainText),
+            ("MARKDOWN", ContentFormat::Markdown),
+        ] {
+            let json = format!(
+                r#"{{ "id": 1, "title": null, "description": "Some description", "format": "{}" }}"#,
+                name
+            );
+            let payload: EditCategoryPayload = serde_json::from_str(&json).unwrap();
+            assert_eq!(payload.format, Some(expt));
+            assert_eq!(serde_json::to_value(expt).unwrap(), serde_json::json!(name));
+        }
+    }
+
+    #[test]
+    fn edits_without_a_format_leave_it_unchanged() {
+        let json = r#"{ "id": 1, "description": "Some description" }"#;
+        let payload: EditCategoryPayload = serde_json::from_str(json).unwrap();
+        assert_eq!(payload.format, None);
+        assert_eq!(serde_json::to_value(&payload).unwrap().get("format"), None);
+
+        let json = r#"{ "id": 1, "content": "Hello there" }"#;
+        let payload: EditCommentPayload = serde_json::from_str(json).unwrap();
+        assert_eq!(payload.format, None);
+        assert_eq!(serde_json::to_value(&payload).unwrap().get("format"), None);
+    }
+
+    #[test]
+    fn content_format_rejects_unknown_format() {
+        let json = r#"{ "id": 1, "user_id": null, "content": "Hello there", "format": "HTML" }"#;
+        assert!(serde_json::from_str::<EditCommentPayload>(json).is_err());
+    }
+
+    #[test]
+    fn sort_order_defaults_to_newest_first() {
+        let json = r#"{ "id": 1, "include_hidden": false }"#;
+        let payload: GetThreadsPayload = serde_json::from_str(json).unwrap();
+        assert_eq!(payload.sort, SortOrder::NewestFirst);
+
+        let json = r#"{ "query": "rust" }"#;
+        let payload: SearchPayload = serde_json::from_str(json).unwrap();
+        assert_eq!(payload.sort, SortOrder::default());
+        assert!(!payload.include_hidden);
+    }
+
+    #[test]
+    fn sort_order_wire_names() {
+        for order in SortOrder::all() {
+            let expt = match order {
+                SortOrder::NewestFirst => "NEWEST_FIRST",
+                SortOrder::OldestFirst => "OLDEST_FIRST",
+                SortOrder::RecentlyActive => "RECENTLY_ACTIVE",
+                SortOrder::TopVoted => "TOP_VOTED",
+            };
+            assert_eq!(serde_json::to_value(order).unwrap(), serde_json::json!(expt));
+            let json = format!(r#"{{ "id": 1, "include_hidden": true, "sort": "{}" }}"#, expt);
+            let payload: GetThreadsPayload = serde_json::from_str(&json).unwrap();
+            assert_eq!(payload.sort, *order);
+        }
+    }
+
+    #[test]
+    fn comment_sort_defaults_to_oldest_first() {
+        let json = r#"{ "id": 1, "include_hidden": false }"#;
+        let payload: GetCommentsPayload = serde_json::from_str(json).unwrap();
+        assert_eq!(payload.sort, CommentSort::OldestFirst);
+        assert_eq!(CommentSort::default(), CommentSort::OldestFirst);
+    }
+
+    #[test]
+    fn comment_sort_wire_names() {
+        for order in CommentSort::all() {
+            let expt = match order {
+                CommentSort::OldestFirst => "OLDEST_FIRST",
+                CommentSort::NewestFirst => "NEWEST_FIRST",
+                CommentSort::TopVoted => "TOP_VOTED",
+            };
+            let value = serde_json::to_value(order).unwrap();
+            assert_eq!(value, serde_json::json!(expt));
+            let json = serde_json::json!({ "id": 1, "include_hidden": true, "sort": expt });
+            let payload: GetCommentsPayload = serde_json::from_value(json).unwrap();
+            assert_eq!(payload.sort, *order);
+        }
+    }
+
+    #[test]
+    fn unknown_sort_is_rejected() {
+        for sort in &["RANDOM", "newest_first", "TopVoted", ""] {
+            let json = serde_json::json!({ "id": 1, "include_hidden": true, "sort": sort });
+            let threads = serde_json::from_value::<GetThreadsPayload>(json.clone());
+            assert!(threads.is_err(), "{}", sort);
+            let comments = serde_json::from_value::<GetCommentsPayload>(json);
+            assert!(comments.is_err(), "{}", sort);
+        }
+        // Comments are not listed by activity
+        let json = r#"{ "id": 1, "include_hidden": true, "sort": "RECENTLY_ACTIVE" }"#;
+        assert!(serde_json::from_str::<GetCommentsPayload>(json).is_err());
+    }
+
+    #[test]
+    fn author_filter_is_optional() {
+        let json = r#"{ "id": 1, "include_hidden": false }"#;
+        let payload: GetCommentsPayload = serde_json::from_str(json).unwrap();
+        assert_eq!(payload.author, None);
+    }
+
+    #[test]
+    fn author_filter_explicit() {
+        let json = r#"{ "id": 1, "include_hidden": false, "author": 7 }"#;
+        let payload: GetThreadsPayload = serde_json::from_str(json).unwrap();
+        assert_eq!(payload.author, Some(UserId::from(7)));
+
+        let json = r#"{ "query": "rust", "author": 7 }"#;
+        let payload: SearchPayload = serde_json::from_str(json).unwrap();
+        assert_eq!(payload.author, Some(UserId::from(7)));
+    }
+
+    #[test]
+    fn author_filter_is_skipped_when_none() {
+        let payload = GetCommentsPayload {
+            id: ThreadId::from(1),
+            include_hidden: false,
+            sort: CommentSort::default(),
+            author: None,
+        };
+        let value = serde_json::to_value(&payload).unwrap();
+        assert!(value.get("author").is_none());
+    }
+
+    #[test]
+    fn set_thread_pinned_envelope() {
+        let json = r#"{ "type": "SET_THREAD_PINNED", "payload": { "thread_id": 3, "pinned": true } }"#;
+        let expt = ContentRequest::SetThreadPinned(PinThreadPayload {
+            thread_id: ThreadId::from(3),
+            pinned: true,
+            scope: PinScope::Category,
+        });
+        assert_eq!(serde_json::from_str::<ContentRequest>(json).unwrap(), expt);
+
+        // The default scope is always sent
+        let json = serde_json::to_value(&expt).unwrap();
+        assert_eq!(json["payload"]["scope"], "CATEGORY");
+    }
+
+    #[test]
+    fn unpin_thread_with_scope_envelope() {
+        let json = r#"{
+            "type": "SET_THREAD_PINNED",
+            "payload": { "thread_id": 3, "pinned": false, "scope": "GLOBAL" }
+        }"#;
+        let expt = ContentRequest::SetThreadPinned(PinThreadPayload {
+            thread_id: ThreadId::from(3),
+            pinned: false,
+            scope: PinScope::Global,
+        });
+        assert_eq!(serde_json::from_str::<ContentRequest>(json).unwrap(), expt);
+    }
+
+    #[test]
+    fn pin_scope_tags() {
+        let scope: PinScope = serde_json::from_str(r#""CATEGORY""#).unwrap();
+        assert_eq!(scope, PinScope::Category);
+        assert_eq!(PinScope::default(), PinScope::Category);
+        assert_eq!(
+            serde_json::to_string(&PinScope::Global).unwrap(),
+            r#""GLOBAL""#
+        );
+        assert!(serde_json::from_str::<PinScope>(r#""global""#).is_err());
+    }
+
+    #[test]
+    fn react_to_thread_and_comment() {
+        let json = r#"{
+            "type": "REACT",
+            "payload": { "target": { "type": "THREAD", "id": 1 }, "kind": "LIKE" }
+        }"#;
+        let expt = ContentRequest::React(ReactPayload {
+            target: ContentRef::Thread(ThreadId::from(1)),
+            kind: ReactionKind::Like,
+        });
+        assert_eq!(serde_json::from_str::<ContentRequest>(json).unwrap(), expt);
+
+        let json = r#"{
+            "type": "REACT",
+            "payload": { "target": { "type": "COMMENT", "id": 17 }, "kind": "CONFUSED" }
+        }"#;
+        let expt = ContentRequest::React(ReactPayload {
+            target: ContentRef::Comment(CommentId::from(17)),
+            kind: ReactionKind::Confused,
+        });
+        assert_eq!(serde_json::from_str::<ContentRequest>(json).unwrap(), expt);
+    }
+
+    #[test]
+    fn remove_reaction() {
+        let json = r#"{
+            "type": "REMOVE_REACTION",
+            "payload": { "target": { "type": "COMMENT", "id": 17 }, "kind": "HEART" }
+        }"#;
+        let expt = ContentRequest::RemoveReaction(ReactPayload {
+            target: ContentRef::Comment(CommentId::from(17)),
+            kind: ReactionKind::Heart,
+        });
+        assert_eq!(serde_json::from_str::<ContentRequest>(json).unwrap(), expt);
+    }
+
+    #[test]
+    fn unknown_reaction_kind() {
+        let json = r#"{ "target": { "type": "THREAD", "id": 1 }, "kind": "ANGRY" }"#;
+        assert!(serde_json::from_str::<ReactPayload>(json).is_err());
+    }
+
+    #[test]
+    fn report_with_reason() {
+        let json = r#"{
+            "type": "REPORT",
+            "payload": { "target": { "type": "THREAD", "id": 4 }, "reason": "SPAM" }
+        }"#;
+        let expt = ContentRequest::Report(ReportPayload {
+            target: ContentRef::Thread(ThreadId::from(4)),
+            reason: ReportReason::Spam,
+            details: None,
+        });
+        assert_eq!(serde_json::from_str::<ContentRequest>(json).unwrap(), expt);
+    }
+
+    #[test]
+    fn report_other_with_details_is_accepted() {
+        let json = r#"{
+            "target": { "type": "COMMENT", "id": 4 },
+            "reason": "OTHER",
+            "details": "Posts the same link in every thread"
+        }"#;
+        let report: ReportPayload = serde_json::from_str(json).unwrap();
+        assert_eq!(report.reason, ReportReason::Other);
+        assert!(report.validate().is_ok());
+    }
+
+    #[test]
+    fn report_other_without_details_is_rejected() {
+        let json = r#"{ "target": { "type": "COMMENT", "id": 4 }, "reason": "OTHER" }"#;
+        assert!(serde_json::from_str::<ReportPayload>(json).is_err());
+
+        let json = r#"{ "target": { "type": "COMMENT", "id": 4 }, "reason": "OTHER", "details": " " }"#;
+        assert!(serde_json::from_str::<ReportPayload>(json).is_err());
+
+        let report = ReportPayload {
+            target: ContentRef::Comment(CommentId::from(4)),
+            reason: ReportReason::Other,
+            details: None,
+        };
+        assert_eq!(report.validate(), Err(ValidationError::MissingReportDetails));
+    }
+
+    #[test]
+    fn attach_to_comment_envelope() {
+        let json = r#"{ "type": "ATTACH_TO_COMMENT", "payload": { "comment_id": 1, "attachment_id": 2 } }"#;
+        let expt = ContentRequest::AttachToComment(AttachPayload {
+            comment_id: CommentId::from(1),
+            attachment_id: AttachmentId::from(2),
+        });
+        assert_eq!(serde_json::from_str::<ContentRequest>(json).unwrap(), expt);
+    }
+
+    #[test]
+    fn subscription_and_bookmark_tags() {
+        let thread = || ThreadRefPayload {
+            thread_id: ThreadId::from(5),
+        };
+        let requests = vec![
+            ("SUBSCRIBE", ContentRequest::Subscribe(thread())),
+            ("UNSUBSCRIBE", ContentRequest::Unsubscribe(thread())),
+            ("BOOKMARK", ContentRequest::Bookmark(thread())),
+            ("UNBOOKMARK", ContentRequest::Unbookmark(thread())),
+        ];
+        for (tag, expt) in requests {
+            let json = format!(r#"{{ "type": "{}", "payload": {{ "thread_id": 5 }} }}"#, tag);
+            assert_eq!(serde_json::from_str::<ContentRequest>(&json).unwrap(), expt);
+            assert_eq!(serde_json::to_value(&expt).unwrap()["type"], tag);
+        }
+    }
+
+    #[test]
+    fn list_subscriptions_and_bookmarks_default_pagination() {
+        let requests = vec![
+            (
+                "LIST_SUBSCRIPTIONS",
+                ContentRequest::ListSubscriptions(PaginationPayload::default()),
+            ),
+            (
+                "LIST_BOOKMARKS",
+                ContentRequest::ListBookmarks(PaginationPayload::default()),
+            ),
+        ];
+        for (tag, expt) in requests {
+            let json = format!(r#"{{ "type": "{}", "payload": {{}} }}"#, tag);
+            assert_eq!(serde_json::from_str::<ContentRequest>(&json).unwrap(), expt);
+        }
+
+        let json = r#"{ "type": "LIST_BOOKMARKS", "payload": { "page": 2 } }"#;
+        let expt = ContentRequest::ListBookmarks(PaginationPayload {
+            page: 2,
+            ..PaginationPayload::default()
+        });
+        assert_eq!(serde_json::from_str::<ContentRequest>(json).unwrap(), expt);
+    }
+
+    fn draft(target: DraftTarget, title: Option<&str>) -> DraftPayload {
+        DraftPayload {
+            draft_id: None,
+            target,
+            title: title.map(|t| Title::try_from(t.to_owned()).unwrap()),
+            content: CommentContent::try_from("Work in progress".to_owned()).unwrap(),
+            updated_at: 1_540_000_000,
+        }
+    }
+
+    #[test]
+    fn reply_draft_without_title_is_valid() {
+        let target = DraftTarget::ReplyTo {
+            thread_id: ThreadId::from(1),
+            parent_id: None,
+        };
+        assert_eq!(draft(target, None).validate(), Ok(()));
+    }
+
+    #[test]
+    fn reply_draft_with_title_is_invalid() {
+        let target = DraftTarget::ReplyTo {
+            thread_id: ThreadId::from(1),
+            parent_id: Some(CommentId::from(2)),
+        };
+        assert_eq!(
+            draft(target, Some("A title")).validate(),
+            Err(ValidationError::InvalidDraft)
+        );
+    }
+
+    #[test]
+    fn thread_draft_with_or_without_title_is_valid() {
+        let target = DraftTarget::NewThread {
+            category_id: CategoryId::from(1),
+        };
+        assert_eq!(draft(target, Some("A title")).validate(), Ok(()));
+        assert_eq!(draft(target, None).validate(), Ok(()));
+    }
+
+    #[test]
+    fn request_validation_errors() {
+        let target = DraftTarget::ReplyTo {
+            thread_id: ThreadId::from(1),
+            parent_id: None,
+        };
+        let request = ContentRequest::SaveDraft(draft(target, Some("A title")));
+        let expt = vec![ValidationError::InvalidDraft];
+        assert_eq!(request.validation_errors(), expt);
+
+        let request = ContentRequest::SaveDraft(draft(target, None));
+        assert_eq!(request.validation_errors(), vec![]);
+    }
+
+    #[test]
+    fn draft_requests() {
+        let json = r#"{
+            "type": "SAVE_DRAFT",
+            "payload": {
+                "draft_id": null,
+                "target": { "type": "NEW_THREAD", "payload": { "category_id": 1 } },
+                "title": null,
+                "content": "Work in progress",
+                "updated_at": 1540000000
+            }
+        }"#;
+        let target = DraftTarget::NewThread {
+            category_id: CategoryId::from(1),
+        };
+        let expt = ContentRequest::SaveDraft(draft(target, None));
+        assert_eq!(serde_json::from_str::<ContentRequest>(json).unwrap(), expt);
+
+        let json = r#"{ "type": "LIST_DRAFTS" }"#;
+        assert_eq!(
+            serde_json::from_str::<ContentRequest>(json).unwrap(),
+            ContentRequest::ListDrafts(AbsentOrEmpty::Absent)
+        );
+
+        let json = r#"{ "type": "DELETE_DRAFT", "payload": { "draft_id": 9 } }"#;
+        let expt = ContentRequest::DeleteDraft(DraftRefPayload {
+            draft_id: DraftId::from(9),
+        });
+        assert_eq!(serde_json::from_str::<ContentRequest>(json).unwrap(), expt);
+    }
+
+    #[test]
+    fn list_drafts_payloads() {
+        use crate::payloads::AbsentOrEmpty::*;
+
+        let cases = [
+            (r#"{ "type": "LIST_DRAFTS", "payload": {} }"#, Some(Empty)),
+            (r#"{ "type": "LIST_DRAFTS" }"#, Some(Absent)),
+            (r#"{ "type": "LIST_DRAFTS", "payload": null }"#, None),
+            (r#"{ "payload": {}, "type": "LIST_DRAFTS" }"#, Some(Empty)),
+            (r#"{ "payload": null, "type": "LIST_DRAFTS" }"#, None),
+            (r#"{ "type": "LIST_DRAFTS", "payload": { "a": 1 } }"#, None),
+        ];
+        for (json, expt) in &cases {
+            let expt = expt.map(ContentRequest::ListDrafts);
+            let request = serde_json::from_str::<ContentRequest>(json);
+            assert_eq!(request.ok(), expt, "{}", json);
+            assert_eq!(ContentRequest::parse_json(json).ok(), expt, "{}", json);
+            let request = ContentRequest::from_json_lenient(json);
+            assert_eq!(request.ok(), expt, "{}", json);
+        }
+    }
+
+    #[test]
+    fn list_drafts_is_sent_with_a_empty_payload() {
+        for payload in &[AbsentOrEmpty::Absent, AbsentOrEmpty::Empty] {
+            let request = ContentRequest::ListDrafts(*payload);
+            let json = serde_json::to_string(&request).unwrap();
+            assert_eq!(json, r#"{"type":"LIST_DRAFTS","payload":{}}"#);
+        }
+    }
+
+    fn poll(multiple_choice: bool) -> PollPayload {
+        let options = (1..=3)
+            .map(|n| PollOption {
+                id: PollOptionId::from(n),
+                text: Title::try_from(format!("Option {}", n)).unwrap(),
+                votes: 0,
+            })
+            .collect();
+        let question = Title::try_from("Which option?".to_owned()).unwrap();
+        PollPayload::new(question, options, multiple_choice, None).unwrap()
+    }
+
+    fn vote(option_ids: Vec<u32>) -> VotePayload {
+        VotePayload {
+            thread_id: ThreadId::from(1),
+            option_ids: IdList::new(option_ids.into_iter().map(PollOptionId::from).collect()),
+        }
+    }
+
+    #[test]
+    fn single_choice_poll_takes_exactly_one_option() {
+        let poll = poll(false);
+        assert_eq!(vote(vec![1]).validate(&poll), Ok(()));
+        assert_eq!(vote(vec![]).validate(&poll), Err(ValidationError::InvalidVote));
+        assert_eq!(
+            vote(vec![1, 2]).validate(&poll),
+            Err(ValidationError::InvalidVote)
+        );
+    }
+
+    #[test]
+    fn multiple_choice_poll_takes_several_options() {
+        let poll = poll(true);
+        assert_eq!(vote(vec![1, 3]).validate(&poll), Ok(()));
+        assert_eq!(vote(vec![]).validate(&poll), Err(ValidationError::InvalidVote));
+        assert_eq!(
+            vote(vec![2, 2]).validate(&poll),
+            Err(ValidationError::InvalidVote)
+        );
+        assert_eq!(vote(vec![4]).validate(&poll), Err(ValidationError::InvalidVote));
+    }
+
+    #[test]
+    fn create_poll_option_count_bounds() {
+        let request = |options: &[&str]| {
+            serde_json::json!({
+                "type": "CREATE_POLL",
+                "payload": {
+                    "thread_id": 1,
+                    "question": "Which option?",
+                    "options": options,
+                    "multiple_choice": false,
+                    "closes_at": null
+                }
+            })
+        };
+        assert!(serde_json::from_value::<ContentRequest>(request(&["Option 1"])).is_err());
+        assert!(serde_json::from_value::<ContentRequest>(request(&["Option 1", "Option 2"])).is_ok());
+
+        let options: Vec<_> = (1..=11).map(|n| format!("Option {}", n)).collect();
+        let options: Vec<_> = options.iter().map(String::as_str).collect();
+        assert!(serde_json::from_value::<ContentRequest>(request(&options)).is_err());
+    }
+
+    #[test]
+    fn vote_envelope() {
+        let json = r#"{ "type": "VOTE", "payload": { "thread_id": 1, "option_ids": [2, 3] } }"#;
+        let expt = ContentRequest::Vote(vote(vec![2, 3]));
+        assert_eq!(serde_json::from_str::<ContentRequest>(json).unwrap(), expt);
+    }
+
+    #[test]
+    fn comment_without_reply_to() {
+        let json = r#"{
+            "type": "ADD_COMMENT",
+            "payload": { "thread_id": 1, "user_id": null, "parent_id": null, "content": "A reply" }
+        }"#;
+        match serde_json::from_str::<ContentRequest>(json).unwrap() {
+            ContentRequest::AddComment(payload) => {
+                assert_eq!(payload.reply_to, None);
+                let value = serde_json::to_value(&payload).unwrap();
+                assert!(value.get("reply_to").is_none());
+            }
+            _ => panic!("expected a AddComment request"),
+        }
+    }
+
+    #[test]
+    fn comment_reply_to_without_excerpt() {
+        let json = r#"{
+            "type": "ADD_COMMENT",
+            "payload": {
+                "thread_id": 1,
+                "user_id": null,
+                "parent_id": null,
+                "content": "A reply",
+                "reply_to": { "comment_id": 17 }
+            }
+        }"#;
+        match serde_json::from_str::<ContentRequest>(json).unwrap() {
+            ContentRequest::AddComment(payload) => assert_eq!(
+                payload.reply_to,
+                Some(ReplyRef::new(CommentId::from(17), None).unwrap())
+            ),
+            _ => panic!("expected a AddComment request"),
+        }
+    }
+
+    #[test]
+    fn comment_reply_to_with_long_excerpt_is_rejected() {
+        let request = |excerpt: String| {
+            serde_json::json!({
+                "type": "ADD_COMMENT",
+                "payload": {
+                    "thread_id": 1,
+                    "user_id": null,
+                    "parent_id": null,
+                    "content": "A reply",
+                    "reply_to": { "comment_id": 17, "excerpt": excerpt }
+                }
+            })
+        };
+        let excerpt = "å".repeat(MAX_EXCERPT_LEN);
+        assert!(serde_json::from_value::<ContentRequest>(request(excerpt)).is_ok());
+
+        let excerpt = "a".repeat(MAX_EXCERPT_LEN + 1);
+        assert!(serde_json::from_value::<ContentRequest>(request(excerpt)).is_err());
+        assert_eq!(
+            ReplyRef::new(CommentId::from(17), Some("a".repeat(MAX_EXCERPT_LEN + 1))),
+            Err(ValidationError::InvalidExcerpt)
+        );
+    }
+
+    fn update_profile(json: &str) -> Result<UpdateProfilePayload, serde_json::Error> {
+        let json = format!(r#"{{ "type": "UPDATE_PROFILE", "payload": {} }}"#, json);
+        serde_json::from_str::<ContentRequest>(&json).map(|request| match request {
+            ContentRequest::UpdateProfile(payload) => payload,
+            _ => panic!("expected a UpdateProfile request"),
+        })
+    }
+
+    #[test]
+    fn update_profile_absent_fields_are_unchanged() {
+        let payload = update_profile(r#"{ "id": 1 }"#).unwrap();
+        assert_eq!(payload.display_name, Patch::Absent);
+        assert_eq!(payload.bio, Patch::Absent);
+        assert_eq!(payload.avatar_url, Patch::Absent);
+        assert_eq!(serde_json::to_string(&payload).unwrap(), r#"{"id":1}"#);
+    }
+
+    #[test]
+    fn update_profile_null_fields_are_cleared() {
+        let payload =
+            update_profile(r#"{ "id": 1, "display_name": null, "avatar_url": null }"#).unwrap();
+        assert_eq!(payload.display_name, Patch::Null);
+        assert_eq!(payload.bio, Patch::Absent);
+        assert_eq!(payload.avatar_url, Patch::Null);
+        assert_eq!(
+            serde_json::to_string(&payload).unwrap(),
+            r#"{"id":1,"display_name":null,"avatar_url":null}"#
+        );
+    }
+
+    #[test]
+    fn update_profile_present_fields_are_validated() {
+        let payload = update_profile(
+            r#"{
+                "id": null,
+                "display_name": "John Doe",
+                "bio": "Likes forums",
+                "avatar_url": "https://example.com/john.png"
+            }"#,
+        )
+        .unwrap();
+        assert_eq!(
+            payload.display_name,
+            Patch::Set(DisplayName::try_from("John Doe".to_owned()).unwrap())
+        );
+        assert!(!payload.bio.is_absent());
+        assert!(!payload.avatar_url.is_absent());
+
+        assert!(update_profile(r#"{ "id": null, "avatar_url": "javascript:alert(1)" }"#).is_err());
+    }
+
+    #[test]
+    fn update_profile_rejects_overlong_bio() {
+        let bio = "a".repeat(300);
+        let json = format!(r#"{{ "id": null, "bio": "{}" }}"#, bio);
+        assert!(update_profile(&json).is_err());
+    }
+
+    fn edit_thread(json: &str) -> EditThreadPayload {
+        // The payload comes before the type, so it is buffered before it is read
+        let json = format!(r#"{{ "payload": {}, "type": "EDIT_THREAD" }}"#, json);
+        match serde_json::from_str::<ContentRequest>(&json).unwrap() {
+            ContentRequest::EditThread(payload) => payload,
+            _ => panic!("expected a EditThread request"),
+        }
+    }
+
+    #[test]
+    fn edit_thread_description_patch() {
+        let payload = edit_thread(r#"{ "id": 1 }"#);
+        assert_eq!(payload.description, Patch::Absent);
+        assert_eq!(serde_json::to_string(&payload).unwrap(), r#"{"id":1}"#);
+
+        let payload = edit_thread(r#"{ "id": 1, "description": null }"#);
+        assert_eq!(payload.description, Patch::Null);
+        assert_eq!(
+            serde_json::to_string(&payload).unwrap(),
+            r#"{"id":1,"description":null}"#
+        );
+
+        let payload = edit_thread(r#"{ "id": 1, "description": "About something" }"#);
+        let description = Description::try_from("About something".to_owned()).unwrap();
+        assert_eq!(payload.description, Patch::Set(description));
+    }
+
+    #[test]
+    fn thread_format() {
+        let json = r#"{ "category_id": 1, "title": "A thread", "description": "About it" }"#;
+        let payload: AddThreadPayload = serde_json::from_str(json).unwrap();
+        assert_eq!(payload.format, ContentFormat::Markdown);
+
+        for (name, expt) in vec![
+            ("PLAIN_TEXT", ContentFormat::PlainText),
+            ("MARKDOWN", ContentFormat::Markdown),
+        ] {
+            let payload = edit_thread(&format!(r#"{{ "id": 1, "format": "{}" }}"#, name));
+            assert_eq!(payload.format, Some(expt));
+        }
+        assert_eq!(edit_thread(r#"{ "id": 1 }"#).format, None);
+    }
+
+    #[test]
+    fn edit_thread_title_cant_be_cleared() {
+        let payload = edit_thread(r#"{ "id": 1, "title": null, "description": null }"#);
+        assert_eq!(payload.title, None);
+        assert_eq!(payload.description, Patch::Null);
+    }
+
+    #[test]
+    fn edit_patch_is_validated() {
+        let json = format!(r#"{{ "id": 1, "description": "{}" }}"#, "a".repeat(5000));
+        assert!(serde_json::from_str::<EditCategoryPayload>(&json).is_err());
+    }
+
+    fn get_threads(json: &str) -> GetThreadsPayload {
+        serde_json::from_str(json).unwrap()
+    }
+
+    #[test]
+    fn get_threads_with_page_or_cursor_is_valid() {
+        let payload = get_threads(r#"{ "id": 1, "include_hidden": false }"#);
+        assert_eq!(payload.validate(), Ok(()));
+
+        let payload = get_threads(r#"{ "id": 1, "include_hidden": false, "page": 2, "per_page": 10 }"#);
+        assert_eq!(payload.validate(), Ok(()));
+
+        let payload = get_threads(r#"{ "id": 1, "include_hidden": false, "after": "YWJj" }"#);
+        assert_eq!(payload.after, Some(Cursor::try_from("YWJj".to_owned()).unwrap()));
+        assert_eq!(payload.validate(), Ok(()));
+    }
+
+    #[test]
+    fn get_threads_with_page_and_cursor_is_invalid() {
+        let payload = get_threads(r#"{ "id": 1, "include_hidden": false, "page": 2, "after": "YWJj" }"#);
+        assert_eq!(payload.validate(), Err(ValidationError::InvalidPagination));
+
+        let payload =
+            get_threads(r#"{ "id": 1, "include_hidden": false, "per_page": 5, "after": "YWJj" }"#);
+        assert_eq!(payload.validate(), Err(ValidationError::InvalidPagination));
+    }
+
+    #[test]
+    fn get_threads_rejects_bad_cursor() {
+        let json = r#"{ "id": 1, "include_hidden": false, "after": "not a cursor" }"#;
+        assert!(serde_json::from_str::<GetThreadsPayload>(json).is_err());
+    }
+
+    #[test]
+    fn add_thread_without_idempotency_key() {
+        let json = r#"{
+            "type": "ADD_THREAD",
+            "payload": { "category_id": 1, "user_id": null, "title": "A thread", "description": "About it" }
+        }"#;
+        match serde_json::from_str::<ContentRequest>(json).unwrap() {
+            ContentRequest::AddThread(payload) => {
+                assert_eq!(payload.idempotency_key, None);
+                let value = serde_json::to_value(&payload).unwrap();
+                assert!(value.get("idempotency_key").is_none());
+            }
+            _ => panic!("expected a AddThread request"),
+        }
+    }
+
+    #[test]
+    fn add_comment_with_attachments() {
+        let json = r#"{
+            "type": "ADD_COMMENT",
+            "payload": {
+                "thread_id": 1,
+                "content": "A comment",
+                "attachments": ["q2-_Xw8AAAB9aBcDeFgHiJ", "AAAAAAAAAAAAAAAAAAAAAA"]
+            }
+        }"#;
+        match serde_json::from_str::<ContentRequest>(json).unwrap() {
+            ContentRequest::AddComment(payload) => {
+                let refs = vec!["q2-_Xw8AAAB9aBcDeFgHiJ", "AAAAAAAAAAAAAAAAAAAAAA"]
+                    .into_iter()
+                    .map(|r| AttachmentRef::try_from(r.to_owned()).unwrap())
+                    .collect::<Vec<_>>();
+                assert_eq!(payload.attachments.into_inner(), refs);
+            }
+            _ => panic!("expected a AddComment request"),
+        }
+    }
+
+    #[test]
+    fn add_comment_without_attachments() {
+        let json = r#"{ "thread_id": 1, "content": "A comment" }"#;
+        let payload: AddCommentPayload = serde_json::from_str(json).unwrap();
+        assert!(payload.attachments.is_empty());
+        let value = serde_json::to_value(&payload).unwrap();
+        assert!(value.get("attachments").is_none());
+    }
+
+    #[test]
+    fn add_comment_with_invalid_attachment_is_rejected() {
+        let request = |attachment: serde_json::Value| {
+            serde_json::json!({
+                "type": "ADD_COMMENT",
+                "payload": { "thread_id": 1, "content": "A comment", "attachments": [attachment] }
+            })
+        };
+        for attachment in &[
+            serde_json::json!("q2+/Xw8AAAB9aBcDeFgHiJ"),
+            serde_json::json!("short"),
+            serde_json::json!(4),
+        ] {
+            let request = request(attachment.clone());
+            assert!(serde_json::from_value::<ContentRequest>(request).is_err());
+        }
+    }
+
+    #[test]
+    fn add_comment_with_too_many_attachments_is_rejected() {
+        let request = |count: usize| {
+            let attachments = vec!["AAAAAAAAAAAAAAAAAAAAAA"; count];
+            serde_json::json!({
+                "type": "ADD_COMMENT",
+                "payload": { "thread_id": 1, "content": "A comment", "attachments": attachments }
+            })
+        };
+        let request_at_cap = request(MAX_COMMENT_ATTACHMENTS);
+        assert!(serde_json::from_value::<ContentRequest>(request_at_cap).is_ok());
+        let request = request(MAX_COMMENT_ATTACHMENTS + 1);
+        assert!(serde_json::from_value::<ContentRequest>(request).is_err());
+    }
+
+    #[test]
+    fn add_comment_with_idempotency_key() {
+        let key = "123e4567-e89b-12d3-a456-426655440000";
+        let json = format!(
+            r#"{{
+                "type": "ADD_COMMENT",
+                "payload": {{
+                    "thread_id": 1,
+                    "user_id": null,
+                    "parent_id": null,
+                    "content": "A comment",
+                    "idempotency_key": "{}"
+                }}
+            }}"#,
+            key
+        );
+        match serde_json::from_str::<ContentRequest>(&json).unwrap() {
+            ContentRequest::AddComment(payload) => {
+                assert_eq!(
+                    payload.idempotency_key,
+                    Some(IdempotencyKey::try_from(key.to_owned()).unwrap())
+                );
+                let value = serde_json::to_value(&payload).unwrap();
+                assert_eq!(value["idempotency_key"], key);
+            }
+            _ => panic!("expected a AddComment request"),
+        }
+    }
+
+    #[test]
+    fn add_category_with_malformed_idempotency_key() {
+        let json = r#"{
+            "type": "ADD_CATEGORY",
+            "payload": { "title": "A category", "description": "About it", "idempotency_key": "retry-1" }
+        }"#;
+        assert!(serde_json::from_str::<ContentRequest>(json).is_err());
+    }
+
+    #[test]
+    fn fetch_thread_round_trip() {
+        let request = ContentRequest::FetchThread(FetchThreadPayload {
+            thread_id: ThreadId::from(4),
+            include_hidden: false,
+            comments: PaginationPayload {
+                page: 2,
+                per_page: 50,
+            },
+        });
+        let json = serde_json::to_string(&request).unwrap();
+        assert_eq!(serde_json::from_str::<ContentRequest>(&json).unwrap(), request);
+
+        let json = r#"{ "type": "FETCH_THREAD", "payload": { "thread_id": 4, "include_hidden": true } }"#;
+        match serde_json::from_str::<ContentRequest>(json).unwrap() {
+            ContentRequest::FetchThread(payload) => {
+                assert_eq!(payload.comments, PaginationPayload::default())
+            }
+            _ => panic!("expected a FetchThread request"),
+        }
+    }
+
+    #[test]
+    fn unknown_fields_are_rejected() {
+        let json = r#"{ "display_name": "John", "dispaly_name": "Johnny" }"#;
+        assert!(serde_json::from_str::<UpdateProfilePayload>(json).is_err());
+
+        let json = r#"{
+            "thread_id": 1,
+            "question": "Which option?",
+            "options": ["First option", "Second option"],
+            "multiple_choice": false,
+            "anonymous": true
+        }"#;
+        assert!(serde_json::from_str::<NewPollPayload>(json).is_err());
+
+        let json = r#"{ "thread_id": 1, "option_ids": [1], "option_id": 2 }"#;
+        assert!(serde_json::from_str::<VotePayload>(json).is_err());
+
+        let json = r#"{ "thread_id": 1, "include_hidden": false, "comment": { "page": 2 } }"#;
+        assert!(serde_json::from_str::<FetchThreadPayload>(json).is_err());
+
+        let json = r#"{ "target": { "type": "THREAD", "id": 1 }, "reason": "spam" }"#;
+        assert!(serde_json::from_str::<ContentRefPayload>(json).is_err());
+
+        let json = r#"{ "thread_id": 1, "notify": true }"#;
+        assert!(serde_json::from_str::<ThreadRefPayload>(json).is_err());
+
+        let json = r#"{ "thread_id": 1, "pinned": true, "scop": "GLOBAL" }"#;
+        assert!(serde_json::from_str::<PinThreadPayload>(json).is_err());
+
+        let json = r#"{ "comment_id": 1, "exerpt": "Earlier comment" }"#;
+        assert!(serde_json::from_str::<ReplyRef>(json).is_err());
+
+        let json = r#"{ "comment_id": 1, "attachment_id": 2, "position": 0 }"#;
+        assert!(serde_json::from_str::<AttachPayload>(json).is_err());
+
+        let json = r#"{ "target": { "type": "COMMENT", "id": 2 }, "kind": "LIKE", "count": 2 }"#;
+        assert!(serde_json::from_str::<ReactPayload>(json).is_err());
+
+        let json = r#"{
+            "target": { "type": "NEW_THREAD", "payload": { "category_id": 1 } },
+            "content": "A draft",
+            "updated_at": 1540000000,
+            "updatedAt": 1540000000
+        }"#;
+        assert!(serde_json::from_str::<DraftPayload>(json).is_err());
+
+        let json = r#"{ "draft_id": 9, "id": 9 }"#;
+        assert!(serde_json::from_str::<DraftRefPayload>(json).is_err());
+    }
+
+    #[test]
+    fn unknown_fields_are_rejected_inside_envelopes() {
+        let json = r#"{
+            "type": "VOTE",
+            "payload": { "thread_id": 1, "option_ids": [1], "weight": 2 }
+        }"#;
+        assert!(serde_json::from_str::<ContentRequest>(json).is_err());
+
+        let json = r#"{ "type": "VOTE", "payload": { "thread_id": 1, "option_ids": [1] } }"#;
+        assert!(serde_json::from_str::<ContentRequest>(json).is_ok());
+    }
+
+    #[test]
+    fn bulk_delete_mixed_batch() {
+        let json = r#"{
+            "type": "BULK_DELETE",
+            "payload": {
+                "targets": [
+                    { "type": "THREAD", "id": 1 },
+                    { "type": "COMMENT", "id": 2 },
+                    { "type": "COMMENT", "id": 3 }
+                ]
+            }
+        }"#;
+        let request: ContentRequest = serde_json::from_str(json).unwrap();
+        let targets = vec![
+            ContentRef::Thread(ThreadId::from(1)),
+            ContentRef::Comment(CommentId::from(2)),
+            ContentRef::Comment(CommentId::from(3)),
+        ];
+        let expt = ContentRequest::BulkDelete(BulkDeletePayload {
+            targets: BoundedVec::try_from(targets).unwrap(),
+        });
+        assert_eq!(request, expt);
+    }
+
+    #[test]
+    fn bulk_delete_empty_batch() {
+        let json = r#"{ "type": "BULK_DELETE", "payload": { "targets": [] } }"#;
+        assert!(serde_json::from_str::<ContentRequest>(json).is_err());
+        assert_eq!(
+            BoundedVec::<ContentRef, BulkDeleteBounds>::try_from(vec![]),
+            Err(ValidationError::InvalidListLength)
+        );
+    }
+
+    #[test]
+    fn bulk_delete_oversized_batch() {
+        let targets: Vec<_> = (0..=MAX_BULK_DELETE as u32)
+            .map(|id| serde_json::json!({ "type": "COMMENT", "id": id }))
+            .collect();
+        let json = serde_json::json!({
+            "type": "BULK_DELETE",
+            "payload": { "targets": targets }
+        });
+        assert!(serde_json::from_value::<ContentRequest>(json).is_err());
+
+        let targets: Vec<_> = (0..MAX_BULK_DELETE as u32)
+            .map(|id| ContentRef::Comment(CommentId::from(id)))
+            .collect();
+        assert!(BoundedVec::<_, BulkDeleteBounds>::try_from(targets).is_ok());
+    }
+
+    #[test]
+    fn type_tags_are_matched_case_insensitively() {
+        let expt = ContentRequest::SetThreadPinned(PinThreadPayload {
+            thread_id: ThreadId::from(1),
+            pinned: true,
+            scope: PinScope::Category,
+        });
+        for tag in &["SET_THREAD_PINNED", "set_thread_pinned", "SetThreadPinned"] {
+            let json = format!(
+                r#"{{ "type": "{}", "payload": {{ "thread_id": 1, "pinned": true }} }}"#,
+                tag
+            );
+            let request = ContentRequest::from_json_lenient(&json).unwrap();
+            assert_eq!(request, expt, "{}", tag);
+        }
+        let json = serde_json::to_string(&expt).unwrap();
+        assert_eq!(
+            json,
+            r#"{"type":"SET_THREAD_PINNED","payload":{"thread_id":1,"pinned":true}}"#
+        );
+
+        let json = r#"{ "type": "frobnicate" }"#;
+        assert!(ContentRequest::from_json_lenient(json).is_err());
+    }
+}
@@ -1,30 +1,82 @@
 //! The responses a user will get on requests to the content-database
 
 use chrono::naive::NaiveDateTime;
+use crate::content::models::{CommentState, ThreadState};
+use crate::content::requests::{
+    validate_poll_options, ContentFormat, ContentRef, DraftPayload, ReactionKind, ReplyRef,
+};
+use crate::error::ErrorCode;
+use crate::payloads::Paginated;
+use crate::valid::attachment::{FileName, HexDigest, MimeType};
 use crate::valid::fields::*;
 use crate::valid::ids::*;
+use crate::valid::ValidationError;
 
 /// All the successful responses to a `ContentRequest`
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, PartialEq, Debug)]
+#[cfg_attr(feature = "schemars", derive(JsonSchema))]
+#[cfg_attr(feature = "utoipa", derive(ToSchema))]
 #[serde(
     tag = "type",
     content = "payload",
     rename_all = "SCREAMING_SNAKE_CASE"
 )]
+#[cfg_attr(feature = "forward-compat", serde(remote = "Self"))]
 pub enum ContentSuccess {
     Category(CategoryPayload),
-    Categories(Vec<CategoryPayload>),
-    Thread(ThreadPayload),
-    Threads(Vec<ThreadPayload>),
-    Comment(CommentPayload),
-    Comments(Vec<CommentPayload>),
+    Categories(Paginated<CategoryPayload>),
+    Thread(ThreadState),
+    Threads(Paginated<ThreadState>),
+    ThreadPage(ThreadPagePayload),
+    Comment(CommentState),
+    Comments(Paginated<CommentState>),
     User(UserPayload),
-    Users(Vec<UserPayload>),
+    Users(Paginated<UserPayload>),
     SearchResult(SearchResultsPayload),
+    Subscriptions(Paginated<SubscriptionPayload>),
+    Bookmarks(Paginated<BookmarkPayload>),
+    Drafts(Vec<DraftPayload>),
+    EditHistory(EditHistoryPayload),
+    /// A response of a type which is not known to this version of the crate
+    ///
+    /// It can't be serialized, so a unknown response can only be logged and
+    /// ignored.
+    #[cfg(feature = "forward-compat")]
+    #[serde(skip)]
+    Unknown {
+        type_tag: String,
+        payload: serde_json::Value,
+    },
 }
 
+impl ContentSuccess {
+    /// The type tags of the responses
+    pub const TAGS: &'static [&'static str] = &[
+        "CATEGORY",
+        "CATEGORIES",
+        "THREAD",
+        "THREADS",
+        "THREAD_PAGE",
+        "COMMENT",
+        "COMMENTS",
+        "USER",
+        "USERS",
+        "SEARCH_RESULT",
+        "SUBSCRIPTIONS",
+        "BOOKMARKS",
+        "DRAFTS",
+        "EDIT_HISTORY",
+    ];
+}
+
+#[cfg(feature = "forward-compat")]
+impl_forward_compatible!(ContentSuccess);
+
 /// All the unsuccessful responses to a `ContentRequest`
 #[derive(Fail, Serialize, Deserialize, PartialEq, Clone, Copy, Debug)]
+#[cfg_attr(feature = "schemars", derive(JsonSchema))]
+#[cfg_attr(feature = "utoipa", derive(ToSchema))]
+#[non_exhaustive]
 pub enum ContentError {
     #[fail(display = "content was not found")]
     MissingContent,
@@ -38,51 +90,626 @@ pub enum ContentError {
     InvalidId,
     #[fail(display = "invalid content")]
     InvalidContent,
+    #[fail(display = "the comment which is replied to was not found")]
+    ReplyTargetMissing,
+}
+
+impl ContentError {
+    /// The machine-stable code of the error
+    pub fn code(&self) -> ErrorCode {
+        match self {
+            ContentError::MissingContent => ErrorCode::ContentMissingContent,
+            ContentError::HiddenContent => ErrorCode::ContentHiddenContent,
+            ContentError::InternalServerError => ErrorCode::ContentInternalServerError,
+            ContentError::InvalidSearchQuery => ErrorCode::ContentInvalidSearchQuery,
+            ContentError::InvalidId => ErrorCode::ContentInvalidId,
+            ContentError::InvalidContent => ErrorCode::ContentInvalidContent,
+            ContentError::ReplyTargetMissing => ErrorCode::ContentReplyTargetMissing,
+        }
+    }
+
+    /// The error which has the given code, if it is a error of this kind
+    pub fn from_code(code: ErrorCode) -> Option<Self> {
+        match code {
+            ErrorCode::ContentMissingContent => Some(ContentError::MissingContent),
+            ErrorCode::ContentHiddenContent => Some(ContentError::HiddenContent),
+            ErrorCode::ContentInternalServerError => Some(ContentError::InternalServerError),
+            ErrorCode::ContentInvalidSearchQuery => Some(ContentError::InvalidSearchQuery),
+            ErrorCode::ContentInvalidId => Some(ContentError::InvalidId),
+            ErrorCode::ContentInvalidContent => Some(ContentError::InvalidContent),
+            ErrorCode::ContentReplyTargetMissing => Some(ContentError::ReplyTargetMissing),
+            _ => None,
+        }
+    }
 }
 
 #[derive(Serialize, Deserialize, Debug, PartialEq)]
+#[cfg_attr(feature = "schemars", derive(JsonSchema))]
+#[cfg_attr(feature = "utoipa", derive(ToSchema))]
+#[serde(rename_all = "snake_case")]
 pub struct UserPayload {
     pub id: UserId,
     pub username: Username,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
     pub description: Option<Description>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
     pub avatar: Option<String>,
 }
 
 #[derive(Serialize, Deserialize, Debug, PartialEq)]
+#[cfg_attr(feature = "schemars", derive(JsonSchema))]
+#[cfg_attr(feature = "utoipa", derive(ToSchema))]
+#[serde(rename_all = "snake_case")]
 pub struct CategoryPayload {
     pub id: CategoryId,
     pub title: Title,
     pub description: Description,
+    #[serde(default)]
+    pub format: ContentFormat,
     pub hidden: bool,
 }
 
 #[derive(Serialize, Deserialize, Debug, PartialEq)]
+#[cfg_attr(feature = "schemars", derive(JsonSchema))]
+#[cfg_attr(feature = "utoipa", derive(ToSchema))]
+#[serde(rename_all = "snake_case")]
 pub struct ThreadPayload {
     pub id: ThreadId,
     pub category_id: CategoryId,
     pub user_id: UserId,
     pub title: Title,
     pub description: Description,
+    #[serde(default)]
+    pub format: ContentFormat,
+    #[serde(with = "crate::timestamp::naive_seconds")]
+    #[cfg_attr(feature = "schemars", schemars(with = "chrono::NaiveDateTime"))]
     pub timestamp: NaiveDateTime,
     pub hidden: bool,
+    /// Pinned threads are listed before the other threads of a category
+    #[serde(default)]
+    pub pinned: bool,
+    #[serde(default)]
+    pub reactions: Vec<ReactionCount>,
+    /// When the thread was last edited (seconds since the unix epoch)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub edited_at: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub poll: Option<PollPayload>,
+}
+
+/// A poll which belongs to a thread
+///
+/// A poll always has between 2 and 10 options.
+#[derive(Serialize, Debug, PartialEq)]
+#[cfg_attr(feature = "schemars", derive(JsonSchema))]
+#[cfg_attr(feature = "utoipa", derive(ToSchema))]
+#[serde(rename_all = "snake_case")]
+pub struct PollPayload {
+    pub question: Title,
+    pub options: Vec<PollOption>,
+    pub multiple_choice: bool,
+    /// When the poll stops accepting votes (seconds since the unix epoch)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub closes_at: Option<u64>,
+}
+
+impl PollPayload {
+    pub fn new(
+        question: Title,
+        options: Vec<PollOption>,
+        multiple_choice: bool,
+        closes_at: Option<u64>,
+    ) -> Result<Self, ValidationError> {
+        validate_poll_options(options.len())?;
+        Ok(PollPayload {
+            question,
+            options,
+            multiple_choice,
+            closes_at,
+        })
+    }
+}
+
+impl<'de> serde::de::Deserialize<'de> for PollPayload {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::de::Deserializer<'de>,
+    {
+        #[derive(Deserialize)]
+        #[serde(rename_all = "snake_case")]
+        struct RawPollPayload {
+            question: Title,
+            options: Vec<PollOption>,
+            multiple_choice: bool,
+            #[serde(default)]
+            closes_at: Option<u64>,
+        }
+
+        let raw = RawPollPayload::deserialize(deserializer)?;
+        PollPayload::new(
+            raw.question,
+            raw.options,
+            raw.multiple_choice,
+            raw.closes_at,
+        )
+        .map_err(serde::de::Error::custom)
+    }
 }
 
 #[derive(Serialize, Deserialize, Debug, PartialEq)]
+#[cfg_attr(feature = "schemars", derive(JsonSchema))]
+#[cfg_attr(feature = "utoipa", derive(ToSchema))]
+#[serde(rename_all = "snake_case")]
+pub struct PollOption {
+    pub id: PollOptionId,
+    pub text: Title,
+    pub votes: u32,
+}
+
+/// A thread together with a page of its comments
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+#[cfg_attr(feature = "schemars", derive(JsonSchema))]
+#[cfg_attr(feature = "utoipa", derive(ToSchema))]
+#[serde(rename_all = "snake_case")]
+pub struct ThreadPagePayload {
+    pub thread: ThreadPayload,
+    pub comments: Paginated<CommentPayload>,
+}
+
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+#[cfg_attr(feature = "schemars", derive(JsonSchema))]
+#[cfg_attr(feature = "utoipa", derive(ToSchema))]
+#[serde(rename_all = "snake_case")]
 pub struct CommentPayload {
     pub id: CommentId,
     pub thread_id: ThreadId,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
     pub parent_id: Option<CommentId>,
     pub user_id: UserId,
     pub content: CommentContent,
+    #[serde(default)]
+    pub format: ContentFormat,
+    #[serde(with = "crate::timestamp::naive_seconds")]
+    #[cfg_attr(feature = "schemars", schemars(with = "chrono::NaiveDateTime"))]
     pub timestamp: NaiveDateTime,
     pub hidden: bool,
+    #[serde(default)]
+    pub reactions: Vec<ReactionCount>,
+    /// When the comment was last edited (seconds since the unix epoch)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub edited_at: Option<u64>,
+    #[serde(default)]
+    pub attachments: Vec<AttachmentMeta>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reply_to: Option<ReplyRef>,
+}
+
+/// The metadata of a uploaded attachment
+///
+/// The attachment itself is stored in the object storage.
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+#[cfg_attr(feature = "schemars", derive(JsonSchema))]
+#[cfg_attr(feature = "utoipa", derive(ToSchema))]
+#[serde(rename_all = "snake_case")]
+pub struct AttachmentMeta {
+    pub id: AttachmentId,
+    pub filename: FileName,
+    pub mime: MimeType,
+    pub size_bytes: u64,
+    pub sha256: HexDigest,
+}
+
+/// The amount of reactions of a kind which a thread or comment has received
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+#[cfg_attr(feature = "schemars", derive(JsonSchema))]
+#[cfg_attr(feature = "utoipa", derive(ToSchema))]
+#[serde(rename_all = "snake_case")]
+pub struct ReactionCount {
+    pub kind: ReactionKind,
+    pub count: u32,
+}
+
+/// A thread which the user is subscribed to
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+#[cfg_attr(feature = "schemars", derive(JsonSchema))]
+#[cfg_attr(feature = "utoipa", derive(ToSchema))]
+#[serde(rename_all = "snake_case")]
+pub struct SubscriptionPayload {
+    pub thread: ThreadPayload,
+    /// When the user subscribed to the thread (seconds since the unix epoch)
+    pub subscribed_at: u64,
+}
+
+/// A thread which the user has bookmarked
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+#[cfg_attr(feature = "schemars", derive(JsonSchema))]
+#[cfg_attr(feature = "utoipa", derive(ToSchema))]
+#[serde(rename_all = "snake_case")]
+pub struct BookmarkPayload {
+    pub thread: ThreadPayload,
+    /// When the user bookmarked the thread (seconds since the unix epoch)
+    pub bookmarked_at: u64,
+}
+
+/// The previous revisions of a thread or comment, oldest first
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+#[cfg_attr(feature = "schemars", derive(JsonSchema))]
+#[cfg_attr(feature = "utoipa", derive(ToSchema))]
+#[serde(rename_all = "snake_case")]
+pub struct EditHistoryPayload {
+    pub target: ContentRef,
+    pub revisions: Vec<RevisionPayload>,
+}
+
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+#[cfg_attr(feature = "schemars", derive(JsonSchema))]
+#[cfg_attr(feature = "utoipa", derive(ToSchema))]
+#[serde(rename_all = "snake_case")]
+pub struct RevisionPayload {
+    pub revision: u32,
+    pub edited_by: UserId,
+    /// When the revision was made (seconds since the unix epoch)
+    pub edited_at: u64,
+    pub content: CommentContent,
 }
 
-/// A search result which contains all the elements that matched the search
+/// A page of elements that matched a search
+pub type SearchResultsPayload = Paginated<SearchHit>;
+
+/// A single element that matched a search
 #[derive(Serialize, Deserialize, PartialEq, Debug)]
-pub struct SearchResultsPayload {
-    pub categories: Vec<CategoryPayload>,
-    pub threads: Vec<ThreadPayload>,
-    pub comments: Vec<CommentPayload>,
-    pub users: Vec<UserPayload>,
+#[cfg_attr(feature = "schemars", derive(JsonSchema))]
+#[cfg_attr(feature = "utoipa", derive(ToSchema))]
+#[serde(
+    tag = "type",
+    content = "payload",
+    rename_all = "SCREAMING_SNAKE_CASE"
+)]
+pub enum SearchHit {
+    Category(CategoryPayload),
+    Thread(ThreadPayload),
+    Comment(CommentPayload),
+    User(UserPayload),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::payloads::PaginationPayload;
+    use std::convert::TryFrom;
+
+    fn comment(edited_at: Option<u64>) -> CommentPayload {
+        CommentPayload {
+            id: CommentId::from(1),
+            thread_id: ThreadId::from(2),
+            parent_id: None,
+            user_id: UserId::from(3),
+            content: CommentContent::try_from("A comment".to_owned()).unwrap(),
+            format: ContentFormat::PlainText,
+            timestamp: NaiveDateTime::from_timestamp(1_540_000_000, 0),
+            hidden: false,
+            reactions: vec![],
+            edited_at,
+            attachments: vec![],
+            reply_to: None,
+        }
+    }
+
+    #[test]
+    fn edited_at_round_trip() {
+        let edited = comment(Some(1_540_000_100));
+        let json = serde_json::to_string(&edited).unwrap();
+        assert_eq!(serde_json::from_str::<CommentPayload>(&json).unwrap(), edited);
+
+        let unedited = comment(None);
+        let value = serde_json::to_value(&unedited).unwrap();
+        assert!(value.get("edited_at").is_none());
+        assert_eq!(serde_json::from_value::<CommentPayload>(value).unwrap(), unedited);
+    }
+
+    #[test]
+    fn comment_attachments() {
+        let mut with_attachment = comment(None);
+        with_attachment.attachments.push(AttachmentMeta {
+            id: AttachmentId::from(5),
+            filename: FileName::try_from("cat.png".to_owned()).unwrap(),
+            mime: MimeType::ImagePng,
+            size_bytes: 1024,
+            sha256: HexDigest::try_from("ab".repeat(32)).unwrap(),
+        });
+        let value = serde_json::to_value(&with_attachment).unwrap();
+        assert_eq!(value["attachments"][0]["mime"], "image/png");
+        assert_eq!(
+            serde_json::from_value::<CommentPayload>(value).unwrap(),
+            with_attachment
+        );
+    }
+
+    #[test]
+    fn edit_history_preserves_revision_order() {
+        let revisions = (1..=3)
+            .map(|revision| RevisionPayload {
+                revision,
+                edited_by: UserId::from(3),
+                edited_at: 1_540_000_000 + u64::from(revision),
+                content: CommentContent::try_from(format!("Revision {}", revision)).unwrap(),
+            })
+            .collect();
+        let history = ContentSuccess::EditHistory(EditHistoryPayload {
+            target: ContentRef::Comment(CommentId::from(1)),
+            revisions,
+        });
+        let json = serde_json::to_string(&history).unwrap();
+        match serde_json::from_str::<ContentSuccess>(&json).unwrap() {
+            ContentSuccess::EditHistory(payload) => {
+                let order: Vec<_> = payload.revisions.iter().map(|r| r.revision).collect();
+                assert_eq!(order, vec![1, 2, 3]);
+            }
+            _ => panic!("expected a EditHistory response"),
+        }
+    }
+
+    #[test]
+    fn thread_pinned_defaults_to_false() {
+        let json = r#"{
+            "id": 1,
+            "category_id": 2,
+            "user_id": 3,
+            "title": "A thread",
+            "description": "About something",
+            "timestamp": "2018-10-20T12:00:00",
+            "hidden": false
+        }"#;
+        let thread: ThreadPayload = serde_json::from_str(json).unwrap();
+        assert!(!thread.pinned);
+        assert_eq!(thread.format, ContentFormat::Markdown);
+        assert!(thread.reactions.is_empty());
+        assert_eq!(thread.edited_at, None);
+        assert_eq!(thread.poll, None);
+    }
+
+    #[test]
+    fn comment_reactions() {
+        let json = r#"{
+            "id": 1,
+            "thread_id": 2,
+            "parent_id": null,
+            "user_id": 3,
+            "content": "A comment",
+            "timestamp": "2018-10-20T12:00:00",
+            "hidden": false
+        }"#;
+        let comment: CommentPayload = serde_json::from_str(json).unwrap();
+        assert!(comment.reactions.is_empty());
+        assert!(comment.attachments.is_empty());
+
+        let json = r#"{
+            "id": 1,
+            "thread_id": 2,
+            "parent_id": null,
+            "user_id": 3,
+            "content": "A comment",
+            "timestamp": "2018-10-20T12:00:00",
+            "hidden": false,
+            "reactions": [{ "kind": "LAUGH", "count": 4 }]
+        }"#;
+        let comment: CommentPayload = serde_json::from_str(json).unwrap();
+        assert_eq!(
+            comment.reactions,
+            vec![ReactionCount {
+                kind: ReactionKind::Laugh,
+                count: 4
+            }]
+        );
+    }
+
+    #[test]
+    fn fetch_responses_are_states() {
+        use crate::content::models::{ContentState, Tombstone};
+        use chrono::{TimeZone, Utc};
+
+        let response = ContentSuccess::Thread(ContentState::Live(thread(None)));
+        let value = serde_json::to_value(&response).unwrap();
+        assert_eq!(value["type"], "THREAD");
+        assert_eq!(value["payload"]["type"], "LIVE");
+        assert_eq!(value["payload"]["payload"]["title"], "A thread");
+
+        let tombstone = Tombstone {
+            id: ContentRef::Comment(CommentId::from(2)),
+            deleted_by: UserId::from(5),
+            deleted_at: Utc.timestamp(1_540_000_200, 0),
+            reason: None,
+        };
+        let page = Paginated::new(
+            vec![
+                ContentState::Live(comment(None)),
+                ContentState::Deleted(tombstone),
+            ],
+            PaginationPayload::default(),
+            2,
+        );
+        let response = ContentSuccess::Comments(page);
+        let json = serde_json::to_string(&response).unwrap();
+        assert_eq!(
+            serde_json::from_str::<ContentSuccess>(&json).unwrap(),
+            response
+        );
+        let value = serde_json::to_value(&response).unwrap();
+        assert_eq!(value["payload"]["items"][1]["type"], "DELETED");
+    }
+
+    fn poll(options: usize, multiple_choice: bool) -> Result<PollPayload, ValidationError> {
+        let options = (1..=options as u32)
+            .map(|n| PollOption {
+                id: PollOptionId::from(n),
+                text: Title::try_from(format!("Option {}", n)).unwrap(),
+                votes: n,
+            })
+            .collect();
+        let question = Title::try_from("Which option?".to_owned()).unwrap();
+        PollPayload::new(question, options, multiple_choice, None)
+    }
+
+    fn thread(poll: Option<PollPayload>) -> ThreadPayload {
+        ThreadPayload {
+            id: ThreadId::from(1),
+            category_id: CategoryId::from(2),
+            user_id: UserId::from(3),
+            title: Title::try_from("A thread".to_owned()).unwrap(),
+            description: Description::try_from("About something".to_owned()).unwrap(),
+            format: ContentFormat::PlainText,
+            timestamp: NaiveDateTime::from_timestamp(1_540_000_000, 0),
+            hidden: false,
+            pinned: false,
+            reactions: vec![],
+            edited_at: None,
+            poll,
+        }
+    }
+
+    #[test]
+    fn poll_option_count_bounds() {
+        assert_eq!(poll(0, false), Err(ValidationError::InvalidPoll));
+        assert_eq!(poll(1, false), Err(ValidationError::InvalidPoll));
+        assert!(poll(2, false).is_ok());
+        assert!(poll(10, true).is_ok());
+        assert_eq!(poll(11, true), Err(ValidationError::InvalidPoll));
+    }
+
+    #[test]
+    fn poll_with_one_option_is_not_deserialized() {
+        let json = r#"{
+            "question": "Which option?",
+            "options": [{ "id": 1, "text": "Option 1", "votes": 0 }],
+            "multiple_choice": false,
+            "closes_at": null
+        }"#;
+        assert!(serde_json::from_str::<PollPayload>(json).is_err());
+    }
+
+    #[test]
+    fn thread_round_trip_with_and_without_poll() {
+        let with_poll = thread(Some(poll(3, false).unwrap()));
+        let json = serde_json::to_string(&with_poll).unwrap();
+        assert_eq!(serde_json::from_str::<ThreadPayload>(&json).unwrap(), with_poll);
+
+        let without_poll = thread(None);
+        let json = serde_json::to_string(&without_poll).unwrap();
+        assert_eq!(
+            serde_json::from_str::<ThreadPayload>(&json).unwrap(),
+            without_poll
+        );
+    }
+
+    #[test]
+    fn search_result_round_trip() {
+        let results = ContentSuccess::SearchResult(SearchResultsPayload::new(
+            vec![
+                SearchHit::Thread(thread(None)),
+                SearchHit::Comment(comment(None)),
+            ],
+            PaginationPayload::default(),
+            12,
+        ));
+        let json = serde_json::to_string(&results).unwrap();
+        match serde_json::from_str::<ContentSuccess>(&json).unwrap() {
+            ContentSuccess::SearchResult(page) => {
+                assert_eq!(page.items.len(), 2);
+                assert_eq!(page.items[0], SearchHit::Thread(thread(None)));
+                assert_eq!(page.total, Some(12));
+                assert!(!page.has_next());
+            }
+            _ => panic!("expected a SearchResult response"),
+        }
+    }
+
+    #[test]
+    fn comment_reply_to_round_trip() {
+        let mut reply = comment(None);
+        reply.reply_to = Some(
+            ReplyRef::new(CommentId::from(17), Some("Earlier comment".to_owned())).unwrap(),
+        );
+        let json = serde_json::to_string(&reply).unwrap();
+        assert_eq!(serde_json::from_str::<CommentPayload>(&json).unwrap(), reply);
+    }
+
+    #[test]
+    fn thread_page_round_trip() {
+        let comments = vec![comment(None), comment(Some(1_540_000_100))];
+        let response = ContentSuccess::ThreadPage(ThreadPagePayload {
+            thread: thread(Some(poll(2, false).unwrap())),
+            comments: Paginated::new(comments, PaginationPayload::default(), 2),
+        });
+        let value = serde_json::to_value(&response).unwrap();
+        assert_eq!(value["type"], "THREAD_PAGE");
+        assert_eq!(value["payload"]["comments"]["total"], 2);
+        assert_eq!(serde_json::from_value::<ContentSuccess>(value).unwrap(), response);
+    }
+
+    #[cfg(feature = "forward-compat")]
+    #[test]
+    fn unknown_response_keeps_the_payload() {
+        let json = r#"{ "type": "TRENDING", "payload": { "threads": [1, 2] } }"#;
+        let response: ContentSuccess = serde_json::from_str(json).unwrap();
+        let expt = ContentSuccess::Unknown {
+            type_tag: "TRENDING".to_owned(),
+            payload: serde_json::json!({ "threads": [1, 2] }),
+        };
+        assert_eq!(response, expt);
+
+        let json = r#"{ "type": "TRENDING" }"#;
+        match serde_json::from_str::<ContentSuccess>(json).unwrap() {
+            ContentSuccess::Unknown { payload, .. } => assert!(payload.is_null()),
+            response => panic!("expected a unknown response, got {:?}", response),
+        }
+    }
+
+    #[cfg(feature = "forward-compat")]
+    #[test]
+    fn known_response_is_typed() {
+        let json = r#"{
+            "type": "USER",
+            "payload": { "id": 1, "username": "john", "description": null, "avatar": null }
+        }"#;
+        match serde_json::from_str::<ContentSuccess>(json).unwrap() {
+            ContentSuccess::User(user) => assert_eq!(&*user.username, "john"),
+            response => panic!("expected a user, got {:?}", response),
+        }
+
+        // A known type with a invalid payload is still a error
+        let json = r#"{ "type": "USER", "payload": { "id": "one" } }"#;
+        assert!(serde_json::from_str::<ContentSuccess>(json).is_err());
+
+        // A known type without a payload is a error, not a unknown response
+        for tag in ContentSuccess::TAGS {
+            let json = format!(r#"{{ "type": "{}" }}"#, tag);
+            let response = serde_json::from_str::<ContentSuccess>(&json);
+            assert!(response.is_err(), "{}", tag);
+        }
+    }
+
+    #[cfg(feature = "forward-compat")]
+    #[test]
+    fn unknown_response_cant_be_serialized() {
+        let response = ContentSuccess::Unknown {
+            type_tag: "TRENDING".to_owned(),
+            payload: serde_json::Value::Null,
+        };
+        assert!(serde_json::to_string(&response).is_err());
+
+        let response = crate::auth::responses::AuthSuccess::Unknown {
+            type_tag: "LOCKED_OUT".to_owned(),
+            payload: serde_json::Value::Null,
+        };
+        assert!(serde_json::to_string(&response).is_err());
+    }
+
+    #[cfg(feature = "forward-compat")]
+    #[test]
+    fn requests_stay_strict() {
+        use crate::content::requests::ContentRequest;
+
+        let json = r#"{ "type": "TRENDING", "payload": {} }"#;
+        assert!(serde_json::from_str::<ContentRequest>(json).is_err());
+    }
 }
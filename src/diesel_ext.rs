@@ -0,0 +1,197 @@
+//! `ToSql` and `FromSql` of diesel, for storing the ids and validated types
+//! in the columns of a table
+//!
+//! | type                                                  | sql type               |
+//! |-------------------------------------------------------|------------------------|
+//! | the ids                                               | `Integer` or `BigInt`  |
+//! | `Username`, `Email`, `Title`, `HexDigest` and `Token` | `Text`                 |
+//!
+//! The types derive `AsExpression` and `FromSqlRow` themselves (which must
+//! be on the type), with the sql types of the table above.
+//!
+//! A row which is read is validated like any other input, and a invalid row
+//! is read as a error, and not as a invalid value. A `Title` is stored
+//! escaped, and is read with `Title::from_escaped` so that it isn't escaped
+//! again. A stored `Token` must only contain the characters of a `b64token`,
+//! like the token of a `Authorization` header.
+//!
+//! A id which doesn't fit in a `Integer` can't be written as one, and a
+//! negative (or too large) number is read as a error.
+
+use crate::valid::attachment::HexDigest;
+use crate::valid::fields::{Email, Username};
+use crate::valid::ids::{
+    AttachmentId, CategoryId, CommentId, DraftId, Id, PollOptionId, ThreadId, UserId,
+};
+use crate::valid::title::Title;
+use crate::valid::token::Token;
+use crate::valid::ValidationError;
+use diesel::backend::Backend;
+use diesel::deserialize::{self, FromSql};
+use diesel::serialize::{self, Output, ToSql};
+use diesel::sql_types::{BigInt, Integer, Text};
+use failure::Fail;
+use std::convert::TryFrom;
+use std::io::Write;
+
+macro_rules! impl_diesel_id {
+    ($($id:ty),*) => {
+        $(
+            impl<DB> ToSql<Integer, DB> for $id
+            where
+                DB: Backend,
+                i32: ToSql<Integer, DB>,
+            {
+                fn to_sql<W: Write>(&self, out: &mut Output<W, DB>) -> serialize::Result {
+                    let n = i32::try_from(self.inner()).map_err(|_| ValidationError::InvalidId)?;
+                    <i32 as ToSql<Integer, DB>>::to_sql(&n, out)
+                }
+            }
+
+            impl<DB> ToSql<BigInt, DB> for $id
+            where
+                DB: Backend,
+                i64: ToSql<BigInt, DB>,
+            {
+                fn to_sql<W: Write>(&self, out: &mut Output<W, DB>) -> serialize::Result {
+                    <i64 as ToSql<BigInt, DB>>::to_sql(&i64::from(self.inner()), out)
+                }
+            }
+
+            impl<DB> FromSql<Integer, DB> for $id
+            where
+                DB: Backend,
+                i32: FromSql<Integer, DB>,
+            {
+                fn from_sql(bytes: Option<&DB::RawValue>) -> deserialize::Result<Self> {
+                    let n = <i32 as FromSql<Integer, DB>>::from_sql(bytes)?;
+                    let id = u32::try_from(n).map_err(|_| ValidationError::InvalidId)?;
+                    Ok(<$id>::from(id))
+                }
+            }
+
+            impl<DB> FromSql<BigInt, DB> for $id
+            where
+                DB: Backend,
+                i64: FromSql<BigInt, DB>,
+            {
+                fn from_sql(bytes: Option<&DB::RawValue>) -> deserialize::Result<Self> {
+                    let n = <i64 as FromSql<BigInt, DB>>::from_sql(bytes)?;
+                    let id = u32::try_from(n).map_err(|_| ValidationError::InvalidId)?;
+                    Ok(<$id>::from(id))
+                }
+            }
+        )*
+    };
+}
+
+macro_rules! impl_diesel_text {
+    ($($ty:ty => $validate:expr),*) => {
+        $(
+            impl<DB> ToSql<Text, DB> for $ty
+            where
+                DB: Backend,
+                str: ToSql<Text, DB>,
+            {
+                fn to_sql<W: Write>(&self, out: &mut Output<W, DB>) -> serialize::Result {
+                    <str as ToSql<Text, DB>>::to_sql(AsRef::<str>::as_ref(self), out)
+                }
+            }
+
+            impl<DB> FromSql<Text, DB> for $ty
+            where
+                DB: Backend,
+                String: FromSql<Text, DB>,
+            {
+                fn from_sql(bytes: Option<&DB::RawValue>) -> deserialize::Result<Self> {
+                    let s = <String as FromSql<Text, DB>>::from_sql(bytes)?;
+                    let validate: fn(String) -> deserialize::Result<$ty> = $validate;
+                    validate(s)
+                }
+            }
+        )*
+    };
+}
+
+impl_diesel_id!(
+    CategoryId,
+    ThreadId,
+    CommentId,
+    UserId,
+    DraftId,
+    AttachmentId,
+    PollOptionId
+);
+
+impl_diesel_text!(
+    Username => |s| Ok(Username::try_from(s)?),
+    Email => |s| Ok(Email::try_from(s)?),
+    Title => |s| Ok(Title::from_escaped(&s)?),
+    HexDigest => |s| Ok(HexDigest::try_from(s)?),
+    Token => |s| Token::from_b64token(&s).map_err(|e| e.compat().into())
+);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use diesel::pg::Pg;
+
+    #[test]
+    fn id_to_and_from_sql() {
+        let mut out: Output<Vec<u8>, Pg> = Output::test();
+        ToSql::<Integer, Pg>::to_sql(&UserId::from(42), &mut out).unwrap();
+        assert_eq!(&out[..], &[0, 0, 0, 42]);
+        let id: UserId = FromSql::<Integer, Pg>::from_sql(Some(&out[..])).unwrap();
+        assert_eq!(id, UserId::from(42));
+
+        let mut out: Output<Vec<u8>, Pg> = Output::test();
+        ToSql::<BigInt, Pg>::to_sql(&ThreadId::from(u32::max_value()), &mut out).unwrap();
+        let id: ThreadId = FromSql::<BigInt, Pg>::from_sql(Some(&out[..])).unwrap();
+        assert_eq!(id, ThreadId::from(u32::max_value()));
+
+        // A negative number is not a id
+        let negative = [0xff; 4];
+        let id: Result<UserId, _> = FromSql::<Integer, Pg>::from_sql(Some(&negative[..]));
+        assert!(id.is_err());
+
+        // Nor is a id which doesn't fit in a `Integer` written as one
+        let mut out: Output<Vec<u8>, Pg> = Output::test();
+        let too_large = ThreadId::from(u32::max_value());
+        assert!(ToSql::<Integer, Pg>::to_sql(&too_large, &mut out).is_err());
+    }
+
+    #[test]
+    fn username_and_email_from_sql() {
+        let name: Username = FromSql::<Text, Pg>::from_sql(Some(&b"john"[..])).unwrap();
+        assert_eq!(&*name, "john");
+        let name: Result<Username, _> = FromSql::<Text, Pg>::from_sql(Some(&b"jo"[..]));
+        assert!(name.is_err());
+
+        let email: Email = FromSql::<Text, Pg>::from_sql(Some(&b"john@example.com"[..])).unwrap();
+        assert_eq!(&*email, "john@example.com");
+        let email: Result<Email, _> = FromSql::<Text, Pg>::from_sql(Some(&b"john"[..]));
+        assert!(email.is_err());
+    }
+
+    #[test]
+    fn title_to_and_from_sql() {
+        // The title is stored escaped, and isn't escaped again when it's read
+        let title = Title::try_from("A title with <script>").unwrap();
+        let mut out: Output<Vec<u8>, Pg> = Output::test();
+        ToSql::<Text, Pg>::to_sql(&title, &mut out).unwrap();
+        assert_eq!(&out[..], &b"A title with &lt;script&gt;"[..]);
+        let read: Title = FromSql::<Text, Pg>::from_sql(Some(&out[..])).unwrap();
+        assert_eq!(read, title);
+
+        let read: Result<Title, _> = FromSql::<Text, Pg>::from_sql(Some(&b"Hi"[..]));
+        assert!(read.is_err());
+    }
+
+    #[test]
+    fn token_from_sql() {
+        let token: Token = FromSql::<Text, Pg>::from_sql(Some(&b"c2VjcmV0=="[..])).unwrap();
+        assert_eq!(token, Token::new("c2VjcmV0=="));
+        let token: Result<Token, _> = FromSql::<Text, Pg>::from_sql(Some(&b"a b"[..]));
+        assert!(token.is_err());
+    }
+}
@@ -0,0 +1,280 @@
+//! A deserializer of a request envelope (`{ "type": ..., "payload": ... }`),
+//! which tells a `null` payload apart from a missing one (see
+//! `payloads::AbsentOrEmpty`)
+//!
+//! serde deserializes a missing payload of a adjacently tagged enum with
+//! `deserialize_option`, which is answered with `visit_none`. So is a `null`
+//! payload in json, which means that a payload can't tell the two apart.
+//! Inside of the envelope a `null` payload is handed over as a unit instead,
+//! which a `Option` still takes as `None`, but which `AbsentOrEmpty` rejects.
+//!
+//! Only the payload itself is changed, the values inside of it (and a payload
+//! which serde buffers, as it is given before the tag) are deserialized as
+//! they are. A buffered `null` is a unit already.
+
+use serde::de::{DeserializeSeed, Deserializer, EnumAccess, MapAccess, SeqAccess, Visitor};
+use std::fmt;
+
+/// A deserializer of a envelope, where the values of its map are payloads
+pub(crate) struct Envelope<D>(pub(crate) D);
+
+macro_rules! forward_deserialize {
+    ($wrap:ident; $($method:ident($($arg:ident: $ty:ty),*)),* $(,)*) => {
+        $(
+            fn $method<V>(self, $($arg: $ty,)* visitor: V) -> Result<V::Value, Self::Error>
+            where
+                V: Visitor<'de>,
+            {
+                self.0.$method($($arg,)* $wrap(visitor))
+            }
+        )*
+    };
+}
+
+macro_rules! forward_deserialize_all {
+    ($wrap:ident) => {
+        forward_deserialize! {
+            $wrap;
+            deserialize_any(),
+            deserialize_bool(),
+            deserialize_i8(),
+            deserialize_i16(),
+            deserialize_i32(),
+            deserialize_i64(),
+            deserialize_i128(),
+            deserialize_u8(),
+            deserialize_u16(),
+            deserialize_u32(),
+            deserialize_u64(),
+            deserialize_u128(),
+            deserialize_f32(),
+            deserialize_f64(),
+            deserialize_char(),
+            deserialize_str(),
+            deserialize_string(),
+            deserialize_bytes(),
+            deserialize_byte_buf(),
+            deserialize_option(),
+            deserialize_unit(),
+            deserialize_unit_struct(name: &'static str),
+            deserialize_newtype_struct(name: &'static str),
+            deserialize_seq(),
+            deserialize_tuple(len: usize),
+            deserialize_tuple_struct(name: &'static str, len: usize),
+            deserialize_map(),
+            deserialize_struct(name: &'static str, fields: &'static [&'static str]),
+            deserialize_enum(name: &'static str, variants: &'static [&'static str]),
+            deserialize_identifier(),
+            deserialize_ignored_any(),
+        }
+    };
+}
+
+impl<'de, D> Deserializer<'de> for Envelope<D>
+where
+    D: Deserializer<'de>,
+{
+    type Error = D::Error;
+
+    fn is_human_readable(&self) -> bool {
+        self.0.is_human_readable()
+    }
+
+    forward_deserialize_all!(EnvelopeVisitor);
+}
+
+/// A visitor of a envelope, which hands its map over as a `EnvelopeMap`
+struct EnvelopeVisitor<V>(V);
+
+macro_rules! forward_visit {
+    ($($method:ident($ty:ty)),* $(,)*) => {
+        $(
+            fn $method<E>(self, v: $ty) -> Result<Self::Value, E>
+            where
+                E: serde::de::Error,
+            {
+                self.0.$method(v)
+            }
+        )*
+    };
+}
+
+/// The methods of a visitor which only forward, except for `visit_none` and
+/// `visit_map`
+macro_rules! forward_visit_all {
+    () => {
+        fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            self.0.expecting(f)
+        }
+
+        forward_visit! {
+            visit_bool(bool),
+            visit_i8(i8),
+            visit_i16(i16),
+            visit_i32(i32),
+            visit_i64(i64),
+            visit_i128(i128),
+            visit_u8(u8),
+            visit_u16(u16),
+            visit_u32(u32),
+            visit_u64(u64),
+            visit_u128(u128),
+            visit_f32(f32),
+            visit_f64(f64),
+            visit_char(char),
+            visit_str(&str),
+            visit_borrowed_str(&'de str),
+            visit_string(String),
+            visit_bytes(&[u8]),
+            visit_borrowed_bytes(&'de [u8]),
+            visit_byte_buf(Vec<u8>),
+        }
+
+        fn visit_unit<E>(self) -> Result<Self::Value, E>
+        where
+            E: serde::de::Error,
+        {
+            self.0.visit_unit()
+        }
+
+        fn visit_some<D>(self, deserializer: D) -> Result<Self::Value, D::Error>
+        where
+            D: Deserializer<'de>,
+        {
+            self.0.visit_some(deserializer)
+        }
+
+        fn visit_newtype_struct<D>(self, deserializer: D) -> Result<Self::Value, D::Error>
+        where
+            D: Deserializer<'de>,
+        {
+            self.0.visit_newtype_struct(deserializer)
+        }
+
+        fn visit_seq<A>(self, seq: A) -> Result<Self::Value, A::Error>
+        where
+            A: SeqAccess<'de>,
+        {
+            self.0.visit_seq(seq)
+        }
+
+        fn visit_enum<A>(self, data: A) -> Result<Self::Value, A::Error>
+        where
+            A: EnumAccess<'de>,
+        {
+            self.0.visit_enum(data)
+        }
+    };
+}
+
+impl<'de, V> Visitor<'de> for EnvelopeVisitor<V>
+where
+    V: Visitor<'de>,
+{
+    type Value = V::Value;
+
+    forward_visit_all!();
+
+    fn visit_none<E>(self) -> Result<Self::Value, E>
+    where
+        E: serde::de::Error,
+    {
+        self.0.visit_none()
+    }
+
+    fn visit_map<A>(self, map: A) -> Result<Self::Value, A::Error>
+    where
+        A: MapAccess<'de>,
+    {
+        self.0.visit_map(EnvelopeMap(map))
+    }
+}
+
+/// The entries of a envelope, where every value is deserialized as a
+/// `Payload` (the tag is a string either way)
+struct EnvelopeMap<A>(A);
+
+impl<'de, A> MapAccess<'de> for EnvelopeMap<A>
+where
+    A: MapAccess<'de>,
+{
+    type Error = A::Error;
+
+    fn next_key_seed<K>(&mut self, seed: K) -> Result<Option<K::Value>, Self::Error>
+    where
+        K: DeserializeSeed<'de>,
+    {
+        self.0.next_key_seed(seed)
+    }
+
+    fn next_value_seed<V>(&mut self, seed: V) -> Result<V::Value, Self::Error>
+    where
+        V: DeserializeSeed<'de>,
+    {
+        self.0.next_value_seed(PayloadSeed(seed))
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        self.0.size_hint()
+    }
+}
+
+/// A seed of a value of a envelope
+struct PayloadSeed<S>(S);
+
+impl<'de, S> DeserializeSeed<'de> for PayloadSeed<S>
+where
+    S: DeserializeSeed<'de>,
+{
+    type Value = S::Value;
+
+    fn deserialize<D>(self, deserializer: D) -> Result<Self::Value, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        self.0.deserialize(Payload(deserializer))
+    }
+}
+
+/// A deserializer of a payload, where a `null` payload is a unit
+struct Payload<D>(D);
+
+impl<'de, D> Deserializer<'de> for Payload<D>
+where
+    D: Deserializer<'de>,
+{
+    type Error = D::Error;
+
+    fn is_human_readable(&self) -> bool {
+        self.0.is_human_readable()
+    }
+
+    forward_deserialize_all!(NoneAsUnit);
+}
+
+/// A visitor which is given a unit for `None` (and which leaves everything
+/// else as it is)
+struct NoneAsUnit<V>(V);
+
+impl<'de, V> Visitor<'de> for NoneAsUnit<V>
+where
+    V: Visitor<'de>,
+{
+    type Value = V::Value;
+
+    forward_visit_all!();
+
+    fn visit_none<E>(self) -> Result<Self::Value, E>
+    where
+        E: serde::de::Error,
+    {
+        self.0.visit_unit()
+    }
+
+    fn visit_map<A>(self, map: A) -> Result<Self::Value, A::Error>
+    where
+        A: MapAccess<'de>,
+    {
+        self.0.visit_map(map)
+    }
+}
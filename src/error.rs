@@ -1,17 +1,321 @@
 //! A collection of all common errors
+//!
+//! The serialized form of the errors is stable, see the golden tests in
+//! `wire` for the guarantees which are given.
 
 use crate::admin::responses::AdminError;
 use crate::auth::responses::AuthError;
+use crate::content::builder::BuildError;
 use crate::content::responses::ContentError;
+use crate::valid::token::TokenParseError;
+use crate::valid::ValidationError;
+use std::fmt;
 
 pub type ResponseResult<T> = Result<T, ResponseError>;
 
+/// A machine-stable code which identifies an error
+///
+/// Clients should match on the code (and localize the message themselves)
+/// instead of parsing the English messages of the errors, which can change.
+/// Once a code is added it must never be renamed.
+#[derive(Serialize, Deserialize, PartialEq, Eq, Hash, Clone, Copy, Debug)]
+#[cfg_attr(feature = "schemars", derive(JsonSchema))]
+#[cfg_attr(feature = "utoipa", derive(ToSchema))]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum ErrorCode {
+    // General errors of a request
+    InvalidPayload,
+    /// The request must be authenticated, but it had no (valid) token
+    Unauthenticated,
+    /// The request is authenticated, but the user is not allowed to do it
+    Forbidden,
+
+    // Validation errors
+    InvalidUsername,
+    InvalidPassword,
+    InvalidId,
+    InvalidTitle,
+    InvalidDescription,
+    InvalidCommentContent,
+    InvalidEmail,
+    InvalidQuery,
+    InvalidPhoneNumber,
+    InvalidTwoFactorCode,
+    MissingReportDetails,
+    InvalidDraft,
+    InvalidFileName,
+    InvalidMimeType,
+    InvalidDigest,
+    InvalidPoll,
+    InvalidVote,
+    InvalidExcerpt,
+    InvalidIpAddr,
+    InvalidDisplayName,
+    InvalidUrl,
+    InvalidCursor,
+    InvalidPagination,
+    InvalidIdempotencyKey,
+    InvalidDuration,
+    InvalidListLength,
+    InvalidCountryCode,
+    InvalidAttachmentRef,
+
+    // Errors of auth requests
+    AuthInvalidToken,
+    AuthInvalidUsername,
+    AuthInvalidPassword,
+    AuthInvalidCredentials,
+    AuthExistingUser,
+    AuthInvalidTwoFactorCode,
+    AuthInsufficientRole,
+    AuthRateLimited,
+    AuthInternalServerError,
+
+    // Errors of admin requests
+    AdminInternalError,
+
+    // Errors of content requests
+    ContentMissingContent,
+    ContentHiddenContent,
+    ContentInternalServerError,
+    ContentInvalidSearchQuery,
+    ContentInvalidId,
+    ContentInvalidContent,
+    ContentReplyTargetMissing,
+}
+
+impl ErrorCode {
+    /// All the error codes
+    pub const ALL: &'static [ErrorCode] = &[
+        ErrorCode::InvalidPayload,
+        ErrorCode::Unauthenticated,
+        ErrorCode::Forbidden,
+        ErrorCode::InvalidUsername,
+        ErrorCode::InvalidPassword,
+        ErrorCode::InvalidId,
+        ErrorCode::InvalidTitle,
+        ErrorCode::InvalidDescription,
+        ErrorCode::InvalidCommentContent,
+        ErrorCode::InvalidEmail,
+        ErrorCode::InvalidQuery,
+        ErrorCode::InvalidPhoneNumber,
+        ErrorCode::InvalidTwoFactorCode,
+        ErrorCode::MissingReportDetails,
+        ErrorCode::InvalidDraft,
+        ErrorCode::InvalidFileName,
+        ErrorCode::InvalidMimeType,
+        ErrorCode::InvalidDigest,
+        ErrorCode::InvalidPoll,
+        ErrorCode::InvalidVote,
+        ErrorCode::InvalidExcerpt,
+        ErrorCode::InvalidIpAddr,
+        ErrorCode::InvalidDisplayName,
+        ErrorCode::InvalidUrl,
+        ErrorCode::InvalidCursor,
+        ErrorCode::InvalidPagination,
+        ErrorCode::InvalidIdempotencyKey,
+        ErrorCode::InvalidDuration,
+        ErrorCode::InvalidListLength,
+        ErrorCode::InvalidCountryCode,
+        ErrorCode::InvalidAttachmentRef,
+        ErrorCode::AuthInvalidToken,
+        ErrorCode::AuthInvalidUsername,
+        ErrorCode::AuthInvalidPassword,
+        ErrorCode::AuthInvalidCredentials,
+        ErrorCode::AuthExistingUser,
+        ErrorCode::AuthInvalidTwoFactorCode,
+        ErrorCode::AuthInsufficientRole,
+        ErrorCode::AuthRateLimited,
+        ErrorCode::AuthInternalServerError,
+        ErrorCode::AdminInternalError,
+        ErrorCode::ContentMissingContent,
+        ErrorCode::ContentHiddenContent,
+        ErrorCode::ContentInternalServerError,
+        ErrorCode::ContentInvalidSearchQuery,
+        ErrorCode::ContentInvalidId,
+        ErrorCode::ContentInvalidContent,
+        ErrorCode::ContentReplyTargetMissing,
+    ];
+
+    /// The code as a string, which is the same as its serialized form
+    pub fn as_str(&self) -> &'static str {
+        let i = ErrorCode::ALL
+            .iter()
+            .position(|code| code == self)
+            .expect("every code is in `ErrorCode::ALL`");
+        &CODE_STRS[i]
+    }
+
+    /// Look up a code from its string form
+    pub fn from_code(code: &str) -> Option<ErrorCode> {
+        ErrorCode::ALL.iter().cloned().find(|c| c.as_str() == code)
+    }
+
+    /// The http status of a response with this code
+    ///
+    /// `Unauthenticated` (401) means that the client should log in and retry,
+    /// while `Forbidden` (403) means that retrying will not help.
+    pub fn http_status(&self) -> u16 {
+        match self {
+            ErrorCode::InvalidPayload => 400,
+            ErrorCode::Unauthenticated => 401,
+            ErrorCode::Forbidden => 403,
+            ErrorCode::InvalidUsername
+            | ErrorCode::InvalidPassword
+            | ErrorCode::InvalidId
+            | ErrorCode::InvalidTitle
+            | ErrorCode::InvalidDescription
+            | ErrorCode::InvalidCommentContent
+            | ErrorCode::InvalidEmail
+            | ErrorCode::InvalidQuery
+            | ErrorCode::InvalidPhoneNumber
+            | ErrorCode::InvalidTwoFactorCode
+            | ErrorCode::MissingReportDetails
+            | ErrorCode::InvalidDraft
+            | ErrorCode::InvalidFileName
+            | ErrorCode::InvalidMimeType
+            | ErrorCode::InvalidDigest
+            | ErrorCode::InvalidPoll
+            | ErrorCode::InvalidVote
+            | ErrorCode::InvalidExcerpt
+            | ErrorCode::InvalidIpAddr
+            | ErrorCode::InvalidDisplayName
+            | ErrorCode::InvalidUrl
+            | ErrorCode::InvalidCursor
+            | ErrorCode::InvalidPagination
+            | ErrorCode::InvalidIdempotencyKey
+            | ErrorCode::InvalidDuration
+            | ErrorCode::InvalidListLength
+            | ErrorCode::InvalidCountryCode
+            | ErrorCode::InvalidAttachmentRef => 422,
+            ErrorCode::AuthInvalidToken => 401,
+            ErrorCode::AuthInvalidUsername => 422,
+            ErrorCode::AuthInvalidPassword => 422,
+            ErrorCode::AuthInvalidCredentials => 401,
+            ErrorCode::AuthExistingUser => 409,
+            ErrorCode::AuthInvalidTwoFactorCode => 401,
+            ErrorCode::AuthInsufficientRole => 403,
+            ErrorCode::AuthRateLimited => 429,
+            ErrorCode::AuthInternalServerError => 500,
+            ErrorCode::AdminInternalError => 500,
+            ErrorCode::ContentMissingContent => 404,
+            ErrorCode::ContentHiddenContent => 403,
+            ErrorCode::ContentInternalServerError => 500,
+            ErrorCode::ContentInvalidSearchQuery => 422,
+            ErrorCode::ContentInvalidId => 422,
+            ErrorCode::ContentInvalidContent => 422,
+            ErrorCode::ContentReplyTargetMissing => 422,
+        }
+    }
+}
+
+lazy_static! {
+    /// The serialized form of every code, in the order of `ErrorCode::ALL`
+    static ref CODE_STRS: Vec<String> = ErrorCode::ALL
+        .iter()
+        .map(|code| match serde_json::to_value(code) {
+            Ok(serde_json::Value::String(s)) => s,
+            _ => unreachable!("a code is serialized as a string"),
+        })
+        .collect();
+}
+
+impl fmt::Display for ErrorCode {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// The serialized form of any error of the crate
+///
+/// This is a shared shape for all the errors, which means that clients can
+/// handle every error the same way. `details` contains extra data of the
+/// error, if it has any.
+#[derive(Serialize, Deserialize, PartialEq, Clone, Debug)]
+#[cfg_attr(feature = "schemars", derive(JsonSchema))]
+#[cfg_attr(feature = "utoipa", derive(ToSchema))]
+#[serde(rename_all = "snake_case")]
+pub struct WireError {
+    pub code: ErrorCode,
+    pub message: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub details: Option<serde_json::Value>,
+}
+
+impl WireError {
+    pub fn new(code: ErrorCode, message: impl Into<String>) -> Self {
+        WireError {
+            code,
+            message: message.into(),
+            details: None,
+        }
+    }
+}
+
+impl From<ValidationError> for WireError {
+    fn from(e: ValidationError) -> Self {
+        WireError::new(e.code(), e.message())
+    }
+}
+
+impl From<AuthError> for WireError {
+    fn from(e: AuthError) -> Self {
+        let mut wire = WireError::new(e.code(), e.to_string());
+        if let Some(retry_after) = e.retry_after() {
+            let mut details = serde_json::Map::new();
+            details.insert("retry_after".to_owned(), retry_after.into());
+            wire.details = Some(details.into());
+        }
+        wire
+    }
+}
+
+impl From<AdminError> for WireError {
+    fn from(e: AdminError) -> Self {
+        WireError::new(e.code(), e.to_string())
+    }
+}
+
+impl From<ContentError> for WireError {
+    fn from(e: ContentError) -> Self {
+        WireError::new(e.code(), e.to_string())
+    }
+}
+
+/// The payload of a error which is sent back to a client
+///
+/// `code` is the general kind of the error, while `reason` (if present) is
+/// the specific cause, e.g. which field of a request was invalid.
+#[derive(Serialize, Deserialize, PartialEq, Clone, Debug)]
+#[cfg_attr(feature = "schemars", derive(JsonSchema))]
+#[cfg_attr(feature = "utoipa", derive(ToSchema))]
+#[serde(rename_all = "snake_case")]
+pub struct ErrorPayload {
+    pub code: ErrorCode,
+    pub message: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reason: Option<ErrorCode>,
+}
+
+impl From<ValidationError> for ErrorPayload {
+    fn from(e: ValidationError) -> Self {
+        ErrorPayload {
+            code: ErrorCode::InvalidPayload,
+            message: e.message(),
+            reason: Some(e.code()),
+        }
+    }
+}
+
 #[derive(Fail, Serialize, Deserialize, PartialEq, Debug)]
+#[cfg_attr(feature = "schemars", derive(JsonSchema))]
+#[cfg_attr(feature = "utoipa", derive(ToSchema))]
 #[serde(
     tag = "type",
     content = "payload",
     rename_all = "SCREAMING_SNAKE_CASE"
 )]
+#[non_exhaustive]
 pub enum ResponseError {
     #[fail(display = "error specific to auth requests")]
     AuthRequestError(#[cause] AuthError),
@@ -23,10 +327,13 @@ pub enum ResponseError {
     Unauthenticated,
     #[fail(display = "user is not authorized to perform action")]
     Unauthorized,
+    #[fail(display = "request payload is invalid")]
+    InvalidPayload(ErrorPayload),
     #[fail(display = "internal server error occured")]
     InternalServerError,
 }
 
+#[cfg(all(feature = "tarpc", not(target_arch = "wasm32")))]
 impl From<tarpc::Error<ContentError>> for ResponseError {
     fn from(e: tarpc::Error<ContentError>) -> ResponseError {
         let ee: ContentError = e.into();
@@ -37,6 +344,7 @@ impl From<tarpc::Error<ContentError>> for ResponseError {
     }
 }
 
+#[cfg(all(feature = "tarpc", not(target_arch = "wasm32")))]
 impl From<tarpc::Error<AuthError>> for ResponseError {
     fn from(e: tarpc::Error<AuthError>) -> ResponseError {
         let ee: AuthError = e.into();
@@ -47,6 +355,7 @@ impl From<tarpc::Error<AuthError>> for ResponseError {
     }
 }
 
+#[cfg(all(feature = "tarpc", not(target_arch = "wasm32")))]
 impl From<tarpc::Error<ContentError>> for ContentError {
     fn from(e: tarpc::Error<ContentError>) -> ContentError {
         use tarpc::Error::*;
@@ -57,6 +366,7 @@ impl From<tarpc::Error<ContentError>> for ContentError {
     }
 }
 
+#[cfg(all(feature = "tarpc", not(target_arch = "wasm32")))]
 impl From<tarpc::Error<AuthError>> for AuthError {
     fn from(e: tarpc::Error<AuthError>) -> AuthError {
         use tarpc::Error::*;
@@ -84,3 +394,414 @@ impl From<AdminError> for ResponseError {
         ResponseError::AdminRequestError(e)
     }
 }
+
+impl From<ValidationError> for ResponseError {
+    fn from(e: ValidationError) -> Self {
+        ResponseError::InvalidPayload(e.into())
+    }
+}
+
+/// The error of anything fallible in this crate
+///
+/// All the more specific errors of the crate can be converted into this
+/// error, which means that `?` can be used on them in a function which returns
+/// a `Result<_, Error>`.
+#[derive(PartialEq, Debug)]
+pub enum Error {
+    /// A field of a payload did not pass validation
+    Validation(ValidationError),
+    /// A payload could not be parsed, `context` tells what was wrong
+    MalformedPayload { context: &'static str },
+    /// A request which must be authenticated did not contain a token
+    MissingToken,
+    /// A request had a type which is not known to the crate
+    UnknownRequestType(String),
+    /// A request was of a version of the wire format which is not supported
+    UnsupportedVersion { got: u16, supported: u16 },
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Error::Validation(e) => write!(f, "invalid payload: {}", e),
+            Error::MalformedPayload { context } => write!(f, "malformed payload: {}", context),
+            Error::MissingToken => write!(f, "missing authorization token"),
+            Error::UnknownRequestType(t) => write!(f, "unknown request type '{}'", t),
+            Error::UnsupportedVersion { got, supported } => write!(
+                f,
+                "unsupported version {} (supported version is {})",
+                got, supported
+            ),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::Validation(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+/// A payload which could not be parsed, together with the path of the field
+/// which was wrong (see `payloads::parse_with_path`)
+///
+/// The path of a error which is not in a field (like invalid json) is `.`.
+#[derive(PartialEq, Clone, Debug)]
+pub struct PathError {
+    /// The path of the field from the root of the payload, like `payload.email`
+    pub path: String,
+    /// What was wrong with the field
+    pub message: String,
+}
+
+impl fmt::Display for PathError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}: {}", self.path, self.message)
+    }
+}
+
+impl std::error::Error for PathError {}
+
+/// A request which could not be parsed, with the type of the request and the
+/// field which was wrong (see `payloads::parse_request`)
+///
+/// It can be sent back to the client as is, and the `Display` gives a message
+/// which names the request, like `missing field 'ip' in a BAN_IP request`.
+#[derive(Serialize, Deserialize, PartialEq, Clone, Debug)]
+#[cfg_attr(feature = "schemars", derive(JsonSchema))]
+#[cfg_attr(feature = "utoipa", derive(ToSchema))]
+#[serde(rename_all = "snake_case")]
+pub struct ParseError {
+    /// The type tag of the request, if it had one
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub request_type: Option<String>,
+    /// The path of the field inside the payload, like `target.id`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub field: Option<String>,
+    pub kind: ParseErrorKind,
+}
+
+/// What was wrong with a request which could not be parsed
+#[derive(Serialize, Deserialize, PartialEq, Clone, Debug)]
+#[cfg_attr(feature = "schemars", derive(JsonSchema))]
+#[cfg_attr(feature = "utoipa", derive(ToSchema))]
+#[serde(
+    tag = "type",
+    content = "payload",
+    rename_all = "SCREAMING_SNAKE_CASE"
+)]
+pub enum ParseErrorKind {
+    /// The request was not valid json
+    InvalidJson { message: String },
+    /// The request was not a object with a `type` string
+    MissingTag,
+    /// The type of the request is not known, `expected` are the known types
+    UnknownTag { expected: Vec<String> },
+    /// The request had no payload, and its type must have one
+    MissingPayload,
+    /// A field of the payload was missing
+    MissingField,
+    /// A field of the payload (or the payload itself) was invalid
+    InvalidValue { message: String },
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let request = self.request_type.as_ref().map(String::as_str);
+        let field = self.field.as_ref().map(String::as_str);
+        match (&self.kind, request, field) {
+            (ParseErrorKind::InvalidJson { message }, _, _) => {
+                write!(f, "invalid json: {}", message)
+            }
+            (ParseErrorKind::MissingTag, _, _) => write!(f, "the request has no 'type'"),
+            (ParseErrorKind::UnknownTag { expected }, request, _) => write!(
+                f,
+                "unknown request type '{}', expected one of {}",
+                request.unwrap_or_default(),
+                expected.join(", ")
+            ),
+            (ParseErrorKind::MissingPayload, request, _) => write!(
+                f,
+                "missing payload in a {} request",
+                request.unwrap_or_default()
+            ),
+            (ParseErrorKind::MissingField, request, field) => write!(
+                f,
+                "missing field '{}' in a {} request",
+                field.unwrap_or_default(),
+                request.unwrap_or_default()
+            ),
+            (ParseErrorKind::InvalidValue { message }, request, Some(field)) => write!(
+                f,
+                "invalid field '{}' in a {} request: {}",
+                field,
+                request.unwrap_or_default(),
+                message
+            ),
+            (ParseErrorKind::InvalidValue { message }, request, None) => write!(
+                f,
+                "invalid payload in a {} request: {}",
+                request.unwrap_or_default(),
+                message
+            ),
+        }
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+impl From<ValidationError> for Error {
+    fn from(e: ValidationError) -> Self {
+        Error::Validation(e)
+    }
+}
+
+impl From<BuildError> for Error {
+    fn from(e: BuildError) -> Self {
+        match e {
+            BuildError::MissingField(field) => Error::MalformedPayload { context: field },
+            BuildError::Invalid(e) => Error::Validation(e),
+        }
+    }
+}
+
+impl From<TokenParseError> for Error {
+    fn from(e: TokenParseError) -> Self {
+        match e {
+            TokenParseError::EmptyToken => Error::MissingToken,
+            TokenParseError::MissingPrefix => Error::MalformedPayload {
+                context: "authorization header is not a bearer token",
+            },
+            TokenParseError::InvalidToken => Error::MalformedPayload {
+                context: "bearer token contains invalid characters",
+            },
+        }
+    }
+}
+
+impl From<serde_json::Error> for Error {
+    fn from(e: serde_json::Error) -> Self {
+        use serde_json::error::Category;
+        let context = match e.classify() {
+            Category::Io => "could not be read",
+            Category::Syntax => "invalid json",
+            Category::Data => "unexpected data",
+            Category::Eof => "unexpected end of json",
+        };
+        Error::MalformedPayload { context }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::valid::fields::Username;
+    use std::convert::TryFrom;
+
+    #[test]
+    fn short_username_maps_to_invalid_payload() {
+        let e = Username::try_from("jo".to_owned()).unwrap_err();
+        let payload = ErrorPayload::from(e);
+        assert_eq!(payload.code, ErrorCode::InvalidPayload);
+        assert_eq!(payload.reason, Some(ErrorCode::InvalidUsername));
+        assert!(payload.message.contains("username"), "{}", payload.message);
+    }
+
+    #[test]
+    fn validation_error_into_response_error() {
+        let e = Username::try_from("jo".to_owned()).unwrap_err();
+        match ResponseError::from(e) {
+            ResponseError::InvalidPayload(payload) => {
+                assert_eq!(payload.code, ErrorCode::InvalidPayload)
+            }
+            e => panic!("expected a InvalidPayload error, got {:?}", e),
+        }
+    }
+
+    #[test]
+    fn error_payload_json() {
+        let payload = ErrorPayload::from(ValidationError::InvalidEmail);
+        let expt = serde_json::json!({
+            "code": "INVALID_PAYLOAD",
+            "message": "invalid (badly formatted) email",
+            "reason": "INVALID_EMAIL"
+        });
+        assert_eq!(serde_json::to_value(&payload).unwrap(), expt);
+    }
+
+    #[test]
+    fn error_display_messages_are_stable() {
+        let messages = vec![
+            (
+                Error::from(ValidationError::InvalidUsername),
+                "invalid payload: invalid (badly formatted) username",
+            ),
+            (
+                Error::MalformedPayload { context: "title" },
+                "malformed payload: title",
+            ),
+            (Error::MissingToken, "missing authorization token"),
+            (
+                Error::UnknownRequestType("FROBNICATE".to_owned()),
+                "unknown request type 'FROBNICATE'",
+            ),
+            (
+                Error::UnsupportedVersion {
+                    got: 999,
+                    supported: 1,
+                },
+                "unsupported version 999 (supported version is 1)",
+            ),
+        ];
+        for (error, expt) in messages {
+            assert_eq!(error.to_string(), expt);
+        }
+    }
+
+    #[test]
+    fn error_source_is_preserved() {
+        use std::error::Error as StdError;
+
+        let e = Username::try_from("jo".to_owned()).unwrap_err();
+        let error = Error::from(e);
+        let source = error.source().expect("validation errors have a source");
+        assert_eq!(source.to_string(), "invalid (badly formatted) username");
+        assert!(source.source().is_none());
+
+        assert!(Error::MissingToken.source().is_none());
+    }
+
+    #[test]
+    fn error_from_json_and_build_errors() {
+        let e = serde_json::from_str::<ErrorPayload>("{").unwrap_err();
+        assert_eq!(
+            Error::from(e),
+            Error::MalformedPayload {
+                context: "unexpected end of json"
+            }
+        );
+
+        let e = BuildError::MissingField("title");
+        assert_eq!(Error::from(e), Error::MalformedPayload { context: "title" });
+    }
+
+    /// The kinds of the errors (of the crate) which are made from `code`
+    fn kinds_of(code: ErrorCode) -> Vec<&'static str> {
+        let mut kinds = vec![];
+        if let Some(e) = ValidationError::from_code(code) {
+            assert_eq!(e.code(), code);
+            kinds.push("validation");
+        }
+        if let Some(e) = AuthError::from_code(code) {
+            assert_eq!(e.code(), code);
+            kinds.push("auth");
+        }
+        if let Some(e) = AdminError::from_code(code) {
+            assert_eq!(e.code(), code);
+            kinds.push("admin");
+        }
+        if let Some(e) = ContentError::from_code(code) {
+            assert_eq!(e.code(), code);
+            kinds.push("content");
+        }
+        kinds
+    }
+
+    #[test]
+    fn error_codes_are_unique_across_the_crate() {
+        let mut seen = std::collections::HashSet::new();
+        for code in ErrorCode::ALL {
+            assert!(seen.insert(code.as_str()), "{} is used twice", code);
+        }
+
+        // Every code belongs to a single error, except the general ones and
+        // the codes of errors with data (which can't be made from their code)
+        let without_error = [
+            ErrorCode::InvalidPayload,
+            ErrorCode::Unauthenticated,
+            ErrorCode::Forbidden,
+            ErrorCode::AuthRateLimited,
+        ];
+        for code in ErrorCode::ALL {
+            let kinds = kinds_of(*code);
+            let expt = if without_error.contains(code) { 0 } else { 1 };
+            assert_eq!(kinds.len(), expt, "{} is used by {:?} errors", code, kinds);
+        }
+        let e = AuthError::RateLimited { retry_after: 30 };
+        assert_eq!(e.code(), ErrorCode::AuthRateLimited);
+    }
+
+    #[test]
+    fn error_code_strings_match_serialization() {
+        for code in ErrorCode::ALL {
+            assert_eq!(serde_json::to_value(code).unwrap(), code.as_str());
+            assert_eq!(ErrorCode::from_code(code.as_str()), Some(*code));
+        }
+        assert_eq!(
+            ErrorCode::AuthInvalidCredentials.as_str(),
+            "AUTH_INVALID_CREDENTIALS"
+        );
+        assert_eq!(ErrorCode::from_code("NOT_A_CODE"), None);
+    }
+
+    #[test]
+    fn unauthenticated_and_forbidden_are_distinct() {
+        assert_ne!(ErrorCode::Unauthenticated, ErrorCode::Forbidden);
+        assert_eq!(ErrorCode::Unauthenticated.http_status(), 401);
+        assert_eq!(ErrorCode::Forbidden.http_status(), 403);
+        assert_eq!(ErrorCode::Unauthenticated.as_str(), "UNAUTHENTICATED");
+        assert_eq!(ErrorCode::Forbidden.as_str(), "FORBIDDEN");
+    }
+
+    #[test]
+    fn error_code_http_statuses() {
+        let statuses = vec![
+            (ErrorCode::InvalidPayload, 400),
+            (ErrorCode::InvalidTitle, 422),
+            (ErrorCode::AuthExistingUser, 409),
+            (ErrorCode::AuthRateLimited, 429),
+            (ErrorCode::ContentMissingContent, 404),
+            (ErrorCode::AdminInternalError, 500),
+        ];
+        for (code, status) in statuses {
+            assert_eq!(code.http_status(), status, "{}", code);
+        }
+        for code in ErrorCode::ALL {
+            let status = code.http_status();
+            assert!(status >= 400 && status < 600, "{}", code);
+        }
+    }
+
+    #[test]
+    fn codes_of_other_kinds_are_not_constructible() {
+        assert_eq!(AuthError::from_code(ErrorCode::InvalidTitle), None);
+        assert_eq!(ContentError::from_code(ErrorCode::AuthInvalidToken), None);
+        assert_eq!(AdminError::from_code(ErrorCode::InvalidPayload), None);
+        assert_eq!(ValidationError::from_code(ErrorCode::InvalidPayload), None);
+    }
+
+    #[test]
+    fn wire_error_json() {
+        let wire = WireError::from(AuthError::InvalidCredentials);
+        let expt = serde_json::json!({
+            "code": "AUTH_INVALID_CREDENTIALS",
+            "message": "username or password is invalid"
+        });
+        assert_eq!(serde_json::to_value(&wire).unwrap(), expt);
+        assert_eq!(serde_json::from_value::<WireError>(expt).unwrap(), wire);
+    }
+
+    #[test]
+    fn wire_error_details() {
+        let wire = WireError::from(AuthError::RateLimited { retry_after: 30 });
+        let expt = serde_json::json!({
+            "code": "AUTH_RATE_LIMITED",
+            "message": "too many attempts, retry after 30 seconds",
+            "details": { "retry_after": 30 }
+        });
+        assert_eq!(serde_json::to_value(&wire).unwrap(), expt);
+    }
+}
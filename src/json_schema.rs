@@ -0,0 +1,92 @@
+//! JSON schemas of the wire types, for generating the types of clients
+//!
+//! Every request, response and payload implements `schemars::JsonSchema`. The
+//! schemas follow the serialized form, so the requests and responses are a
+//! `oneOf` of their variants (with the `type` and `payload` fields), and the
+//! validated types carry the limits of the default `ValidationPolicy` (like
+//! the `minLength` and `maxLength` of a `Username`), their pattern and a
+//! `format` where there is one (like `email`).
+//!
+//! ```
+//! # use datatypes::json_schema::schemas;
+//! for (name, schema) in schemas() {
+//!     let json = serde_json::to_string_pretty(&schema).unwrap();
+//!     println!("{}.json: {}", name, json);
+//! }
+//! ```
+
+use crate::admin::requests::AdminRequest;
+use crate::admin::responses::{AdminError, AdminSuccess};
+use crate::auth::requests::AuthRequest;
+use crate::auth::responses::{AuthError, AuthSuccess};
+use crate::content::requests::ContentRequest;
+use crate::content::responses::{ContentError, ContentSuccess};
+use crate::error::{ResponseError, WireError};
+use crate::payloads::ResponseResult;
+use schemars::schema::{RootSchema, Schema, SchemaObject, SubschemaValidation};
+use schemars::schema_for;
+
+/// The schemas of the requests and responses of every service, by name
+pub fn schemas() -> Vec<(&'static str, RootSchema)> {
+    vec![
+        ("AuthRequest", schema_for!(AuthRequest)),
+        (
+            "AuthResponse",
+            schema_for!(ResponseResult<AuthSuccess, AuthError>),
+        ),
+        ("AdminRequest", schema_for!(AdminRequest)),
+        (
+            "AdminResponse",
+            schema_for!(ResponseResult<AdminSuccess, AdminError>),
+        ),
+        ("ContentRequest", schema_for!(ContentRequest)),
+        (
+            "ContentResponse",
+            schema_for!(ResponseResult<ContentSuccess, ContentError>),
+        ),
+        ("ResponseError", schema_for!(ResponseError)),
+        ("WireError", schema_for!(WireError)),
+    ]
+}
+
+/// A value which matches any of `schemas`
+pub(crate) fn any_of(schemas: Vec<Schema>) -> Schema {
+    Schema::Object(SchemaObject {
+        subschemas: Some(Box::new(SubschemaValidation {
+            any_of: Some(schemas),
+            ..Default::default()
+        })),
+        ..Default::default()
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::valid::policy::ValidationPolicy;
+
+    #[test]
+    fn auth_request_schema() {
+        let schema = serde_json::to_value(schema_for!(AuthRequest)).unwrap();
+        let variants = schema["oneOf"].as_array().unwrap();
+        assert_eq!(variants.len(), AuthRequest::TAGS.len());
+        for variant in variants {
+            assert!(variant["properties"]["type"]["enum"].is_array());
+        }
+
+        let policy = ValidationPolicy::default();
+        let username = &schema["definitions"]["Username"];
+        assert_eq!(username["type"], "string");
+        assert_eq!(username["minLength"], policy.min_username_len);
+        assert_eq!(username["maxLength"], policy.max_username_len);
+        assert_eq!(schema["definitions"]["Email"]["format"], "email");
+    }
+
+    #[test]
+    fn every_schema_can_be_generated() {
+        for (name, schema) in schemas() {
+            let json = serde_json::to_value(&schema).unwrap();
+            assert!(json.get("definitions").is_some(), "{}", name);
+        }
+    }
+}
@@ -0,0 +1,201 @@
+//! GraphQL scalars of the ids and validated types for juniper
+//!
+//! | scalar                                   | GraphQL type                 |
+//! |------------------------------------------|------------------------------|
+//! | `UserId`, `ThreadId` and `CommentId`     | `Int`                        |
+//! | `Username`, `Email`, `Title` and `Token` | `String`                     |
+//!
+//! The ids are `Int`s like in the json of the payloads, and a id is also
+//! accepted as a `String` of digits. A id which is larger than a `Int` (which
+//! is 32 bits and signed) is resolved as a `String`.
+//!
+//! An input is validated like any other input, and the message of the
+//! `ValidationError` is the GraphQL error. A text is resolved as it was given,
+//! a `Title` without the HTML-escaping it is stored with (see
+//! `Title::unescaped`). A `Token` is input-only, resolving
+//! one panics, as a token should never be sent back to a client.
+
+use crate::valid::ids::Id;
+use crate::valid::ValidationError;
+use juniper::{graphql_scalar, InputValue, ScalarValue, Value};
+use std::convert::TryFrom;
+
+/// A id as a `Int`, or as a `String` if it doesn't fit in a `Int`
+fn id_to_output<S: ScalarValue>(id: u32) -> Value<S> {
+    match i32::try_from(id) {
+        Ok(n) => Value::scalar(n),
+        Err(_) => Value::scalar(id.to_string()),
+    }
+}
+
+/// A id from a `Int` or a `String` of digits
+fn id_from_input<S: ScalarValue>(v: &InputValue<S>) -> Result<u32, String> {
+    let invalid = |_| ValidationError::InvalidId.to_string();
+    match (v.as_int_value(), v.as_string_value()) {
+        (Some(n), _) => u32::try_from(n).map_err(invalid),
+        (None, Some(s)) => s.parse().map_err(invalid),
+        (None, None) => Err(format!("Expected `Int` or `String`, found: {}", v)),
+    }
+}
+
+/// A validated type from a `String`
+fn text_from_input<S, T>(
+    v: &InputValue<S>,
+    validate: fn(&str) -> Result<T, ValidationError>,
+) -> Result<T, String>
+where
+    S: ScalarValue,
+{
+    let s = v
+        .as_string_value()
+        .ok_or_else(|| format!("Expected `String`, found: {}", v))?;
+    validate(s).map_err(|e| e.to_string())
+}
+
+macro_rules! id_scalars {
+    ($($id:ident => $module:ident),*) => {
+        $(
+            #[graphql_scalar(with = $module, parse_token(i32, String))]
+            type $id = crate::valid::ids::$id;
+
+            mod $module {
+                use super::*;
+
+                pub(super) fn to_output<S: ScalarValue>(v: &$id) -> Value<S> {
+                    id_to_output(v.inner())
+                }
+
+                pub(super) fn from_input<S: ScalarValue>(v: &InputValue<S>) -> Result<$id, String> {
+                    id_from_input(v).map(<$id>::from)
+                }
+            }
+        )*
+    };
+}
+
+macro_rules! text_scalars {
+    ($($ty:ident = $path:path => $module:ident, $validate:expr, $resolve:expr),*) => {
+        $(
+            #[graphql_scalar(with = $module, parse_token(String))]
+            type $ty = $path;
+
+            mod $module {
+                use super::*;
+
+                pub(super) fn to_output<S: ScalarValue>(v: &$ty) -> Value<S> {
+                    let resolve: fn(&$ty) -> String = $resolve;
+                    Value::scalar(resolve(v))
+                }
+
+                pub(super) fn from_input<S: ScalarValue>(v: &InputValue<S>) -> Result<$ty, String> {
+                    text_from_input(v, $validate)
+                }
+            }
+        )*
+    };
+}
+
+id_scalars!(
+    UserId => user_id,
+    ThreadId => thread_id,
+    CommentId => comment_id
+);
+
+text_scalars!(
+    Username = crate::valid::fields::Username => username,
+        |s| Username::try_from(s.to_owned()), |v| v.to_string(),
+    Email = crate::valid::fields::Email => email,
+        |s| Email::try_from(s.to_owned()), |v| v.to_string(),
+    Title = crate::valid::title::Title => title,
+        |s| Title::try_from(s), Title::unescaped
+);
+
+/// A token is input-only, see the module docs
+#[graphql_scalar(with = token, parse_token(String))]
+type Token = crate::valid::token::Token;
+
+mod token {
+    use super::*;
+
+    pub(super) fn to_output<S: ScalarValue>(_: &Token) -> Value<S> {
+        panic!("a `Token` is input-only, and must never be resolved in a response")
+    }
+
+    pub(super) fn from_input<S: ScalarValue>(v: &InputValue<S>) -> Result<Token, String> {
+        let s = v
+            .as_string_value()
+            .ok_or_else(|| format!("Expected `String`, found: {}", v))?;
+        Token::from_b64token(s).map_err(|e| e.to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use juniper::DefaultScalarValue;
+
+    type Input = InputValue<DefaultScalarValue>;
+
+    fn string(s: &str) -> Input {
+        InputValue::scalar(String::from(s))
+    }
+
+    #[test]
+    fn parse_ids() {
+        let id = UserId::from(42);
+        assert_eq!(user_id::from_input(&Input::scalar(42)), Ok(id));
+        assert_eq!(user_id::from_input(&string("42")), Ok(id));
+
+        let invalid = ValidationError::InvalidId.to_string();
+        for v in &[Input::scalar(-1), string("forty-two"), string("-1")] {
+            assert_eq!(thread_id::from_input(v), Err(invalid.clone()));
+        }
+        assert!(comment_id::from_input(&Input::scalar(true)).is_err());
+    }
+
+    #[test]
+    fn resolve_ids() {
+        let id = CommentId::from(42);
+        let v: Value = comment_id::to_output(&id);
+        assert_eq!(v, Value::scalar(42));
+
+        let id = UserId::from(u32::max_value());
+        let v: Value = user_id::to_output(&id);
+        assert_eq!(v, Value::scalar(u32::max_value().to_string()));
+    }
+
+    #[test]
+    fn parse_and_resolve_text() {
+        let name = username::from_input(&string("john")).unwrap();
+        let v: Value = username::to_output(&name);
+        assert_eq!(v, Value::scalar(String::from("john")));
+
+        let e = username::from_input(&string("jo")).unwrap_err();
+        assert_eq!(e, ValidationError::InvalidUsername.to_string());
+        let e = email::from_input(&string("john")).unwrap_err();
+        assert_eq!(e, ValidationError::InvalidEmail.to_string());
+        assert!(title::from_input(&string("Hi")).is_err());
+        assert!(title::from_input(&Input::scalar(42)).is_err());
+    }
+
+    #[test]
+    fn titles_are_resolved_unescaped() {
+        let title = title::from_input(&string("Tom & Jerry")).unwrap();
+        assert_eq!(&*title, "Tom &amp; Jerry");
+        let v: Value = title::to_output(&title);
+        assert_eq!(v, Value::scalar(String::from("Tom & Jerry")));
+    }
+
+    #[test]
+    fn parse_token() {
+        let token = token::from_input(&string("c2VjcmV0")).unwrap();
+        assert_eq!(token, Token::new("c2VjcmV0"));
+        assert!(token::from_input(&string("a b")).is_err());
+    }
+
+    #[test]
+    #[should_panic(expected = "input-only")]
+    fn token_is_never_resolved() {
+        let _: Value = token::to_output(&Token::new("c2VjcmV0"));
+    }
+}
@@ -0,0 +1,445 @@
+//! A deserializer which skips the fields which are unknown to a struct, and
+//! collects their paths instead of rejecting them (see
+//! `payloads::parse_lenient`)
+//!
+//! A struct tells the names of its fields when it is deserialized
+//! (`deserialize_struct`), so every field of its map which isn't one of them
+//! is skipped before the struct sees it. This means that a struct with
+//! `#[serde(deny_unknown_fields)]` never rejects a field, and that the fields
+//! which a struct would have ignored are collected as well.
+//!
+//! Only the values which are deserialized straight from the input are looked
+//! into. The fields of a value which serde buffers before it is deserialized
+//! (like a internally tagged enum, or a payload which is given before the tag
+//! of its request) are left to the value itself. The keys of a map are read
+//! as strings, which is what they are in json.
+
+use serde::de::{
+    DeserializeSeed, Deserializer, EnumAccess, IgnoredAny, IntoDeserializer, MapAccess, SeqAccess,
+    VariantAccess, Visitor,
+};
+use std::cell::RefCell;
+use std::fmt;
+
+/// The paths of the unknown fields, in the order they were found
+pub(crate) type Unknown = RefCell<Vec<String>>;
+
+/// The path of `key` inside of `path`
+fn join(path: &str, key: &str) -> String {
+    if path.is_empty() {
+        key.to_owned()
+    } else {
+        format!("{}.{}", path, key)
+    }
+}
+
+/// A deserializer of the value at `path`
+pub(crate) struct Lenient<'a, D> {
+    deserializer: D,
+    path: String,
+    unknown: &'a Unknown,
+}
+
+impl<'a, D> Lenient<'a, D> {
+    /// A deserializer of the root of the input
+    pub(crate) fn new(deserializer: D, unknown: &'a Unknown) -> Self {
+        Lenient {
+            deserializer,
+            path: String::new(),
+            unknown,
+        }
+    }
+}
+
+macro_rules! forward_deserialize {
+    ($($method:ident($($arg:ident: $ty:ty),*)),* $(,)*) => {
+        $(
+            fn $method<V>(self, $($arg: $ty,)* visitor: V) -> Result<V::Value, Self::Error>
+            where
+                V: Visitor<'de>,
+            {
+                let visitor = LenientVisitor::new(visitor, self.path, self.unknown, None);
+                self.deserializer.$method($($arg,)* visitor)
+            }
+        )*
+    };
+}
+
+impl<'de, 'a, D> Deserializer<'de> for Lenient<'a, D>
+where
+    D: Deserializer<'de>,
+{
+    type Error = D::Error;
+
+    fn deserialize_struct<V>(
+        self,
+        name: &'static str,
+        fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        let visitor = LenientVisitor::new(visitor, self.path, self.unknown, Some(fields));
+        self.deserializer.deserialize_struct(name, fields, visitor)
+    }
+
+    fn is_human_readable(&self) -> bool {
+        self.deserializer.is_human_readable()
+    }
+
+    forward_deserialize! {
+        deserialize_any(),
+        deserialize_bool(),
+        deserialize_i8(),
+        deserialize_i16(),
+        deserialize_i32(),
+        deserialize_i64(),
+        deserialize_i128(),
+        deserialize_u8(),
+        deserialize_u16(),
+        deserialize_u32(),
+        deserialize_u64(),
+        deserialize_u128(),
+        deserialize_f32(),
+        deserialize_f64(),
+        deserialize_char(),
+        deserialize_str(),
+        deserialize_string(),
+        deserialize_bytes(),
+        deserialize_byte_buf(),
+        deserialize_option(),
+        deserialize_unit(),
+        deserialize_unit_struct(name: &'static str),
+        deserialize_newtype_struct(name: &'static str),
+        deserialize_seq(),
+        deserialize_tuple(len: usize),
+        deserialize_tuple_struct(name: &'static str, len: usize),
+        deserialize_map(),
+        deserialize_enum(name: &'static str, variants: &'static [&'static str]),
+        deserialize_identifier(),
+        deserialize_ignored_any(),
+    }
+}
+
+/// A visitor which deserializes the values inside of the value at `path`
+/// leniently, `fields` are the fields of the value if it is a struct
+struct LenientVisitor<'a, V> {
+    visitor: V,
+    path: String,
+    unknown: &'a Unknown,
+    fields: Option<&'static [&'static str]>,
+}
+
+impl<'a, V> LenientVisitor<'a, V> {
+    fn new(
+        visitor: V,
+        path: String,
+        unknown: &'a Unknown,
+        fields: Option<&'static [&'static str]>,
+    ) -> Self {
+        LenientVisitor {
+            visitor,
+            path,
+            unknown,
+            fields,
+        }
+    }
+}
+
+macro_rules! forward_visit {
+    ($($method:ident($ty:ty)),* $(,)*) => {
+        $(
+            fn $method<E>(self, v: $ty) -> Result<Self::Value, E>
+            where
+                E: serde::de::Error,
+            {
+                self.visitor.$method(v)
+            }
+        )*
+    };
+}
+
+impl<'de, 'a, V> Visitor<'de> for LenientVisitor<'a, V>
+where
+    V: Visitor<'de>,
+{
+    type Value = V::Value;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.visitor.expecting(f)
+    }
+
+    forward_visit! {
+        visit_bool(bool),
+        visit_i8(i8),
+        visit_i16(i16),
+        visit_i32(i32),
+        visit_i64(i64),
+        visit_i128(i128),
+        visit_u8(u8),
+        visit_u16(u16),
+        visit_u32(u32),
+        visit_u64(u64),
+        visit_u128(u128),
+        visit_f32(f32),
+        visit_f64(f64),
+        visit_char(char),
+        visit_str(&str),
+        visit_borrowed_str(&'de str),
+        visit_string(String),
+        visit_bytes(&[u8]),
+        visit_borrowed_bytes(&'de [u8]),
+        visit_byte_buf(Vec<u8>),
+    }
+
+    fn visit_none<E>(self) -> Result<Self::Value, E>
+    where
+        E: serde::de::Error,
+    {
+        self.visitor.visit_none()
+    }
+
+    fn visit_unit<E>(self) -> Result<Self::Value, E>
+    where
+        E: serde::de::Error,
+    {
+        self.visitor.visit_unit()
+    }
+
+    fn visit_some<D>(self, deserializer: D) -> Result<Self::Value, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        self.visitor.visit_some(Lenient {
+            deserializer,
+            path: self.path,
+            unknown: self.unknown,
+        })
+    }
+
+    fn visit_newtype_struct<D>(self, deserializer: D) -> Result<Self::Value, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        self.visitor.visit_newtype_struct(Lenient {
+            deserializer,
+            path: self.path,
+            unknown: self.unknown,
+        })
+    }
+
+    fn visit_seq<A>(self, seq: A) -> Result<Self::Value, A::Error>
+    where
+        A: SeqAccess<'de>,
+    {
+        self.visitor.visit_seq(LenientSeq {
+            seq,
+            path: self.path,
+            unknown: self.unknown,
+            index: 0,
+        })
+    }
+
+    fn visit_map<A>(self, map: A) -> Result<Self::Value, A::Error>
+    where
+        A: MapAccess<'de>,
+    {
+        self.visitor.visit_map(LenientMap {
+            map,
+            path: self.path,
+            unknown: self.unknown,
+            fields: self.fields,
+            key: None,
+        })
+    }
+
+    fn visit_enum<A>(self, data: A) -> Result<Self::Value, A::Error>
+    where
+        A: EnumAccess<'de>,
+    {
+        self.visitor.visit_enum(LenientEnum {
+            data,
+            path: self.path,
+            unknown: self.unknown,
+        })
+    }
+}
+
+/// A seed of the value at `path`
+struct LenientSeed<'a, S> {
+    seed: S,
+    path: String,
+    unknown: &'a Unknown,
+}
+
+impl<'de, 'a, S> DeserializeSeed<'de> for LenientSeed<'a, S>
+where
+    S: DeserializeSeed<'de>,
+{
+    type Value = S::Value;
+
+    fn deserialize<D>(self, deserializer: D) -> Result<Self::Value, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        self.seed.deserialize(Lenient {
+            deserializer,
+            path: self.path,
+            unknown: self.unknown,
+        })
+    }
+}
+
+/// The elements of the list at `path`, which are at `path[index]`
+struct LenientSeq<'a, A> {
+    seq: A,
+    path: String,
+    unknown: &'a Unknown,
+    index: usize,
+}
+
+impl<'de, 'a, A> SeqAccess<'de> for LenientSeq<'a, A>
+where
+    A: SeqAccess<'de>,
+{
+    type Error = A::Error;
+
+    fn next_element_seed<T>(&mut self, seed: T) -> Result<Option<T::Value>, Self::Error>
+    where
+        T: DeserializeSeed<'de>,
+    {
+        let path = format!("{}[{}]", self.path, self.index);
+        self.index += 1;
+        self.seq.next_element_seed(LenientSeed {
+            seed,
+            path,
+            unknown: self.unknown,
+        })
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        self.seq.size_hint()
+    }
+}
+
+/// The entries of the map at `path`, where the entries which are not in
+/// `fields` (if the map is a struct) are skipped and collected
+struct LenientMap<'a, A> {
+    map: A,
+    path: String,
+    unknown: &'a Unknown,
+    fields: Option<&'static [&'static str]>,
+    /// The key of the value which is deserialized next
+    key: Option<String>,
+}
+
+impl<'de, 'a, A> MapAccess<'de> for LenientMap<'a, A>
+where
+    A: MapAccess<'de>,
+{
+    type Error = A::Error;
+
+    fn next_key_seed<K>(&mut self, seed: K) -> Result<Option<K::Value>, Self::Error>
+    where
+        K: DeserializeSeed<'de>,
+    {
+        while let Some(key) = self.map.next_key::<String>()? {
+            if self.fields.map_or(true, |f| f.contains(&key.as_str())) {
+                let value = seed.deserialize(key.as_str().into_deserializer());
+                self.key = Some(key);
+                return value.map(Some);
+            }
+            self.unknown.borrow_mut().push(join(&self.path, &key));
+            self.map.next_value::<IgnoredAny>()?;
+        }
+        Ok(None)
+    }
+
+    fn next_value_seed<V>(&mut self, seed: V) -> Result<V::Value, Self::Error>
+    where
+        V: DeserializeSeed<'de>,
+    {
+        let key = self.key.take().unwrap_or_default();
+        self.map.next_value_seed(LenientSeed {
+            seed,
+            path: join(&self.path, &key),
+            unknown: self.unknown,
+        })
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        self.map.size_hint()
+    }
+}
+
+/// A enum at `path`, where the content of its variant is deserialized
+/// leniently
+struct LenientEnum<'a, A> {
+    data: A,
+    path: String,
+    unknown: &'a Unknown,
+}
+
+impl<'de, 'a, A> EnumAccess<'de> for LenientEnum<'a, A>
+where
+    A: EnumAccess<'de>,
+{
+    type Error = A::Error;
+    type Variant = LenientEnum<'a, A::Variant>;
+
+    fn variant_seed<V>(self, seed: V) -> Result<(V::Value, Self::Variant), Self::Error>
+    where
+        V: DeserializeSeed<'de>,
+    {
+        let (value, variant) = self.data.variant_seed(seed)?;
+        let variant = LenientEnum {
+            data: variant,
+            path: self.path,
+            unknown: self.unknown,
+        };
+        Ok((value, variant))
+    }
+}
+
+impl<'de, 'a, A> VariantAccess<'de> for LenientEnum<'a, A>
+where
+    A: VariantAccess<'de>,
+{
+    type Error = A::Error;
+
+    fn unit_variant(self) -> Result<(), Self::Error> {
+        self.data.unit_variant()
+    }
+
+    fn newtype_variant_seed<T>(self, seed: T) -> Result<T::Value, Self::Error>
+    where
+        T: DeserializeSeed<'de>,
+    {
+        self.data.newtype_variant_seed(LenientSeed {
+            seed,
+            path: self.path,
+            unknown: self.unknown,
+        })
+    }
+
+    fn tuple_variant<V>(self, len: usize, visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        let visitor = LenientVisitor::new(visitor, self.path, self.unknown, None);
+        self.data.tuple_variant(len, visitor)
+    }
+
+    fn struct_variant<V>(
+        self,
+        fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        let visitor = LenientVisitor::new(visitor, self.path, self.unknown, Some(fields));
+        self.data.struct_variant(fields, visitor)
+    }
+}
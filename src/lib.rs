@@ -1,11 +1,13 @@
 #![feature(transpose_result)]
 #![feature(try_from)]
+#![feature(non_exhaustive)]
 
 extern crate serde;
 #[macro_use]
 extern crate serde_derive;
 extern crate failure;
 extern crate serde_json;
+extern crate serde_path_to_error;
 #[macro_use]
 extern crate failure_derive;
 #[macro_use]
@@ -13,17 +15,138 @@ extern crate lazy_static;
 extern crate chrono;
 extern crate htmlescape;
 extern crate regex;
+
+#[cfg(all(feature = "rocket", not(target_arch = "wasm32")))]
+extern crate rocket;
+#[cfg(all(feature = "tarpc", not(target_arch = "wasm32")))]
 extern crate tarpc;
+#[cfg(feature = "actix")]
+extern crate actix_web;
+#[cfg(feature = "actix")]
+extern crate futures;
+#[cfg(feature = "axum")]
+extern crate axum;
+#[cfg(feature = "diesel")]
+#[macro_use]
+extern crate diesel;
+#[cfg(feature = "sqlx-postgres")]
+extern crate sqlx;
+#[cfg(feature = "postgres")]
+#[macro_use]
+extern crate postgres_types;
+#[cfg(feature = "postgres")]
+extern crate bytes;
+#[cfg(feature = "redis")]
+extern crate log;
+#[cfg(feature = "redis")]
+extern crate redis;
+#[cfg(feature = "juniper")]
+extern crate juniper;
+#[cfg(feature = "async-graphql")]
+extern crate async_graphql;
+#[cfg(feature = "schemars")]
+#[macro_use]
+extern crate schemars;
+#[cfg(feature = "utoipa")]
+#[macro_use]
+extern crate utoipa;
+#[cfg(feature = "proto")]
+extern crate prost;
+#[cfg(feature = "hmac")]
+extern crate hmac;
+#[cfg(feature = "hmac")]
+extern crate sha2;
+#[cfg(feature = "url")]
+extern crate url;
+#[cfg(feature = "http")]
+extern crate http;
+#[cfg(feature = "cbor")]
+extern crate serde_cbor;
+#[cfg(feature = "query")]
+extern crate form_urlencoded;
+#[cfg(feature = "query")]
+extern crate serde_urlencoded;
+#[cfg(feature = "small-token")]
+extern crate smallstr;
+#[cfg(feature = "wasm-bindgen")]
+extern crate serde_wasm_bindgen;
+#[cfg(feature = "wasm-bindgen")]
+extern crate wasm_bindgen;
 
-#[cfg(test)]
+#[cfg(any(test, feature = "arbitrary"))]
 #[macro_use]
 extern crate proptest;
+#[cfg(test)]
+extern crate bincode;
+#[cfg(all(test, feature = "axum"))]
+extern crate http_body_util;
+#[cfg(all(
+    test,
+    any(feature = "axum", feature = "sqlx-postgres", feature = "async-graphql")
+))]
+extern crate tokio;
+#[cfg(all(test, feature = "axum"))]
+extern crate tower;
+#[cfg(any(test, feature = "msgpack"))]
+extern crate rmp_serde;
 
 #[macro_use]
 pub mod macros;
+#[cfg(feature = "actix")]
+pub mod actix;
 pub mod admin;
+#[cfg(feature = "arbitrary")]
+pub mod arbitrary;
+#[cfg(feature = "async-graphql")]
+pub mod async_graphql_ext;
 pub mod auth;
+#[cfg(feature = "axum")]
+pub mod axum_ext;
+pub mod cache;
+#[cfg(feature = "cbor")]
+pub mod cbor;
+pub mod codec;
 pub mod content;
+#[cfg(feature = "diesel")]
+pub mod diesel_ext;
+mod envelope_de;
 pub mod error;
+#[cfg(feature = "schemars")]
+pub mod json_schema;
+#[cfg(feature = "juniper")]
+pub mod juniper_ext;
+mod lenient;
+#[cfg(feature = "utoipa")]
+pub mod openapi;
 pub mod payloads;
+#[cfg(feature = "proto")]
+pub mod proto;
+#[cfg(feature = "query")]
+pub mod query;
+#[cfg(feature = "postgres")]
+pub mod postgres_ext;
+pub mod rate_limit;
+#[cfg(feature = "redis")]
+pub mod redis_ext;
+pub mod request_kind;
+#[cfg(any(feature = "schemars", feature = "utoipa"))]
+mod schema;
+#[cfg(all(feature = "rocket", not(target_arch = "wasm32")))]
+pub mod session;
+#[cfg(feature = "sqlx-postgres")]
+pub mod sqlx_ext;
+#[cfg(feature = "http")]
+mod status;
+pub mod timestamp;
+#[cfg(feature = "ts-export")]
+pub mod typescript;
 pub mod valid;
+mod value_de;
+#[cfg(feature = "wasm-bindgen")]
+pub mod wasm_bindgen_ext;
+#[cfg(test)]
+mod wire;
+#[cfg(test)]
+mod wire_compat;
+
+pub use crate::error::Error;
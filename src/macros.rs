@@ -16,6 +16,66 @@ macro_rules! impl_deserialize_with_try_from {
     };
 }
 
+/// Implements rocket's `FromParam` and `FromFormValue` for a item which
+/// validates the url decoded string with `TryFrom<String>`
+///
+/// A string which is not valid utf-8 once decoded is validated as a empty
+/// string, and rejected with the error of the item.
+///
+/// With these (and the impls of the ids) a handler can take the validated
+/// types straight away, like `fn user(id: UserId)` or `fn profile(username:
+/// Username)`, and a invalid value is rejected with its `ValidationError`.
+///
+/// # Example usage
+///
+/// The impls only exist with the `rocket` feature.
+///
+/// ```
+/// # #[cfg(feature = "rocket")]
+/// # fn main() {
+/// # use datatypes::valid::fields::Username;
+/// # use datatypes::valid::ValidationError;
+/// use rocket::http::RawStr;
+/// use rocket::request::{FromFormValue, FromParam};
+///
+/// let username = Username::from_param(RawStr::from_str("john_doe")).unwrap();
+/// assert_eq!(&*username, "john_doe");
+///
+/// let e = Username::from_form_value(RawStr::from_str("jo")).unwrap_err();
+/// assert_eq!(e, ValidationError::InvalidUsername);
+/// # }
+/// # #[cfg(not(feature = "rocket"))]
+/// # fn main() {}
+/// ```
+#[macro_export]
+macro_rules! impl_from_param_and_form_value {
+    ($ident:ident) => {
+        #[cfg(all(feature = "rocket", not(target_arch = "wasm32")))]
+        impl<'a> rocket::request::FromParam<'a> for $ident {
+            type Error = <$ident as std::convert::TryFrom<String>>::Error;
+            fn from_param(s: &'a rocket::http::RawStr) -> Result<Self, Self::Error> {
+                let decoded = s.url_decode().unwrap_or_default();
+                <$ident as std::convert::TryFrom<String>>::try_from(decoded)
+            }
+        }
+        #[cfg(all(feature = "rocket", not(target_arch = "wasm32")))]
+        impl<'a> rocket::request::FromFormValue<'a> for $ident {
+            type Error = <$ident as std::convert::TryFrom<String>>::Error;
+            fn from_form_value(s: &'a rocket::http::RawStr) -> Result<Self, Self::Error> {
+                let decoded = s.url_decode().unwrap_or_default();
+                <$ident as std::convert::TryFrom<String>>::try_from(decoded)
+            }
+        }
+    };
+}
+
+/// Implements serialize for a item which is written as a HTML-escaped string
+///
+/// A item which is marked with `raw` is written as it is, like a item which
+/// never ends up in HTML unescaped (like a url). A item which is marked with
+/// `escaped` is already escaped when it is validated, and is written without
+/// the escaping so it is escaped once again when it is deserialized. Only a
+/// raw or escaped item is the same after a round trip.
 #[macro_export]
 macro_rules! impl_serialize {
     ($ident:ident) => {
@@ -24,11 +84,71 @@ macro_rules! impl_serialize {
             where
                 S: serde::Serializer,
             {
-                let encoded = encode_minimal(self.as_ref());
+                let encoded = htmlescape::encode_minimal(self.as_ref());
                 serializer.serialize_str(&encoded)
             }
         }
     };
+    ($ident:ident, raw) => {
+        impl serde::Serialize for $ident {
+            fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+            where
+                S: serde::Serializer,
+            {
+                serializer.serialize_str(self.as_ref())
+            }
+        }
+    };
+    ($ident:ident, escaped) => {
+        impl serde::Serialize for $ident {
+            fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+            where
+                S: serde::Serializer,
+            {
+                let escaped: &str = self.as_ref();
+                match htmlescape::decode_html(escaped) {
+                    Ok(decoded) => serializer.serialize_str(&decoded),
+                    Err(_) => serializer.serialize_str(escaped),
+                }
+            }
+        }
+    };
+}
+
+/// Implements schemars' `JsonSchema` and utoipa's `ToSchema` for a item
+/// which is (de)serialized as a string, with the limits of a
+/// `schema::StringSchema`
+///
+/// The schemas are only built with the `schemars` or `utoipa` feature, so
+/// the limits can name the (private) constants of the item.
+#[macro_export]
+macro_rules! impl_schema {
+    ($ident:ident => $schema:expr) => {
+        #[cfg(feature = "schemars")]
+        impl schemars::JsonSchema for $ident {
+            fn schema_name() -> String {
+                stringify!($ident).to_owned()
+            }
+
+            fn json_schema(_: &mut schemars::gen::SchemaGenerator) -> schemars::schema::Schema {
+                $schema.into()
+            }
+        }
+
+        #[cfg(feature = "utoipa")]
+        impl utoipa::PartialSchema for $ident {
+            fn schema() -> utoipa::openapi::RefOr<utoipa::openapi::schema::Schema> {
+                $schema.into()
+            }
+        }
+
+        #[cfg(feature = "utoipa")]
+        impl utoipa::ToSchema for $ident {
+            fn name() -> std::borrow::Cow<'static, str> {
+                std::borrow::Cow::Borrowed(stringify!($ident))
+            }
+        }
+    };
 }
 
 /// Generates relevant impls for ids
@@ -49,6 +169,7 @@ macro_rules! id_impls {
                     .map_err(|_| crate::valid::ValidationError::InvalidId)
             }
         }
+        #[cfg(all(feature = "rocket", not(target_arch = "wasm32")))]
         impl<'a> rocket::request::FromParam<'a> for $outer_ty {
             type Error = crate::valid::ValidationError;
             fn from_param(s: &'a rocket::http::RawStr) -> Result<Self, Self::Error> {
@@ -56,6 +177,14 @@ macro_rules! id_impls {
                 <rocket::http::RawStr as std::convert::AsRef<str>>::as_ref(s).try_into()
             }
         }
+        #[cfg(all(feature = "rocket", not(target_arch = "wasm32")))]
+        impl<'a> rocket::request::FromFormValue<'a> for $outer_ty {
+            type Error = crate::valid::ValidationError;
+            fn from_form_value(s: &'a rocket::http::RawStr) -> Result<Self, Self::Error> {
+                use std::convert::TryInto;
+                <rocket::http::RawStr as std::convert::AsRef<str>>::as_ref(s).try_into()
+            }
+        }
         impl std::convert::From<$inner> for $outer_ty {
             fn from(n: $inner) -> Self {
                 $outer_cons(n)
@@ -100,3 +229,37 @@ macro_rules! impl_deref_and_as_ref {
         }
     };
 }
+
+/// Implements `Serialize` and `Deserialize` for a adjacently tagged response
+/// enum with a `Unknown { type_tag, payload }` variant
+///
+/// The enum must derive both with `#[serde(remote = "Self")]` and skip the
+/// `Unknown` variant, so that serializing it fails. A response with a tag
+/// which is not known is deserialized as `Unknown` (see
+/// `payloads::deserialize_forward_compatible`).
+#[macro_export]
+macro_rules! impl_forward_compatible {
+    ($ident:ident) => {
+        impl serde::Serialize for $ident {
+            fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+            where
+                S: serde::Serializer,
+            {
+                $ident::serialize(self, serializer)
+            }
+        }
+        impl<'de> serde::de::Deserialize<'de> for $ident {
+            fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+            where
+                D: serde::de::Deserializer<'de>,
+            {
+                $crate::payloads::deserialize_forward_compatible(
+                    deserializer,
+                    $ident::TAGS,
+                    |value| $ident::deserialize(value),
+                    |type_tag, payload| $ident::Unknown { type_tag, payload },
+                )
+            }
+        }
+    };
+}
@@ -0,0 +1,164 @@
+//! OpenAPI schemas of the wire types, for documenting a HTTP API with utoipa
+//!
+//! Every request, response and payload implements `utoipa::ToSchema`, so a
+//! gateway can list them as the components of its `OpenApi` instead of
+//! mirroring them. The validated types carry the limits of the default
+//! `ValidationPolicy` like in the JSON schemas (see `json_schema`), a `Token`
+//! is write-only and a IP address is a string with the format `ip`.
+//!
+//! The tagged enums (like the requests) are a `oneOf` of their variants,
+//! which `Discriminators` marks with a discriminator on their tag:
+//!
+//! ```
+//! # use datatypes::auth::requests::AuthRequest;
+//! # use datatypes::openapi::Discriminators;
+//! use utoipa::OpenApi;
+//!
+//! #[derive(OpenApi)]
+//! #[openapi(components(schemas(AuthRequest)), modifiers(&Discriminators))]
+//! struct ApiDoc;
+//!
+//! let json = ApiDoc::openapi().to_pretty_json().unwrap();
+//! # assert!(json.contains("\"AuthRequest\""));
+//! ```
+
+use utoipa::openapi::schema::{Discriminator, ObjectBuilder, OneOfBuilder, Schema, Type};
+use utoipa::openapi::{OpenApi, RefOr};
+use utoipa::Modify;
+
+/// The tags of the tagged enums, `type` for the requests and responses and
+/// `status` for a `ResponseResult`
+const TAGS: &[&str] = &["type", "status"];
+
+/// Adds a discriminator to every component which is a tagged enum
+///
+/// A component is a tagged enum when it is a `oneOf` of objects which all
+/// require the same tag (see `TAGS`).
+pub struct Discriminators;
+
+impl Modify for Discriminators {
+    fn modify(&self, openapi: &mut OpenApi) {
+        let components = match openapi.components.as_mut() {
+            Some(components) => components,
+            None => return,
+        };
+        for schema in components.schemas.values_mut() {
+            if let RefOr::T(Schema::OneOf(one_of)) = schema {
+                if one_of.discriminator.is_some() || one_of.items.is_empty() {
+                    continue;
+                }
+                let tag = TAGS
+                    .iter()
+                    .find(|tag| one_of.items.iter().all(|v| requires(v, tag)));
+                if let Some(tag) = tag {
+                    one_of.discriminator = Some(Discriminator::new(*tag));
+                }
+            }
+        }
+    }
+}
+
+/// Whether `variant` is a object which requires `property`
+fn requires(variant: &RefOr<Schema>, property: &str) -> bool {
+    match variant {
+        RefOr::T(Schema::Object(object)) => {
+            object.properties.contains_key(property)
+                && object.required.iter().any(|r| r == property)
+        }
+        _ => false,
+    }
+}
+
+/// A enum which is tagged with its `type` and has its content in its
+/// `payload`, for the enums which can't derive their schema
+pub(crate) fn tagged(variants: Vec<(&'static str, RefOr<Schema>)>) -> RefOr<Schema> {
+    let mut schema = OneOfBuilder::new().discriminator(Some(Discriminator::new("type")));
+    for (tag, payload) in variants {
+        let variant = ObjectBuilder::new()
+            .schema_type(Type::Object)
+            .property("type", crate::schema::string().values(&[tag]))
+            .required("type")
+            .property("payload", payload)
+            .required("payload");
+        schema = schema.item(RefOr::T(Schema::Object(variant.build())));
+    }
+    RefOr::T(Schema::OneOf(schema.build()))
+}
+
+/// A IP address, which is written as a string
+pub(crate) fn ip_addr() -> RefOr<Schema> {
+    crate::schema::string().format("ip").into()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::admin::responses::BanEntry;
+    use crate::auth::requests::AuthRequest;
+    use crate::content::requests::EditCategoryPayload;
+    use crate::valid::policy::ValidationPolicy;
+    use utoipa::OpenApi as _;
+
+    #[derive(utoipa::OpenApi)]
+    #[openapi(
+        components(schemas(AuthRequest, BanEntry, EditCategoryPayload)),
+        modifiers(&Discriminators)
+    )]
+    struct ApiDoc;
+
+    fn component(json: &serde_json::Value, name: &str) -> serde_json::Value {
+        json["components"]["schemas"][name].clone()
+    }
+
+    #[test]
+    fn auth_request_component() {
+        let doc = ApiDoc::openapi();
+        let components = doc.components.as_ref().unwrap();
+        assert!(components.schemas.contains_key("AuthRequest"));
+
+        let json = serde_json::to_value(&doc).unwrap();
+        let request = component(&json, "AuthRequest");
+        let variants = request["oneOf"].as_array().unwrap();
+        assert_eq!(variants.len(), AuthRequest::TAGS.len());
+        assert_eq!(request["discriminator"]["propertyName"], "type");
+    }
+
+    #[test]
+    fn validated_fields() {
+        let json = serde_json::to_value(&ApiDoc::openapi()).unwrap();
+        let policy = ValidationPolicy::default();
+        let username = component(&json, "Username");
+        assert_eq!(username["type"], "string");
+        assert_eq!(username["minLength"], policy.min_username_len);
+        assert_eq!(username["maxLength"], policy.max_username_len);
+        assert_eq!(component(&json, "Email")["format"], "email");
+        assert_eq!(component(&json, "Token")["writeOnly"], true);
+    }
+
+    #[test]
+    fn ip_addr_is_a_string() {
+        let json = serde_json::to_value(&ApiDoc::openapi()).unwrap();
+        let subject = component(&json, "BanSubject");
+        assert_eq!(subject["discriminator"]["propertyName"], "type");
+        let ip = &subject["oneOf"][0]["properties"];
+        assert_eq!(ip["type"]["enum"], serde_json::json!(["IP"]));
+        assert_eq!(ip["payload"]["type"], "string");
+        assert_eq!(ip["payload"]["format"], "ip");
+    }
+
+    #[test]
+    fn patch_is_nullable_and_not_required() {
+        let json = serde_json::to_value(&ApiDoc::openapi()).unwrap();
+        let edit = component(&json, "EditCategoryPayload");
+        let required = edit["required"].as_array().unwrap();
+        assert!(!required.iter().any(|r| r == "description"));
+
+        let description = &edit["properties"]["description"];
+        let description = match description["$ref"].as_str() {
+            Some(path) => component(&json, path.rsplit('/').next().unwrap()),
+            None => description.clone(),
+        };
+        assert_eq!(description["oneOf"][0]["type"], "null");
+        assert_eq!(description["oneOf"][1]["type"], "string");
+    }
+}
@@ -1,210 +1,3005 @@
 //! Contains useful template payloads
 
+use crate::error::{Error, ParseError, ParseErrorKind, PathError};
 use crate::valid::ids::UserId;
 use crate::valid::token::Token;
+use crate::valid::{Validate, ValidationError};
+use crate::value_de::{DeError, ValueDeserializer};
+use std::convert::TryFrom;
+use std::fmt;
+use std::marker::PhantomData;
 use std::ops::{Deref, DerefMut};
 
-/// A payload which must be present, but empty
+/// A payload which must be present, but empty (or `null`)
 ///
-/// # Examples
+/// Many HTTP clients send "no payload" as `null`, so a `null` payload is
+/// accepted as well. The payloads which are accepted are
 ///
-/// **For the examples it is assumed that the payload is used in a surrounding
-/// type**
+/// | payload                 | `EmptyPayloadStrict` | `EmptyPayload` |
+/// |-------------------------|----------------------|----------------|
+/// | `"payload": {}`         | accepted             | accepted       |
+/// | `"payload": null`       | accepted             | accepted       |
+/// | no `payload` field      | rejected             | accepted       |
+/// | `"payload": { "a": 1 }` | rejected             | rejected       |
+/// | `"payload": [1]`        | rejected             | rejected       |
 ///
-/// The following JSON object is parsed as a valid request because there is an
-/// empty payload.
+/// # Examples
 ///
-/// ```json
-/// {
-///     "type": "SOME_TYPE",
-///     "payload": {}
-/// }
 /// ```
+/// # #[macro_use]
+/// # extern crate serde_derive;
+/// # use datatypes::payloads::EmptyPayloadStrict;
+/// #[derive(Deserialize)]
+/// #[serde(tag = "type", content = "payload")]
+/// enum Request {
+///     SomeType(EmptyPayloadStrict),
+/// }
 ///
-/// However this JSON object is parsed as a invalid request because `payload`
-/// is missing.
-///
-/// ```json
-/// {
-///     "type": "SOME_TYPE"
+/// fn main() {
+///     let parse = |json: &str| serde_json::from_str::<Request>(json);
+///     assert!(parse(r#"{ "type": "SomeType", "payload": {} }"#).is_ok());
+///     assert!(parse(r#"{ "type": "SomeType", "payload": null }"#).is_ok());
+///     assert!(parse(r#"{ "type": "SomeType" }"#).is_err());
+///     assert!(parse(r#"{ "type": "SomeType", "payload": { "a": 1 } }"#).is_err());
+///     assert!(parse(r#"{ "type": "SomeType", "payload": [1] }"#).is_err());
 /// }
 /// ```
-#[derive(Serialize, Deserialize, PartialEq, PartialOrd, Debug)]
+#[derive(Serialize, PartialEq, PartialOrd, Debug)]
+#[cfg_attr(feature = "schemars", derive(JsonSchema))]
+#[cfg_attr(feature = "utoipa", derive(ToSchema))]
 pub struct EmptyPayloadStrict {}
 
-/// A payload which can either be present and empty or not present
+impl<'de> serde::de::Deserialize<'de> for EmptyPayloadStrict {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::de::Deserializer<'de>,
+    {
+        let visitor = EmptyVisitor;
+        if deserializer.is_human_readable() {
+            // Not `deserialize_option`, which serde answers with `None` for a
+            // missing payload (instead of a missing field error)
+            deserializer.deserialize_any(visitor)
+        } else {
+            // Formats like bincode can only read a option if one was written
+            deserializer.deserialize_struct("EmptyPayloadStrict", &[], visitor)
+        }
+    }
+}
+
+/// Accepts a empty map or list (like a struct without fields), and `null`
 ///
-/// # Examples
+/// A map or list with any entries in it is rejected.
+struct EmptyVisitor;
+
+impl<'de> serde::de::Visitor<'de> for EmptyVisitor {
+    type Value = EmptyPayloadStrict;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "a empty payload or null")
+    }
+
+    fn visit_none<E: serde::de::Error>(self) -> Result<Self::Value, E> {
+        Ok(EmptyPayloadStrict {})
+    }
+
+    fn visit_unit<E: serde::de::Error>(self) -> Result<Self::Value, E> {
+        Ok(EmptyPayloadStrict {})
+    }
+
+    fn visit_some<D>(self, deserializer: D) -> Result<Self::Value, D::Error>
+    where
+        D: serde::de::Deserializer<'de>,
+    {
+        deserializer.deserialize_struct("EmptyPayloadStrict", &[], self)
+    }
+
+    fn visit_map<A>(self, mut map: A) -> Result<Self::Value, A::Error>
+    where
+        A: serde::de::MapAccess<'de>,
+    {
+        use serde::de::{Error, IgnoredAny};
+        if map.next_key::<IgnoredAny>()?.is_some() {
+            return Err(A::Error::invalid_length(1, &self));
+        }
+        Ok(EmptyPayloadStrict {})
+    }
+
+    fn visit_seq<A>(self, mut seq: A) -> Result<Self::Value, A::Error>
+    where
+        A: serde::de::SeqAccess<'de>,
+    {
+        use serde::de::{Error, IgnoredAny};
+        if seq.next_element::<IgnoredAny>()?.is_some() {
+            return Err(A::Error::invalid_length(1, &self));
+        }
+        Ok(EmptyPayloadStrict {})
+    }
+}
+
+/// A payload which can either be empty, `null` or not present
 ///
-/// **For the examples it is assumed that the payload is used in a surrounding
-/// type**
+/// See `EmptyPayloadStrict` for the payloads which are accepted.
 ///
-/// The following JSON object is parsed as a valid request because there is an
-/// empty payload.
+/// # Examples
 ///
-/// ```json
-/// {
-///     "type": "SOME_TYPE",
-///     "payload": {}
-/// }
 /// ```
+/// # #[macro_use]
+/// # extern crate serde_derive;
+/// # use datatypes::payloads::EmptyPayload;
+/// #[derive(Deserialize)]
+/// #[serde(tag = "type", content = "payload")]
+/// enum Request {
+///     SomeType(EmptyPayload),
+/// }
 ///
-/// This JSON object is also parsed as a valid request even though the field is
-/// missing.
-///
-/// ```json
-/// {
-///     "type": "SOME_TYPE"
+/// fn main() {
+///     let parse = |json: &str| serde_json::from_str::<Request>(json);
+///     assert!(parse(r#"{ "type": "SomeType", "payload": {} }"#).is_ok());
+///     assert!(parse(r#"{ "type": "SomeType", "payload": null }"#).is_ok());
+///     assert!(parse(r#"{ "type": "SomeType" }"#).is_ok());
+///     assert!(parse(r#"{ "type": "SomeType", "payload": { "a": 1 } }"#).is_err());
 /// }
 /// ```
 pub type EmptyPayload = Option<EmptyPayloadStrict>;
 
-/// Represents a payload that also contains a authorization token
+/// A payload which can be empty or left out, but which can't be `null`
 ///
-/// This payload is generic for both the inner type and the token type, this
-/// means that the consumer can provide the types they want. The sole purpose
-/// of this structure is to provide a simple way of wrapping an existing type
-/// with a token.
+/// | payload             | `AbsentOrEmpty` |
+/// |---------------------|-----------------|
+/// | `"payload": {}`     | `Empty`         |
+/// | no `payload` field  | `Absent`        |
+/// | `"payload": null`   | rejected        |
 ///
-/// This wrapper is mainly intended for internal use as the token will be
-/// stored in the session cookies.
+/// Serde hands a missing payload and a `null` payload over in the same way
+/// (as `None`), so the two are only told apart inside of a request envelope
+/// which hands a `null` over as a unit (like `ContentRequest`). As a field of
+/// any other struct a `null` is taken as `Absent`.
 ///
-/// NB! The type that is wrapped cannot contain a field named `token`
-/// (`#[serde(rename="...")]` could be used to circument this)
+/// Both are serialized as a empty payload, unless the field is skipped with
+/// `#[serde(skip_serializing_if = "AbsentOrEmpty::is_absent")]`.
 ///
-/// # Example usage
+/// # Examples
+///
+/// ```
+/// # use datatypes::content::requests::ContentRequest;
+/// # use datatypes::payloads::AbsentOrEmpty;
+/// let parse = |json: &str| serde_json::from_str::<ContentRequest>(json);
+///
+/// let request = parse(r#"{ "type": "LIST_DRAFTS", "payload": {} }"#).unwrap();
+/// assert_eq!(request, ContentRequest::ListDrafts(AbsentOrEmpty::Empty));
+///
+/// let request = parse(r#"{ "type": "LIST_DRAFTS" }"#).unwrap();
+/// assert_eq!(request, ContentRequest::ListDrafts(AbsentOrEmpty::Absent));
+///
+/// assert!(parse(r#"{ "type": "LIST_DRAFTS", "payload": null }"#).is_err());
+/// ```
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+pub enum AbsentOrEmpty {
+    Absent,
+    Empty,
+}
+
+impl AbsentOrEmpty {
+    pub fn is_absent(&self) -> bool {
+        *self == AbsentOrEmpty::Absent
+    }
+}
+
+impl Default for AbsentOrEmpty {
+    fn default() -> Self {
+        AbsentOrEmpty::Absent
+    }
+}
+
+#[cfg(feature = "schemars")]
+impl schemars::JsonSchema for AbsentOrEmpty {
+    fn is_referenceable() -> bool {
+        false
+    }
+
+    fn schema_name() -> String {
+        EmptyPayloadStrict::schema_name()
+    }
+
+    fn json_schema(gen: &mut schemars::gen::SchemaGenerator) -> schemars::schema::Schema {
+        EmptyPayloadStrict::json_schema(gen)
+    }
+}
+
+#[cfg(feature = "utoipa")]
+impl utoipa::PartialSchema for AbsentOrEmpty {
+    fn schema() -> utoipa::openapi::RefOr<utoipa::openapi::schema::Schema> {
+        <EmptyPayloadStrict as utoipa::PartialSchema>::schema()
+    }
+}
+
+#[cfg(feature = "utoipa")]
+impl utoipa::ToSchema for AbsentOrEmpty {
+    fn name() -> std::borrow::Cow<'static, str> {
+        <EmptyPayloadStrict as utoipa::ToSchema>::name()
+    }
+}
+
+impl serde::Serialize for AbsentOrEmpty {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        serde::Serialize::serialize(&EmptyPayloadStrict {}, serializer)
+    }
+}
+
+impl<'de> serde::de::Deserialize<'de> for AbsentOrEmpty {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::de::Deserializer<'de>,
+    {
+        if deserializer.is_human_readable() {
+            // A missing payload is `None`, while the envelope hands a `null`
+            // over as a unit (see `envelope_de`)
+            deserializer.deserialize_option(AbsentOrEmptyVisitor)
+        } else {
+            deserializer
+                .deserialize_struct("EmptyPayloadStrict", &[], EmptyVisitor)
+                .map(|_| AbsentOrEmpty::Empty)
+        }
+    }
+}
+
+/// Accepts a missing payload and a empty map or list, but not `null`
+struct AbsentOrEmptyVisitor;
+
+impl<'de> serde::de::Visitor<'de> for AbsentOrEmptyVisitor {
+    type Value = AbsentOrEmpty;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "a empty payload")
+    }
+
+    fn visit_none<E: serde::de::Error>(self) -> Result<Self::Value, E> {
+        Ok(AbsentOrEmpty::Absent)
+    }
+
+    fn visit_unit<E: serde::de::Error>(self) -> Result<Self::Value, E> {
+        Err(E::invalid_type(serde::de::Unexpected::Unit, &self))
+    }
+
+    fn visit_some<D>(self, deserializer: D) -> Result<Self::Value, D::Error>
+    where
+        D: serde::de::Deserializer<'de>,
+    {
+        deserializer
+            .deserialize_struct("EmptyPayloadStrict", &[], EmptyVisitor)
+            .map(|_| AbsentOrEmpty::Empty)
+    }
+}
+
+/// A update of a field of a edit request
+///
+/// | field               | `Patch`   | the update              |
+/// |---------------------|-----------|-------------------------|
+/// | no field            | `Absent`  | the field is unchanged  |
+/// | `"field": null`     | `Null`    | the field is cleared    |
+/// | `"field": value`    | `Set`     | the field is set        |
+///
+/// A field must be marked with
+/// `#[serde(default, skip_serializing_if = "Patch::is_absent")]`, as the
+/// absence of the field can't be serialized (`Absent` is serialized as
+/// `null` without it). A field which can't be cleared (like the title of a
+/// thread) is a `Option`, where `null` is the same as a missing field.
+///
+/// # Examples
 ///
 /// ```
-/// # use datatypes::payloads::TokenPayload;
-/// # use datatypes::valid::token::Token;
 /// # #[macro_use]
 /// # extern crate serde_derive;
-/// #[derive(Serialize, Deserialize, PartialEq, Debug)]
-/// struct UserPayload<'a> {
-///     name: &'a str,
-///     email: &'a str,
+/// # use datatypes::payloads::Patch;
+/// #[derive(Deserialize)]
+/// struct Update {
+///     #[serde(default)]
+///     name: Patch<String>,
 /// }
 ///
-/// type AuthUserPayload<'a> = TokenPayload<UserPayload<'a>>;
-///
 /// fn main() {
+///     let update: Update = serde_json::from_str(r#"{}"#).unwrap();
+///     assert_eq!(update.name, Patch::Absent);
 ///
-///     let user_payload = UserPayload {
-///         name: "John Doe",
-///         email: "john@doe.com"
-///     };
-///     let token = Token::new("random-token");
-///
-///     // Make a new authentication payload with a inner type and a token
-///     let payload = AuthUserPayload::new(user_payload, token);
-///
-///     let json = r#"{
-///                      "name": "John Doe",
-///                      "email": "john@doe.com",
-///                      "token": "random-token"
-///                   }"#;
+///     let update: Update = serde_json::from_str(r#"{ "name": null }"#).unwrap();
+///     assert_eq!(update.name, Patch::Null);
 ///
-///     let expt: AuthUserPayload = serde_json::from_str(json).unwrap();
-///     assert_eq!(expt, payload);
+///     let update: Update = serde_json::from_str(r#"{ "name": "john" }"#).unwrap();
+///     assert_eq!(update.name, Patch::Set("john".to_owned()));
 /// }
 /// ```
-#[derive(Serialize, Deserialize, PartialEq, PartialOrd, Debug)]
-pub struct TokenPayload<Inner> {
-    token: Token,
-    #[serde(flatten)]
-    inner: Inner,
+#[derive(PartialEq, Eq, Clone, Debug)]
+pub enum Patch<T> {
+    Absent,
+    Null,
+    Set(T),
 }
 
-impl<Inner> TokenPayload<Inner> {
-    pub fn new(i: impl Into<Inner>, t: impl Into<Token>) -> TokenPayload<Inner> {
-        TokenPayload {
-            inner: i.into(),
-            token: t.into(),
+impl<T> Patch<T> {
+    pub fn is_absent(&self) -> bool {
+        match self {
+            Patch::Absent => true,
+            _ => false,
         }
     }
 
-    /// Get a reference to the token of the payload
-    pub fn token(&self) -> &Token {
-        &self.token
+    pub fn as_ref(&self) -> Patch<&T> {
+        match self {
+            Patch::Absent => Patch::Absent,
+            Patch::Null => Patch::Null,
+            Patch::Set(value) => Patch::Set(value),
+        }
     }
 
-    /// Set the token of a payload
-    pub fn set_token(&mut self, t: impl Into<Token>) -> Token {
-        std::mem::replace(&mut self.token, t.into())
+    pub fn map<U>(self, f: impl FnOnce(T) -> U) -> Patch<U> {
+        match self {
+            Patch::Absent => Patch::Absent,
+            Patch::Null => Patch::Null,
+            Patch::Set(value) => Patch::Set(f(value)),
+        }
     }
 
-    /// Turn the payload into its inner type
-    pub fn into_inner(self) -> (Inner, Token) {
-        (self.inner, self.token)
+    /// Apply the update to the current value of the field
+    pub fn apply(self, field: &mut Option<T>) {
+        match self {
+            Patch::Absent => {}
+            Patch::Null => *field = None,
+            Patch::Set(value) => *field = Some(value),
+        }
     }
 }
 
-impl<Inner> Deref for TokenPayload<Inner> {
-    type Target = Inner;
-    fn deref(&self) -> &Self::Target {
-        &self.inner
+impl<T> Default for Patch<T> {
+    fn default() -> Self {
+        Patch::Absent
     }
 }
 
-impl<Inner> DerefMut for TokenPayload<Inner> {
-    fn deref_mut(&mut self) -> &mut Self::Target {
-        &mut self.inner
+impl<T> From<Option<T>> for Patch<T> {
+    fn from(value: Option<T>) -> Self {
+        match value {
+            Some(value) => Patch::Set(value),
+            None => Patch::Null,
+        }
     }
 }
 
-/// Represents a payload that also contains a user id
-///
-/// This payload is generic for the inner type, this means that the consumer
-/// can provide the type of payload they want. The sole purpose of this
-/// structure is to provide a simple way of wrapping an existing payload with a
-/// user id.
-///
-/// This wrapper is mainly intended for internal use to transport a payload
-/// with a user id between the auth service to the security gate, and from the
-/// security gate to the controller.
+/// A patch is (de)serialized as a `Option<T>`, a absent patch is left out
+#[cfg(feature = "schemars")]
+impl<T: schemars::JsonSchema> schemars::JsonSchema for Patch<T> {
+    fn is_referenceable() -> bool {
+        false
+    }
+
+    fn schema_name() -> String {
+        <Option<T>>::schema_name()
+    }
+
+    fn json_schema(gen: &mut schemars::gen::SchemaGenerator) -> schemars::schema::Schema {
+        <Option<T>>::json_schema(gen)
+    }
+}
+
+/// The OpenAPI schema of a patch is the schema of `T` or `null`, and its field
+/// is not required as it has `#[serde(default)]`
+#[cfg(feature = "utoipa")]
+impl<T: utoipa::ToSchema> utoipa::PartialSchema for Patch<T> {
+    fn schema() -> utoipa::openapi::RefOr<utoipa::openapi::schema::Schema> {
+        use utoipa::openapi::schema::{ObjectBuilder, OneOfBuilder, Schema, Type};
+        use utoipa::openapi::RefOr;
+
+        let schema = OneOfBuilder::new()
+            .item(ObjectBuilder::new().schema_type(Type::Null))
+            .item(T::schema());
+        RefOr::T(Schema::OneOf(schema.build()))
+    }
+}
+
+#[cfg(feature = "utoipa")]
+impl<T: utoipa::ToSchema> utoipa::ToSchema for Patch<T> {
+    fn name() -> std::borrow::Cow<'static, str> {
+        std::borrow::Cow::Owned(format!("Patch_{}", T::name()))
+    }
+
+    fn schemas(
+        schemas: &mut Vec<(
+            String,
+            utoipa::openapi::RefOr<utoipa::openapi::schema::Schema>,
+        )>,
+    ) {
+        T::schemas(schemas)
+    }
+}
+
+impl<T: serde::Serialize> serde::Serialize for Patch<T> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        match self {
+            Patch::Absent | Patch::Null => serializer.serialize_none(),
+            Patch::Set(value) => serializer.serialize_some(value),
+        }
+    }
+}
+
+impl<'de, T> serde::de::Deserialize<'de> for Patch<T>
+where
+    T: serde::de::Deserialize<'de>,
+{
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::de::Deserializer<'de>,
+    {
+        // A missing field never gets here, it is `Absent` by `#[serde(default)]`
+        Option::<T>::deserialize(deserializer).map(Patch::from)
+    }
+}
+
+/// Find the tag in `tags` (the known tags of a request, like
+/// `AuthRequest::TAGS`) which `tag` is a other spelling of
 ///
-/// NB! The type that is wrapped cannot contain a field named `user_id`
-/// (`#[serde(rename="...")]` could be used to circument this)
-#[derive(Serialize, Deserialize, PartialEq, PartialOrd, Debug)]
-pub struct UserIdPayload<Inner> {
-    id: UserId,
-    #[serde(flatten)]
-    inner: Inner,
+/// The tags are compared without regard to case, and without the
+/// underscores, dashes and spaces between their words, which means that
+/// `authenticate`, `Authenticate` and `register_user`, `RegisterUser` are
+/// `AUTHENTICATE` and `REGISTER_USER`.
+pub fn canonical_type_tag<'a>(tag: &str, tags: &[&'a str]) -> Option<&'a str> {
+    fn words(tag: &str) -> String {
+        tag.chars().filter(|c| !"_- ".contains(*c)).collect()
+    }
+    let tag = words(tag);
+    tags.iter()
+        .find(|known| words(known).eq_ignore_ascii_case(&tag))
+        .cloned()
 }
 
-impl<Inner> UserIdPayload<Inner> {
-    pub fn new(i: impl Into<Inner>, id: impl Into<UserId>) -> UserIdPayload<Inner> {
-        UserIdPayload {
-            inner: i.into(),
-            id: id.into(),
+/// Parse a tagged request from json, where the type tag is matched
+/// case-insensitively
+///
+/// The `type` field of the request is replaced by the tag in `tags` which it
+/// matches (see `canonical_type_tag`) before the request is deserialized,
+/// which means that `"authenticate"` and `"Authenticate"` are read as
+/// `"AUTHENTICATE"`. A tag which matches none of them is left as it is. Only
+/// the tag of the request itself is changed, not the tags inside its payload.
+/// A request is always serialized with the canonical tag.
+///
+/// The request enums use this through their own `from_json_lenient`, like
+/// `AuthRequest::from_json_lenient`.
+///
+/// # Example usage
+///
+/// ```
+/// # use datatypes::content::requests::ContentRequest;
+/// # use datatypes::payloads::from_json_lenient;
+/// let json = r#"{ "type": "subscribe", "payload": { "thread_id": 1 } }"#;
+/// let request: ContentRequest = from_json_lenient(json, ContentRequest::TAGS).unwrap();
+///
+/// let json = serde_json::to_value(&request).unwrap();
+/// assert_eq!(json["type"], "SUBSCRIBE");
+/// ```
+pub fn from_json_lenient<T>(json: &str, tags: &[&str]) -> Result<T, Error>
+where
+    T: serde::de::DeserializeOwned,
+{
+    let mut value: serde_json::Value = serde_json::from_str(json)?;
+    if let Some(tag) = value.get_mut("type") {
+        let canonical = tag.as_str().and_then(|t| canonical_type_tag(t, tags));
+        if let Some(canonical) = canonical {
+            *tag = serde_json::Value::String(canonical.to_owned());
         }
     }
+    Ok(serde_json::from_value(value)?)
+}
 
-    /// Get a reference to the token of the payload
-    pub fn id(&self) -> &UserId {
-        &self.id
+/// Parse a tagged request from json, where a error tells the type of the
+/// request and the field which was wrong
+///
+/// The `type` tag is taken out of the request first, and checked against
+/// `tags` (the known tags of the request, like `AuthRequest::TAGS`) when the
+/// request can't be parsed. The payload is then parsed as the payload of that
+/// type, and the path of a error is given from the root of the payload.
+///
+/// The request enums use this through their own `parse_json`, like
+/// `AdminRequest::parse_json`.
+///
+/// # Example usage
+///
+/// ```
+/// # use datatypes::admin::requests::AdminRequest;
+/// # use datatypes::error::ParseErrorKind;
+/// let json = r#"{ "type": "BAN_IP", "payload": { "duration": "2h" } }"#;
+/// let e = AdminRequest::parse_json(json).unwrap_err();
+/// assert_eq!(e.request_type.as_ref().map(String::as_str), Some("BAN_IP"));
+/// assert_eq!(e.field.as_ref().map(String::as_str), Some("ip"));
+/// assert_eq!(e.kind, ParseErrorKind::MissingField);
+/// assert_eq!(e.to_string(), "missing field 'ip' in a BAN_IP request");
+/// ```
+pub fn parse_request<T>(json: &str, tags: &[&str]) -> Result<T, ParseError>
+where
+    T: serde::de::DeserializeOwned,
+{
+    let value = serde_json::from_str(json).map_err(|e| ParseError {
+        request_type: None,
+        field: None,
+        kind: ParseErrorKind::InvalidJson {
+            message: e.to_string(),
+        },
+    })?;
+    parse_request_value(value, tags)
+}
+
+/// Parse a tagged request from json which has already been parsed, where a
+/// error tells the type of the request and the field which was wrong
+///
+/// Works like `parse_request`, the request enums use this for their
+/// `TryFrom<serde_json::Value>`.
+pub fn parse_request_value<T>(value: serde_json::Value, tags: &[&str]) -> Result<T, ParseError>
+where
+    T: serde::de::DeserializeOwned,
+{
+    use serde::de::value::MapDeserializer;
+    use serde_json::Value;
+
+    let mut map = match value {
+        Value::Object(map) => map,
+        _ => return Err(missing_tag()),
+    };
+    let tag = match map.remove("type") {
+        Some(Value::String(tag)) => tag,
+        _ => return Err(missing_tag()),
+    };
+
+    // The tag is always given before the payload, which means that the payload
+    // is parsed straight away (and not buffered, which loses the path)
+    let mut entries = vec![("type", Value::String(tag.clone()))];
+    if let Some(payload) = map.remove("payload") {
+        entries.push(("payload", payload));
     }
+    let entries = entries
+        .into_iter()
+        .map(|(key, value)| (key, ValueDeserializer::new(value)));
+    let deserializer = MapDeserializer::<_, DeError>::new(entries);
+    serde_path_to_error::deserialize(deserializer).map_err(|e| {
+        let path = e.path().to_string();
+        request_error(tag, tags, &path, e.into_inner())
+    })
+}
 
-    /// Set the token of a payload
-    pub fn set_id(&mut self, id: impl Into<UserId>) -> UserId {
-        std::mem::replace(&mut self.id, id.into())
+fn missing_tag() -> ParseError {
+    ParseError {
+        request_type: None,
+        field: None,
+        kind: ParseErrorKind::MissingTag,
     }
+}
 
-    /// Turn the payload into its inner type
-    pub fn into_inner(self) -> (Inner, UserId) {
-        (self.inner, self.id)
+/// The `ParseError` of a request of type `tag`, where the payload had a error
+/// at `path`
+fn request_error(tag: String, tags: &[&str], path: &str, e: DeError) -> ParseError {
+    let (field, kind) = match path {
+        "type" => (
+            None,
+            ParseErrorKind::UnknownTag {
+                expected: tags.iter().map(|t| t.to_string()).collect(),
+            },
+        ),
+        "." if e == DeError::MissingField("payload") => (None, ParseErrorKind::MissingPayload),
+        "payload" => field_error(None, e),
+        path if path.starts_with("payload.") => field_error(Some(&path["payload.".len()..]), e),
+        _ => field_error(None, e),
+    };
+    ParseError {
+        request_type: Some(tag),
+        field,
+        kind,
     }
 }
 
-impl<Inner> Deref for UserIdPayload<Inner> {
-    type Target = Inner;
-    fn deref(&self) -> &Self::Target {
-        &self.inner
+/// The field and the kind of a error at `path` (from the root of the payload)
+fn field_error(path: Option<&str>, e: DeError) -> (Option<String>, ParseErrorKind) {
+    // The path of a missing field is the path of the struct it is missing from
+    match (path, e) {
+        (Some(path), DeError::MissingField(missing)) => (
+            Some(format!("{}.{}", path, missing)),
+            ParseErrorKind::MissingField,
+        ),
+        (None, DeError::MissingField(missing)) => {
+            (Some(missing.to_owned()), ParseErrorKind::MissingField)
+        }
+        (path, DeError::Custom(message)) => (
+            path.map(str::to_owned),
+            ParseErrorKind::InvalidValue { message },
+        ),
     }
 }
 
-impl<Inner> DerefMut for UserIdPayload<Inner> {
-    fn deref_mut(&mut self) -> &mut Self::Target {
-        &mut self.inner
+/// Parse a (untagged) payload from json, where a error tells the field which
+/// was wrong
+///
+/// Works like `parse_request` for a payload which is not a tagged request,
+/// the `request_type` of a error is always `None`.
+///
+/// # Example usage
+///
+/// ```
+/// # use datatypes::auth::requests::AuthPayload;
+/// # use datatypes::error::ParseErrorKind;
+/// # use datatypes::payloads::parse_payload;
+/// let e = parse_payload::<AuthPayload>(r#"{ "username": "john" }"#).unwrap_err();
+/// assert_eq!(e.field.as_ref().map(String::as_str), Some("password"));
+/// assert_eq!(e.kind, ParseErrorKind::MissingField);
+/// ```
+pub fn parse_payload<T>(json: &str) -> Result<T, ParseError>
+where
+    T: serde::de::DeserializeOwned,
+{
+    let value: serde_json::Value = serde_json::from_str(json).map_err(|e| ParseError {
+        request_type: None,
+        field: None,
+        kind: ParseErrorKind::InvalidJson {
+            message: e.to_string(),
+        },
+    })?;
+    serde_path_to_error::deserialize(ValueDeserializer::new(value)).map_err(|e| {
+        let path = e.path().to_string();
+        let path = Some(path.as_str()).filter(|p| *p != ".");
+        let (field, kind) = field_error(path, e.into_inner());
+        ParseError {
+            request_type: None,
+            field,
+            kind,
+        }
+    })
+}
+
+/// Parse a payload from json, where a error tells the path of the field which
+/// was wrong
+///
+/// The path is given from the root of the json, like `payload.email`. It goes
+/// through the flattened payloads (`TokenPayload`, `UserIdPayload` and
+/// `Versioned`), which means that the path of a field inside the inner type
+/// is the same as if it was not wrapped.
+///
+/// # Example usage
+///
+/// ```
+/// # use datatypes::auth::requests::RegisterUserPayload;
+/// # use datatypes::payloads::{parse_with_path, TokenPayload};
+/// let json = r#"{
+///     "token": "random-token",
+///     "username": "john",
+///     "password": "helloAndWelcome123",
+///     "email": "not an email"
+/// }"#;
+/// let e = parse_with_path::<TokenPayload<RegisterUserPayload>>(json).unwrap_err();
+/// assert_eq!(e.path, "email");
+/// ```
+pub fn parse_with_path<T>(input: &str) -> Result<T, PathError>
+where
+    T: serde::de::DeserializeOwned,
+{
+    let mut deserializer = serde_json::Deserializer::from_str(input);
+    let value = serde_path_to_error::deserialize(&mut deserializer).map_err(|e| PathError {
+        path: e.path().to_string(),
+        message: e.into_inner().to_string(),
+    })?;
+    deserializer.end().map_err(|e| PathError {
+        path: ".".to_owned(),
+        message: e.to_string(),
+    })?;
+    Ok(value)
+}
+
+/// Parse a payload from json, where the fields which are unknown are skipped
+/// and returned (by their path) instead of rejected
+///
+/// Works like `parse_with_path`, except that a struct never rejects a field
+/// it doesn't know, even if it has `#[serde(deny_unknown_fields)]`. This lets
+/// a service accept the requests of a client which is newer than itself
+/// (while the new fields are rolled out), and record which fields it didn't
+/// know. A field which is missing or invalid is still a error.
+///
+/// The fields of a value which serde buffers before it is deserialized (like
+/// a internally tagged enum, or a payload which is given before the `type` of
+/// its request) are left to the value itself, see `lenient`.
+///
+/// # Example usage
+///
+/// ```
+/// # use datatypes::admin::requests::AdminRequest;
+/// # use datatypes::payloads::parse_lenient;
+/// let json = r#"{ "type": "BAN_IP", "payload": { "ip": "10.0.0.1", "port": 80 } }"#;
+/// let (request, unknown) = parse_lenient::<AdminRequest>(json).unwrap();
+/// assert!(serde_json::from_str::<AdminRequest>(json).is_err());
+/// assert_eq!(unknown, vec!["payload.port"]);
+/// # let _ = request;
+/// ```
+pub fn parse_lenient<T>(input: &str) -> Result<(T, Vec<String>), PathError>
+where
+    T: serde::de::DeserializeOwned,
+{
+    use crate::lenient::{Lenient, Unknown};
+
+    let unknown = Unknown::default();
+    let mut deserializer = serde_json::Deserializer::from_str(input);
+    let lenient = Lenient::new(&mut deserializer, &unknown);
+    let value = serde_path_to_error::deserialize(lenient).map_err(|e| PathError {
+        path: e.path().to_string(),
+        message: e.into_inner().to_string(),
+    })?;
+    deserializer.end().map_err(|e| PathError {
+        path: ".".to_owned(),
+        message: e.to_string(),
+    })?;
+    Ok((value, unknown.into_inner()))
+}
+
+/// Deserialize a adjacently tagged response, where a response with a tag which
+/// is not known becomes `unknown(type_tag, payload)`
+///
+/// `known` deserializes the typed variants, which have the type tags in
+/// `tags`. A response with any other tag is unknown, while a known tag with a
+/// invalid payload is still a error. Used by `impl_forward_compatible!`.
+#[cfg(feature = "forward-compat")]
+pub fn deserialize_forward_compatible<'de, D, T>(
+    deserializer: D,
+    tags: &[&str],
+    known: impl FnOnce(serde_json::Value) -> Result<T, serde_json::Error>,
+    unknown: impl FnOnce(String, serde_json::Value) -> T,
+) -> Result<T, D::Error>
+where
+    D: serde::de::Deserializer<'de>,
+{
+    use serde::de::{Deserialize, Error};
+
+    let mut value = serde_json::Value::deserialize(deserializer)?;
+    let tag = match value.get("type").and_then(serde_json::Value::as_str) {
+        Some(tag) if !tags.contains(&tag) => tag.to_owned(),
+        _ => return known(value).map_err(D::Error::custom),
+    };
+
+    let payload = value
+        .as_object_mut()
+        .and_then(|fields| fields.remove("payload"))
+        .unwrap_or(serde_json::Value::Null);
+    Ok(unknown(tag, payload))
+}
+
+/// The amount of items per page when a client doesn't specify it
+pub const DEFAULT_PER_PAGE: u32 = 20;
+
+/// A payload which selects a page of a listing
+///
+/// Pages are counted from 1. Both fields can be left out, in which case the
+/// first page with `DEFAULT_PER_PAGE` items is selected.
+///
+/// # Examples
+///
+/// ```
+/// # use datatypes::payloads::PaginationPayload;
+/// let pagination: PaginationPayload = serde_json::from_str(r#"{ "page": 3 }"#).unwrap();
+/// assert_eq!(pagination, PaginationPayload { page: 3, per_page: 20 });
+/// ```
+#[derive(Serialize, Deserialize, PartialEq, PartialOrd, Clone, Copy, Debug)]
+#[cfg_attr(feature = "schemars", derive(JsonSchema))]
+#[cfg_attr(feature = "utoipa", derive(ToSchema))]
+#[serde(rename_all = "snake_case", default)]
+pub struct PaginationPayload {
+    pub page: u32,
+    pub per_page: u32,
+}
+
+impl Default for PaginationPayload {
+    fn default() -> Self {
+        PaginationPayload {
+            page: 1,
+            per_page: DEFAULT_PER_PAGE,
+        }
+    }
+}
+
+/// The max amount of characters in a cursor
+const CURSOR_MAX_LEN: usize = 256;
+
+/// A opaque position in a listing, given by the service to fetch the
+/// neighbouring pages
+///
+/// A cursor is a (standard or url-safe) base64 string. Clients should not try
+/// to make sense of a cursor, only send it back as-is. This crate does not
+/// know how to make a cursor, that is up to the service which hands them out.
+#[derive(Serialize, PartialEq, Eq, Clone, Debug, Hash)]
+#[cfg_attr(feature = "schemars", derive(JsonSchema))]
+#[cfg_attr(feature = "utoipa", derive(ToSchema))]
+#[serde(transparent)]
+pub struct Cursor(String);
+
+impl TryFrom<String> for Cursor {
+    type Error = ValidationError;
+    fn try_from(s: String) -> Result<Self, Self::Error> {
+        let is_base64 = s
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || "+/-_=".contains(c));
+        if !s.is_empty() && s.len() <= CURSOR_MAX_LEN && is_base64 {
+            Ok(Cursor(s))
+        } else {
+            Err(ValidationError::InvalidCursor)
+        }
+    }
+}
+
+impl_deserialize_with_try_from!(Cursor);
+impl_deref_and_as_ref!(Cursor => str);
+impl_into_inner!(Cursor => String);
+
+/// A page of items from a listing which is paginated with cursors
+///
+/// Unlike `Paginated` the pages stay consistent when items are added between
+/// page loads. `next` and `prev` are missing on the last and first page.
+#[derive(Serialize, Deserialize, PartialEq, Clone, Debug)]
+#[cfg_attr(feature = "schemars", derive(JsonSchema))]
+#[cfg_attr(feature = "utoipa", derive(ToSchema))]
+#[serde(rename_all = "snake_case")]
+pub struct CursorPage<T> {
+    pub items: Vec<T>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub next: Option<Cursor>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub prev: Option<Cursor>,
+}
+
+impl<T> CursorPage<T> {
+    pub fn new(items: Vec<T>, next: Option<Cursor>, prev: Option<Cursor>) -> CursorPage<T> {
+        CursorPage { items, next, prev }
+    }
+}
+
+/// A page of items from a listing
+///
+/// The page metadata is serialized next to the items. `total` is the amount of
+/// items in the whole listing (if it is known), and `next` is the cursor of
+/// the following page (if the service gives one). Both are left out when they
+/// are `None`.
+///
+/// # Examples
+///
+/// ```
+/// # use datatypes::payloads::{Paginated, PaginationPayload};
+/// let pagination = PaginationPayload { page: 1, per_page: 2 };
+/// let page = Paginated::new(vec![1, 2], pagination, 5);
+/// assert_eq!(page.total_pages(), Some(3));
+/// assert!(page.has_next());
+///
+/// let json = serde_json::to_string(&page).unwrap();
+/// assert_eq!(json, r#"{"items":[1,2],"page":1,"per_page":2,"total":5}"#);
+///
+/// let page = Paginated::new(vec![1, 2], pagination, None);
+/// let json = serde_json::to_string(&page).unwrap();
+/// assert_eq!(json, r#"{"items":[1,2],"page":1,"per_page":2}"#);
+/// ```
+#[derive(Serialize, Deserialize, PartialEq, Clone, Debug)]
+#[cfg_attr(feature = "schemars", derive(JsonSchema))]
+#[cfg_attr(feature = "utoipa", derive(ToSchema))]
+#[serde(rename_all = "snake_case")]
+pub struct Paginated<T> {
+    pub items: Vec<T>,
+    pub page: u32,
+    pub per_page: u32,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub total: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub next: Option<Cursor>,
+}
+
+impl<T> Paginated<T> {
+    pub fn new(
+        items: Vec<T>,
+        pagination: PaginationPayload,
+        total: impl Into<Option<u64>>,
+    ) -> Paginated<T> {
+        Paginated {
+            items,
+            page: pagination.page,
+            per_page: pagination.per_page,
+            total: total.into(),
+            next: None,
+        }
+    }
+
+    /// The same page, with the cursor of the following page
+    pub fn with_next(self, next: Cursor) -> Paginated<T> {
+        Paginated {
+            next: Some(next),
+            ..self
+        }
+    }
+
+    /// The amount of pages in the whole listing, if the total is known
+    pub fn total_pages(&self) -> Option<u64> {
+        let total = self.total?;
+        if self.per_page == 0 {
+            return Some(0);
+        }
+        let per_page = u64::from(self.per_page);
+        // Rounded up without adding to `total`, which could overflow
+        Some(total / per_page + u64::from(total % per_page != 0))
+    }
+
+    /// Whether there is a page after this one
+    ///
+    /// A page with a `next` cursor always has one, otherwise it is told by
+    /// the total (and a page without either is taken as the last one).
+    pub fn has_next(&self) -> bool {
+        self.next.is_some()
+            || self
+                .total_pages()
+                .map_or(false, |pages| u64::from(self.page) < pages)
+    }
+
+    /// Whether this page has no items
+    pub fn is_empty(&self) -> bool {
+        self.items.is_empty()
+    }
+
+    /// Convert the items of the page, the metadata is left untouched
+    pub fn map<U>(self, f: impl FnMut(T) -> U) -> Paginated<U> {
+        Paginated {
+            items: self.items.into_iter().map(f).collect(),
+            page: self.page,
+            per_page: self.per_page,
+            total: self.total,
+            next: self.next,
+        }
+    }
+}
+
+/// The outcome of a request as it is sent to a client
+///
+/// It is serialized with the status next to the payload, which makes both
+/// outcomes have the same shape. Unlike `error::ResponseResult` the error type
+/// is up to the consumer.
+///
+/// # Examples
+///
+/// ```
+/// # use datatypes::payloads::ResponseResult;
+/// let response: ResponseResult<u32, String> = Ok(7).into();
+/// let json = serde_json::to_string(&response).unwrap();
+/// assert_eq!(json, r#"{"status":"SUCCESS","payload":7}"#);
+/// ```
+#[derive(Serialize, Deserialize, PartialEq, Eq, Clone, Copy, Debug)]
+#[cfg_attr(feature = "schemars", derive(JsonSchema))]
+#[cfg_attr(feature = "utoipa", derive(ToSchema))]
+#[serde(
+    tag = "status",
+    content = "payload",
+    rename_all = "SCREAMING_SNAKE_CASE"
+)]
+pub enum ResponseResult<T, E> {
+    Success(T),
+    Error(E),
+}
+
+impl<T, E> ResponseResult<T, E> {
+    pub fn is_success(&self) -> bool {
+        match self {
+            ResponseResult::Success(_) => true,
+            ResponseResult::Error(_) => false,
+        }
+    }
+
+    pub fn is_error(&self) -> bool {
+        !self.is_success()
+    }
+
+    /// Map the payload of a successful response, the error is left untouched
+    pub fn map<U>(self, f: impl FnOnce(T) -> U) -> ResponseResult<U, E> {
+        match self {
+            ResponseResult::Success(t) => ResponseResult::Success(f(t)),
+            ResponseResult::Error(e) => ResponseResult::Error(e),
+        }
+    }
+
+    /// Map the payload of a unsuccessful response, the success is left
+    /// untouched
+    pub fn map_err<F>(self, f: impl FnOnce(E) -> F) -> ResponseResult<T, F> {
+        match self {
+            ResponseResult::Success(t) => ResponseResult::Success(t),
+            ResponseResult::Error(e) => ResponseResult::Error(f(e)),
+        }
+    }
+}
+
+impl<T, E> From<Result<T, E>> for ResponseResult<T, E> {
+    fn from(result: Result<T, E>) -> Self {
+        match result {
+            Ok(t) => ResponseResult::Success(t),
+            Err(e) => ResponseResult::Error(e),
+        }
+    }
+}
+
+impl<T, E> From<ResponseResult<T, E>> for Result<T, E> {
+    fn from(response: ResponseResult<T, E>) -> Self {
+        match response {
+            ResponseResult::Success(t) => Ok(t),
+            ResponseResult::Error(e) => Err(e),
+        }
+    }
+}
+
+/// Represents a payload that also contains a authorization token
+///
+/// This payload is generic for both the inner type and the token type, this
+/// means that the consumer can provide the types they want. The sole purpose
+/// of this structure is to provide a simple way of wrapping an existing type
+/// with a token.
+///
+/// This wrapper is mainly intended for internal use as the token will be
+/// stored in the session cookies.
+/// With the `rocket` feature a handler can read the token from the session
+/// cookie with `session::SessionToken`, and wrap the payload with
+/// `session::with_session`.
+///
+/// NB! The type that is wrapped cannot contain a field named `token`
+/// (`#[serde(rename="...")]` could be used to circument this). Both tokens
+/// are serialized under the same key, which can't be deserialized again, and
+/// deserializing such a payload panics in debug builds (the inner type would
+/// never get its `token`).
+///
+/// ```
+/// # #[macro_use]
+/// # extern crate serde_derive;
+/// # use datatypes::payloads::TokenPayload;
+/// # use datatypes::valid::token::Token;
+/// #[derive(Serialize, Deserialize)]
+/// struct Login {
+///     token: String,
+/// }
+///
+/// fn main() {
+///     let inner = Login { token: "inner".into() };
+///     let payload = TokenPayload::new(inner, Token::new("outer"));
+///     let json = serde_json::to_string(&payload).unwrap();
+///     assert_eq!(json, r#"{"token":"outer","token":"inner"}"#);
+/// }
+/// ```
+///
+/// NB! Because the inner type is flattened, fields which are unknown to it
+/// are silently ignored, even if it has `#[serde(deny_unknown_fields)]`. Use
+/// `Strict<TokenPayload<_>>` to reject them.
+///
+/// NB! The flattening only works with self-describing formats (like json),
+/// use `TokenPayloadCompact` with formats like bincode.
+///
+/// # Field order
+///
+/// The `token` is always serialized first, followed by the fields of the
+/// inner type in their own order (like `{"token":"..","page":1,"per_page":20}`).
+/// This is guaranteed and tested, but a signature or a cache key should not
+/// depend on it anyway, use `codec::to_canonical_json` which sorts the keys.
+///
+/// # Example usage
+///
+/// ```
+/// # use datatypes::payloads::TokenPayload;
+/// # use datatypes::valid::token::Token;
+/// # #[macro_use]
+/// # extern crate serde_derive;
+/// #[derive(Serialize, Deserialize, PartialEq, Debug)]
+/// struct UserPayload<'a> {
+///     name: &'a str,
+///     email: &'a str,
+/// }
+///
+/// type AuthUserPayload<'a> = TokenPayload<UserPayload<'a>>;
+///
+/// fn main() {
+///
+///     let user_payload = UserPayload {
+///         name: "John Doe",
+///         email: "john@doe.com"
+///     };
+///     let token = Token::new("random-token");
+///
+///     // Make a new authentication payload with a inner type and a token
+///     let payload = AuthUserPayload::new(user_payload, token);
+///
+///     let json = r#"{
+///                      "name": "John Doe",
+///                      "email": "john@doe.com",
+///                      "token": "random-token"
+///                   }"#;
+///
+///     let expt: AuthUserPayload = serde_json::from_str(json).unwrap();
+///     assert_eq!(expt, payload);
+/// }
+/// ```
+#[derive(Serialize, PartialEq, PartialOrd, Debug)]
+#[cfg_attr(feature = "schemars", derive(JsonSchema))]
+#[cfg_attr(feature = "utoipa", derive(ToSchema))]
+#[serde(rename_all = "snake_case")]
+pub struct TokenPayload<Inner> {
+    token: Token,
+    #[serde(flatten)]
+    inner: Inner,
+}
+
+impl<Inner> TokenPayload<Inner> {
+    pub fn new(i: impl Into<Inner>, t: impl Into<Token>) -> TokenPayload<Inner> {
+        TokenPayload {
+            inner: i.into(),
+            token: t.into(),
+        }
+    }
+
+    /// Get a reference to the token of the payload
+    pub fn token(&self) -> &Token {
+        &self.token
+    }
+
+    /// Set the token of a payload
+    pub fn set_token(&mut self, t: impl Into<Token>) -> Token {
+        std::mem::replace(&mut self.token, t.into())
+    }
+
+    /// Turn the payload into its inner type
+    pub fn into_inner(self) -> (Inner, Token) {
+        (self.inner, self.token)
+    }
+
+    /// Get a reference to the inner payload, without the token
+    ///
+    /// The same as the `Deref` of the payload, but it tells the reader that
+    /// the token is left out on purpose (e.g. when the payload is logged).
+    pub fn without_token(&self) -> &Inner {
+        &self.inner
+    }
+
+    /// A copy of the inner payload, which can be serialized (e.g. by a
+    /// logging sink) without the token
+    pub fn clone_inner(&self) -> Inner
+    where
+        Inner: Clone,
+    {
+        self.inner.clone()
+    }
+}
+
+impl<Inner> TokenPayload<Inner>
+where
+    Inner: serde::de::DeserializeOwned,
+{
+    /// Parse a payload from json, where the inner payload is either flattened
+    /// or nested under a `payload` key
+    ///
+    /// Some older clients send `{ "token": ..., "payload": { ... } }` instead
+    /// of the flattened layout. The flattened layout is tried first, and the
+    /// nested one is only used if the json has no other keys than `token` and
+    /// `payload` and the flattened parse failed because of a missing field. A
+    /// field which is invalid is always reported, which means that a inner type
+    /// with a field named `payload` is still read as flattened.
+    ///
+    /// NB! A inner type where every field has a default is always read as
+    /// flattened, as the flattened parse of it can't fail.
+    ///
+    /// The payload is always serialized with the flattened layout.
+    ///
+    /// A error tells the path of the field which was wrong, like
+    /// `parse_with_path` (the path of a field of the nested layout starts with
+    /// `payload`). The error of the flattened layout is the one which is
+    /// reported, unless the nested layout is tried.
+    ///
+    /// # Example usage
+    ///
+    /// ```
+    /// # use datatypes::auth::requests::AuthPayload;
+    /// # use datatypes::payloads::TokenPayload;
+    /// let flattened = r#"{
+    ///     "token": "random-token",
+    ///     "username": "john",
+    ///     "password": "helloAndWelcome123"
+    /// }"#;
+    /// let nested = r#"{
+    ///     "token": "random-token",
+    ///     "payload": { "username": "john", "password": "helloAndWelcome123" }
+    /// }"#;
+    /// for json in &[flattened, nested] {
+    ///     let payload = TokenPayload::<AuthPayload>::from_json_compat(json).unwrap();
+    ///     assert_eq!(&*payload.username, "john");
+    ///     assert_eq!(payload.token().as_ref(), "random-token");
+    /// }
+    /// ```
+    pub fn from_json_compat(json: &str) -> Result<Self, PathError> {
+        let value: serde_json::Value = serde_json::from_str(json).map_err(|e| PathError {
+            path: ".".to_owned(),
+            message: e.to_string(),
+        })?;
+        let deserializer = ValueDeserializer::new(value.clone());
+        let e = match serde_path_to_error::deserialize(deserializer) {
+            Ok(payload) => return Ok(payload),
+            Err(e) => e,
+        };
+        let missing_field = match e.inner() {
+            DeError::MissingField(_) => true,
+            DeError::Custom(_) => false,
+        };
+        if !missing_field || !is_nested_token_layout(&value) {
+            return Err(PathError {
+                path: e.path().to_string(),
+                message: e.into_inner().to_string(),
+            });
+        }
+        let nested: NestedTokenPayload<Inner> =
+            serde_path_to_error::deserialize(&value).map_err(|e| PathError {
+                path: e.path().to_string(),
+                message: e.into_inner().to_string(),
+            })?;
+        Ok(TokenPayload::new(nested.payload, nested.token))
+    }
+}
+
+/// The layout of a `TokenPayload` where the inner payload is not flattened
+#[derive(Deserialize)]
+struct NestedTokenPayload<Inner> {
+    token: Token,
+    payload: Inner,
+}
+
+/// If `value` is a object with only a `token` and a `payload` object
+fn is_nested_token_layout(value: &serde_json::Value) -> bool {
+    match value.as_object() {
+        Some(map) => {
+            map.len() == 2
+                && map.contains_key("token")
+                && map.get("payload").map_or(false, |p| p.is_object())
+        }
+        None => false,
+    }
+}
+
+impl<Inner> Deref for TokenPayload<Inner> {
+    type Target = Inner;
+    fn deref(&self) -> &Self::Target {
+        &self.inner
+    }
+}
+
+impl<Inner> DerefMut for TokenPayload<Inner> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.inner
+    }
+}
+
+impl<Inner: Validate> Validate for TokenPayload<Inner> {
+    fn validation_errors(&self) -> Vec<ValidationError> {
+        self.inner.validation_errors()
+    }
+}
+
+/// A compact form for logs and tracing spans, the token is never shown
+impl<Inner: fmt::Display> fmt::Display for TokenPayload<Inner> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} (token redacted)", self.inner)
+    }
+}
+
+/// Represents a payload that also contains a user id
+///
+/// This payload is generic for the inner type, this means that the consumer
+/// can provide the type of payload they want. The sole purpose of this
+/// structure is to provide a simple way of wrapping an existing payload with a
+/// user id.
+///
+/// This wrapper is mainly intended for internal use to transport a payload
+/// with a user id between the auth service to the security gate, and from the
+/// security gate to the controller.
+///
+/// NB! The type that is wrapped cannot contain a field named `user_id`
+/// (`#[serde(rename="...")]` could be used to circument this)
+///
+/// NB! Unknown fields are ignored in the same way as for `TokenPayload`, use
+/// `Strict<UserIdPayload<_>>` to reject them.
+///
+/// NB! Like `TokenPayload` it only works with self-describing formats, use
+/// `UserIdPayloadCompact` with formats like bincode.
+///
+/// The `id` is always serialized first, followed by the fields of the inner
+/// type (see the field order of `TokenPayload`).
+#[derive(Serialize, PartialEq, PartialOrd, Debug)]
+#[cfg_attr(feature = "schemars", derive(JsonSchema))]
+#[cfg_attr(feature = "utoipa", derive(ToSchema))]
+#[serde(rename_all = "snake_case")]
+pub struct UserIdPayload<Inner> {
+    id: UserId,
+    #[serde(flatten)]
+    inner: Inner,
+}
+
+impl<Inner> UserIdPayload<Inner> {
+    pub fn new(i: impl Into<Inner>, id: impl Into<UserId>) -> UserIdPayload<Inner> {
+        UserIdPayload {
+            inner: i.into(),
+            id: id.into(),
+        }
+    }
+
+    /// Get a reference to the token of the payload
+    pub fn id(&self) -> &UserId {
+        &self.id
+    }
+
+    /// Set the token of a payload
+    pub fn set_id(&mut self, id: impl Into<UserId>) -> UserId {
+        std::mem::replace(&mut self.id, id.into())
+    }
+
+    /// Turn the payload into its inner type
+    pub fn into_inner(self) -> (Inner, UserId) {
+        (self.inner, self.id)
+    }
+}
+
+impl<Inner> Deref for UserIdPayload<Inner> {
+    type Target = Inner;
+    fn deref(&self) -> &Self::Target {
+        &self.inner
+    }
+}
+
+impl<Inner> DerefMut for UserIdPayload<Inner> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.inner
+    }
+}
+
+/// A compact form for logs and tracing spans
+impl<Inner: fmt::Display> fmt::Display for UserIdPayload<Inner> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "user={} {}", self.id, self.inner)
+    }
+}
+
+/// A `TokenPayload` which works with formats that are not self-describing
+///
+/// `#[serde(flatten)]` only works with self-describing formats like json,
+/// which means that `TokenPayload` can't be used with formats like bincode.
+/// This payload stores the token and the inner payload as two separate fields
+/// instead, so it works with any format (as long as the inner type does).
+///
+/// The two payloads can be converted into each other.
+///
+/// # Example usage
+///
+/// ```
+/// # use datatypes::payloads::{PaginationPayload, TokenPayload, TokenPayloadCompact};
+/// # use datatypes::valid::token::Token;
+/// let payload = TokenPayload::new(PaginationPayload::default(), Token::new("random-token"));
+/// let compact = TokenPayloadCompact::from(payload);
+///
+/// let json = serde_json::to_value(&compact).unwrap();
+/// assert_eq!(json["token"], "random-token");
+/// assert_eq!(json["inner"]["page"], 1);
+/// ```
+#[derive(Serialize, Deserialize, PartialEq, PartialOrd, Debug)]
+#[cfg_attr(feature = "schemars", derive(JsonSchema))]
+#[cfg_attr(feature = "utoipa", derive(ToSchema))]
+#[serde(rename_all = "snake_case")]
+pub struct TokenPayloadCompact<Inner> {
+    token: Token,
+    inner: Inner,
+}
+
+impl<Inner> TokenPayloadCompact<Inner> {
+    pub fn new(i: impl Into<Inner>, t: impl Into<Token>) -> TokenPayloadCompact<Inner> {
+        TokenPayloadCompact {
+            inner: i.into(),
+            token: t.into(),
+        }
+    }
+
+    /// Get a reference to the token of the payload
+    pub fn token(&self) -> &Token {
+        &self.token
+    }
+
+    /// Set the token of a payload
+    pub fn set_token(&mut self, t: impl Into<Token>) -> Token {
+        std::mem::replace(&mut self.token, t.into())
+    }
+
+    /// Turn the payload into its inner type
+    pub fn into_inner(self) -> (Inner, Token) {
+        (self.inner, self.token)
+    }
+}
+
+impl<Inner> Deref for TokenPayloadCompact<Inner> {
+    type Target = Inner;
+    fn deref(&self) -> &Self::Target {
+        &self.inner
+    }
+}
+
+impl<Inner> DerefMut for TokenPayloadCompact<Inner> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.inner
+    }
+}
+
+impl<Inner> From<TokenPayload<Inner>> for TokenPayloadCompact<Inner> {
+    fn from(p: TokenPayload<Inner>) -> Self {
+        TokenPayloadCompact {
+            token: p.token,
+            inner: p.inner,
+        }
+    }
+}
+
+impl<Inner> From<TokenPayloadCompact<Inner>> for TokenPayload<Inner> {
+    fn from(p: TokenPayloadCompact<Inner>) -> Self {
+        TokenPayload {
+            token: p.token,
+            inner: p.inner,
+        }
+    }
+}
+
+/// A `UserIdPayload` which works with formats that are not self-describing
+///
+/// See `TokenPayloadCompact` for why it exists.
+#[derive(Serialize, Deserialize, PartialEq, PartialOrd, Debug)]
+#[cfg_attr(feature = "schemars", derive(JsonSchema))]
+#[cfg_attr(feature = "utoipa", derive(ToSchema))]
+#[serde(rename_all = "snake_case")]
+pub struct UserIdPayloadCompact<Inner> {
+    id: UserId,
+    inner: Inner,
+}
+
+impl<Inner> UserIdPayloadCompact<Inner> {
+    pub fn new(i: impl Into<Inner>, id: impl Into<UserId>) -> UserIdPayloadCompact<Inner> {
+        UserIdPayloadCompact {
+            inner: i.into(),
+            id: id.into(),
+        }
+    }
+
+    /// Get a reference to the user id of the payload
+    pub fn id(&self) -> &UserId {
+        &self.id
+    }
+
+    /// Set the user id of a payload
+    pub fn set_id(&mut self, id: impl Into<UserId>) -> UserId {
+        std::mem::replace(&mut self.id, id.into())
+    }
+
+    /// Turn the payload into its inner type
+    pub fn into_inner(self) -> (Inner, UserId) {
+        (self.inner, self.id)
+    }
+}
+
+impl<Inner> Deref for UserIdPayloadCompact<Inner> {
+    type Target = Inner;
+    fn deref(&self) -> &Self::Target {
+        &self.inner
+    }
+}
+
+impl<Inner> DerefMut for UserIdPayloadCompact<Inner> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.inner
+    }
+}
+
+impl<Inner> From<UserIdPayload<Inner>> for UserIdPayloadCompact<Inner> {
+    fn from(p: UserIdPayload<Inner>) -> Self {
+        UserIdPayloadCompact {
+            id: p.id,
+            inner: p.inner,
+        }
+    }
+}
+
+impl<Inner> From<UserIdPayloadCompact<Inner>> for UserIdPayload<Inner> {
+    fn from(p: UserIdPayloadCompact<Inner>) -> Self {
+        UserIdPayload {
+            id: p.id,
+            inner: p.inner,
+        }
+    }
+}
+
+/// The bounds of the length of a `BoundedVec`
+///
+/// The bounds are given by a (zero-sized) marker type, so every kind of list
+/// documents its bounds in one place.
+pub trait LengthBounds {
+    /// The fewest items the list can have
+    const MIN: usize;
+    /// The most items the list can have
+    const MAX: usize;
+}
+
+/// A list with a length within the bounds `B`
+///
+/// The length is vertified when the list is made or deserialized, a list
+/// which is too short or too long is rejected with
+/// `ValidationError::InvalidListLength`. The list is serialized as a plain
+/// list.
+///
+/// # Example usage
+///
+/// ```
+/// # #![feature(try_from)]
+/// # use datatypes::payloads::{BoundedVec, LengthBounds};
+/// # use std::convert::TryFrom;
+/// #[derive(PartialEq, Debug)]
+/// struct OneOrTwo;
+///
+/// impl LengthBounds for OneOrTwo {
+///     const MIN: usize = 1;
+///     const MAX: usize = 2;
+/// }
+///
+/// let list: BoundedVec<u32, OneOrTwo> = serde_json::from_str("[1, 2]").unwrap();
+/// assert_eq!(&*list, &[1, 2]);
+///
+/// assert!(BoundedVec::<u32, OneOrTwo>::try_from(vec![]).is_err());
+/// assert!(serde_json::from_str::<BoundedVec<u32, OneOrTwo>>("[1, 2, 3]").is_err());
+/// ```
+#[derive(PartialEq, Eq, Debug)]
+pub struct BoundedVec<T, B: LengthBounds> {
+    items: Vec<T>,
+    bounds: PhantomData<B>,
+}
+
+impl<T, B: LengthBounds> BoundedVec<T, B> {
+    /// Turn the list into the items it contains
+    pub fn into_inner(self) -> Vec<T> {
+        self.items
+    }
+
+    /// Whether the list has no items, which only some bounds allow
+    pub fn is_empty(&self) -> bool {
+        self.items.is_empty()
+    }
+}
+
+impl<T, B: LengthBounds> TryFrom<Vec<T>> for BoundedVec<T, B> {
+    type Error = ValidationError;
+    fn try_from(items: Vec<T>) -> Result<Self, Self::Error> {
+        if B::MIN <= items.len() && items.len() <= B::MAX {
+            Ok(BoundedVec {
+                items,
+                bounds: PhantomData,
+            })
+        } else {
+            Err(ValidationError::InvalidListLength)
+        }
+    }
+}
+
+impl<T, B: LengthBounds> Deref for BoundedVec<T, B> {
+    type Target = [T];
+    fn deref(&self) -> &Self::Target {
+        &self.items
+    }
+}
+
+impl<T: Clone, B: LengthBounds> Clone for BoundedVec<T, B> {
+    fn clone(&self) -> Self {
+        BoundedVec {
+            items: self.items.clone(),
+            bounds: PhantomData,
+        }
+    }
+}
+
+#[cfg(feature = "schemars")]
+impl<T, B> schemars::JsonSchema for BoundedVec<T, B>
+where
+    T: schemars::JsonSchema,
+    B: LengthBounds,
+{
+    fn is_referenceable() -> bool {
+        false
+    }
+
+    fn schema_name() -> String {
+        format!("BoundedVec_of_{}", T::schema_name())
+    }
+
+    fn json_schema(gen: &mut schemars::gen::SchemaGenerator) -> schemars::schema::Schema {
+        let mut schema = <Vec<T>>::json_schema(gen).into_object();
+        schema.array().min_items = Some(B::MIN as u32);
+        schema.array().max_items = Some(B::MAX as u32);
+        schema.into()
+    }
+}
+
+/// The OpenAPI schema of a `BoundedVec` field, which is given with
+/// `#[schema(schema_with = BoundedVec::<T, B>::openapi_schema)]`
+#[cfg(feature = "utoipa")]
+impl<T, B> BoundedVec<T, B>
+where
+    T: utoipa::PartialSchema,
+    B: LengthBounds,
+{
+    pub fn openapi_schema() -> utoipa::openapi::RefOr<utoipa::openapi::schema::Schema> {
+        use utoipa::openapi::schema::{ArrayBuilder, Schema};
+        use utoipa::openapi::RefOr;
+
+        let schema = ArrayBuilder::new()
+            .items(T::schema())
+            .min_items(Some(B::MIN))
+            .max_items(Some(B::MAX));
+        RefOr::T(Schema::Array(schema.build()))
+    }
+}
+
+impl<T, B> serde::Serialize for BoundedVec<T, B>
+where
+    T: serde::Serialize,
+    B: LengthBounds,
+{
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        self.items.serialize(serializer)
+    }
+}
+
+impl<'de, T, B> serde::de::Deserialize<'de> for BoundedVec<T, B>
+where
+    T: serde::de::Deserialize<'de>,
+    B: LengthBounds,
+{
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::de::Deserializer<'de>,
+    {
+        let items = Vec::deserialize(deserializer)?;
+        BoundedVec::try_from(items).map_err(serde::de::Error::custom)
+    }
+}
+
+/// The version of the wire format which is spoken by this crate
+pub const CURRENT_VERSION: u16 = 1;
+
+/// The version of a request which has no version field
+///
+/// Clients which predate the version field speak the first version, so the
+/// default must stay at 1 even when `CURRENT_VERSION` is bumped.
+fn default_version() -> u16 {
+    1
+}
+
+/// A request (or payload) together with the version of the wire format
+///
+/// The version is sent as `v` next to the fields of the inner type, and a
+/// missing `v` is read as version 1. Any version is accepted when the
+/// envelope is deserialized, it is up to the receiver to reject versions it
+/// does not support (see `check_version`).
+///
+/// NB! Like `TokenPayload` the inner type is flattened, which means that the
+/// inner type cannot contain a field named `v` and that it only works with
+/// self-describing formats.
+///
+/// # Example usage
+///
+/// ```
+/// # use datatypes::content::requests::{ContentRequest, ThreadRefPayload};
+/// # use datatypes::payloads::{Versioned, CURRENT_VERSION};
+/// let json = r#"{ "v": 1, "type": "SUBSCRIBE", "payload": { "thread_id": 1 } }"#;
+/// let request: Versioned<ContentRequest> = serde_json::from_str(json).unwrap();
+/// assert_eq!(request.version(), 1);
+/// assert!(request.check_version(CURRENT_VERSION).is_ok());
+/// ```
+#[derive(Serialize, PartialEq, PartialOrd, Debug)]
+#[cfg_attr(feature = "schemars", derive(JsonSchema))]
+#[cfg_attr(feature = "utoipa", derive(ToSchema))]
+#[serde(rename_all = "snake_case")]
+pub struct Versioned<Inner> {
+    v: u16,
+    #[serde(flatten)]
+    inner: Inner,
+}
+
+impl<Inner> Versioned<Inner> {
+    /// Wrap `i` in a envelope of the current version
+    pub fn new(i: impl Into<Inner>) -> Versioned<Inner> {
+        Versioned::with_version(i, CURRENT_VERSION)
+    }
+
+    /// Wrap `i` in a envelope of the given version
+    pub fn with_version(i: impl Into<Inner>, v: u16) -> Versioned<Inner> {
+        Versioned { v, inner: i.into() }
+    }
+
+    /// Get the version of the envelope
+    pub fn version(&self) -> u16 {
+        self.v
+    }
+
+    /// Check that the envelope is of the `supported` version
+    pub fn check_version(&self, supported: u16) -> Result<(), Error> {
+        if self.v == supported {
+            Ok(())
+        } else {
+            Err(Error::UnsupportedVersion {
+                got: self.v,
+                supported,
+            })
+        }
+    }
+
+    /// Turn the envelope into its inner type
+    pub fn into_inner(self) -> (Inner, u16) {
+        (self.inner, self.v)
+    }
+}
+
+impl<Inner> Deref for Versioned<Inner> {
+    type Target = Inner;
+    fn deref(&self) -> &Self::Target {
+        &self.inner
+    }
+}
+
+impl<Inner> DerefMut for Versioned<Inner> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.inner
+    }
+}
+
+/// A wrapper which makes a flattened payload reject unknown fields
+///
+/// `#[serde(deny_unknown_fields)]` has no effect on a type which is flattened
+/// into another one (a limitation of serde), which means that `TokenPayload`
+/// and `UserIdPayload` accept any field. `Strict` takes out the field of the
+/// wrapper and deserializes the inner type from the fields which are left, so
+/// a inner type with `#[serde(deny_unknown_fields)]` rejects unknown fields.
+///
+/// Only the inner type decides what is unknown, so a inner type without
+/// `#[serde(deny_unknown_fields)]` still ignores them. The fields are buffered
+/// before the inner type is deserialized, which means that the inner type
+/// cannot borrow from the input.
+///
+/// # Example usage
+///
+/// ```
+/// # use datatypes::payloads::{Strict, TokenPayload};
+/// # #[macro_use]
+/// # extern crate serde_derive;
+/// #[derive(Deserialize, PartialEq, Debug)]
+/// #[serde(deny_unknown_fields)]
+/// struct PasswordPayload {
+///     password: String,
+/// }
+///
+/// type StrictPasswordPayload = Strict<TokenPayload<PasswordPayload>>;
+///
+/// fn main() {
+///     let json = r#"{ "token": "random-token", "password": "secret" }"#;
+///     assert!(serde_json::from_str::<StrictPasswordPayload>(json).is_ok());
+///
+///     let json = r#"{ "token": "random-token", "pasword": "secret" }"#;
+///     assert!(serde_json::from_str::<StrictPasswordPayload>(json).is_err());
+/// }
+/// ```
+#[derive(Serialize, PartialEq, PartialOrd, Debug)]
+#[cfg_attr(feature = "schemars", derive(JsonSchema))]
+#[cfg_attr(feature = "utoipa", derive(ToSchema))]
+#[serde(transparent)]
+pub struct Strict<T>(T);
+
+impl<T> Strict<T> {
+    pub fn new(inner: T) -> Self {
+        Strict(inner)
+    }
+
+    /// Turn the wrapper into the payload it wraps
+    pub fn into_inner(self) -> T {
+        self.0
+    }
+}
+
+impl<T> From<T> for Strict<T> {
+    fn from(inner: T) -> Self {
+        Strict(inner)
+    }
+}
+
+impl<T> Deref for Strict<T> {
+    type Target = T;
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl<T> DerefMut for Strict<T> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.0
+    }
+}
+
+// The flattened payloads (`TokenPayload`, `UserIdPayload` and `Versioned`)
+// are deserialized without `#[serde(flatten)]`. The derived impl buffers the
+// fields of the inner type before it is deserialized, which means that the
+// error of a field inside the inner type loses its path. The inner type is
+// instead deserialized straight from the input, with the field of the wrapper
+// taken out on the way. Otherwise it works like `#[serde(flatten)]`, a inner
+// struct only sees its own fields.
+
+/// Deserialize `Inner` from a map, with `field` taken out of it
+fn deserialize_flattened<'de, D, F, Inner>(
+    deserializer: D,
+    field: &'static str,
+) -> Result<(Option<F>, Inner), D::Error>
+where
+    D: serde::de::Deserializer<'de>,
+    F: serde::de::Deserialize<'de>,
+    Inner: serde::de::Deserialize<'de>,
+{
+    let mut slot = None;
+    let inner = Inner::deserialize(FlattenedDeserializer {
+        deserializer,
+        field,
+        slot: &mut slot,
+    })?;
+    Ok((slot, inner))
+}
+
+/// A deserializer of the fields of a map, except `field`, which is stored in
+/// `slot`
+struct FlattenedDeserializer<'a, D, F> {
+    deserializer: D,
+    field: &'static str,
+    slot: &'a mut Option<F>,
+}
+
+impl<'de, 'a, D, F> serde::de::Deserializer<'de> for FlattenedDeserializer<'a, D, F>
+where
+    D: serde::de::Deserializer<'de>,
+    F: serde::de::Deserialize<'de>,
+{
+    type Error = D::Error;
+
+    fn deserialize_any<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: serde::de::Visitor<'de>,
+    {
+        self.deserialize_map(visitor)
+    }
+
+    fn deserialize_map<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: serde::de::Visitor<'de>,
+    {
+        let FlattenedDeserializer {
+            deserializer,
+            field,
+            slot,
+        } = self;
+        deserializer.deserialize_map(FlattenedVisitor {
+            visitor,
+            field,
+            fields: None,
+            slot,
+        })
+    }
+
+    fn deserialize_struct<V>(
+        self,
+        _name: &'static str,
+        fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Self::Error>
+    where
+        V: serde::de::Visitor<'de>,
+    {
+        // The field is always taken by the wrapper, so the inner type's own
+        // field of the same name would never be deserialized
+        debug_assert!(
+            !fields.contains(&self.field),
+            "the inner type of a flattened payload has a field named `{}`",
+            self.field
+        );
+        let FlattenedDeserializer {
+            deserializer,
+            field,
+            slot,
+        } = self;
+        deserializer.deserialize_map(FlattenedVisitor {
+            visitor,
+            field,
+            fields: Some(fields),
+            slot,
+        })
+    }
+
+    fn deserialize_option<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: serde::de::Visitor<'de>,
+    {
+        visitor.visit_some(self)
+    }
+
+    fn deserialize_newtype_struct<V>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, Self::Error>
+    where
+        V: serde::de::Visitor<'de>,
+    {
+        visitor.visit_newtype_struct(self)
+    }
+
+    serde::forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 u8 u16 u32 u64 f32 f64 char str string bytes
+        byte_buf unit unit_struct seq tuple tuple_struct enum identifier
+        ignored_any
+    }
+}
+
+/// A visitor which hands the map to `visitor` with `field` taken out of it
+struct FlattenedVisitor<'a, V, F> {
+    visitor: V,
+    field: &'static str,
+    fields: Option<&'static [&'static str]>,
+    slot: &'a mut Option<F>,
+}
+
+impl<'de, 'a, V, F> serde::de::Visitor<'de> for FlattenedVisitor<'a, V, F>
+where
+    V: serde::de::Visitor<'de>,
+    F: serde::de::Deserialize<'de>,
+{
+    type Value = V::Value;
+
+    fn expecting(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        self.visitor.expecting(f)
+    }
+
+    fn visit_map<A>(self, map: A) -> Result<Self::Value, A::Error>
+    where
+        A: serde::de::MapAccess<'de>,
+    {
+        self.visitor.visit_map(FlattenedMap {
+            map,
+            field: self.field,
+            fields: self.fields,
+            slot: self.slot,
+        })
+    }
+}
+
+/// The entries of a map, except `field` and the ones which are not in `fields`
+struct FlattenedMap<'a, A, F> {
+    map: A,
+    field: &'static str,
+    fields: Option<&'static [&'static str]>,
+    slot: &'a mut Option<F>,
+}
+
+impl<'de, 'a, A, F> serde::de::MapAccess<'de> for FlattenedMap<'a, A, F>
+where
+    A: serde::de::MapAccess<'de>,
+    F: serde::de::Deserialize<'de>,
+{
+    type Error = A::Error;
+
+    fn next_key_seed<K>(&mut self, seed: K) -> Result<Option<K::Value>, Self::Error>
+    where
+        K: serde::de::DeserializeSeed<'de>,
+    {
+        use serde::de::{Error, IgnoredAny, IntoDeserializer};
+
+        while let Some(key) = self.map.next_key::<String>()? {
+            let known = self.fields.map_or(true, |f| f.contains(&key.as_str()));
+            if key == self.field {
+                if self.slot.is_some() {
+                    return Err(A::Error::duplicate_field(self.field));
+                }
+                *self.slot = Some(self.map.next_value()?);
+            } else if known {
+                return seed.deserialize(key.into_deserializer()).map(Some);
+            } else {
+                self.map.next_value::<IgnoredAny>()?;
+            }
+        }
+        Ok(None)
+    }
+
+    fn next_value_seed<V>(&mut self, seed: V) -> Result<V::Value, Self::Error>
+    where
+        V: serde::de::DeserializeSeed<'de>,
+    {
+        self.map.next_value_seed(seed)
+    }
+}
+
+impl<'de, Inner> serde::de::Deserialize<'de> for TokenPayload<Inner>
+where
+    Inner: serde::de::Deserialize<'de>,
+{
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::de::Deserializer<'de>,
+    {
+        use serde::de::Error;
+
+        let (token, inner) = deserialize_flattened(deserializer, "token")?;
+        let token = token.ok_or_else(|| D::Error::missing_field("token"))?;
+        Ok(TokenPayload { token, inner })
+    }
+}
+
+impl<'de, Inner> serde::de::Deserialize<'de> for UserIdPayload<Inner>
+where
+    Inner: serde::de::Deserialize<'de>,
+{
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::de::Deserializer<'de>,
+    {
+        use serde::de::Error;
+
+        let (id, inner) = deserialize_flattened(deserializer, "id")?;
+        let id = id.ok_or_else(|| D::Error::missing_field("id"))?;
+        Ok(UserIdPayload { id, inner })
+    }
+}
+
+impl<'de, Inner> serde::de::Deserialize<'de> for Versioned<Inner>
+where
+    Inner: serde::de::Deserialize<'de>,
+{
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::de::Deserializer<'de>,
+    {
+        let (v, inner) = deserialize_flattened(deserializer, "v")?;
+        let v = v.unwrap_or_else(default_version);
+        Ok(Versioned { v, inner })
+    }
+}
+
+/// Take out `field` of a object and deserialize the rest of it as `Inner`
+fn split_off_field<'de, D, F, Inner>(
+    deserializer: D,
+    field: &'static str,
+) -> Result<(F, Inner), D::Error>
+where
+    D: serde::de::Deserializer<'de>,
+    F: serde::de::DeserializeOwned,
+    Inner: serde::de::DeserializeOwned,
+{
+    use serde::de::{Deserialize, Error};
+
+    let mut fields = serde_json::Map::deserialize(deserializer)?;
+    let value = fields
+        .remove(field)
+        .ok_or_else(|| D::Error::missing_field(field))?;
+    let value = F::deserialize(value).map_err(D::Error::custom)?;
+    let inner = Inner::deserialize(serde_json::Value::Object(fields));
+    Ok((value, inner.map_err(D::Error::custom)?))
+}
+
+impl<'de, Inner> serde::de::Deserialize<'de> for Strict<TokenPayload<Inner>>
+where
+    Inner: serde::de::DeserializeOwned,
+{
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::de::Deserializer<'de>,
+    {
+        let (token, inner) = split_off_field(deserializer, "token")?;
+        Ok(Strict(TokenPayload { token, inner }))
+    }
+}
+
+impl<'de, Inner> serde::de::Deserialize<'de> for Strict<UserIdPayload<Inner>>
+where
+    Inner: serde::de::DeserializeOwned,
+{
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::de::Deserializer<'de>,
+    {
+        let (id, inner) = split_off_field(deserializer, "id")?;
+        Ok(Strict(UserIdPayload { id, inner }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn patch_apply() {
+        let mut field = Some(1);
+        Patch::Absent.apply(&mut field);
+        assert_eq!(field, Some(1));
+        Patch::Set(2).apply(&mut field);
+        assert_eq!(field, Some(2));
+        Patch::Null.apply(&mut field);
+        assert_eq!(field, None);
+    }
+
+    #[derive(Deserialize, Debug)]
+    #[serde(
+        tag = "type",
+        content = "payload",
+        rename_all = "SCREAMING_SNAKE_CASE"
+    )]
+    enum EmptyRequest {
+        Lenient(EmptyPayload),
+        Strict(EmptyPayloadStrict),
+    }
+
+    #[test]
+    fn empty_payloads() {
+        let cases = [
+            (r#"{ "type": "LENIENT", "payload": {} }"#, true),
+            (r#"{ "type": "LENIENT", "payload": null }"#, true),
+            (r#"{ "type": "LENIENT" }"#, true),
+            (r#"{ "type": "STRICT", "payload": {} }"#, true),
+            (r#"{ "type": "STRICT", "payload": null }"#, true),
+            (r#"{ "type": "STRICT" }"#, false),
+            (r#"{ "type": "LENIENT", "payload": { "a": 1 } }"#, false),
+            (r#"{ "type": "LENIENT", "payload": [1] }"#, false),
+            (r#"{ "type": "STRICT", "payload": { "a": 1 } }"#, false),
+            (r#"{ "type": "STRICT", "payload": [1] }"#, false),
+        ];
+        for (json, accepted) in &cases {
+            let result = serde_json::from_str::<EmptyRequest>(json);
+            assert_eq!(result.is_ok(), *accepted, "{}: {:?}", json, result);
+        }
+    }
+
+    #[test]
+    fn empty_payloads_with_the_payload_first() {
+        // The payload is buffered when it comes before the tag
+        let json = r#"{ "payload": null, "type": "STRICT" }"#;
+        assert!(serde_json::from_str::<EmptyRequest>(json).is_ok());
+        let json = r#"{ "payload": null, "type": "LENIENT" }"#;
+        assert!(serde_json::from_str::<EmptyRequest>(json).is_ok());
+    }
+
+    #[test]
+    fn empty_payload_is_not_a_value() {
+        for json in &[
+            "1",
+            r#""""#,
+            "true",
+            "[1]",
+            "[{}]",
+            r#"{ "a": 1 }"#,
+            r#"{ "a": null }"#,
+        ] {
+            let result = serde_json::from_str::<EmptyPayloadStrict>(json);
+            assert!(result.is_err(), "{}", json);
+        }
+        assert!(serde_json::from_str::<EmptyPayloadStrict>("[]").is_ok());
+    }
+
+    #[test]
+    fn null_payload_is_accepted_by_requests() {
+        use crate::auth::requests::AuthRequest;
+
+        let json = r#"{ "type": "DEAUTHENTICATE", "payload": null }"#;
+        match serde_json::from_str::<AuthRequest>(json).unwrap() {
+            AuthRequest::Deauthenticate(_) => (),
+            request => panic!("expected a logout, got {:?}", request),
+        }
+        let json = r#"{ "type": "WHO_AM_I", "payload": null }"#;
+        match serde_json::from_str::<AuthRequest>(json).unwrap() {
+            AuthRequest::WhoAmI(None) => (),
+            request => panic!("expected a who am i, got {:?}", request),
+        }
+    }
+
+    #[derive(Serialize, Deserialize, PartialEq, Debug)]
+    struct Request {
+        #[serde(default, skip_serializing_if = "AbsentOrEmpty::is_absent")]
+        payload: AbsentOrEmpty,
+    }
+
+    /// Parse a `Request` the way a request envelope is parsed
+    fn parse_envelope(json: &str) -> Result<Request, serde_json::Error> {
+        use crate::envelope_de::Envelope;
+        use serde::de::Deserialize;
+
+        let mut deserializer = serde_json::Deserializer::from_str(json);
+        Request::deserialize(Envelope(&mut deserializer))
+    }
+
+    #[test]
+    fn absent_or_empty_with_empty_payload() {
+        let req = parse_envelope(r#"{ "payload": {} }"#).unwrap();
+        assert_eq!(req.payload, AbsentOrEmpty::Empty);
+        assert_eq!(serde_json::to_string(&req).unwrap(), r#"{"payload":{}}"#);
+    }
+
+    #[test]
+    fn absent_or_empty_with_absent_payload() {
+        let req = parse_envelope(r#"{}"#).unwrap();
+        assert_eq!(req.payload, AbsentOrEmpty::Absent);
+        assert_eq!(serde_json::to_string(&req).unwrap(), r#"{}"#);
+    }
+
+    #[test]
+    fn absent_or_empty_with_null_payload() {
+        assert!(parse_envelope(r#"{ "payload": null }"#).is_err());
+        assert!(parse_envelope(r#"{ "payload": { "a": 1 } }"#).is_err());
+    }
+
+    #[derive(Serialize, Deserialize, PartialEq, Debug)]
+    struct Greeting<'a> {
+        #[serde(borrow)]
+        text: &'a str,
+    }
+
+    #[derive(Serialize, Deserialize, PartialEq, Debug)]
+    struct Failure<'a> {
+        #[serde(borrow)]
+        reason: &'a str,
+    }
+
+    type GreetingResult<'a> = ResponseResult<Greeting<'a>, Failure<'a>>;
+
+    #[test]
+    fn response_result_success_shape() {
+        let response: GreetingResult = ResponseResult::Success(Greeting { text: "hello" });
+        let json = serde_json::to_string(&response).unwrap();
+        assert_eq!(json, r#"{"status":"SUCCESS","payload":{"text":"hello"}}"#);
+        assert_eq!(serde_json::from_str::<GreetingResult>(&json).unwrap(), response);
+        assert!(response.is_success());
+    }
+
+    #[test]
+    fn response_result_error_shape() {
+        let response: GreetingResult = ResponseResult::Error(Failure { reason: "nope" });
+        let json = serde_json::to_string(&response).unwrap();
+        assert_eq!(json, r#"{"status":"ERROR","payload":{"reason":"nope"}}"#);
+        assert_eq!(serde_json::from_str::<GreetingResult>(&json).unwrap(), response);
+        assert!(response.is_error());
+    }
+
+    #[test]
+    fn response_result_to_and_from_result() {
+        let ok: Result<u32, String> = Ok(3);
+        let response = ResponseResult::from(ok.clone());
+        assert_eq!(Result::from(response), ok);
+
+        let err: Result<u32, String> = Err("failed".to_owned());
+        let response = ResponseResult::from(err.clone());
+        assert_eq!(Result::from(response), err);
+    }
+
+    #[test]
+    fn response_result_map() {
+        let response: ResponseResult<u32, u32> = ResponseResult::Success(2);
+        assert_eq!(response.map(|n| n * 2), ResponseResult::Success(4));
+        assert_eq!(response.map_err(|n| n * 2), ResponseResult::Success(2));
+
+        let response: ResponseResult<u32, u32> = ResponseResult::Error(2);
+        assert_eq!(response.map(|n| n * 2), ResponseResult::Error(2));
+        assert_eq!(response.map_err(|n| n * 2), ResponseResult::Error(4));
+    }
+
+    fn pagination(page: u32, per_page: u32) -> PaginationPayload {
+        PaginationPayload { page, per_page }
+    }
+
+    #[test]
+    fn paginated_total_pages_rounds_up() {
+        let page = Paginated::<UserId>::new(vec![], pagination(1, 20), 101);
+        assert_eq!(page.total_pages(), Some(6));
+        let page = Paginated::<UserId>::new(vec![], pagination(1, 20), 100);
+        assert_eq!(page.total_pages(), Some(5));
+        let page = Paginated::<UserId>::new(vec![], pagination(1, 0), 100);
+        assert_eq!(page.total_pages(), Some(0));
+    }
+
+    #[test]
+    fn paginated_total_pages_of_a_huge_total() {
+        let page = Paginated::<UserId>::new(vec![], pagination(1, 20), u64::MAX);
+        assert_eq!(page.total_pages(), Some(u64::MAX / 20 + 1));
+        let page = Paginated::<UserId>::new(vec![], pagination(1, 1), u64::MAX);
+        assert_eq!(page.total_pages(), Some(u64::MAX));
+    }
+
+    #[test]
+    fn paginated_has_next() {
+        let page = Paginated::<UserId>::new(vec![], pagination(5, 20), 101);
+        assert!(page.has_next());
+        let page = Paginated::<UserId>::new(vec![], pagination(6, 20), 101);
+        assert!(!page.has_next());
+    }
+
+    #[test]
+    fn paginated_without_total() {
+        let json = r#"{ "items": [3], "page": 2, "per_page": 20 }"#;
+        let page: Paginated<UserId> = serde_json::from_str(json).unwrap();
+        assert_eq!(page.items, vec![UserId::from(3)]);
+        assert_eq!(page.total, None);
+        assert_eq!(page.total_pages(), None);
+        assert!(!page.has_next());
+        assert_eq!(
+            serde_json::to_string(&page).unwrap(),
+            r#"{"items":[3],"page":2,"per_page":20}"#
+        );
+    }
+
+    #[test]
+    fn paginated_next_cursor() {
+        let next = Cursor::try_from("bmV4dA".to_owned()).unwrap();
+        let page = Paginated::new(vec![UserId::from(1)], pagination(1, 20), None).with_next(next);
+        assert!(page.has_next());
+        let json = serde_json::to_string(&page).unwrap();
+        assert_eq!(
+            json,
+            r#"{"items":[1],"page":1,"per_page":20,"next":"bmV4dA"}"#
+        );
+        let round_trip: Paginated<UserId> = serde_json::from_str(&json).unwrap();
+        assert_eq!(round_trip, page);
+
+        // The cursor is kept on the last page of a known total
+        let next = Cursor::try_from("bmV4dA".to_owned()).unwrap();
+        let page = Paginated::<UserId>::new(vec![], pagination(6, 20), 101).with_next(next);
+        assert!(page.has_next());
+    }
+
+    #[test]
+    fn paginated_empty_page() {
+        let page = Paginated::<UserId>::new(vec![], PaginationPayload::default(), 0);
+        assert!(page.is_empty());
+        assert_eq!(page.total_pages(), Some(0));
+        assert!(!page.has_next());
+        assert_eq!(
+            serde_json::to_string(&page).unwrap(),
+            r#"{"items":[],"page":1,"per_page":20,"total":0}"#
+        );
+    }
+
+    #[test]
+    fn paginated_map_keeps_metadata() {
+        let next = Cursor::try_from("bmV4dA".to_owned()).unwrap();
+        let page = Paginated::new(vec![1u32, 2], pagination(2, 2), 5).with_next(next);
+        let mapped = page.clone().map(UserId::from);
+        assert_eq!(mapped.items, vec![UserId::from(1), UserId::from(2)]);
+        assert_eq!(
+            (mapped.page, mapped.per_page, mapped.total, mapped.next),
+            (page.page, page.per_page, page.total, page.next)
+        );
+    }
+
+    #[test]
+    fn cursor_charset() {
+        for s in &["YWJj", "YWJjZA==", "a-b_c", "a+b/c"] {
+            assert!(Cursor::try_from(s.to_string()).is_ok(), "{}", s);
+        }
+        for s in &["", "has space", "semi;colon", "ünicode", "<script>"] {
+            assert_eq!(
+                Cursor::try_from(s.to_string()),
+                Err(ValidationError::InvalidCursor),
+                "{}",
+                s
+            );
+        }
+        assert!(Cursor::try_from("a".repeat(CURSOR_MAX_LEN + 1)).is_err());
+        assert!(serde_json::from_str::<Cursor>(r#""not a cursor""#).is_err());
+    }
+
+    #[test]
+    fn cursor_page_round_trip() {
+        let cursor = |s: &str| Some(Cursor::try_from(s.to_owned()).unwrap());
+        let page = CursorPage::new(vec![UserId::from(1)], cursor("bmV4dA"), None);
+        let json = serde_json::to_string(&page).unwrap();
+        assert_eq!(serde_json::from_str::<CursorPage<UserId>>(&json).unwrap(), page);
+    }
+
+    #[derive(Serialize, Deserialize, PartialEq, Debug)]
+    struct Item {
+        id: UserId,
+        tags: Vec<String>,
+        pagination: Option<PaginationPayload>,
+    }
+
+    #[test]
+    fn paginated_round_trip() {
+        let page = Paginated::new(
+            vec![
+                Item {
+                    id: UserId::from(1),
+                    tags: vec!["first".to_owned()],
+                    pagination: None,
+                },
+                Item {
+                    id: UserId::from(2),
+                    tags: vec![],
+                    pagination: Some(pagination(4, 5)),
+                },
+            ],
+            pagination(3, 2),
+            10,
+        );
+        let json = serde_json::to_string(&page).unwrap();
+        assert_eq!(serde_json::from_str::<Paginated<Item>>(&json).unwrap(), page);
+    }
+
+    #[derive(Serialize, Deserialize, PartialEq, Debug)]
+    #[serde(deny_unknown_fields)]
+    struct StrictItem {
+        name: String,
+    }
+
+    #[test]
+    fn deny_unknown_fields_is_ignored_when_flattened() {
+        let json = r#"{ "token": "abc", "name": "john", "nmae": "typo" }"#;
+        let payload: TokenPayload<StrictItem> = serde_json::from_str(json).unwrap();
+        assert_eq!(payload.name, "john");
+
+        let json = r#"{ "id": 1, "name": "john", "nmae": "typo" }"#;
+        assert!(serde_json::from_str::<UserIdPayload<StrictItem>>(json).is_ok());
+    }
+
+    #[test]
+    fn strict_rejects_unknown_fields() {
+        let json = r#"{ "token": "abc", "name": "john" }"#;
+        let payload: Strict<TokenPayload<StrictItem>> = serde_json::from_str(json).unwrap();
+        assert_eq!(payload.token(), &Token::new("abc"));
+        assert_eq!(payload.name, "john");
+
+        let json = r#"{ "token": "abc", "name": "john", "nmae": "typo" }"#;
+        assert!(serde_json::from_str::<Strict<TokenPayload<StrictItem>>>(json).is_err());
+
+        let json = r#"{ "id": 1, "name": "john", "nmae": "typo" }"#;
+        assert!(serde_json::from_str::<Strict<UserIdPayload<StrictItem>>>(json).is_err());
+    }
+
+    #[test]
+    fn strict_requires_the_wrapper_field() {
+        let json = r#"{ "name": "john" }"#;
+        let e = serde_json::from_str::<Strict<TokenPayload<StrictItem>>>(json).unwrap_err();
+        assert!(e.to_string().contains("token"), "{}", e);
+    }
+
+    #[test]
+    fn strict_only_enforces_what_the_inner_type_denies() {
+        #[derive(Deserialize, PartialEq, Debug)]
+        struct LaxItem {
+            name: String,
+        }
+
+        let json = r#"{ "token": "abc", "name": "john", "nmae": "typo" }"#;
+        assert!(serde_json::from_str::<Strict<TokenPayload<LaxItem>>>(json).is_ok());
+    }
+
+    #[test]
+    fn strict_serializes_as_the_wrapped_payload() {
+        let payload = TokenPayload::new(
+            StrictItem {
+                name: "john".to_owned(),
+            },
+            Token::new("abc"),
+        );
+        let strict = Strict::new(payload);
+        let expt = serde_json::json!({ "token": "abc", "name": "john" });
+        assert_eq!(serde_json::to_value(&strict).unwrap(), expt);
+    }
+
+    /// Serialize and deserialize `value` with json, MessagePack and bincode
+    fn round_trip_all_formats<T>(value: &T)
+    where
+        T: serde::Serialize + serde::de::DeserializeOwned + PartialEq + std::fmt::Debug,
+    {
+        let json = serde_json::to_vec(value).unwrap();
+        let from_json: T = serde_json::from_slice(&json).unwrap();
+        assert_eq!(&from_json, value, "json");
+
+        let msgpack = rmp_serde::to_vec(value).unwrap();
+        let from_msgpack: T = rmp_serde::from_read_ref(&msgpack).unwrap();
+        assert_eq!(&from_msgpack, value, "msgpack");
+
+        let bincode = bincode::serialize(value).unwrap();
+        let from_bincode: T = bincode::deserialize(&bincode).unwrap();
+        assert_eq!(&from_bincode, value, "bincode");
+    }
+
+    #[test]
+    fn empty_payload_round_trips_in_all_formats() {
+        round_trip_all_formats(&EmptyPayloadStrict {});
+    }
+
+    #[test]
+    fn compact_payloads_round_trip_in_all_formats() {
+        let payload = TokenPayloadCompact::new(pagination(2, 10), Token::new("random-token"));
+        round_trip_all_formats(&payload);
+
+        let payload = UserIdPayloadCompact::new(pagination(2, 10), UserId::from(7));
+        round_trip_all_formats(&payload);
+
+        let item = Item {
+            id: UserId::from(1),
+            tags: vec!["first".to_owned()],
+            pagination: Some(pagination(4, 5)),
+        };
+        round_trip_all_formats(&TokenPayloadCompact::new(item, Token::new("abc")));
+    }
+
+    #[test]
+    fn flattened_payloads_need_a_self_describing_format() {
+        let payload = TokenPayload::new(pagination(2, 10), Token::new("random-token"));
+        let bytes = bincode::serialize(&payload);
+        let round_trip = bytes
+            .ok()
+            .and_then(|b| bincode::deserialize::<TokenPayload<PaginationPayload>>(&b).ok());
+        assert_eq!(round_trip, None);
+
+        // The compact payload works, and can be turned into the flattened one
+        let compact = TokenPayloadCompact::from(payload);
+        let bytes = bincode::serialize(&compact).unwrap();
+        let compact: TokenPayloadCompact<PaginationPayload> = bincode::deserialize(&bytes).unwrap();
+        let payload = TokenPayload::from(compact);
+        assert_eq!(payload.token(), &Token::new("random-token"));
+        assert_eq!(*payload, pagination(2, 10));
+    }
+
+    #[test]
+    fn compact_and_flattened_payloads_convert() {
+        let payload = UserIdPayload::new(pagination(1, 20), UserId::from(3));
+        let compact = UserIdPayloadCompact::from(payload);
+        assert_eq!(compact.id(), &UserId::from(3));
+        assert_eq!(
+            serde_json::to_value(&compact).unwrap(),
+            serde_json::json!({ "id": 3, "inner": { "page": 1, "per_page": 20 } })
+        );
+        let (inner, id) = UserIdPayload::from(compact).into_inner();
+        assert_eq!(inner, pagination(1, 20));
+        assert_eq!(id, UserId::from(3));
+    }
+
+    #[test]
+    fn missing_version_defaults_to_one() {
+        use crate::content::requests::ContentRequest;
+
+        let json = r#"{ "type": "SUBSCRIBE", "payload": { "thread_id": 1 } }"#;
+        let request: Versioned<ContentRequest> = serde_json::from_str(json).unwrap();
+        assert_eq!(request.version(), 1);
+        let expt = serde_json::json!({
+            "v": 1,
+            "type": "SUBSCRIBE",
+            "payload": { "thread_id": 1 }
+        });
+        assert_eq!(serde_json::to_value(&request).unwrap(), expt);
+    }
+
+    #[test]
+    fn version_round_trip() {
+        use crate::content::requests::{ContentRequest, ThreadRefPayload};
+
+        let request = Versioned::<ContentRequest>::with_version(
+            ContentRequest::Bookmark(ThreadRefPayload {
+                thread_id: 1.into(),
+            }),
+            2,
+        );
+        let json = serde_json::to_string(&request).unwrap();
+        assert!(json.starts_with(r#"{"v":2,"#), "{}", json);
+        let parsed: Versioned<ContentRequest> = serde_json::from_str(&json).unwrap();
+        assert_eq!(parsed, request);
+        let payload = Versioned::<PaginationPayload>::new(pagination(1, 20));
+        assert_eq!(payload.version(), CURRENT_VERSION);
+    }
+
+    #[test]
+    fn unsupported_version_still_parses() {
+        use crate::content::requests::ContentRequest;
+
+        let json = r#"{ "v": 999, "type": "LIST_DRAFTS" }"#;
+        let request: Versioned<ContentRequest> = serde_json::from_str(json).unwrap();
+        assert_eq!(request.version(), 999);
+        assert_eq!(
+            request.check_version(CURRENT_VERSION),
+            Err(Error::UnsupportedVersion {
+                got: 999,
+                supported: CURRENT_VERSION
+            })
+        );
+        let (inner, _) = request.into_inner();
+        assert_eq!(inner, ContentRequest::ListDrafts(AbsentOrEmpty::Absent));
+    }
+
+    #[derive(PartialEq, Eq, Debug)]
+    struct TwoToThree;
+
+    impl LengthBounds for TwoToThree {
+        const MIN: usize = 2;
+        const MAX: usize = 3;
+    }
+
+    type SmallList = BoundedVec<u32, TwoToThree>;
+
+    #[test]
+    fn bounded_vec_length_bounds() {
+        assert_eq!(
+            SmallList::try_from(vec![1]),
+            Err(ValidationError::InvalidListLength)
+        );
+        assert_eq!(&*SmallList::try_from(vec![1, 2]).unwrap(), &[1, 2]);
+        assert_eq!(&*SmallList::try_from(vec![1, 2, 3]).unwrap(), &[1, 2, 3]);
+        assert_eq!(
+            SmallList::try_from(vec![1, 2, 3, 4]),
+            Err(ValidationError::InvalidListLength)
+        );
+    }
+
+    #[test]
+    fn bounded_vec_json() {
+        let list: SmallList = serde_json::from_str("[1, 2]").unwrap();
+        assert_eq!(serde_json::to_string(&list).unwrap(), "[1,2]");
+        assert_eq!(list.into_inner(), vec![1, 2]);
+        assert!(serde_json::from_str::<SmallList>("[]").is_err());
+        assert!(serde_json::from_str::<SmallList>("[1, 2, 3, 4]").is_err());
+    }
+
+    #[test]
+    fn canonical_type_tags() {
+        let known = &["AUTHENTICATE", "REGISTER_USER", "BAN_IP", "GET_2FA_CODE"];
+        let tags = vec![
+            ("AUTHENTICATE", Some("AUTHENTICATE")),
+            ("authenticate", Some("AUTHENTICATE")),
+            ("Authenticate", Some("AUTHENTICATE")),
+            ("RegisterUser", Some("REGISTER_USER")),
+            ("registerUser", Some("REGISTER_USER")),
+            ("register_user", Some("REGISTER_USER")),
+            ("REGISTER_USER", Some("REGISTER_USER")),
+            ("register-user", Some("REGISTER_USER")),
+            (" Ban Ip ", Some("BAN_IP")),
+            ("get2faCode", Some("GET_2FA_CODE")),
+            ("Get2FACode", Some("GET_2FA_CODE")),
+            ("register", None),
+            ("frobnicate", None),
+        ];
+        for (tag, expt) in tags {
+            assert_eq!(canonical_type_tag(tag, known), expt, "{}", tag);
+        }
+    }
+
+    #[test]
+    fn lenient_json_must_still_be_valid() {
+        assert!(from_json_lenient::<PaginationPayload>("{", &[]).is_err());
+        let payload: PaginationPayload = from_json_lenient(r#"{ "page": 2 }"#, &[]).unwrap();
+        assert_eq!(payload.page, 2);
+    }
+
+    const BAD_EMAIL: &str = r#"{
+        "token": "random-token",
+        "username": "john",
+        "password": "helloAndWelcome123",
+        "email": "not an email"
+    }"#;
+
+    #[test]
+    fn path_of_a_field_inside_a_flattened_payload() {
+        use crate::auth::requests::RegisterUserPayload;
+
+        let e = parse_with_path::<TokenPayload<RegisterUserPayload>>(BAD_EMAIL).unwrap_err();
+        assert_eq!(e.path, "email");
+        assert!(e.message.contains("email"), "{}", e.message);
+
+        let json = BAD_EMAIL.replace("\"token\"", "\"v\": 2, \"token\"");
+        let e = parse_with_path::<Versioned<TokenPayload<RegisterUserPayload>>>(&json);
+        assert_eq!(e.unwrap_err().path, "email");
+    }
+
+    #[test]
+    fn path_of_a_field_inside_a_flattened_request() {
+        use crate::auth::requests::AuthRequest;
+
+        let json = r#"{
+            "token": "random-token",
+            "type": "REGISTER",
+            "payload": {
+                "username": "john",
+                "password": "helloAndWelcome123",
+                "email": "not an email"
+            }
+        }"#;
+        let e = parse_with_path::<TokenPayload<AuthRequest>>(json).unwrap_err();
+        assert_eq!(e.path, "payload.email");
+        assert_eq!(e.to_string(), format!("payload.email: {}", e.message));
+    }
+
+    #[test]
+    fn lenient_parse_collects_unknown_fields() {
+        use crate::admin::requests::{AdminRequest, BanIpPayload};
+
+        let json = r#"{
+            "type": "BAN_IP",
+            "payload": { "ip": "10.0.0.1", "port": 80, "reason": "spam" },
+            "trace_id": "abc"
+        }"#;
+        let (request, unknown) = parse_lenient::<AdminRequest>(json).unwrap();
+        let expt = AdminRequest::BanIp(BanIpPayload {
+            ip: "10.0.0.1".parse().unwrap(),
+            duration: None,
+            country: None,
+        });
+        assert_eq!(request, expt);
+        assert_eq!(unknown, vec!["payload.port", "payload.reason", "trace_id"]);
+
+        let json = r#"{ "type": "BAN_IP", "payload": { "ip": "10.0.0.1" } }"#;
+        let (request, unknown) = parse_lenient::<AdminRequest>(json).unwrap();
+        assert_eq!(request, expt);
+        assert!(unknown.is_empty());
+    }
+
+    #[test]
+    fn lenient_parse_of_nested_fields() {
+        use crate::content::requests::ContentRequest;
+
+        let json = r#"{
+            "type": "LIST_SUBSCRIPTIONS",
+            "payload": { "page": 2, "per_page": 10, "sort": "new" }
+        }"#;
+        let (_, unknown) = parse_lenient::<ContentRequest>(json).unwrap();
+        assert_eq!(unknown, vec!["payload.sort"]);
+
+        // A payload without fields skips all of them
+        let json = r#"{ "type": "DEAUTHENTICATE", "payload": { "all": true } }"#;
+        let (_, unknown) = parse_lenient::<crate::auth::requests::AuthRequest>(json).unwrap();
+        assert_eq!(unknown, vec!["payload.all"]);
+    }
+
+    #[test]
+    fn lenient_parse_still_rejects_invalid_fields() {
+        use crate::admin::requests::AdminRequest;
+
+        let json = r#"{ "type": "BAN_IP", "payload": { "port": 80 } }"#;
+        let e = parse_lenient::<AdminRequest>(json).unwrap_err();
+        assert!(e.message.contains("missing field `ip`"), "{}", e.message);
+
+        let json = r#"{ "type": "BAN_IP", "payload": { "ip": "not an ip", "port": 80 } }"#;
+        let e = parse_lenient::<AdminRequest>(json).unwrap_err();
+        assert_eq!(e.path, "payload.ip");
+        assert_eq!(parse_lenient::<AdminRequest>("{ ").unwrap_err().path, ".");
+    }
+
+    #[test]
+    fn path_of_a_wrapper_field() {
+        let json = r#"{ "id": "one", "page": 1 }"#;
+        let e = parse_with_path::<UserIdPayload<PaginationPayload>>(json).unwrap_err();
+        assert_eq!(e.path, "id");
+
+        let e = parse_with_path::<UserIdPayload<PaginationPayload>>("{ ").unwrap_err();
+        assert_eq!(e.path, ".");
+    }
+
+    #[test]
+    fn flattened_payloads_are_deserialized_like_before() {
+        let json = r#"{ "token": "random-token", "page": 2, "unknown": true }"#;
+        let payload: TokenPayload<PaginationPayload> = serde_json::from_str(json).unwrap();
+        assert_eq!(*payload, pagination(2, DEFAULT_PER_PAGE));
+
+        let json = r#"{ "page": 2 }"#;
+        assert!(serde_json::from_str::<TokenPayload<PaginationPayload>>(json).is_err());
+        let json = r#"{ "token": "random-token", "token": "random-token", "page": 2 }"#;
+        assert!(serde_json::from_str::<TokenPayload<PaginationPayload>>(json).is_err());
+
+        #[derive(Deserialize)]
+        struct Borrowed<'a> {
+            name: &'a str,
+        }
+        let json = r#"{ "name": "john", "token": "random-token" }"#;
+        let payload: TokenPayload<Borrowed> = serde_json::from_str(json).unwrap();
+        assert_eq!(payload.name, "john");
+    }
+
+    #[derive(Deserialize, PartialEq, Debug)]
+    struct Login {
+        username: String,
+        password: String,
+    }
+
+    #[test]
+    fn token_payload_from_flattened_and_nested_json() {
+        let expected = TokenPayload::new(
+            Login {
+                username: "john".to_owned(),
+                password: "secret".to_owned(),
+            },
+            Token::new("random-token"),
+        );
+        let flattened = r#"{ "token": "random-token", "username": "john", "password": "secret" }"#;
+        let nested = r#"{
+            "token": "random-token",
+            "payload": { "username": "john", "password": "secret" }
+        }"#;
+        for json in &[flattened, nested] {
+            let payload = TokenPayload::<Login>::from_json_compat(json);
+            assert_eq!(payload.as_ref(), Ok(&expected), "{}", json);
+        }
+    }
+
+    #[test]
+    fn token_payload_compat_doesnt_mask_invalid_fields() {
+        let path = |json: &str| {
+            TokenPayload::<Login>::from_json_compat(json)
+                .unwrap_err()
+                .path
+        };
+        // A invalid field of the flattened layout
+        let json = r#"{ "token": "random-token", "username": 1, "password": "secret" }"#;
+        assert_eq!(path(json), "username");
+        // A invalid field of the nested layout
+        let json = r#"{ "token": "random-token", "payload": { "username": 1, "password": "x" } }"#;
+        assert_eq!(path(json), "payload.username");
+        // Other keys than `token` and `payload` is not the nested layout
+        let json = r#"{
+            "token": "random-token",
+            "payload": { "username": "john", "password": "secret" },
+            "username": "john"
+        }"#;
+        assert_eq!(path(json), ".");
+    }
+
+    #[test]
+    fn token_payload_compat_carries_the_error() {
+        let json = r#"{ "token": "random-token", "username": 1, "password": "secret" }"#;
+        let e = TokenPayload::<Login>::from_json_compat(json).unwrap_err();
+        assert!(e.message.starts_with("invalid type: integer `1`"), "{}", e);
+        let json = r#"{ "token": "random-token", "username": "john" }"#;
+        let e = TokenPayload::<Login>::from_json_compat(json).unwrap_err();
+        assert_eq!(e.message, "missing field `password`");
+        let e = TokenPayload::<Login>::from_json_compat("{").unwrap_err();
+        assert_eq!(e.path, ".");
+        assert!(e.message.starts_with("EOF while parsing"), "{}", e);
+    }
+
+    #[test]
+    fn token_payload_compat_with_a_payload_field() {
+        #[derive(Deserialize, PartialEq, Debug)]
+        struct Envelope {
+            payload: Login,
+        }
+
+        let json = r#"{
+            "token": "random-token",
+            "payload": { "username": "john", "password": "secret" }
+        }"#;
+        let payload = TokenPayload::<Envelope>::from_json_compat(json).unwrap();
+        assert_eq!(payload.payload.username, "john");
+
+        // The nested layout of the same payload
+        let json = r#"{
+            "token": "random-token",
+            "payload": { "payload": { "username": "john", "password": "secret" } }
+        }"#;
+        let payload = TokenPayload::<Envelope>::from_json_compat(json).unwrap();
+        assert_eq!(payload.payload.username, "john");
+
+        let json = r#"{ "token": "random-token", "payload": { "username": 1 } }"#;
+        assert!(TokenPayload::<Envelope>::from_json_compat(json).is_err());
+    }
+
+    #[test]
+    fn token_is_not_serialized_with_the_inner_payload() {
+        let token = "secret-token";
+        let payload = TokenPayload::new(pagination(2, 10), Token::new(token));
+        let json = serde_json::to_value(&payload).unwrap();
+        assert_eq!(json["token"], token);
+
+        for json in &[
+            serde_json::to_value(payload.without_token()).unwrap(),
+            serde_json::to_value(payload.clone_inner()).unwrap(),
+        ] {
+            assert_eq!(json, &serde_json::json!({ "page": 2, "per_page": 10 }));
+            assert!(json.get("token").is_none());
+            assert!(!json.to_string().contains(token));
+        }
+        assert_eq!(payload.clone_inner(), *payload);
+    }
+
+    /// A inner type with a field which collides with the token
+    #[derive(Serialize, Deserialize, PartialEq, Debug)]
+    struct Collision {
+        token: String,
+    }
+
+    #[test]
+    fn token_field_collision_is_serialized_twice() {
+        let inner = Collision {
+            token: String::from("inner"),
+        };
+        let payload = TokenPayload::new(inner, Token::new("outer"));
+
+        // Both tokens are written under the same key
+        let json = serde_json::to_string(&payload).unwrap();
+        assert_eq!(json, r#"{"token":"outer","token":"inner"}"#);
+        // A map keeps the last one, which is the inner token
+        let value = serde_json::to_value(&payload).unwrap();
+        assert_eq!(value, serde_json::json!({ "token": "inner" }));
+    }
+
+    #[test]
+    #[cfg(debug_assertions)]
+    #[should_panic(expected = "has a field named `token`")]
+    fn token_field_collision_panics_in_debug_builds() {
+        let json = r#"{ "token": "outer" }"#;
+        let _ = serde_json::from_str::<TokenPayload<Collision>>(json);
+    }
+
+    #[test]
+    #[cfg(not(debug_assertions))]
+    fn token_field_collision_cant_be_deserialized() {
+        // The wrapper takes the token, so the inner type never gets it
+        let json = r#"{ "token": "outer" }"#;
+        let e = serde_json::from_str::<TokenPayload<Collision>>(json).unwrap_err();
+        assert!(e.to_string().starts_with("missing field `token`"), "{}", e);
+        // And a serialized payload has the token twice
+        let json = r#"{"token":"outer","token":"inner"}"#;
+        let e = serde_json::from_str::<TokenPayload<Collision>>(json).unwrap_err();
+        let duplicate = e.to_string().starts_with("duplicate field `token`");
+        assert!(duplicate, "{}", e);
+    }
+
+    #[test]
+    fn token_and_user_id_are_serialized_first() {
+        let payload = TokenPayload::new(pagination(2, 10), Token::new("random-token"));
+        let json = serde_json::to_string(&payload).unwrap();
+        assert_eq!(json, r#"{"token":"random-token","page":2,"per_page":10}"#);
+
+        let payload = UserIdPayload::new(pagination(2, 10), UserId::from(3));
+        let json = serde_json::to_string(&payload).unwrap();
+        assert_eq!(json, r#"{"id":3,"page":2,"per_page":10}"#);
+
+        let inner = UserIdPayload::new(pagination(2, 10), UserId::from(3));
+        let payload = TokenPayload::new(inner, Token::new("random-token"));
+        let json = serde_json::to_string(&payload).unwrap();
+        let expt = r#"{"token":"random-token","id":3,"page":2,"per_page":10}"#;
+        assert_eq!(json, expt);
+    }
+
+    #[test]
+    fn token_and_user_id_payloads_in_canonical_json() {
+        use crate::codec::to_canonical_json;
+
+        let inner = UserIdPayload::new(pagination(2, 10), UserId::from(3));
+        let payload = TokenPayload::new(inner, Token::new("random-token"));
+        let bytes = to_canonical_json(&payload).unwrap();
+        let expt = br#"{"id":3,"page":2,"per_page":10,"token":"random-token"}"#;
+        assert_eq!(bytes, expt.to_vec());
+    }
+
+    #[test]
+    fn user_id_payload_display() {
+        let payload = UserIdPayload::<&str>::new("LIST_BANS", UserId::from(3));
+        assert_eq!(payload.to_string(), "user=3 LIST_BANS");
+    }
+
+    #[test]
+    fn token_payload_display_redacts_the_token() {
+        let token = "secret-token";
+        let payload = TokenPayload::<&str>::new("LIST_BANS", Token::new(token));
+        let s = payload.to_string();
+        assert_eq!(s, "LIST_BANS (token redacted)");
+        assert!(!s.contains(token));
+
+        let inner = UserIdPayload::<&str>::new("LIST_BANS", UserId::from(3));
+        let payload = TokenPayload::<UserIdPayload<&str>>::new(inner, Token::new(token));
+        let s = payload.to_string();
+        assert_eq!(s, "user=3 LIST_BANS (token redacted)");
+        assert!(!s.contains(token));
     }
 }
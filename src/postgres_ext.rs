@@ -0,0 +1,239 @@
+//! `ToSql` and `FromSql` of `postgres-types`, for using the ids and
+//! validated types with `tokio-postgres` directly
+//!
+//! | type                                         | postgres type    |
+//! |----------------------------------------------|------------------|
+//! | the ids                                      | `INT4` or `INT8` |
+//! | `Username`, `Email`, `Title` and `HexDigest` | `TEXT`           |
+//! | `IpAddrPayload`                              | `INET`           |
+//! | `BanSubject`                                 | `INET` or `INT4` |
+//!
+//! A value which is read from the database is validated like any other
+//! input, and a invalid value is a error with the `ValidationError` as its
+//! source. A `BanSubject` is a `INET` when it bans a IP address and a id when
+//! it bans a user.
+
+use crate::admin::requests::IpAddrPayload;
+use crate::admin::responses::BanSubject;
+use crate::valid::attachment::HexDigest;
+use crate::valid::fields::{Email, Username};
+use crate::valid::ids::{
+    AttachmentId, CategoryId, CommentId, DraftId, Id, PollOptionId, ThreadId, UserId,
+};
+use crate::valid::title::Title;
+use crate::valid::ValidationError;
+use bytes::BytesMut;
+use postgres_types::{FromSql, IsNull, ToSql, Type};
+use std::convert::TryFrom;
+use std::error::Error;
+use std::net::IpAddr;
+
+type BoxError = Box<dyn Error + Sync + Send>;
+
+/// Write a id as a `INT4` or a `INT8`, a id which doesn't fit in a `INT4`
+/// can't be written as one
+fn id_to_sql(id: u32, ty: &Type, out: &mut BytesMut) -> Result<IsNull, BoxError> {
+    if *ty == Type::INT4 {
+        let n = i32::try_from(id).map_err(|_| ValidationError::InvalidId)?;
+        n.to_sql(ty, out)
+    } else {
+        i64::from(id).to_sql(ty, out)
+    }
+}
+
+/// Read a id from a `INT4` or a `INT8`, a negative (or too large) number is
+/// not a id
+fn id_from_sql<I: From<u32>>(ty: &Type, raw: &[u8]) -> Result<I, BoxError> {
+    let n = if *ty == Type::INT4 {
+        i64::from(i32::from_sql(ty, raw)?)
+    } else {
+        i64::from_sql(ty, raw)?
+    };
+    let id = u32::try_from(n).map_err(|_| ValidationError::InvalidId)?;
+    Ok(I::from(id))
+}
+
+macro_rules! impl_postgres_id {
+    ($($id:ty),*) => {
+        $(
+            impl ToSql for $id {
+                fn to_sql(&self, ty: &Type, out: &mut BytesMut) -> Result<IsNull, BoxError> {
+                    id_to_sql(self.inner(), ty, out)
+                }
+
+                accepts!(INT4, INT8);
+                to_sql_checked!();
+            }
+
+            impl<'a> FromSql<'a> for $id {
+                fn from_sql(ty: &Type, raw: &'a [u8]) -> Result<Self, BoxError> {
+                    id_from_sql(ty, raw)
+                }
+
+                accepts!(INT4, INT8);
+            }
+        )*
+    };
+}
+
+macro_rules! impl_postgres_text {
+    ($($ty:ty => $validate:expr),*) => {
+        $(
+            impl ToSql for $ty {
+                fn to_sql(&self, ty: &Type, out: &mut BytesMut) -> Result<IsNull, BoxError> {
+                    AsRef::<str>::as_ref(self).to_sql(ty, out)
+                }
+
+                accepts!(TEXT, VARCHAR);
+                to_sql_checked!();
+            }
+
+            impl<'a> FromSql<'a> for $ty {
+                fn from_sql(ty: &Type, raw: &'a [u8]) -> Result<Self, BoxError> {
+                    let s = <&str as FromSql>::from_sql(ty, raw)?;
+                    let validate: fn(&str) -> Result<$ty, ValidationError> = $validate;
+                    Ok(validate(s)?)
+                }
+
+                accepts!(TEXT, VARCHAR);
+            }
+        )*
+    };
+}
+
+impl_postgres_id!(
+    CategoryId,
+    ThreadId,
+    CommentId,
+    UserId,
+    DraftId,
+    AttachmentId,
+    PollOptionId
+);
+
+impl_postgres_text!(
+    Username => |s| Username::try_from(s.to_owned()),
+    Email => |s| Email::try_from(s.to_owned()),
+    Title => Title::from_escaped,
+    HexDigest => |s| HexDigest::try_from(s.to_owned())
+);
+
+impl ToSql for IpAddrPayload {
+    fn to_sql(&self, ty: &Type, out: &mut BytesMut) -> Result<IsNull, BoxError> {
+        self.ip.to_sql(ty, out)
+    }
+
+    accepts!(INET);
+    to_sql_checked!();
+}
+
+impl<'a> FromSql<'a> for IpAddrPayload {
+    fn from_sql(ty: &Type, raw: &'a [u8]) -> Result<Self, BoxError> {
+        let ip = IpAddr::from_sql(ty, raw)?;
+        Ok(IpAddrPayload { ip })
+    }
+
+    accepts!(INET);
+}
+
+impl ToSql for BanSubject {
+    fn to_sql(&self, ty: &Type, out: &mut BytesMut) -> Result<IsNull, BoxError> {
+        match (self, *ty == Type::INET) {
+            (BanSubject::Ip(ip), true) => ip.to_sql(ty, out),
+            (BanSubject::User(id), false) => id.to_sql(ty, out),
+            (subject, _) => Err(format!("{:?} can't be written as a {}", subject, ty).into()),
+        }
+    }
+
+    accepts!(INET, INT4, INT8);
+    to_sql_checked!();
+}
+
+impl<'a> FromSql<'a> for BanSubject {
+    fn from_sql(ty: &Type, raw: &'a [u8]) -> Result<Self, BoxError> {
+        if *ty == Type::INET {
+            IpAddr::from_sql(ty, raw).map(BanSubject::Ip)
+        } else {
+            UserId::from_sql(ty, raw).map(BanSubject::User)
+        }
+    }
+
+    accepts!(INET, INT4, INT8);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn to_sql(value: &dyn ToSql, ty: &Type) -> Result<Vec<u8>, BoxError> {
+        let mut out = BytesMut::new();
+        value.to_sql_checked(ty, &mut out)?;
+        Ok(out.to_vec())
+    }
+
+    fn validation_error(e: BoxError) -> ValidationError {
+        *e.downcast::<ValidationError>().unwrap()
+    }
+
+    #[test]
+    fn id_to_and_from_sql() {
+        let id = UserId::from(42);
+        assert_eq!(to_sql(&id, &Type::INT4).unwrap(), vec![0, 0, 0, 42]);
+        assert_eq!(UserId::from_sql(&Type::INT4, &[0, 0, 0, 42]).unwrap(), id);
+
+        let raw = to_sql(&id, &Type::INT8).unwrap();
+        assert_eq!(raw, vec![0, 0, 0, 0, 0, 0, 0, 42]);
+        assert_eq!(UserId::from_sql(&Type::INT8, &raw).unwrap(), id);
+
+        assert!(<UserId as ToSql>::accepts(&Type::INT4));
+        assert!(<UserId as FromSql>::accepts(&Type::INT8));
+        assert!(!<UserId as FromSql>::accepts(&Type::TEXT));
+        assert!(to_sql(&id, &Type::TEXT).is_err());
+    }
+
+    #[test]
+    fn invalid_id() {
+        let e = UserId::from_sql(&Type::INT4, &[0xff; 4]).unwrap_err();
+        assert_eq!(validation_error(e), ValidationError::InvalidId);
+
+        let too_large = ThreadId::from(u32::max_value());
+        let e = to_sql(&too_large, &Type::INT4).unwrap_err();
+        assert_eq!(validation_error(e), ValidationError::InvalidId);
+        assert!(to_sql(&too_large, &Type::INT8).is_ok());
+    }
+
+    #[test]
+    fn text_to_and_from_sql() {
+        let name = Username::try_from(String::from("john")).unwrap();
+        assert_eq!(to_sql(&name, &Type::TEXT).unwrap(), b"john".to_vec());
+        assert_eq!(Username::from_sql(&Type::TEXT, b"john").unwrap(), name);
+
+        let e = Email::from_sql(&Type::TEXT, b"john").unwrap_err();
+        assert_eq!(validation_error(e), ValidationError::InvalidEmail);
+
+        // The title is stored escaped, and isn't escaped again when it's read
+        let title = Title::try_from("A title with <script>").unwrap();
+        let raw = to_sql(&title, &Type::TEXT).unwrap();
+        assert_eq!(raw, b"A title with &lt;script&gt;".to_vec());
+        assert_eq!(Title::from_sql(&Type::TEXT, &raw).unwrap(), title);
+    }
+
+    #[test]
+    fn ban_subject_to_and_from_sql() {
+        let ip: IpAddr = "10.0.0.1".parse().unwrap();
+        let raw = to_sql(&IpAddrPayload { ip }, &Type::INET).unwrap();
+        // The family, the netmask, that it isn't a cidr and the 4 bytes
+        assert_eq!(raw, vec![2, 32, 0, 4, 10, 0, 0, 1]);
+        let payload = IpAddrPayload::from_sql(&Type::INET, &raw).unwrap();
+        assert_eq!(payload, IpAddrPayload { ip });
+
+        let subject = BanSubject::from_sql(&Type::INET, &raw).unwrap();
+        assert_eq!(subject, BanSubject::Ip(ip));
+        assert_eq!(to_sql(&subject, &Type::INET).unwrap(), raw);
+        assert!(to_sql(&subject, &Type::INT4).is_err());
+
+        let subject = BanSubject::from_sql(&Type::INT4, &[0, 0, 0, 3]).unwrap();
+        assert_eq!(subject, BanSubject::User(UserId::from(3)));
+        assert!(to_sql(&subject, &Type::INET).is_err());
+    }
+}
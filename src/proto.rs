@@ -0,0 +1,709 @@
+//! Protobuf messages of the auth and admin requests, for consuming them over
+//! gRPC
+//!
+//! The messages are written by hand with `prost`, and mirror
+//! `proto/datatypes.proto` (which is embedded as `PROTO`). A request of the
+//! crate is converted to its message with `From`, and a message back with
+//! `TryFrom`, which validates every field like deserializing does.
+//!
+//! Since protobuf can't tell a field which is left out from its default, the
+//! defaults which aren't valid are rejected: a id of 0 is
+//! `ValidationError::InvalidId`, a empty username is
+//! `ValidationError::InvalidUsername` and so on. A oneof which is missing or
+//! a enum which is `UNSPECIFIED` is a `Error::MalformedPayload`. An empty
+//! payload (like the one of `Deauthenticate`) is converted to `None`, the
+//! form of a request which is sent without a payload. Both forms are the same
+//! request, so `Some(EmptyPayloadStrict {})` is converted to `None` as well.
+//!
+//! ```
+//! # #![feature(try_from)]
+//! # use datatypes::admin::requests::{AdminRequest, UserIdRefPayload};
+//! # use datatypes::proto;
+//! # use datatypes::valid::ids::UserId;
+//! # use prost::Message;
+//! # use std::convert::TryFrom;
+//! let request = AdminRequest::UnbanUser(UserIdRefPayload { id: UserId::from(42) });
+//! let bytes = proto::AdminRequest::from(request).encode_to_vec();
+//!
+//! let message = proto::AdminRequest::decode(&bytes[..]).unwrap();
+//! let request = AdminRequest::try_from(message).unwrap();
+//! assert_eq!(request, AdminRequest::UnbanUser(UserIdRefPayload { id: UserId::from(42) }));
+//! ```
+
+use crate::admin::requests as admin;
+use crate::auth::requests as auth;
+use crate::auth::responses;
+use crate::error::Error;
+use crate::payloads::{self, TokenPayload};
+use crate::valid::country::CountryCode;
+use crate::valid::duration::HumanDuration;
+use crate::valid::fields::{Email, PlainPassword, TwoFactorCode, Username};
+use crate::valid::ids::UserId;
+use crate::valid::token::Token;
+use crate::valid::ValidationError;
+use std::convert::TryFrom;
+use std::net::IpAddr;
+
+/// The protobuf definitions of the messages
+pub const PROTO: &str = include_str!("../proto/datatypes.proto");
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct EmptyPayload {}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct PaginationPayload {
+    #[prost(uint32, tag = "1")]
+    pub page: u32,
+    #[prost(uint32, tag = "2")]
+    pub per_page: u32,
+}
+
+// Auth
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct AuthRequest {
+    #[prost(oneof = "auth_request::Request", tags = "1, 2, 3, 4, 5")]
+    pub request: Option<auth_request::Request>,
+}
+
+pub mod auth_request {
+    #[derive(Clone, PartialEq, prost::Oneof)]
+    pub enum Request {
+        #[prost(message, tag = "1")]
+        Authenticate(super::AuthPayload),
+        #[prost(message, tag = "2")]
+        Deauthenticate(super::EmptyPayload),
+        #[prost(message, tag = "3")]
+        Register(super::RegisterUserPayload),
+        #[prost(message, tag = "4")]
+        SubmitTwoFactor(super::TwoFactorPayload),
+        #[prost(message, tag = "5")]
+        WhoAmI(super::EmptyPayload),
+    }
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct AuthPayload {
+    #[prost(string, tag = "1")]
+    pub username: String,
+    #[prost(string, tag = "2")]
+    pub password: String,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct RegisterUserPayload {
+    #[prost(string, tag = "1")]
+    pub username: String,
+    #[prost(string, tag = "2")]
+    pub password: String,
+    #[prost(string, tag = "3")]
+    pub email: String,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct TwoFactorPayload {
+    #[prost(string, tag = "1")]
+    pub challenge: String,
+    #[prost(string, tag = "2")]
+    pub code: String,
+}
+
+// Admin
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct TokenAdminRequest {
+    #[prost(string, tag = "1")]
+    pub token: String,
+    #[prost(message, optional, tag = "2")]
+    pub request: Option<AdminRequest>,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct AdminRequest {
+    #[prost(oneof = "admin_request::Request", tags = "1, 2, 3, 4, 5, 6, 7")]
+    pub request: Option<admin_request::Request>,
+}
+
+pub mod admin_request {
+    #[derive(Clone, PartialEq, prost::Oneof)]
+    pub enum Request {
+        #[prost(message, tag = "1")]
+        BanIp(super::BanIpPayload),
+        #[prost(message, tag = "2")]
+        UnbanIp(super::IpAddrPayload),
+        #[prost(message, tag = "3")]
+        BanUser(super::BanUserPayload),
+        #[prost(message, tag = "4")]
+        UnbanUser(super::UserIdRefPayload),
+        #[prost(message, tag = "5")]
+        SetUserRole(super::SetUserRolePayload),
+        #[prost(message, tag = "6")]
+        ListBans(super::ListBansPayload),
+        #[prost(message, tag = "7")]
+        ListReports(super::PaginationPayload),
+    }
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct IpAddrPayload {
+    #[prost(string, tag = "1")]
+    pub ip: String,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct UserIdRefPayload {
+    #[prost(uint32, tag = "1")]
+    pub id: u32,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct BanIpPayload {
+    #[prost(string, tag = "1")]
+    pub ip: String,
+    #[prost(uint64, optional, tag = "2")]
+    pub duration_secs: Option<u64>,
+    #[prost(string, optional, tag = "3")]
+    pub country: Option<String>,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct BanUserPayload {
+    #[prost(uint32, tag = "1")]
+    pub id: u32,
+    #[prost(uint64, optional, tag = "2")]
+    pub duration_secs: Option<u64>,
+    #[prost(string, optional, tag = "3")]
+    pub country: Option<String>,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Debug, prost::Enumeration)]
+#[repr(i32)]
+pub enum Role {
+    Unspecified = 0,
+    User = 10,
+    Moderator = 20,
+    Admin = 30,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct SetUserRolePayload {
+    #[prost(uint32, tag = "1")]
+    pub id: u32,
+    #[prost(enumeration = "Role", tag = "2")]
+    pub role: i32,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct ListBansPayload {
+    #[prost(message, optional, tag = "1")]
+    pub pagination: Option<PaginationPayload>,
+    #[prost(message, optional, tag = "2")]
+    pub filter: Option<BanFilter>,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Debug, prost::Enumeration)]
+#[repr(i32)]
+pub enum IpFamily {
+    Unspecified = 0,
+    V4 = 1,
+    V6 = 2,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Debug, prost::Enumeration)]
+#[repr(i32)]
+pub enum BanKind {
+    Unspecified = 0,
+    User = 1,
+    Ip = 2,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct BanFilter {
+    #[prost(oneof = "ban_filter::Filter", tags = "1, 2")]
+    pub filter: Option<ban_filter::Filter>,
+}
+
+pub mod ban_filter {
+    #[derive(Clone, Copy, PartialEq, prost::Oneof)]
+    pub enum Filter {
+        #[prost(enumeration = "super::IpFamily", tag = "1")]
+        IpFamily(i32),
+        #[prost(enumeration = "super::BanKind", tag = "2")]
+        Kind(i32),
+    }
+}
+
+/// The error of a field which protobuf left out, or which is `UNSPECIFIED`
+fn missing(context: &'static str) -> Error {
+    Error::MalformedPayload { context }
+}
+
+/// A id, where 0 (the default of protobuf) is not a id
+fn user_id(id: u32) -> Result<UserId, ValidationError> {
+    if id == 0 {
+        Err(ValidationError::InvalidId)
+    } else {
+        Ok(UserId::from(id))
+    }
+}
+
+fn ip_addr(ip: &str) -> Result<IpAddr, ValidationError> {
+    ip.parse().map_err(|_| ValidationError::InvalidIpAddr)
+}
+
+fn duration(secs: Option<u64>) -> Option<HumanDuration> {
+    secs.map(HumanDuration::from_secs)
+}
+
+fn country(country: Option<String>) -> Result<Option<CountryCode>, ValidationError> {
+    country.map(CountryCode::try_from).transpose()
+}
+
+// Conversions of the auth requests
+
+impl From<auth::AuthRequest> for AuthRequest {
+    fn from(request: auth::AuthRequest) -> Self {
+        use self::auth_request::Request;
+        let request = match request {
+            auth::AuthRequest::Authenticate(p) => Request::Authenticate(AuthPayload {
+                username: p.username.into_inner(),
+                password: p.password.into_inner(),
+            }),
+            auth::AuthRequest::Deauthenticate(_) => Request::Deauthenticate(EmptyPayload {}),
+            auth::AuthRequest::RegisterUser(p) => Request::Register(RegisterUserPayload {
+                username: p.username.into_inner(),
+                password: p.password.into_inner(),
+                email: p.email.into_inner(),
+            }),
+            auth::AuthRequest::SubmitTwoFactor(p) => Request::SubmitTwoFactor(TwoFactorPayload {
+                challenge: p.challenge.as_ref().to_owned(),
+                code: p.code.into_inner(),
+            }),
+            auth::AuthRequest::WhoAmI(_) => Request::WhoAmI(EmptyPayload {}),
+        };
+        AuthRequest {
+            request: Some(request),
+        }
+    }
+}
+
+impl TryFrom<AuthRequest> for auth::AuthRequest {
+    type Error = Error;
+    fn try_from(message: AuthRequest) -> Result<Self, Self::Error> {
+        use self::auth_request::Request;
+        let request = match message.request.ok_or_else(|| missing("missing request"))? {
+            Request::Authenticate(p) => auth::AuthRequest::Authenticate(auth::AuthPayload {
+                username: Username::try_from(p.username)?,
+                password: PlainPassword::try_from(p.password)?,
+            }),
+            Request::Deauthenticate(_) => auth::AuthRequest::Deauthenticate(None),
+            Request::Register(p) => auth::AuthRequest::RegisterUser(auth::RegisterUserPayload {
+                username: Username::try_from(p.username)?,
+                password: PlainPassword::try_from(p.password)?,
+                email: Email::try_from(p.email)?,
+            }),
+            Request::SubmitTwoFactor(p) => {
+                if p.challenge.is_empty() {
+                    return Err(missing("missing challenge"));
+                }
+                auth::AuthRequest::SubmitTwoFactor(auth::TwoFactorPayload {
+                    challenge: Token::new(p.challenge),
+                    code: TwoFactorCode::try_from(p.code)?,
+                })
+            }
+            Request::WhoAmI(_) => auth::AuthRequest::WhoAmI(None),
+        };
+        Ok(request)
+    }
+}
+
+// Conversions of the admin requests
+
+impl From<admin::TokenAdminRequest> for TokenAdminRequest {
+    fn from(request: admin::TokenAdminRequest) -> Self {
+        let (request, token) = request.into_inner();
+        TokenAdminRequest {
+            token: token.as_ref().to_owned(),
+            request: Some(request.into()),
+        }
+    }
+}
+
+impl TryFrom<TokenAdminRequest> for admin::TokenAdminRequest {
+    type Error = Error;
+    fn try_from(message: TokenAdminRequest) -> Result<Self, Self::Error> {
+        if message.token.is_empty() {
+            return Err(Error::MissingToken);
+        }
+        let request = message.request.ok_or_else(|| missing("missing request"))?;
+        let request = admin::AdminRequest::try_from(request)?;
+        Ok(TokenPayload::new(request, Token::new(message.token)))
+    }
+}
+
+impl From<admin::AdminRequest> for AdminRequest {
+    fn from(request: admin::AdminRequest) -> Self {
+        use self::admin_request::Request;
+        let request = match request {
+            admin::AdminRequest::BanIp(p) => Request::BanIp(BanIpPayload {
+                ip: p.ip.to_string(),
+                duration_secs: p.duration.map(|d| d.as_secs()),
+                country: p.country.map(CountryCode::into_inner),
+            }),
+            admin::AdminRequest::UnbanIp(p) => Request::UnbanIp(IpAddrPayload {
+                ip: p.ip.to_string(),
+            }),
+            admin::AdminRequest::BanUser(p) => Request::BanUser(BanUserPayload {
+                id: *p.id,
+                duration_secs: p.duration.map(|d| d.as_secs()),
+                country: p.country.map(CountryCode::into_inner),
+            }),
+            admin::AdminRequest::UnbanUser(p) => Request::UnbanUser(UserIdRefPayload { id: *p.id }),
+            admin::AdminRequest::SetUserRole(p) => Request::SetUserRole(SetUserRolePayload {
+                id: *p.id,
+                role: Role::from(p.role) as i32,
+            }),
+            admin::AdminRequest::ListBans(p) => Request::ListBans(ListBansPayload {
+                pagination: Some(p.pagination.into()),
+                filter: p.filter.map(BanFilter::from),
+            }),
+            admin::AdminRequest::ListReports(p) => Request::ListReports(p.into()),
+        };
+        AdminRequest {
+            request: Some(request),
+        }
+    }
+}
+
+impl TryFrom<AdminRequest> for admin::AdminRequest {
+    type Error = Error;
+    fn try_from(message: AdminRequest) -> Result<Self, Self::Error> {
+        use self::admin_request::Request;
+        let request = match message.request.ok_or_else(|| missing("missing request"))? {
+            Request::BanIp(p) => admin::AdminRequest::BanIp(admin::BanIpPayload {
+                ip: ip_addr(&p.ip)?,
+                duration: duration(p.duration_secs),
+                country: country(p.country)?,
+            }),
+            Request::UnbanIp(p) => admin::AdminRequest::UnbanIp(admin::IpAddrPayload {
+                ip: ip_addr(&p.ip)?,
+            }),
+            Request::BanUser(p) => admin::AdminRequest::BanUser(admin::BanUserPayload {
+                id: user_id(p.id)?,
+                duration: duration(p.duration_secs),
+                country: country(p.country)?,
+            }),
+            Request::UnbanUser(p) => {
+                admin::AdminRequest::UnbanUser(admin::UserIdRefPayload { id: user_id(p.id)? })
+            }
+            Request::SetUserRole(p) => admin::AdminRequest::SetUserRole(auth::SetUserRolePayload {
+                id: user_id(p.id)?,
+                role: match Role::try_from(p.role) {
+                    Ok(Role::Admin) => responses::Role::Admin,
+                    Ok(Role::Moderator) => responses::Role::Moderator,
+                    Ok(Role::User) => responses::Role::User,
+                    _ => return Err(missing("missing role")),
+                },
+            }),
+            Request::ListBans(p) => admin::AdminRequest::ListBans(admin::ListBansPayload {
+                pagination: p.pagination.map(Into::into).unwrap_or_default(),
+                filter: p.filter.map(admin::BanFilter::try_from).transpose()?,
+            }),
+            Request::ListReports(p) => admin::AdminRequest::ListReports(p.into()),
+        };
+        Ok(request)
+    }
+}
+
+impl From<responses::Role> for Role {
+    fn from(role: responses::Role) -> Self {
+        match role {
+            responses::Role::Admin => Role::Admin,
+            responses::Role::Moderator => Role::Moderator,
+            responses::Role::User => Role::User,
+        }
+    }
+}
+
+impl From<payloads::PaginationPayload> for PaginationPayload {
+    fn from(p: payloads::PaginationPayload) -> Self {
+        PaginationPayload {
+            page: p.page,
+            per_page: p.per_page,
+        }
+    }
+}
+
+impl From<PaginationPayload> for payloads::PaginationPayload {
+    fn from(p: PaginationPayload) -> Self {
+        payloads::PaginationPayload {
+            page: p.page,
+            per_page: p.per_page,
+        }
+    }
+}
+
+impl From<admin::BanFilter> for BanFilter {
+    fn from(filter: admin::BanFilter) -> Self {
+        use self::ban_filter::Filter;
+        let filter = match filter {
+            admin::BanFilter::IpFamily(admin::IpFamily::V4) => {
+                Filter::IpFamily(IpFamily::V4 as i32)
+            }
+            admin::BanFilter::IpFamily(admin::IpFamily::V6) => {
+                Filter::IpFamily(IpFamily::V6 as i32)
+            }
+            admin::BanFilter::Kind(admin::BanKind::User) => Filter::Kind(BanKind::User as i32),
+            admin::BanFilter::Kind(admin::BanKind::Ip) => Filter::Kind(BanKind::Ip as i32),
+        };
+        BanFilter {
+            filter: Some(filter),
+        }
+    }
+}
+
+impl TryFrom<BanFilter> for admin::BanFilter {
+    type Error = Error;
+    fn try_from(message: BanFilter) -> Result<Self, Self::Error> {
+        use self::ban_filter::Filter;
+        let filter = match message.filter.ok_or_else(|| missing("missing filter"))? {
+            Filter::IpFamily(family) => match IpFamily::try_from(family) {
+                Ok(IpFamily::V4) => admin::BanFilter::IpFamily(admin::IpFamily::V4),
+                Ok(IpFamily::V6) => admin::BanFilter::IpFamily(admin::IpFamily::V6),
+                _ => return Err(missing("missing ip family")),
+            },
+            Filter::Kind(kind) => match BanKind::try_from(kind) {
+                Ok(BanKind::User) => admin::BanFilter::Kind(admin::BanKind::User),
+                Ok(BanKind::Ip) => admin::BanFilter::Kind(admin::BanKind::Ip),
+                _ => return Err(missing("missing ban kind")),
+            },
+        };
+        Ok(filter)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::payloads::EmptyPayloadStrict;
+    use prost::Message;
+
+    fn admin_requests() -> Vec<admin::AdminRequest> {
+        let pagination = payloads::PaginationPayload {
+            page: 2,
+            per_page: 50,
+        };
+        vec![
+            admin::AdminRequest::BanIp(admin::BanIpPayload {
+                ip: "2001:db8::1".parse().unwrap(),
+                duration: Some(HumanDuration::from_secs(7200)),
+                country: Some(CountryCode::try_from("NO").unwrap()),
+            }),
+            admin::AdminRequest::BanIp(admin::BanIpPayload {
+                ip: "10.0.0.1".parse().unwrap(),
+                duration: None,
+                country: None,
+            }),
+            admin::AdminRequest::UnbanIp(admin::IpAddrPayload {
+                ip: "10.0.0.1".parse().unwrap(),
+            }),
+            admin::AdminRequest::BanUser(admin::BanUserPayload {
+                id: UserId::from(1),
+                duration: Some(HumanDuration::from_secs(60)),
+                country: Some(CountryCode::try_from("SE").unwrap()),
+            }),
+            admin::AdminRequest::UnbanUser(admin::UserIdRefPayload {
+                id: UserId::from(1),
+            }),
+            admin::AdminRequest::SetUserRole(auth::SetUserRolePayload {
+                id: UserId::from(2),
+                role: responses::Role::Moderator,
+            }),
+            admin::AdminRequest::ListBans(admin::ListBansPayload {
+                pagination,
+                filter: Some(admin::BanFilter::IpFamily(admin::IpFamily::V6)),
+            }),
+            admin::AdminRequest::ListBans(admin::ListBansPayload {
+                pagination,
+                filter: Some(admin::BanFilter::Kind(admin::BanKind::User)),
+            }),
+            admin::AdminRequest::ListBans(admin::ListBansPayload {
+                pagination,
+                filter: None,
+            }),
+            admin::AdminRequest::ListReports(pagination),
+        ]
+    }
+
+    fn auth_requests() -> Vec<auth::AuthRequest> {
+        let username = || Username::try_from("john".to_owned()).unwrap();
+        let password = || PlainPassword::try_from("helloAndWelcome123".to_owned()).unwrap();
+        vec![
+            auth::AuthRequest::Authenticate(auth::AuthPayload {
+                username: username(),
+                password: password(),
+            }),
+            auth::AuthRequest::Deauthenticate(None),
+            auth::AuthRequest::RegisterUser(auth::RegisterUserPayload {
+                username: username(),
+                password: password(),
+                email: Email::try_from("john@example.com".to_owned()).unwrap(),
+            }),
+            auth::AuthRequest::SubmitTwoFactor(auth::TwoFactorPayload {
+                challenge: Token::new("challenge-token"),
+                code: TwoFactorCode::try_from("123456".to_owned()).unwrap(),
+            }),
+            auth::AuthRequest::WhoAmI(None),
+        ]
+    }
+
+    #[test]
+    fn admin_requests_round_trip() {
+        let mut tags = Vec::new();
+        for (request, expt) in admin_requests().into_iter().zip(admin_requests()) {
+            tags.push(serde_json::to_value(&expt).unwrap()["type"].clone());
+            let bytes = AdminRequest::from(request).encode_to_vec();
+            let message = AdminRequest::decode(&bytes[..]).unwrap();
+            assert_eq!(admin::AdminRequest::try_from(message).unwrap(), expt);
+        }
+        for tag in admin::AdminRequest::TAGS {
+            assert!(tags.contains(&serde_json::Value::from(*tag)), "{}", tag);
+        }
+    }
+
+    #[test]
+    fn auth_requests_round_trip() {
+        let requests = auth_requests();
+        assert_eq!(requests.len(), auth::AuthRequest::TAGS.len());
+        for request in requests {
+            let json = serde_json::to_value(&request).unwrap();
+            let bytes = AuthRequest::from(request).encode_to_vec();
+            let message = AuthRequest::decode(&bytes[..]).unwrap();
+            let request = auth::AuthRequest::try_from(message).unwrap();
+            assert_eq!(serde_json::to_value(&request).unwrap(), json);
+        }
+    }
+
+    #[test]
+    fn empty_payloads_are_the_same_request() {
+        let requests = vec![
+            auth::AuthRequest::Deauthenticate(Some(EmptyPayloadStrict {})),
+            auth::AuthRequest::WhoAmI(Some(EmptyPayloadStrict {})),
+        ];
+        for request in requests {
+            let bytes = AuthRequest::from(request).encode_to_vec();
+            let message = AuthRequest::decode(&bytes[..]).unwrap();
+            match auth::AuthRequest::try_from(message).unwrap() {
+                auth::AuthRequest::Deauthenticate(payload) | auth::AuthRequest::WhoAmI(payload) => {
+                    assert_eq!(payload, None)
+                }
+                request => panic!("unexpected request {:?}", request),
+            }
+        }
+    }
+
+    #[test]
+    fn token_admin_request_round_trip() {
+        let request = admin::AdminRequest::UnbanUser(admin::UserIdRefPayload {
+            id: UserId::from(7),
+        });
+        let request = TokenPayload::new(request, Token::new("admin-token"));
+        let message = TokenAdminRequest::from(request);
+        assert_eq!(message.token, "admin-token");
+
+        let (request, token) = admin::TokenAdminRequest::try_from(message)
+            .unwrap()
+            .into_inner();
+        assert_eq!(token, Token::new("admin-token"));
+        assert_eq!(
+            request,
+            admin::AdminRequest::UnbanUser(admin::UserIdRefPayload {
+                id: UserId::from(7)
+            })
+        );
+
+        let message = TokenAdminRequest {
+            token: String::new(),
+            request: Some(AdminRequest::default()),
+        };
+        assert_eq!(
+            admin::TokenAdminRequest::try_from(message).map(|_| ()),
+            Err(Error::MissingToken)
+        );
+    }
+
+    #[test]
+    fn zero_user_id_is_rejected() {
+        let message = AdminRequest {
+            request: Some(admin_request::Request::UnbanUser(UserIdRefPayload {
+                id: 0,
+            })),
+        };
+        assert_eq!(
+            admin::AdminRequest::try_from(message),
+            Err(Error::Validation(ValidationError::InvalidId))
+        );
+
+        let message = AdminRequest {
+            request: Some(admin_request::Request::BanUser(BanUserPayload::default())),
+        };
+        assert_eq!(
+            admin::AdminRequest::try_from(message),
+            Err(Error::Validation(ValidationError::InvalidId))
+        );
+    }
+
+    #[test]
+    fn defaults_which_are_not_valid_are_rejected() {
+        let message = AuthRequest {
+            request: Some(auth_request::Request::Authenticate(AuthPayload::default())),
+        };
+        let err = auth::AuthRequest::try_from(message).unwrap_err();
+        assert_eq!(err, Error::Validation(ValidationError::InvalidUsername));
+
+        let message = AdminRequest {
+            request: Some(admin_request::Request::UnbanIp(IpAddrPayload::default())),
+        };
+        let err = admin::AdminRequest::try_from(message).unwrap_err();
+        assert_eq!(err, Error::Validation(ValidationError::InvalidIpAddr));
+
+        let message = AdminRequest {
+            request: Some(admin_request::Request::SetUserRole(SetUserRolePayload {
+                id: 1,
+                role: Role::Unspecified as i32,
+            })),
+        };
+        assert!(admin::AdminRequest::try_from(message).is_err());
+
+        assert!(admin::AdminRequest::try_from(AdminRequest::default()).is_err());
+        assert!(auth::AuthRequest::try_from(AuthRequest::default()).is_err());
+    }
+
+    #[test]
+    fn missing_pagination_is_the_first_page() {
+        let message = AdminRequest {
+            request: Some(admin_request::Request::ListBans(ListBansPayload::default())),
+        };
+        let expt = admin::AdminRequest::ListBans(admin::ListBansPayload {
+            pagination: payloads::PaginationPayload::default(),
+            filter: None,
+        });
+        assert_eq!(admin::AdminRequest::try_from(message).unwrap(), expt);
+    }
+
+    #[test]
+    fn proto_file_declares_every_message() {
+        for message in &[
+            "AuthRequest",
+            "AdminRequest",
+            "TokenAdminRequest",
+            "BanFilter",
+        ] {
+            assert!(
+                PROTO.contains(&format!("message {} {{", message)),
+                "{}",
+                message
+            );
+        }
+    }
+}
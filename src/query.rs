@@ -0,0 +1,170 @@
+//! Decoding of payloads from url query strings, for the listings which are
+//! requested with a GET
+//!
+//! A query string is a flat list of keys, so only the payloads without
+//! nested objects can be decoded (like `PaginationPayload`, `SearchPayload`
+//! and `GetThreadsPayload`, but not `GetCommentsPayload`). Every value is a
+//! string which is parsed as the type of its field: a number or a boolean
+//! (`true`/`false`) is parsed, a enum is its wire name (like `TOP_VOTED`) and
+//! a key which is left out is the default of its field. The fields are
+//! validated just as when they are deserialized from json, and a field which
+//! is wrong is named by its key (like `parse_with_path`).
+//!
+//! # Example usage
+//!
+//! ```
+//! # use datatypes::query::from_query_str;
+//! # use datatypes::payloads::PaginationPayload;
+//! let payload: PaginationPayload = from_query_str("page=2&per_page=50").unwrap();
+//! assert_eq!(payload, PaginationPayload { page: 2, per_page: 50 });
+//! ```
+
+use crate::error::PathError;
+use serde::de::DeserializeOwned;
+
+/// Decode a `T` from a url query string (without the leading `?`)
+///
+/// Checks which span several fields (like `GetThreadsPayload::validate`) are
+/// not part of deserializing, and are still left to the caller.
+pub fn from_query_str<T>(query: &str) -> Result<T, PathError>
+where
+    T: DeserializeOwned,
+{
+    let parts = form_urlencoded::parse(query.as_bytes());
+    let deserializer = serde_urlencoded::Deserializer::new(parts);
+    serde_path_to_error::deserialize(deserializer).map_err(|e| PathError {
+        path: e.path().to_string(),
+        message: e.into_inner().to_string(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::content::requests::{GetThreadsPayload, SearchPayload, SortOrder};
+    use crate::payloads::{Cursor, PaginationPayload, DEFAULT_PER_PAGE};
+    use crate::valid::fields::QueryStr;
+    use crate::valid::ids::{CategoryId, UserId};
+    use crate::valid::ValidationError;
+    use std::convert::TryFrom;
+
+    #[test]
+    fn pagination() {
+        let payload: PaginationPayload = from_query_str("page=2&per_page=50").unwrap();
+        assert_eq!(
+            payload,
+            PaginationPayload {
+                page: 2,
+                per_page: 50
+            }
+        );
+
+        let payload: PaginationPayload = from_query_str("page=3").unwrap();
+        assert_eq!(payload.per_page, DEFAULT_PER_PAGE);
+        let payload: PaginationPayload = from_query_str("").unwrap();
+        assert_eq!(payload, PaginationPayload::default());
+    }
+
+    #[test]
+    fn pagination_with_invalid_per_page() {
+        for query in &[
+            "per_page=many",
+            "per_page=-5",
+            "per_page=",
+            "per_page=4294967296",
+        ] {
+            let e = from_query_str::<PaginationPayload>(query).unwrap_err();
+            assert_eq!(e.path, "per_page", "{}", query);
+        }
+    }
+
+    #[test]
+    fn search() {
+        let query = "query=rust+lang&sort=TOP_VOTED&author=7";
+        let payload: SearchPayload = from_query_str(query).unwrap();
+        let expt = SearchPayload {
+            query: QueryStr::try_from("rust lang".to_owned()).unwrap(),
+            include_hidden: false,
+            sort: SortOrder::TopVoted,
+            author: Some(UserId::from(7)),
+        };
+        assert_eq!(payload, expt);
+
+        let payload: SearchPayload = from_query_str("query=rust&include_hidden=true").unwrap();
+        assert!(payload.include_hidden);
+        assert_eq!(payload.sort, SortOrder::default());
+        assert_eq!(payload.author, None);
+    }
+
+    #[test]
+    fn search_is_validated() {
+        let rejected = &[
+            "query=r",
+            "query=rust&include_hidden=yes",
+            "query=rust&sort=top_voted",
+            "include_hidden=false",
+        ];
+        for query in rejected {
+            assert!(from_query_str::<SearchPayload>(query).is_err(), "{}", query);
+        }
+    }
+
+    #[test]
+    fn invalid_query_keeps_its_error() {
+        let e = from_query_str::<SearchPayload>("query=r").unwrap_err();
+        assert_eq!(e.path, "query");
+        assert_eq!(e.message, ValidationError::InvalidQuery.to_string());
+
+        let query = "id=1&include_hidden=false&after=not+a+cursor";
+        let e = from_query_str::<GetThreadsPayload>(query).unwrap_err();
+        assert_eq!(e.path, "after");
+        assert_eq!(e.message, ValidationError::InvalidCursor.to_string());
+    }
+
+    #[test]
+    fn sort_orders() {
+        for sort in SortOrder::all() {
+            let name = serde_json::to_value(sort).unwrap();
+            let query = format!("query=rust&sort={}", name.as_str().unwrap());
+            let payload: SearchPayload = from_query_str(&query).unwrap();
+            assert_eq!(payload.sort, *sort);
+        }
+    }
+
+    #[test]
+    fn list_threads() {
+        let query = "id=1&include_hidden=true&sort=RECENTLY_ACTIVE&page=2&per_page=10";
+        let payload: GetThreadsPayload = from_query_str(query).unwrap();
+        assert_eq!(payload.id, CategoryId::from(1));
+        assert!(payload.include_hidden);
+        assert_eq!(payload.sort, SortOrder::RecentlyActive);
+        assert_eq!((payload.page, payload.per_page), (Some(2), Some(10)));
+        assert_eq!(payload.validate(), Ok(()));
+
+        let payload: GetThreadsPayload = from_query_str("id=1&include_hidden=false").unwrap();
+        assert_eq!(payload.sort, SortOrder::default());
+        assert_eq!(
+            (payload.author, payload.page, payload.after),
+            (None, None, None)
+        );
+    }
+
+    #[test]
+    fn list_threads_is_validated() {
+        let query = "id=1&include_hidden=false&after=YWJj";
+        let payload: GetThreadsPayload = from_query_str(query).unwrap();
+        assert_eq!(
+            payload.after,
+            Some(Cursor::try_from("YWJj".to_owned()).unwrap())
+        );
+
+        let query = "id=1&include_hidden=false&after=not+a+cursor";
+        assert!(from_query_str::<GetThreadsPayload>(query).is_err());
+        let query = "id=1&include_hidden=false&per_page=ten";
+        assert!(from_query_str::<GetThreadsPayload>(query).is_err());
+
+        let query = "id=1&include_hidden=false&per_page=5&after=YWJj";
+        let payload: GetThreadsPayload = from_query_str(query).unwrap();
+        assert_eq!(payload.validate(), Err(ValidationError::InvalidPagination));
+    }
+}
@@ -0,0 +1,118 @@
+//! The buckets requests are rate limited in
+
+use crate::valid::ids::UserId;
+use std::fmt::{self, Display};
+use std::net::IpAddr;
+
+/// The bucket of a rate limit
+///
+/// The `Display` of a key is stable, so it can be used as the key of the
+/// bucket in a cache: `user:42`, `ip:10.0.0.1` (or `ip:2001:db8::1`) and
+/// `global`.
+#[derive(PartialEq, Eq, Hash, Clone, Copy, Debug)]
+pub enum RateLimitKey {
+    /// The requests of a authenticated user
+    User(UserId),
+    /// The requests from a IP address
+    Ip(IpAddr),
+    /// Every request
+    Global,
+}
+
+impl RateLimitKey {
+    /// The key of a request from `ip`, made by `user` if it is authenticated
+    ///
+    /// A authenticated user is limited by its id, wherever it makes its
+    /// requests from, while other requests are limited by their IP address.
+    pub fn for_client(user: Option<UserId>, ip: IpAddr) -> Self {
+        match user {
+            Some(user) => RateLimitKey::User(user),
+            None => RateLimitKey::for_ip(ip),
+        }
+    }
+
+    /// The key of the requests from `ip`
+    ///
+    /// A IPv4-mapped IPv6 address (like `::ffff:10.0.0.1`) is the IPv4
+    /// address it maps, so both forms of a address share a bucket.
+    pub fn for_ip(ip: IpAddr) -> Self {
+        let ip = match ip {
+            IpAddr::V6(v6) if v6.segments()[..6] == [0, 0, 0, 0, 0, 0xffff] => {
+                v6.to_ipv4().map_or(IpAddr::V6(v6), IpAddr::V4)
+            }
+            ip => ip,
+        };
+        RateLimitKey::Ip(ip)
+    }
+}
+
+impl Display for RateLimitKey {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            RateLimitKey::User(id) => write!(f, "user:{}", id),
+            RateLimitKey::Ip(ip) => write!(f, "ip:{}", ip),
+            RateLimitKey::Global => write!(f, "global"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashSet;
+
+    #[test]
+    fn stable_keys() {
+        let keys = vec![
+            (RateLimitKey::User(UserId::from(42)), "user:42"),
+            (RateLimitKey::Ip("10.0.0.1".parse().unwrap()), "ip:10.0.0.1"),
+            (
+                RateLimitKey::Ip("2001:db8::1".parse().unwrap()),
+                "ip:2001:db8::1",
+            ),
+            (RateLimitKey::Global, "global"),
+        ];
+        for (key, expected) in keys {
+            assert_eq!(key.to_string(), expected);
+        }
+    }
+
+    #[test]
+    fn distinct_keys() {
+        // A user id and a IP address which look alike don't share a bucket
+        let keys = vec![
+            RateLimitKey::User(UserId::from(1)),
+            RateLimitKey::User(UserId::from(2)),
+            RateLimitKey::Ip("0.0.0.1".parse().unwrap()),
+            RateLimitKey::Ip("::1".parse().unwrap()),
+            RateLimitKey::Global,
+        ];
+        let strings: HashSet<_> = keys.iter().map(RateLimitKey::to_string).collect();
+        assert_eq!(strings.len(), keys.len());
+    }
+
+    #[test]
+    fn key_for_client() {
+        let ip: IpAddr = "10.0.0.1".parse().unwrap();
+        assert_eq!(
+            RateLimitKey::for_client(Some(UserId::from(3)), ip),
+            RateLimitKey::User(UserId::from(3))
+        );
+        assert_eq!(RateLimitKey::for_client(None, ip), RateLimitKey::Ip(ip));
+    }
+
+    #[test]
+    fn mapped_addresses_share_a_bucket() {
+        let v4: IpAddr = "10.0.0.1".parse().unwrap();
+        let mapped: IpAddr = "::ffff:10.0.0.1".parse().unwrap();
+        assert_eq!(RateLimitKey::for_ip(mapped), RateLimitKey::Ip(v4));
+        assert_eq!(RateLimitKey::for_client(None, mapped), RateLimitKey::Ip(v4));
+        assert_eq!(RateLimitKey::for_ip(mapped).to_string(), "ip:10.0.0.1");
+
+        // Only a mapped address is a IPv4 address
+        for ip in &["::1", "::10.0.0.1", "2001:db8::1"] {
+            let ip: IpAddr = ip.parse().unwrap();
+            assert_eq!(RateLimitKey::for_ip(ip), RateLimitKey::Ip(ip));
+        }
+    }
+}
@@ -0,0 +1,184 @@
+//! `ToRedisArgs` and `FromRedisValue` of the session types
+//!
+//! A `Token` is stored as the token itself (never as its `Debug`), and read
+//! back as it is, so every token which is stored can be read again. A
+//! `HexDigest` (like the digest of a session token) is stored as its lowercase
+//! hex string. A `UserIdPayload` is stored as json, the same as it is sent
+//! over the wire, so a session can be read by the tools which only know json.
+//!
+//! Every other value is validated when it is read, a value which isn't valid
+//! is a `TypeError`.
+
+use crate::payloads::UserIdPayload;
+use crate::valid::attachment::HexDigest;
+use crate::valid::ids::UserId;
+use crate::valid::token::Token;
+use redis::{ErrorKind, FromRedisValue, RedisError, RedisResult, RedisWrite, ToRedisArgs, Value};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use std::convert::TryFrom;
+
+/// The key of the session of a user, which is `session:<user id>:<digest>`
+///
+/// The key is stable, and contains the digest of the session's token rather
+/// than the token itself.
+///
+/// # Examples
+///
+/// ```
+/// # use datatypes::redis_ext::session_key;
+/// # use datatypes::valid::attachment::HexDigest;
+/// # use datatypes::valid::ids::UserId;
+/// # use std::convert::TryFrom;
+/// let digest = HexDigest::try_from("ab".repeat(32)).unwrap();
+/// let key = session_key(UserId::from(42), &digest);
+/// assert_eq!(key, format!("session:42:{}", "ab".repeat(32)));
+/// ```
+pub fn session_key(user: UserId, session: &HexDigest) -> String {
+    format!("session:{}:{}", user, session)
+}
+
+/// A error of a stored value which isn't valid
+fn invalid(what: &'static str, detail: impl ToString) -> RedisError {
+    RedisError::from((ErrorKind::TypeError, what, detail.to_string()))
+}
+
+impl ToRedisArgs for Token {
+    fn write_redis_args<W>(&self, out: &mut W)
+    where
+        W: ?Sized + RedisWrite,
+    {
+        out.write_arg(self.as_ref().as_bytes())
+    }
+}
+
+impl FromRedisValue for Token {
+    fn from_redis_value(v: &Value) -> RedisResult<Self> {
+        String::from_redis_value(v).map(Token::new)
+    }
+}
+
+impl ToRedisArgs for HexDigest {
+    fn write_redis_args<W>(&self, out: &mut W)
+    where
+        W: ?Sized + RedisWrite,
+    {
+        out.write_arg(self.as_bytes())
+    }
+}
+
+impl FromRedisValue for HexDigest {
+    fn from_redis_value(v: &Value) -> RedisResult<Self> {
+        let s = String::from_redis_value(v)?;
+        HexDigest::try_from(s).map_err(|e| invalid("invalid digest", e))
+    }
+}
+
+/// A payload which can't be written as json (like a inner type which is not
+/// a struct or map, as it is flattened) is logged and written as a empty
+/// value, which is never read back as a payload
+impl<Inner: Serialize> ToRedisArgs for UserIdPayload<Inner> {
+    fn write_redis_args<W>(&self, out: &mut W)
+    where
+        W: ?Sized + RedisWrite,
+    {
+        match serde_json::to_vec(self) {
+            Ok(json) => out.write_arg(&json),
+            Err(e) => {
+                log::error!("session payload can't be serialized as json: {}", e);
+                out.write_arg(b"")
+            }
+        }
+    }
+}
+
+impl<Inner: DeserializeOwned> FromRedisValue for UserIdPayload<Inner> {
+    fn from_redis_value(v: &Value) -> RedisResult<Self> {
+        let bytes = Vec::<u8>::from_redis_value(v)?;
+        serde_json::from_slice(&bytes).map_err(|e| invalid("invalid payload", e))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Serialize, Deserialize, PartialEq, Debug)]
+    struct SessionInfo {
+        ip: String,
+    }
+
+    fn stored(value: &impl ToRedisArgs) -> Value {
+        let mut args = value.to_redis_args();
+        assert_eq!(args.len(), 1);
+        Value::Data(args.remove(0))
+    }
+
+    #[test]
+    fn token_is_stored_as_itself() {
+        let token = Token::new("c2VjcmV0==");
+        assert_eq!(stored(&token), Value::Data(b"c2VjcmV0==".to_vec()));
+        assert_eq!(Token::from_redis_value(&stored(&token)).unwrap(), token);
+    }
+
+    #[test]
+    fn token_round_trip() {
+        for s in &["c2VjcmV0==", "a-b_c.d~e+f/g", "a b", "ünicode", ""] {
+            let token = Token::new(*s);
+            let read = Token::from_redis_value(&stored(&token)).unwrap();
+            assert_eq!(read, token, "{:?}", s);
+        }
+    }
+
+    #[test]
+    fn missing_token_is_rejected() {
+        let e = Token::from_redis_value(&Value::Nil).unwrap_err();
+        assert_eq!(e.kind(), ErrorKind::TypeError);
+        let v = Value::Data(vec![0xff, 0xfe]);
+        assert!(Token::from_redis_value(&v).is_err());
+    }
+
+    #[test]
+    fn digest_round_trip() {
+        let digest = HexDigest::try_from("AB".repeat(32)).unwrap();
+        assert_eq!(stored(&digest), Value::Data("ab".repeat(32).into_bytes()));
+        let read = HexDigest::from_redis_value(&stored(&digest)).unwrap();
+        assert_eq!(read, digest);
+
+        let v = Value::Data(b"not a digest".to_vec());
+        assert!(HexDigest::from_redis_value(&v).is_err());
+    }
+
+    #[test]
+    fn session_payload_is_stored_as_json() {
+        let info = SessionInfo {
+            ip: String::from("10.0.0.1"),
+        };
+        let payload = UserIdPayload::new(info, UserId::from(42));
+        let v = stored(&payload);
+        assert_eq!(v, Value::Data(br#"{"id":42,"ip":"10.0.0.1"}"#.to_vec()));
+        let read: UserIdPayload<SessionInfo> = FromRedisValue::from_redis_value(&v).unwrap();
+        assert_eq!(read, payload);
+
+        let v = Value::Data(br#"{"id":-1,"ip":"10.0.0.1"}"#.to_vec());
+        let e = UserIdPayload::<SessionInfo>::from_redis_value(&v).unwrap_err();
+        assert_eq!(e.kind(), ErrorKind::TypeError);
+    }
+
+    #[test]
+    fn unserializable_payload_is_never_read_back() {
+        // A string can't be flattened into the payload
+        let payload = UserIdPayload::<&str>::new("10.0.0.1", UserId::from(42));
+        let v = stored(&payload);
+        assert_eq!(v, Value::Data(Vec::new()));
+        let e = UserIdPayload::<SessionInfo>::from_redis_value(&v).unwrap_err();
+        assert_eq!(e.kind(), ErrorKind::TypeError);
+    }
+
+    #[test]
+    fn stable_session_key() {
+        let digest = HexDigest::try_from("0f".repeat(32)).unwrap();
+        let key = session_key(UserId::from(7), &digest);
+        assert_eq!(key, format!("session:7:{}", "0f".repeat(32)));
+    }
+}
@@ -0,0 +1,172 @@
+//! The kinds of requests, by their type tag
+//!
+//! A dispatcher which gets the type of a request apart from its payload (like
+//! the method name in the header of a WebSocket frame) can find the kind of
+//! the request without parsing the whole request.
+//!
+//! The kinds are the `TAGS` of `AuthRequest`, `AdminRequest` and
+//! `ContentRequest` in that order, and the aliases are the `LEGACY_TAGS`, which
+//! the tests check.
+
+use crate::error::{ParseError, ParseErrorKind};
+use std::fmt::{self, Display};
+use std::str::FromStr;
+
+/// Declares `RequestKind` with the type tag (and the aliases of the tag) of
+/// every variant
+macro_rules! request_kinds {
+    ($($kind:ident => $tag:expr $(, alias = $alias:expr)*;)*) => {
+        /// The kind of a request of any of the domains (auth, admin and
+        /// content)
+        ///
+        /// The `Display` of a kind is its type tag, like `"AUTHENTICATE"`, and
+        /// `FromStr` parses the type tag (or one of its aliases) back.
+        #[derive(PartialEq, Eq, Hash, Clone, Copy, Debug)]
+        pub enum RequestKind {
+            $($kind,)*
+        }
+
+        impl RequestKind {
+            /// Every kind of request
+            pub const ALL: &'static [RequestKind] = &[$(RequestKind::$kind,)*];
+
+            /// The type tag of the request
+            pub fn tag(self) -> &'static str {
+                match self {
+                    $(RequestKind::$kind => $tag,)*
+                }
+            }
+        }
+
+        impl FromStr for RequestKind {
+            type Err = ParseError;
+            fn from_str(s: &str) -> Result<Self, Self::Err> {
+                match s {
+                    $($tag $(| $alias)* => Ok(RequestKind::$kind),)*
+                    _ => Err(ParseError {
+                        request_type: Some(s.to_owned()),
+                        field: None,
+                        kind: ParseErrorKind::UnknownTag {
+                            expected: RequestKind::ALL.iter().map(|k| k.to_string()).collect(),
+                        },
+                    }),
+                }
+            }
+        }
+    };
+}
+
+request_kinds! {
+    Authenticate => "AUTHENTICATE";
+    Deauthenticate => "DEAUTHENTICATE";
+    RegisterUser => "REGISTER", alias = "REGISTER_USER";
+    SubmitTwoFactor => "SUBMIT_TWO_FACTOR";
+    WhoAmI => "WHO_AM_I";
+
+    BanIp => "BAN_IP";
+    UnbanIp => "UNBAN_IP";
+    BanUser => "BAN_USER";
+    UnbanUser => "UNBAN_USER";
+    SetUserRole => "SET_USER_ROLE";
+    ListBans => "LIST_BANS";
+    ListReports => "LIST_REPORTS";
+
+    AddUser => "ADD_USER";
+    EditUser => "EDIT_USER";
+    UpdateProfile => "UPDATE_PROFILE";
+    AddCategory => "ADD_CATEGORY";
+    EditCategory => "EDIT_CATEGORY";
+    HideCategory => "HIDE_CATEGORY";
+    AddThread => "ADD_THREAD";
+    EditThread => "EDIT_THREAD";
+    HideThread => "HIDE_THREAD";
+    SetThreadPinned => "SET_THREAD_PINNED";
+    AddComment => "ADD_COMMENT";
+    EditComment => "EDIT_COMMENT";
+    HideComment => "HIDE_COMMENT";
+    AttachToComment => "ATTACH_TO_COMMENT";
+    React => "REACT";
+    RemoveReaction => "REMOVE_REACTION";
+    Report => "REPORT";
+    BulkDelete => "BULK_DELETE";
+    Subscribe => "SUBSCRIBE";
+    Unsubscribe => "UNSUBSCRIBE";
+    ListSubscriptions => "LIST_SUBSCRIPTIONS";
+    Bookmark => "BOOKMARK";
+    Unbookmark => "UNBOOKMARK";
+    ListBookmarks => "LIST_BOOKMARKS";
+    SaveDraft => "SAVE_DRAFT";
+    ListDrafts => "LIST_DRAFTS";
+    DeleteDraft => "DELETE_DRAFT";
+    GetEditHistory => "GET_EDIT_HISTORY";
+    CreatePoll => "CREATE_POLL";
+    Vote => "VOTE";
+    FetchThread => "FETCH_THREAD";
+}
+
+impl Display for RequestKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.tag())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::admin::requests::AdminRequest;
+    use crate::auth::requests::{AuthRequest, LEGACY_TAGS};
+    use crate::content::requests::ContentRequest;
+
+    #[test]
+    fn parse_known_tags() {
+        let tags = AuthRequest::TAGS
+            .iter()
+            .chain(AdminRequest::TAGS)
+            .chain(ContentRequest::TAGS);
+        let mut count = 0;
+        for tag in tags {
+            let kind: RequestKind = tag.parse().unwrap();
+            assert_eq!(kind.to_string(), *tag);
+            count += 1;
+        }
+        assert_eq!(count, RequestKind::ALL.len());
+    }
+
+    #[test]
+    fn kinds_are_the_tags_of_the_requests() {
+        let tags: Vec<&str> = AuthRequest::TAGS
+            .iter()
+            .chain(AdminRequest::TAGS)
+            .chain(ContentRequest::TAGS)
+            .cloned()
+            .collect();
+        let kinds: Vec<&str> = RequestKind::ALL.iter().map(|k| k.tag()).collect();
+        assert_eq!(kinds, tags);
+
+        for (old, current) in LEGACY_TAGS {
+            let kind: RequestKind = old.parse().unwrap();
+            assert_eq!(kind.tag(), *current, "{}", old);
+        }
+    }
+
+    #[test]
+    fn parse_tag_alias() {
+        let kind: RequestKind = "REGISTER_USER".parse().unwrap();
+        assert_eq!(kind, RequestKind::RegisterUser);
+        assert_eq!(kind.to_string(), "REGISTER");
+    }
+
+    #[test]
+    fn parse_unknown_tag() {
+        for s in &["", "authenticate", "UNKNOWN"] {
+            let e = s.parse::<RequestKind>().unwrap_err();
+            assert_eq!(e.request_type.as_ref().map(String::as_str), Some(*s));
+            match e.kind {
+                ParseErrorKind::UnknownTag { expected } => {
+                    assert_eq!(expected.len(), RequestKind::ALL.len())
+                }
+                kind => panic!("{:?}", kind),
+            }
+        }
+    }
+}
@@ -0,0 +1,102 @@
+//! The schemas of the validated strings, shared by the JSON schemas of
+//! schemars and the OpenAPI schemas of utoipa
+//!
+//! The limits of a validated string are only written once (see
+//! `impl_schema!`), and are turned into the schema of whichever library asks
+//! for it.
+
+/// The schema of a validated string, see `impl_schema!`
+#[derive(Default)]
+pub(crate) struct StringSchema {
+    min_length: Option<usize>,
+    max_length: Option<usize>,
+    pattern: Option<String>,
+    format: Option<String>,
+    values: Option<Vec<&'static str>>,
+    write_only: bool,
+}
+
+/// A string without any limits, which are added with the methods of
+/// `StringSchema`
+pub(crate) fn string() -> StringSchema {
+    StringSchema::default()
+}
+
+impl StringSchema {
+    pub fn min_length(mut self, len: usize) -> Self {
+        self.min_length = Some(len);
+        self
+    }
+
+    pub fn max_length(mut self, len: usize) -> Self {
+        self.max_length = Some(len);
+        self
+    }
+
+    pub fn length(self, min: usize, max: usize) -> Self {
+        self.min_length(min).max_length(max)
+    }
+
+    pub fn pattern(mut self, pattern: &str) -> Self {
+        self.pattern = Some(pattern.to_owned());
+        self
+    }
+
+    pub fn format(mut self, format: &str) -> Self {
+        self.format = Some(format.to_owned());
+        self
+    }
+
+    pub fn values(mut self, values: &[&'static str]) -> Self {
+        self.values = Some(values.to_vec());
+        self
+    }
+
+    /// The string is only sent by a client, and never sent back to one (which
+    /// is only marked in the OpenAPI schema)
+    pub fn write_only(mut self) -> Self {
+        self.write_only = true;
+        self
+    }
+}
+
+#[cfg(feature = "schemars")]
+impl From<StringSchema> for schemars::schema::Schema {
+    fn from(s: StringSchema) -> Self {
+        use schemars::schema::{InstanceType, Schema, SchemaObject};
+
+        let mut schema = SchemaObject {
+            instance_type: Some(InstanceType::String.into()),
+            format: s.format,
+            enum_values: s
+                .values
+                .map(|values| values.into_iter().map(serde_json::Value::from).collect()),
+            ..Default::default()
+        };
+        if s.min_length.is_some() || s.max_length.is_some() || s.pattern.is_some() {
+            let string = schema.string();
+            string.min_length = s.min_length.map(|len| len as u32);
+            string.max_length = s.max_length.map(|len| len as u32);
+            string.pattern = s.pattern;
+        }
+        Schema::Object(schema)
+    }
+}
+
+#[cfg(feature = "utoipa")]
+impl From<StringSchema> for utoipa::openapi::RefOr<utoipa::openapi::schema::Schema> {
+    fn from(s: StringSchema) -> Self {
+        use utoipa::openapi::schema::{ObjectBuilder, Schema, SchemaFormat, Type};
+        use utoipa::openapi::RefOr;
+
+        let schema = ObjectBuilder::new()
+            .schema_type(Type::String)
+            .min_length(s.min_length)
+            .max_length(s.max_length)
+            .pattern(s.pattern)
+            .format(s.format.map(SchemaFormat::Custom))
+            .enum_values(s.values)
+            .write_only(if s.write_only { Some(true) } else { None });
+        RefOr::T(Schema::Object(schema.build()))
+    }
+}
@@ -0,0 +1,231 @@
+//! Reading the token of a session from a cookie in rocket
+//!
+//! A handler takes a `SessionToken` as a request guard, and wraps the payload
+//! of the request with it (see `with_session`) before it is sent on to the
+//! services.
+//!
+//! The token is read from a plain cookie, the same as the extractors of actix
+//! and axum read: `DEFAULT_SESSION_COOKIE`, unless a `SessionCookie` with
+//! another name is managed by rocket. So a session which is set by one of the
+//! stacks is read by the others as well. (The private `USER_TOKEN_NAME` cookie
+//! of the `Token` guard is only readable by rocket, and is not a session.)
+//!
+//! A request without the cookie (or with a cookie which is not a valid token)
+//! fails with `401 Unauthorized` and a `SessionError`, which converts into
+//! `ResponseError::Unauthenticated`.
+
+use crate::error::ResponseError;
+use crate::payloads::TokenPayload;
+use crate::valid::token::{Token, TokenParseError, DEFAULT_SESSION_COOKIE};
+use rocket::http::Status;
+use rocket::request::{FromRequest, Outcome, Request};
+use rocket::State;
+use std::ops::Deref;
+
+/// The name of the cookie which contains the token of a session
+///
+/// # Example usage
+///
+/// ```
+/// # use datatypes::session::SessionCookie;
+/// let rocket = rocket::ignite().manage(SessionCookie::new("sid"));
+/// ```
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+pub struct SessionCookie(&'static str);
+
+impl SessionCookie {
+    pub fn new(name: &'static str) -> Self {
+        SessionCookie(name)
+    }
+
+    /// The name of the cookie
+    pub fn name(&self) -> &'static str {
+        self.0
+    }
+}
+
+impl Default for SessionCookie {
+    fn default() -> Self {
+        SessionCookie(DEFAULT_SESSION_COOKIE)
+    }
+}
+
+/// Why the token of a session could not be read from a request
+#[derive(Fail, PartialEq, Eq, Clone, Copy, Debug)]
+pub enum SessionError {
+    #[fail(display = "missing session cookie")]
+    Missing,
+    #[fail(display = "malformed session cookie")]
+    Malformed(TokenParseError),
+}
+
+impl From<SessionError> for ResponseError {
+    fn from(_: SessionError) -> Self {
+        ResponseError::Unauthenticated
+    }
+}
+
+/// The token of a session, read from the session cookie of a request
+///
+/// The cookie is read as a plain cookie, the token must only contain the
+/// characters of a `b64token`.
+#[derive(PartialEq, Eq, Clone, Debug)]
+pub struct SessionToken(Token);
+
+impl SessionToken {
+    /// Turn the session into its token
+    pub fn into_token(self) -> Token {
+        self.0
+    }
+}
+
+impl Deref for SessionToken {
+    type Target = Token;
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl From<SessionToken> for Token {
+    fn from(session: SessionToken) -> Self {
+        session.0
+    }
+}
+
+impl<'a, 'r> FromRequest<'a, 'r> for SessionToken {
+    type Error = SessionError;
+
+    fn from_request(req: &'a Request<'r>) -> Outcome<Self, Self::Error> {
+        let name = match req.guard::<State<SessionCookie>>() {
+            rocket::Outcome::Success(cookie) => cookie.name(),
+            _ => DEFAULT_SESSION_COOKIE,
+        };
+        let value = req.cookies().get(name).map(|c| c.value().to_owned());
+        let result = value
+            .ok_or(SessionError::Missing)
+            .and_then(|value| Token::from_b64token(&value).map_err(SessionError::Malformed));
+        match result {
+            Ok(token) => rocket::Outcome::Success(SessionToken(token)),
+            Err(e) => rocket::Outcome::Failure((Status::Unauthorized, e)),
+        }
+    }
+}
+
+/// Wrap the payload of a request with the token of its session
+///
+/// # Example usage
+///
+/// ```
+/// # use datatypes::content::requests::ThreadRefPayload;
+/// # use datatypes::payloads::TokenPayload;
+/// # use datatypes::session::{with_session, SessionToken};
+/// type Subscribe = TokenPayload<ThreadRefPayload>;
+///
+/// // The body of a `POST /subscriptions` handler
+/// fn subscribe(session: SessionToken, thread: ThreadRefPayload) -> Subscribe {
+///     with_session(session, thread)
+/// }
+/// ```
+pub fn with_session<T>(session: SessionToken, inner: T) -> TokenPayload<T> {
+    TokenPayload::new(inner, session.0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::valid::token::USER_TOKEN_NAME;
+    use rocket::handler;
+    use rocket::http::{Cookie, Method};
+    use rocket::local::Client;
+    use rocket::{Data, Route};
+
+    /// Answers with the token of the session, or fails like the guard
+    fn session<'r>(req: &'r Request, _: Data) -> handler::Outcome<'r> {
+        match req.guard::<SessionToken>() {
+            rocket::Outcome::Success(session) => {
+                let token = session.as_ref().to_owned();
+                handler::Outcome::from(req, token)
+            }
+            rocket::Outcome::Failure((status, _)) => handler::Outcome::Failure(status),
+            rocket::Outcome::Forward(_) => handler::Outcome::Failure(Status::InternalServerError),
+        }
+    }
+
+    fn client(cookie: Option<SessionCookie>) -> Client {
+        let route = Route::new(Method::Get, "/", session);
+        let rocket = rocket::ignite().mount("/", vec![route]);
+        let rocket = match cookie {
+            Some(cookie) => rocket.manage(cookie),
+            None => rocket,
+        };
+        Client::new(rocket).expect("valid rocket")
+    }
+
+    #[test]
+    fn session_cookie() {
+        let client = client(None);
+        let cookie = Cookie::new(DEFAULT_SESSION_COOKIE, "c2VjcmV0");
+        let mut response = client.get("/").cookie(cookie).dispatch();
+        assert_eq!(response.status(), Status::Ok);
+        assert_eq!(response.body_string(), Some("c2VjcmV0".to_owned()));
+    }
+
+    #[test]
+    fn configured_session_cookie() {
+        let client = client(Some(SessionCookie::new("sid")));
+        let cookie = Cookie::new("sid", "c2VjcmV0");
+        let mut response = client.get("/").cookie(cookie).dispatch();
+        assert_eq!(response.status(), Status::Ok);
+        assert_eq!(response.body_string(), Some("c2VjcmV0".to_owned()));
+
+        // The default cookie is not read when another one is configured
+        let cookie = Cookie::new(DEFAULT_SESSION_COOKIE, "c2VjcmV0");
+        let response = client.get("/").cookie(cookie).dispatch();
+        assert_eq!(response.status(), Status::Unauthorized);
+    }
+
+    #[test]
+    fn missing_session_cookie() {
+        let client = client(None);
+        let response = client.get("/").dispatch();
+        assert_eq!(response.status(), Status::Unauthorized);
+        let cookie = Cookie::new("other", "c2VjcmV0");
+        let response = client.get("/").cookie(cookie).dispatch();
+        assert_eq!(response.status(), Status::Unauthorized);
+    }
+
+    #[test]
+    fn private_token_cookie_is_not_a_session() {
+        let client = client(None);
+        let cookie = Cookie::new(USER_TOKEN_NAME, "c2VjcmV0");
+        let response = client.get("/").private_cookie(cookie).dispatch();
+        assert_eq!(response.status(), Status::Unauthorized);
+    }
+
+    #[test]
+    fn garbage_session_cookie() {
+        let client = client(None);
+        for value in &["", "not!a!token", "{}"] {
+            let cookie = Cookie::new(DEFAULT_SESSION_COOKIE, *value);
+            let response = client.get("/").cookie(cookie).dispatch();
+            assert_eq!(response.status(), Status::Unauthorized, "{}", value);
+        }
+    }
+
+    #[test]
+    fn session_error_is_unauthenticated() {
+        let e = SessionError::Malformed(TokenParseError::InvalidToken);
+        assert_eq!(e.to_string(), "malformed session cookie");
+        assert_eq!(ResponseError::from(e), ResponseError::Unauthenticated);
+        let e = SessionError::Missing;
+        assert_eq!(ResponseError::from(e), ResponseError::Unauthenticated);
+    }
+
+    #[test]
+    fn payload_with_session() {
+        let session = SessionToken(Token::new("c2VjcmV0"));
+        let payload = with_session(session, "LIST_DRAFTS");
+        assert_eq!(payload.token(), &Token::new("c2VjcmV0"));
+        assert_eq!(*payload, "LIST_DRAFTS");
+    }
+}
@@ -0,0 +1,213 @@
+//! `sqlx::Type`, `Encode` and `Decode` of the ids and validated types for
+//! postgres
+//!
+//! The ids are `INT4` columns (or `INT8` columns, when they are decoded) and
+//! the validated strings are `TEXT` columns. A value which is read from the
+//! database is validated like any other input, and a invalid value is a
+//! decode error with the `ValidationError` as its source.
+//!
+//! A escaped `Title` is decoded before it is validated, so a stored title
+//! isn't escaped twice.
+
+use crate::valid::attachment::HexDigest;
+use crate::valid::fields::{Email, Username};
+use crate::valid::ids::{
+    AttachmentId, CategoryId, CommentId, DraftId, Id, PollOptionId, ThreadId, UserId,
+};
+use crate::valid::title::Title;
+use crate::valid::ValidationError;
+use sqlx::encode::IsNull;
+use sqlx::error::BoxDynError;
+use sqlx::postgres::{PgArgumentBuffer, PgTypeInfo, PgValueRef, Postgres};
+use sqlx::{Decode, Encode, Type, ValueRef};
+use std::convert::TryFrom;
+
+/// The `INT4` of a id, a id which doesn't fit in a `INT4` can't be written
+fn id_to_int4(id: u32) -> Result<i32, ValidationError> {
+    i32::try_from(id).map_err(|_| ValidationError::InvalidId)
+}
+
+/// The id of a `INT4` or `INT8`, where a negative (or too large) number is
+/// not a id
+fn id_from_int8<I: From<u32>>(n: i64) -> Result<I, ValidationError> {
+    u32::try_from(n)
+        .map(I::from)
+        .map_err(|_| ValidationError::InvalidId)
+}
+
+/// Validate a decoded value, a invalid value is a error with the
+/// `ValidationError` as its source
+fn validate<T>(s: String, f: fn(String) -> Result<T, ValidationError>) -> Result<T, BoxDynError> {
+    f(s).map_err(|e| Box::new(e) as BoxDynError)
+}
+
+macro_rules! impl_sqlx_id {
+    ($($id:ty),*) => {
+        $(
+            impl Type<Postgres> for $id {
+                fn type_info() -> PgTypeInfo {
+                    <i32 as Type<Postgres>>::type_info()
+                }
+
+                fn compatible(ty: &PgTypeInfo) -> bool {
+                    <i32 as Type<Postgres>>::compatible(ty) || <i64 as Type<Postgres>>::compatible(ty)
+                }
+            }
+
+            impl<'q> Encode<'q, Postgres> for $id {
+                fn encode_by_ref(&self, buf: &mut PgArgumentBuffer) -> Result<IsNull, BoxDynError> {
+                    let n = id_to_int4(self.inner())?;
+                    <i32 as Encode<Postgres>>::encode_by_ref(&n, buf)
+                }
+            }
+
+            impl<'r> Decode<'r, Postgres> for $id {
+                fn decode(value: PgValueRef<'r>) -> Result<Self, BoxDynError> {
+                    let is_int8 = <i64 as Type<Postgres>>::compatible(&value.type_info());
+                    let n = if is_int8 {
+                        <i64 as Decode<Postgres>>::decode(value)?
+                    } else {
+                        i64::from(<i32 as Decode<Postgres>>::decode(value)?)
+                    };
+                    Ok(id_from_int8(n)?)
+                }
+            }
+        )*
+    };
+}
+
+macro_rules! impl_sqlx_text {
+    ($($ty:ty => $validate:expr),*) => {
+        $(
+            impl Type<Postgres> for $ty {
+                fn type_info() -> PgTypeInfo {
+                    <String as Type<Postgres>>::type_info()
+                }
+
+                fn compatible(ty: &PgTypeInfo) -> bool {
+                    <String as Type<Postgres>>::compatible(ty)
+                }
+            }
+
+            impl<'q> Encode<'q, Postgres> for $ty {
+                fn encode_by_ref(&self, buf: &mut PgArgumentBuffer) -> Result<IsNull, BoxDynError> {
+                    <&str as Encode<Postgres>>::encode(AsRef::<str>::as_ref(self), buf)
+                }
+            }
+
+            impl<'r> Decode<'r, Postgres> for $ty {
+                fn decode(value: PgValueRef<'r>) -> Result<Self, BoxDynError> {
+                    validate(<String as Decode<Postgres>>::decode(value)?, $validate)
+                }
+            }
+        )*
+    };
+}
+
+impl_sqlx_id!(
+    CategoryId,
+    ThreadId,
+    CommentId,
+    UserId,
+    DraftId,
+    AttachmentId,
+    PollOptionId
+);
+
+impl_sqlx_text!(
+    Username => Username::try_from,
+    Email => Email::try_from,
+    Title => |s: String| Title::from_escaped(&s),
+    HexDigest => HexDigest::try_from
+);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn encoded<'q>(value: &impl Encode<'q, Postgres>) -> Vec<u8> {
+        let mut buf = PgArgumentBuffer::default();
+        value.encode_by_ref(&mut buf).unwrap();
+        buf.to_vec()
+    }
+
+    #[test]
+    fn encode_id() {
+        assert_eq!(encoded(&UserId::from(42)), vec![0, 0, 0, 42]);
+
+        let mut buf = PgArgumentBuffer::default();
+        let too_large = ThreadId::from(u32::max_value());
+        assert!(too_large.encode_by_ref(&mut buf).is_err());
+    }
+
+    #[test]
+    fn decode_id() {
+        assert_eq!(id_from_int8::<UserId>(42), Ok(UserId::from(42)));
+        let max = i64::from(u32::max_value());
+        assert_eq!(
+            id_from_int8::<UserId>(max),
+            Ok(UserId::from(u32::max_value()))
+        );
+        for n in &[-1, max + 1] {
+            assert_eq!(id_from_int8::<UserId>(*n), Err(ValidationError::InvalidId));
+        }
+    }
+
+    #[test]
+    fn encode_text() {
+        let name = Username::try_from(String::from("john")).unwrap();
+        assert_eq!(encoded(&name), b"john".to_vec());
+
+        // The title is stored escaped
+        let title = Title::try_from("A title with <script>").unwrap();
+        assert_eq!(encoded(&title), b"A title with &lt;script&gt;".to_vec());
+    }
+
+    #[test]
+    fn decode_text() {
+        let email = validate(String::from("john@example.com"), Email::try_from).unwrap();
+        assert_eq!(&*email, "john@example.com");
+
+        let title = Title::try_from("A title with <script>").unwrap();
+        let read = Title::from_escaped(&title).unwrap();
+        assert_eq!(read, title);
+    }
+
+    #[test]
+    fn decode_invalid_text() {
+        let e = validate(String::from("jo"), Username::try_from).unwrap_err();
+        assert_eq!(
+            e.downcast_ref::<ValidationError>(),
+            Some(&ValidationError::InvalidUsername)
+        );
+    }
+
+    /// Needs a database at `DATABASE_URL`
+    #[tokio::test]
+    #[ignore]
+    async fn decode_from_database() {
+        let url = std::env::var("DATABASE_URL").expect("DATABASE_URL is not set");
+        let pool = sqlx::PgPool::connect(&url).await.unwrap();
+
+        let name = Username::try_from(String::from("john")).unwrap();
+        let (id, read): (UserId, Username) = sqlx::query_as("SELECT $1::INT8, $2::TEXT")
+            .bind(UserId::from(42))
+            .bind(&name)
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+        assert_eq!(id, UserId::from(42));
+        assert_eq!(read, name);
+
+        let e = sqlx::query_as::<_, (Username,)>("SELECT 'jo'::TEXT")
+            .fetch_one(&pool)
+            .await
+            .unwrap_err();
+        match e {
+            sqlx::Error::ColumnDecode { source, .. } => {
+                assert!(source.downcast_ref::<ValidationError>().is_some())
+            }
+            e => panic!("{:?}", e),
+        }
+    }
+}
@@ -0,0 +1,144 @@
+//! Mapping of errors to http status codes
+//!
+//! The status of a error is the status of its `ErrorCode`
+//! (`ErrorCode::http_status`), so there is a single (exhaustive) match which
+//! a new error code must be added to.
+
+use crate::admin::responses::AdminError;
+use crate::auth::responses::AuthError;
+use crate::content::responses::ContentError;
+use crate::error::ErrorCode;
+use crate::valid::ValidationError;
+use http::StatusCode;
+
+/// The http status of a response with the error `code`
+fn status_of(code: ErrorCode) -> StatusCode {
+    StatusCode::from_u16(code.http_status()).expect("a error code has a valid http status")
+}
+
+impl ValidationError {
+    /// The http status of a response with this error
+    pub fn status(&self) -> StatusCode {
+        status_of(self.code())
+    }
+}
+
+impl AuthError {
+    /// The http status of a response with this error
+    ///
+    /// For `RateLimited` the time to wait is given by `retry_after`, which
+    /// should be sent in the `Retry-After` header.
+    pub fn status(&self) -> StatusCode {
+        status_of(self.code())
+    }
+}
+
+impl AdminError {
+    /// The http status of a response with this error
+    pub fn status(&self) -> StatusCode {
+        status_of(self.code())
+    }
+}
+
+impl ContentError {
+    /// The http status of a response with this error
+    pub fn status(&self) -> StatusCode {
+        status_of(self.code())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn validation_errors_are_unprocessable() {
+        let errors = vec![
+            ValidationError::InvalidUsername,
+            ValidationError::InvalidPassword,
+            ValidationError::InvalidId,
+            ValidationError::InvalidTitle,
+            ValidationError::InvalidDescription,
+            ValidationError::InvalidCommentContent,
+            ValidationError::InvalidEmail,
+            ValidationError::InvalidQuery,
+            ValidationError::InvalidPhoneNumber,
+            ValidationError::InvalidTwoFactorCode,
+            ValidationError::MissingReportDetails,
+            ValidationError::InvalidDraft,
+            ValidationError::InvalidFileName,
+            ValidationError::InvalidMimeType,
+            ValidationError::InvalidDigest,
+            ValidationError::InvalidPoll,
+            ValidationError::InvalidVote,
+            ValidationError::InvalidExcerpt,
+            ValidationError::InvalidIpAddr,
+            ValidationError::InvalidDisplayName,
+            ValidationError::InvalidUrl,
+            ValidationError::InvalidCursor,
+            ValidationError::InvalidPagination,
+            ValidationError::InvalidIdempotencyKey,
+            ValidationError::InvalidDuration,
+            ValidationError::InvalidListLength,
+            ValidationError::InvalidCountryCode,
+            ValidationError::InvalidAttachmentRef,
+        ];
+        for e in errors {
+            assert_eq!(e.status(), StatusCode::UNPROCESSABLE_ENTITY, "{:?}", e);
+        }
+    }
+
+    #[test]
+    fn auth_error_statuses() {
+        let statuses = vec![
+            (AuthError::InvalidToken, 401),
+            (AuthError::InvalidUsername, 422),
+            (AuthError::InvalidPassword, 422),
+            (AuthError::InvalidCredentials, 401),
+            (AuthError::ExistingUser, 409),
+            (AuthError::InvalidTwoFactorCode, 401),
+            (AuthError::InsufficientRole, 403),
+            (AuthError::RateLimited { retry_after: 30 }, 429),
+            (AuthError::InternalServerError, 500),
+        ];
+        for (e, status) in statuses {
+            assert_eq!(e.status().as_u16(), status, "{:?}", e);
+        }
+    }
+
+    #[test]
+    fn rate_limited_has_retry_after() {
+        let e = AuthError::RateLimited { retry_after: 30 };
+        assert_eq!(e.status(), StatusCode::TOO_MANY_REQUESTS);
+        assert_eq!(e.retry_after(), Some(30));
+        assert_eq!(AuthError::InvalidCredentials.retry_after(), None);
+    }
+
+    #[test]
+    fn admin_error_statuses() {
+        assert_eq!(AdminError::InternalError.status().as_u16(), 500);
+    }
+
+    #[test]
+    fn content_error_statuses() {
+        let statuses = vec![
+            (ContentError::MissingContent, 404),
+            (ContentError::HiddenContent, 403),
+            (ContentError::InternalServerError, 500),
+            (ContentError::InvalidSearchQuery, 422),
+            (ContentError::InvalidId, 422),
+            (ContentError::InvalidContent, 422),
+            (ContentError::ReplyTargetMissing, 422),
+        ];
+        for (e, status) in statuses {
+            assert_eq!(e.status().as_u16(), status, "{:?}", e);
+        }
+    }
+
+    #[test]
+    fn every_error_code_is_a_status() {
+        for code in ErrorCode::ALL {
+            assert_eq!(status_of(*code).as_u16(), code.http_status(), "{:?}", code);
+        }
+    }
+}
@@ -0,0 +1,139 @@
+//! (De)serialization of timestamps with whole-second precision
+//!
+//! Chrono writes the fraction of a second when a timestamp has one
+//! (`2018-10-20T12:00:00.123456Z`), which some clients can't parse. Every
+//! timestamp of the wire types is written without the fraction instead
+//! (`2018-10-20T12:00:00Z`), which means that it is truncated to the second.
+//! A timestamp with a fraction is still accepted when it is deserialized.
+//!
+//! Use `#[serde(with = "crate::timestamp::seconds")]` for a `DateTime<Utc>`,
+//! `option_seconds` for a `Option<DateTime<Utc>>` and `naive_seconds` for a
+//! `NaiveDateTime` (which is written without the `Z`).
+
+/// A `DateTime<Utc>` as RFC 3339 with whole seconds
+pub mod seconds {
+    use chrono::{DateTime, SecondsFormat, Utc};
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S>(time: &DateTime<Utc>, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.serialize_str(&time.to_rfc3339_opts(SecondsFormat::Secs, true))
+    }
+
+    pub fn deserialize<'de, D>(deserializer: D) -> Result<DateTime<Utc>, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        DateTime::deserialize(deserializer)
+    }
+}
+
+/// A `Option<DateTime<Utc>>` as RFC 3339 with whole seconds
+pub mod option_seconds {
+    use chrono::{DateTime, Utc};
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S>(time: &Option<DateTime<Utc>>, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        match time {
+            Some(time) => super::seconds::serialize(time, serializer),
+            None => serializer.serialize_none(),
+        }
+    }
+
+    pub fn deserialize<'de, D>(deserializer: D) -> Result<Option<DateTime<Utc>>, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        Option::deserialize(deserializer)
+    }
+}
+
+/// A `NaiveDateTime` as `%Y-%m-%dT%H:%M:%S`
+pub mod naive_seconds {
+    use chrono::naive::NaiveDateTime;
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S>(time: &NaiveDateTime, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.collect_str(&time.format("%Y-%m-%dT%H:%M:%S"))
+    }
+
+    pub fn deserialize<'de, D>(deserializer: D) -> Result<NaiveDateTime, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        NaiveDateTime::deserialize(deserializer)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::naive::NaiveDateTime;
+    use chrono::{DateTime, Duration, TimeZone, Utc};
+
+    #[derive(Serialize, Deserialize, PartialEq, Debug)]
+    struct Times {
+        #[serde(with = "super::seconds")]
+        at: DateTime<Utc>,
+        #[serde(default, with = "super::option_seconds")]
+        updated_at: Option<DateTime<Utc>>,
+        #[serde(with = "super::naive_seconds")]
+        naive: NaiveDateTime,
+    }
+
+    fn fractional() -> Times {
+        let at = Utc.timestamp(1_540_036_800, 123_456_000);
+        Times {
+            at,
+            updated_at: Some(at),
+            naive: at.naive_utc(),
+        }
+    }
+
+    #[test]
+    fn fraction_is_not_serialized() {
+        let expt = serde_json::json!({
+            "at": "2018-10-20T12:00:00Z",
+            "updated_at": "2018-10-20T12:00:00Z",
+            "naive": "2018-10-20T12:00:00",
+        });
+        assert_eq!(serde_json::to_value(&fractional()).unwrap(), expt);
+
+        let times = Times {
+            updated_at: None,
+            ..fractional()
+        };
+        let json = serde_json::to_value(&times).unwrap();
+        assert_eq!(json["updated_at"], serde_json::Value::Null);
+    }
+
+    #[test]
+    fn round_trip_within_a_second() {
+        let times = fractional();
+        let json = serde_json::to_string(&times).unwrap();
+        let decoded: Times = serde_json::from_str(&json).unwrap();
+        assert!(times.at - decoded.at < Duration::seconds(1));
+        assert_eq!(decoded.at, Utc.timestamp(1_540_036_800, 0));
+        assert_eq!(decoded.updated_at, Some(decoded.at));
+        assert!(times.naive - decoded.naive < Duration::seconds(1));
+    }
+
+    #[test]
+    fn fraction_is_accepted() {
+        let json = r#"{
+            "at": "2018-10-20T12:00:00.123456Z",
+            "naive": "2018-10-20T12:00:00.123456"
+        }"#;
+        let times: Times = serde_json::from_str(json).unwrap();
+        assert_eq!(times.at, fractional().at);
+        assert_eq!(times.updated_at, None);
+        assert_eq!(times.naive, fractional().naive);
+    }
+}
@@ -0,0 +1,249 @@
+//! TypeScript definitions of the wire types, for the types of a frontend
+//!
+//! The definitions are generated from the JSON schemas (see `json_schema`),
+//! so they follow the serialized form: a request is a union of its variants
+//! with their `type` tag as a string literal, a field which can be left out
+//! (like a `Option`) is a optional property, and a validated type is a
+//! `string`. A file is written for every schema of `json_schema::schemas`,
+//! which is what `cargo run --bin export_ts --features ts-export` does.
+//!
+//! The output only depends on the schemas, since the properties and
+//! definitions of a schema are sorted by their name.
+//!
+//! ```
+//! # use datatypes::typescript::bindings;
+//! for (file, definitions) in bindings() {
+//!     println!("{}: {}", file, definitions);
+//! }
+//! ```
+
+use crate::json_schema::schemas;
+use schemars::schema::{
+    ArrayValidation, InstanceType, ObjectValidation, RootSchema, Schema, SchemaObject, SingleOrVec,
+};
+
+/// The first line of every file
+const HEADER: &str =
+    "// Generated by `cargo run --bin export_ts --features ts-export`, do not edit";
+
+/// The definitions of every schema of `json_schema::schemas`, by the name of
+/// their file (like `AuthRequest.d.ts`)
+pub fn bindings() -> Vec<(String, String)> {
+    schemas()
+        .into_iter()
+        .map(|(name, schema)| (format!("{}.d.ts", name), definitions(name, &schema)))
+        .collect()
+}
+
+/// The definitions of `schema`, which is named `name`, and of every schema it
+/// refers to
+pub fn definitions(name: &str, schema: &RootSchema) -> String {
+    let mut out = format!("{}\n\n", HEADER);
+    out += &definition(name, &Schema::Object(schema.schema.clone()));
+    for (name, schema) in &schema.definitions {
+        out += "\n";
+        out += &definition(name, schema);
+    }
+    out
+}
+
+/// The definition of a type, where a object is written with a property on
+/// every line and a union with a member on every line
+fn definition(name: &str, schema: &Schema) -> String {
+    if let Some(object) = object_of(schema) {
+        let properties: String = properties(object)
+            .into_iter()
+            .map(|property| format!("  {};\n", property))
+            .collect();
+        return format!("export type {} = {{\n{}}};\n", name, properties);
+    }
+    let members = members(schema);
+    if members.len() > 1 {
+        let members: String = members.iter().map(|m| format!("\n  | {}", m)).collect();
+        format!("export type {} ={};\n", name, members)
+    } else {
+        format!("export type {} = {};\n", name, union(members))
+    }
+}
+
+/// The properties of `schema`, if it is only a object with properties
+fn object_of(schema: &Schema) -> Option<&ObjectValidation> {
+    match schema {
+        Schema::Object(SchemaObject {
+            instance_type: Some(SingleOrVec::Single(instance_type)),
+            object: Some(object),
+            reference: None,
+            enum_values: None,
+            const_value: None,
+            subschemas: None,
+            ..
+        }) if **instance_type == InstanceType::Object && !object.properties.is_empty() => {
+            Some(&**object)
+        }
+        _ => None,
+    }
+}
+
+/// The types of a union of the values which match `schema`
+fn members(schema: &Schema) -> Vec<String> {
+    let object = match schema {
+        Schema::Bool(true) => return vec!["unknown".to_owned()],
+        Schema::Bool(false) => return vec!["never".to_owned()],
+        Schema::Object(object) => object,
+    };
+    if let Some(reference) = &object.reference {
+        let name = reference.rsplit('/').next().unwrap_or(reference);
+        return vec![name.to_owned()];
+    }
+    if let Some(value) = &object.const_value {
+        return vec![value.to_string()];
+    }
+    if let Some(values) = &object.enum_values {
+        return values.iter().map(|value| value.to_string()).collect();
+    }
+    if let Some(subschemas) = &object.subschemas {
+        if let Some(all_of) = &subschemas.all_of {
+            if all_of.len() == 1 {
+                return members(&all_of[0]);
+            }
+            let all: Vec<_> = all_of.iter().map(|s| parenthesized(members(s))).collect();
+            return vec![all.join(" & ")];
+        }
+        if let Some(any_of) = subschemas.one_of.as_ref().or(subschemas.any_of.as_ref()) {
+            return any_of.iter().flat_map(members).collect();
+        }
+    }
+    match &object.instance_type {
+        Some(SingleOrVec::Single(instance_type)) => vec![instance(instance_type, object)],
+        Some(SingleOrVec::Vec(instance_types)) => instance_types
+            .iter()
+            .map(|instance_type| instance(instance_type, object))
+            .collect(),
+        None => vec!["unknown".to_owned()],
+    }
+}
+
+/// The type of the values of `instance_type` which match `object`
+fn instance(instance_type: &InstanceType, object: &SchemaObject) -> String {
+    match instance_type {
+        InstanceType::Null => "null".to_owned(),
+        InstanceType::Boolean => "boolean".to_owned(),
+        InstanceType::Integer | InstanceType::Number => "number".to_owned(),
+        InstanceType::String => "string".to_owned(),
+        InstanceType::Array => array(object.array.as_ref().map(|a| &**a)),
+        InstanceType::Object => inline_object(object.object.as_ref().map(|o| &**o)),
+    }
+}
+
+/// A list, or a tuple if it has a schema for every item
+fn array(array: Option<&ArrayValidation>) -> String {
+    match array.and_then(|array| array.items.as_ref()) {
+        Some(SingleOrVec::Single(items)) => format!("{}[]", parenthesized(members(items))),
+        Some(SingleOrVec::Vec(items)) => {
+            let items: Vec<_> = items.iter().map(|item| union(members(item))).collect();
+            format!("[{}]", items.join(", "))
+        }
+        None => "unknown[]".to_owned(),
+    }
+}
+
+/// A object on a single line, or a map if it only has a schema of its values
+fn inline_object(object: Option<&ObjectValidation>) -> String {
+    let object = match object {
+        Some(object) => object,
+        None => return "{}".to_owned(),
+    };
+    if object.properties.is_empty() {
+        return match object.additional_properties.as_ref().map(|a| &**a) {
+            Some(Schema::Bool(false)) | None => "{}".to_owned(),
+            Some(values) => format!("Record<string, {}>", union(members(values))),
+        };
+    }
+    format!("{{ {} }}", properties(object).join("; "))
+}
+
+/// The properties of a object, where a property which isn't required is
+/// optional
+fn properties(object: &ObjectValidation) -> Vec<String> {
+    object
+        .properties
+        .iter()
+        .map(|(name, schema)| {
+            let optional = if object.required.contains(name) {
+                ""
+            } else {
+                "?"
+            };
+            format!("{}{}: {}", key(name), optional, union(members(schema)))
+        })
+        .collect()
+}
+
+/// The name of a property, which is quoted if it isn't a identifier
+fn key(name: &str) -> String {
+    let mut chars = name.chars();
+    let is_ident = chars
+        .next()
+        .map_or(false, |c| c.is_ascii_alphabetic() || c == '_' || c == '$')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '$');
+    if is_ident {
+        name.to_owned()
+    } else {
+        serde_json::Value::from(name).to_string()
+    }
+}
+
+/// A union of `members`, or the type itself if there is only one
+fn union(members: Vec<String>) -> String {
+    members.join(" | ")
+}
+
+/// A union which can be used inside of a list or a intersection
+fn parenthesized(members: Vec<String>) -> String {
+    if members.len() > 1 {
+        format!("({})", union(members))
+    } else {
+        union(members)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::auth::requests::AuthRequest;
+    use schemars::schema_for;
+
+    #[test]
+    fn auth_request_snapshot() {
+        let generated = definitions("AuthRequest", &schema_for!(AuthRequest));
+        assert_eq!(generated, include_str!("../bindings/AuthRequest.d.ts"));
+    }
+
+    #[test]
+    fn bindings_are_deterministic() {
+        assert_eq!(bindings(), bindings());
+        for (file, definitions) in bindings() {
+            let name = file.trim_end_matches(".d.ts");
+            let export = format!("export type {} =", name);
+            assert!(definitions.contains(&export), "{}", file);
+        }
+    }
+
+    #[test]
+    fn optional_and_nullable_properties() {
+        #[derive(JsonSchema)]
+        #[allow(dead_code)]
+        struct Payload {
+            required: u32,
+            #[serde(default)]
+            optional: Option<String>,
+            #[serde(rename = "two-words")]
+            list: Vec<Option<String>>,
+        }
+
+        let generated = definitions("Payload", &schema_for!(Payload));
+        assert!(generated.contains("  optional?: string | null;\n"));
+        assert!(generated.contains("  required: number;\n"));
+        assert!(generated.contains("  \"two-words\": (string | null)[];\n"));
+    }
+}
@@ -0,0 +1,302 @@
+//! Validated metadata of uploaded attachments
+
+use super::ValidationError;
+use std::convert::TryFrom;
+use std::fmt::{self, Display};
+
+/// The maximum length of a file name in bytes
+const MAX_FILE_NAME_LEN: usize = 255;
+
+/// The maximum length of a mime type which isn't in the allowlist
+const MAX_MIME_TYPE_LEN: usize = 127;
+
+/// The length of a hex encoded SHA-256 digest
+const SHA256_HEX_LEN: usize = 64;
+
+/// The length of a attachment reference, 16 bytes in unpadded base64url
+pub const ATTACHMENT_REF_LEN: usize = 22;
+
+/// A valid (well formatted) file name
+///
+/// A file name is only the name of the file, it can't contain any path
+/// separators and can't refer to the current or parent directory. The name
+/// is serialized as it is (not HTML-escaped), so it is the same after a
+/// round trip.
+#[derive(PartialEq, PartialOrd, Eq, Ord, Debug, Clone)]
+pub struct FileName(String);
+
+impl TryFrom<String> for FileName {
+    type Error = ValidationError;
+    fn try_from(s: String) -> Result<Self, Self::Error> {
+        if !s.is_empty()
+            && s.len() <= MAX_FILE_NAME_LEN
+            && s != "."
+            && s != ".."
+            && !s.chars().any(|c| c == '/' || c == '\\' || c.is_control())
+        {
+            Ok(FileName(s))
+        } else {
+            Err(ValidationError::InvalidFileName)
+        }
+    }
+}
+
+impl_deserialize_with_try_from!(FileName);
+impl_serialize!(FileName, raw);
+impl_schema!(FileName => crate::schema::string().length(1, MAX_FILE_NAME_LEN));
+impl_deref_and_as_ref!(FileName => str);
+impl_into_inner!(FileName => String);
+
+impl Display for FileName {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+/// The mime type of a attachment
+///
+/// The common image types have their own variants, any other valid mime type
+/// is stored in `Other`.
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub enum MimeType {
+    ImagePng,
+    ImageJpeg,
+    ImageGif,
+    ImageWebp,
+    Other(String),
+}
+
+impl MimeType {
+    pub fn as_str(&self) -> &str {
+        match self {
+            MimeType::ImagePng => "image/png",
+            MimeType::ImageJpeg => "image/jpeg",
+            MimeType::ImageGif => "image/gif",
+            MimeType::ImageWebp => "image/webp",
+            MimeType::Other(s) => s.as_str(),
+        }
+    }
+}
+
+impl TryFrom<String> for MimeType {
+    type Error = ValidationError;
+    fn try_from(s: String) -> Result<Self, Self::Error> {
+        let s = s.to_ascii_lowercase();
+        match s.as_str() {
+            "image/png" => return Ok(MimeType::ImagePng),
+            "image/jpeg" | "image/jpg" => return Ok(MimeType::ImageJpeg),
+            "image/gif" => return Ok(MimeType::ImageGif),
+            "image/webp" => return Ok(MimeType::ImageWebp),
+            _ => {}
+        }
+
+        let valid_part = |part: &str| {
+            !part.is_empty()
+                && part
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || "!#$&-^_.+".contains(c))
+        };
+        let mut parts = s.splitn(2, '/');
+        let valid = s.len() <= MAX_MIME_TYPE_LEN
+            && parts.next().map_or(false, valid_part)
+            && parts.next().map_or(false, valid_part);
+
+        if valid {
+            Ok(MimeType::Other(s))
+        } else {
+            Err(ValidationError::InvalidMimeType)
+        }
+    }
+}
+
+impl_deserialize_with_try_from!(MimeType);
+impl_serialize!(MimeType);
+impl_schema!(MimeType => crate::schema::string().max_length(MAX_MIME_TYPE_LEN));
+
+impl AsRef<str> for MimeType {
+    fn as_ref(&self) -> &str {
+        self.as_str()
+    }
+}
+
+impl Display for MimeType {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+/// A valid (well formatted) hex encoded SHA-256 digest
+///
+/// The digest is always stored in lowercase.
+#[derive(PartialEq, PartialOrd, Eq, Ord, Hash, Debug, Clone)]
+#[cfg_attr(feature = "diesel", derive(AsExpression, FromSqlRow))]
+#[cfg_attr(feature = "diesel", sql_type = "diesel::sql_types::Text")]
+pub struct HexDigest(String);
+
+impl TryFrom<String> for HexDigest {
+    type Error = ValidationError;
+    fn try_from(s: String) -> Result<Self, Self::Error> {
+        if s.len() == SHA256_HEX_LEN && s.chars().all(|c| c.is_ascii_hexdigit()) {
+            Ok(HexDigest(s.to_ascii_lowercase()))
+        } else {
+            Err(ValidationError::InvalidDigest)
+        }
+    }
+}
+
+impl_deserialize_with_try_from!(HexDigest);
+impl_serialize!(HexDigest);
+impl_schema!(HexDigest => crate::schema::string()
+    .length(SHA256_HEX_LEN, SHA256_HEX_LEN)
+    .pattern("^[0-9a-fA-F]*$"));
+impl_deref_and_as_ref!(HexDigest => str);
+impl_into_inner!(HexDigest => String);
+
+impl Display for HexDigest {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+/// A valid (well formatted) reference to a uploaded attachment
+///
+/// The reference is the opaque key which the upload service hands out for a
+/// upload, and is always `ATTACHMENT_REF_LEN` characters of unpadded
+/// base64url. It is not the `AttachmentId` of a stored attachment.
+#[derive(PartialEq, PartialOrd, Eq, Ord, Hash, Debug, Clone)]
+pub struct AttachmentRef(String);
+
+impl TryFrom<String> for AttachmentRef {
+    type Error = ValidationError;
+    fn try_from(s: String) -> Result<Self, Self::Error> {
+        let is_base64url = s
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+        if s.len() == ATTACHMENT_REF_LEN && is_base64url {
+            Ok(AttachmentRef(s))
+        } else {
+            Err(ValidationError::InvalidAttachmentRef)
+        }
+    }
+}
+
+impl_deserialize_with_try_from!(AttachmentRef);
+impl_serialize!(AttachmentRef, raw);
+impl_schema!(AttachmentRef => crate::schema::string()
+    .length(ATTACHMENT_REF_LEN, ATTACHMENT_REF_LEN)
+    .pattern("^[A-Za-z0-9_-]*$"));
+impl_deref_and_as_ref!(AttachmentRef => str);
+impl_into_inner!(AttachmentRef => String);
+
+impl Display for AttachmentRef {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn valid_file_names() {
+        for name in &["cat.png", "holiday photo (1).jpg", ".hidden", "..dots.."] {
+            assert!(FileName::try_from(name.to_string()).is_ok(), "'{}'", name);
+        }
+    }
+
+    #[test]
+    fn file_name_traversal_attempts() {
+        for name in &[
+            "../../etc/passwd",
+            "..",
+            ".",
+            "/etc/passwd",
+            "..\\..\\windows\\system32",
+            "images/cat.png",
+            "cat\u{0}.png",
+            "",
+        ] {
+            assert_eq!(
+                FileName::try_from(name.to_string()),
+                Err(ValidationError::InvalidFileName),
+                "'{}'",
+                name
+            );
+        }
+    }
+
+    #[test]
+    fn file_name_length() {
+        assert!(FileName::try_from("a".repeat(MAX_FILE_NAME_LEN)).is_ok());
+        assert!(FileName::try_from("a".repeat(MAX_FILE_NAME_LEN + 1)).is_err());
+    }
+
+    #[test]
+    fn file_name_round_trip() {
+        let name = FileName::try_from("Tom & Jerry <1>.png".to_owned()).unwrap();
+        let json = serde_json::to_string(&name).unwrap();
+        assert_eq!(json, r#""Tom & Jerry <1>.png""#);
+        assert_eq!(serde_json::from_str::<FileName>(&json).unwrap(), name);
+    }
+
+    #[test]
+    fn mime_types() {
+        assert_eq!(
+            MimeType::try_from("image/PNG".to_owned()),
+            Ok(MimeType::ImagePng)
+        );
+        assert_eq!(
+            MimeType::try_from("application/pdf".to_owned()),
+            Ok(MimeType::Other("application/pdf".to_owned()))
+        );
+        for mime in &["image", "image/", "/png", "text/html; charset=utf-8"] {
+            assert!(MimeType::try_from(mime.to_string()).is_err(), "'{}'", mime);
+        }
+        let long = format!("application/{}", "x".repeat(MAX_MIME_TYPE_LEN));
+        assert!(MimeType::try_from(long).is_err());
+    }
+
+    #[test]
+    fn hex_digests() {
+        let digest = "E3B0C44298FC1C149AFBF4C8996FB92427AE41E4649B934CA495991B7852B855";
+        assert_eq!(
+            &*HexDigest::try_from(digest.to_owned()).unwrap(),
+            digest.to_ascii_lowercase()
+        );
+        assert!(HexDigest::try_from("abc".to_owned()).is_err());
+        assert!(HexDigest::try_from("g".repeat(SHA256_HEX_LEN)).is_err());
+    }
+
+    #[test]
+    fn attachment_refs() {
+        let reference = "q2-_Xw8AAAB9aBcDeFgHiJ";
+        assert!(AttachmentRef::try_from(reference.to_owned()).is_ok());
+        for reference in &[
+            "q2+/Xw8AAAB9aBcDeFgHiJ",
+            "q2-_Xw8AAAB9aBcDeFgH==",
+            "q2-_Xw8AAAB9aBcDeFgHi",
+            "q2-_Xw8AAAB9aBcDeFgHiJK",
+            "",
+        ] {
+            assert_eq!(
+                AttachmentRef::try_from(reference.to_string()),
+                Err(ValidationError::InvalidAttachmentRef),
+                "'{}'",
+                reference
+            );
+        }
+    }
+
+    proptest! {
+        #[test]
+        fn file_name_doesnt_crash(s in "\\PC*") {
+            let _ = FileName::try_from(s);
+        }
+
+        #[test]
+        fn mime_type_doesnt_crash(s in "\\PC*") {
+            let _ = MimeType::try_from(s);
+        }
+    }
+}
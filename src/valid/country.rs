@@ -0,0 +1,93 @@
+//! Validated country codes
+
+use super::ValidationError;
+use std::convert::TryFrom;
+use std::fmt::{self, Display};
+
+/// The length of a ISO 3166-1 alpha-2 code
+const COUNTRY_CODE_LEN: usize = 2;
+
+/// A valid (well formatted) ISO 3166-1 alpha-2 country code, like `NO`
+///
+/// A code is stored in uppercase, so `no` is stored as `NO`. Only the shape
+/// of the code is validated, not whether it is assigned to a country.
+#[derive(PartialEq, PartialOrd, Eq, Ord, Hash, Debug, Clone)]
+pub struct CountryCode(String);
+
+impl TryFrom<String> for CountryCode {
+    type Error = ValidationError;
+    fn try_from(s: String) -> Result<Self, Self::Error> {
+        if s.len() == COUNTRY_CODE_LEN && s.chars().all(|c| c.is_ascii_alphabetic()) {
+            Ok(CountryCode(s.to_ascii_uppercase()))
+        } else {
+            Err(ValidationError::InvalidCountryCode)
+        }
+    }
+}
+
+impl<'a> TryFrom<&'a str> for CountryCode {
+    type Error = ValidationError;
+    fn try_from(s: &'a str) -> Result<Self, Self::Error> {
+        CountryCode::try_from(s.to_owned())
+    }
+}
+
+impl_deserialize_with_try_from!(CountryCode);
+impl_serialize!(CountryCode);
+impl_schema!(CountryCode => crate::schema::string().pattern("^[A-Za-z]{2}$"));
+impl_deref_and_as_ref!(CountryCode => str);
+impl_into_inner!(CountryCode => String);
+
+impl Display for CountryCode {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn valid_country_code() {
+        let code = CountryCode::try_from("NO").unwrap();
+        assert_eq!(&*code, "NO");
+    }
+
+    #[test]
+    fn country_code_is_uppercased() {
+        let code = CountryCode::try_from("no").unwrap();
+        assert_eq!(&*code, "NO");
+        assert_eq!(code, CountryCode::try_from("No").unwrap());
+    }
+
+    #[test]
+    fn country_code_round_trip() {
+        let code = CountryCode::try_from("no").unwrap();
+        let json = serde_json::to_string(&code).unwrap();
+        assert_eq!(json, r#""NO""#);
+        assert_eq!(serde_json::from_str::<CountryCode>(&json).unwrap(), code);
+    }
+
+    #[test]
+    fn country_code_with_three_letters() {
+        assert_eq!(
+            CountryCode::try_from("NOR"),
+            Err(ValidationError::InvalidCountryCode)
+        );
+    }
+
+    #[test]
+    fn country_code_with_other_characters() {
+        for s in &["", "N", "N0", "N ", "ØY"] {
+            assert!(CountryCode::try_from(*s).is_err(), "{}", s);
+        }
+    }
+
+    #[test]
+    fn country_code_deserialize() {
+        let code: CountryCode = serde_json::from_str(r#""se""#).unwrap();
+        assert_eq!(&*code, "SE");
+        assert!(serde_json::from_str::<CountryCode>(r#""SWE""#).is_err());
+    }
+}
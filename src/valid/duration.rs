@@ -0,0 +1,223 @@
+//! Durations which are written by humans
+
+use super::ValidationError;
+use std::convert::TryFrom;
+use std::fmt::{self, Display};
+use std::time::Duration;
+
+/// The pattern of a duration with a unit, in the schemas of a duration
+#[cfg(any(feature = "schemars", feature = "utoipa"))]
+const WITH_UNIT_PATTERN: &str = r"^\s*[0-9]+[smhd]?\s*$";
+
+/// A duration which can be written with a unit
+///
+/// The duration can be given as plain seconds (`3600` or `"3600"`) or with a
+/// suffix of `s`, `m`, `h` or `d` (`"30m"`, `"2h"`, `"7d"`). It is always
+/// serialized as seconds.
+///
+/// # Examples
+///
+/// ```
+/// # #![feature(try_from)]
+/// # use datatypes::valid::duration::HumanDuration;
+/// # use std::convert::TryFrom;
+/// # use std::time::Duration;
+/// let duration = HumanDuration::try_from("2h").unwrap();
+/// assert_eq!(*duration, Duration::from_secs(7200));
+/// assert_eq!(serde_json::to_string(&duration).unwrap(), "7200");
+/// ```
+#[derive(PartialEq, PartialOrd, Eq, Ord, Clone, Copy, Debug, Hash)]
+pub struct HumanDuration(Duration);
+
+impl HumanDuration {
+    pub fn from_secs(secs: u64) -> Self {
+        HumanDuration(Duration::from_secs(secs))
+    }
+}
+
+impl<'a> TryFrom<&'a str> for HumanDuration {
+    type Error = ValidationError;
+    fn try_from(s: &'a str) -> Result<Self, Self::Error> {
+        let s = s.trim();
+        let (number, unit) = match s.char_indices().last() {
+            Some((i, c)) if c.is_ascii_alphabetic() => (&s[..i], c),
+            _ => (s, 's'),
+        };
+        let multiplier = match unit {
+            's' => 1,
+            'm' => 60,
+            'h' => 60 * 60,
+            'd' => 24 * 60 * 60,
+            _ => return Err(ValidationError::InvalidDuration),
+        };
+        if number.is_empty() || !number.chars().all(|c| c.is_ascii_digit()) {
+            return Err(ValidationError::InvalidDuration);
+        }
+        number
+            .parse::<u64>()
+            .ok()
+            .and_then(|n| n.checked_mul(multiplier))
+            .map(HumanDuration::from_secs)
+            .ok_or(ValidationError::InvalidDuration)
+    }
+}
+
+impl TryFrom<String> for HumanDuration {
+    type Error = ValidationError;
+    fn try_from(s: String) -> Result<Self, Self::Error> {
+        HumanDuration::try_from(s.as_str())
+    }
+}
+
+impl<'de> serde::de::Deserialize<'de> for HumanDuration {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::de::Deserializer<'de>,
+    {
+        struct HumanDurationVisitor;
+
+        impl<'de> serde::de::Visitor<'de> for HumanDurationVisitor {
+            type Value = HumanDuration;
+
+            fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                f.write_str("seconds or a duration like \"30m\", \"2h\" or \"7d\"")
+            }
+
+            fn visit_u64<E>(self, secs: u64) -> Result<Self::Value, E>
+            where
+                E: serde::de::Error,
+            {
+                Ok(HumanDuration::from_secs(secs))
+            }
+
+            fn visit_i64<E>(self, secs: i64) -> Result<Self::Value, E>
+            where
+                E: serde::de::Error,
+            {
+                if secs < 0 {
+                    Err(E::custom(ValidationError::InvalidDuration))
+                } else {
+                    Ok(HumanDuration::from_secs(secs as u64))
+                }
+            }
+
+            fn visit_str<E>(self, s: &str) -> Result<Self::Value, E>
+            where
+                E: serde::de::Error,
+            {
+                HumanDuration::try_from(s).map_err(E::custom)
+            }
+        }
+
+        deserializer.deserialize_any(HumanDurationVisitor)
+    }
+}
+
+/// Seconds, or a string with a unit, which is how a duration is read
+#[cfg(feature = "schemars")]
+impl schemars::JsonSchema for HumanDuration {
+    fn schema_name() -> String {
+        "HumanDuration".to_owned()
+    }
+
+    fn json_schema(gen: &mut schemars::gen::SchemaGenerator) -> schemars::schema::Schema {
+        let seconds = gen.subschema_for::<u64>();
+        let with_unit = crate::schema::string().pattern(WITH_UNIT_PATTERN);
+        crate::json_schema::any_of(vec![seconds, with_unit.into()])
+    }
+}
+
+/// Seconds, or a string with a unit, which is how a duration is read
+#[cfg(feature = "utoipa")]
+impl utoipa::PartialSchema for HumanDuration {
+    fn schema() -> utoipa::openapi::RefOr<utoipa::openapi::schema::Schema> {
+        use utoipa::openapi::schema::{AnyOfBuilder, Schema};
+        use utoipa::openapi::RefOr;
+
+        let with_unit = crate::schema::string().pattern(WITH_UNIT_PATTERN);
+        let schema = AnyOfBuilder::new()
+            .item(<u64 as utoipa::PartialSchema>::schema())
+            .item(with_unit);
+        RefOr::T(Schema::AnyOf(schema.build()))
+    }
+}
+
+#[cfg(feature = "utoipa")]
+impl utoipa::ToSchema for HumanDuration {
+    fn name() -> std::borrow::Cow<'static, str> {
+        std::borrow::Cow::Borrowed("HumanDuration")
+    }
+}
+
+impl serde::Serialize for HumanDuration {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        serializer.serialize_u64(self.0.as_secs())
+    }
+}
+
+impl_deref_and_as_ref!(HumanDuration => Duration);
+impl_into_inner!(HumanDuration => Duration);
+
+impl Display for HumanDuration {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}s", self.0.as_secs())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn secs(s: &str) -> Result<u64, ValidationError> {
+        HumanDuration::try_from(s).map(|d| d.as_secs())
+    }
+
+    #[test]
+    fn minutes() {
+        assert_eq!(secs("30m"), Ok(30 * 60));
+    }
+
+    #[test]
+    fn hours() {
+        assert_eq!(secs("2h"), Ok(2 * 60 * 60));
+    }
+
+    #[test]
+    fn days() {
+        assert_eq!(secs("7d"), Ok(7 * 24 * 60 * 60));
+    }
+
+    #[test]
+    fn plain_seconds() {
+        assert_eq!(secs("3600"), Ok(3600));
+        assert_eq!(secs("45s"), Ok(45));
+        let duration: HumanDuration = serde_json::from_str("3600").unwrap();
+        assert_eq!(duration, HumanDuration::from_secs(3600));
+    }
+
+    #[test]
+    fn invalid_durations() {
+        for s in &[
+            "2 fortnights",
+            "",
+            "h",
+            "-5m",
+            "1.5h",
+            "2w",
+            "99999999999999999999d",
+        ] {
+            assert_eq!(secs(s), Err(ValidationError::InvalidDuration), "{}", s);
+        }
+        assert!(serde_json::from_str::<HumanDuration>(r#""2 fortnights""#).is_err());
+        assert!(serde_json::from_str::<HumanDuration>("-60").is_err());
+    }
+
+    #[test]
+    fn serializes_as_seconds() {
+        let duration: HumanDuration = serde_json::from_str(r#""30m""#).unwrap();
+        assert_eq!(serde_json::to_string(&duration).unwrap(), "1800");
+    }
+}
@@ -2,27 +2,33 @@
 
 // TODO add tests which vertifies the `TryFrom` implementations
 
+use super::policy::ValidationPolicy;
 use super::ValidationError;
-use htmlescape::encode_minimal;
-use rocket::http::RawStr;
-use rocket::request::FromFormValue;
 use std::convert::TryFrom;
 use std::fmt::{self, Display};
 
 use super::{EMAIL_REGEX, PASSWORD_REGEX, SEARCH_QUERY_REGEX, USERNAME_REGEX};
 use regex::Regex;
 
+pub use super::title::Title;
+
 /// A valid (well formatted) username
 #[derive(PartialEq, PartialOrd, Eq, Ord, Debug, Clone)]
+#[cfg_attr(feature = "diesel", derive(AsExpression, FromSqlRow))]
+#[cfg_attr(feature = "diesel", sql_type = "diesel::sql_types::Text")]
 pub struct Username(String);
 
-impl TryFrom<String> for Username {
-    type Error = ValidationError;
-    fn try_from(s: String) -> Result<Self, Self::Error> {
+impl Username {
+    /// Validate a username with the limits of `policy`
+    pub fn try_new_with_policy(
+        s: impl Into<String>,
+        policy: &ValidationPolicy,
+    ) -> Result<Self, ValidationError> {
         lazy_static! {
             static ref RE: Regex = USERNAME_REGEX.parse().expect("invalid username regex");
         }
-        if RE.is_match(&s) {
+        let s = s.into();
+        if RE.is_match(&s) && policy.username_len(s.chars().count()) {
             Ok(Username(s))
         } else {
             Err(ValidationError::InvalidUsername)
@@ -30,8 +36,22 @@ impl TryFrom<String> for Username {
     }
 }
 
+impl TryFrom<String> for Username {
+    type Error = ValidationError;
+    fn try_from(s: String) -> Result<Self, Self::Error> {
+        Username::try_new_with_policy(s, &ValidationPolicy::default())
+    }
+}
+
 impl_deserialize_with_try_from!(Username);
+impl_from_param_and_form_value!(Username);
 impl_serialize!(Username);
+impl_schema!(Username => {
+    let policy = ValidationPolicy::default();
+    crate::schema::string()
+        .length(policy.min_username_len, policy.max_username_len)
+        .pattern(USERNAME_REGEX)
+});
 impl_deref_and_as_ref!(Username => str);
 impl_into_inner!(Username => String);
 
@@ -43,19 +63,24 @@ impl Display for Username {
 
 /// A valid (well formatted) plaintext password
 ///
-/// NB This type does not implement `Debug` for the simple reason that a
-/// plaintext passwords should **never** be printed.
+/// NB The `Debug` implementation never prints the password, for the simple
+/// reason that a plaintext passwords should **never** be printed.
 #[derive(PartialEq, PartialOrd, Eq, Ord, Clone)]
 //#[serde(rename = "password")]
 pub struct PlainPassword(String);
 
-impl TryFrom<String> for PlainPassword {
-    type Error = ValidationError;
-    fn try_from(s: String) -> Result<Self, Self::Error> {
+impl PlainPassword {
+    /// Validate a password with the limits of `policy`
+    pub fn try_new_with_policy(
+        s: impl Into<String>,
+        policy: &ValidationPolicy,
+    ) -> Result<Self, ValidationError> {
         lazy_static! {
             static ref RE: Regex = PASSWORD_REGEX.parse().expect("invalid password regex");
         }
+        let s = s.into();
         if RE.is_match(&s)
+            && policy.password_len(s.chars().count())
             && s.chars().any(|c| c.is_ascii_lowercase())
             && s.chars().any(|c| c.is_ascii_uppercase())
             && s.chars().any(|c| c.is_numeric())
@@ -67,34 +92,27 @@ impl TryFrom<String> for PlainPassword {
     }
 }
 
-impl_deserialize_with_try_from!(PlainPassword);
-impl_serialize!(PlainPassword);
-impl_deref_and_as_ref!(PlainPassword => str);
-impl_into_inner!(PlainPassword => String);
-
-/// A valid (well formatted) title
-#[derive(PartialEq, PartialOrd, Eq, Ord, Debug, Clone)]
-pub struct Title(String);
-
-impl TryFrom<String> for Title {
+impl TryFrom<String> for PlainPassword {
     type Error = ValidationError;
     fn try_from(s: String) -> Result<Self, Self::Error> {
-        if 4 < s.len() && s.len() < 80 {
-            Ok(Title(htmlescape::encode_minimal(&s)))
-        } else {
-            Err(ValidationError::InvalidTitle)
-        }
+        PlainPassword::try_new_with_policy(s, &ValidationPolicy::default())
     }
 }
 
-impl_deserialize_with_try_from!(Title);
-impl_serialize!(Title);
-impl_deref_and_as_ref!(Title => str);
-impl_into_inner!(Title => String);
+impl_deserialize_with_try_from!(PlainPassword);
+impl_serialize!(PlainPassword);
+impl_schema!(PlainPassword => {
+    let policy = ValidationPolicy::default();
+    crate::schema::string()
+        .length(policy.min_password_len, policy.max_password_len)
+        .pattern(PASSWORD_REGEX)
+});
+impl_deref_and_as_ref!(PlainPassword => str);
+impl_into_inner!(PlainPassword => String);
 
-impl Display for Title {
+impl fmt::Debug for PlainPassword {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(f, "{}", self.0)
+        f.write_str("PlainPassword(..)")
     }
 }
 
@@ -102,10 +120,14 @@ impl Display for Title {
 #[derive(PartialEq, PartialOrd, Eq, Ord, Debug, Clone)]
 pub struct Description(String);
 
-impl TryFrom<String> for Description {
-    type Error = ValidationError;
-    fn try_from(s: String) -> Result<Self, Self::Error> {
-        if s.len() < 255 {
+impl Description {
+    /// Validate a description with the limits of `policy`
+    pub fn try_new_with_policy(
+        s: impl Into<String>,
+        policy: &ValidationPolicy,
+    ) -> Result<Self, ValidationError> {
+        let s = s.into();
+        if policy.description_len(s.len()) {
             Ok(Description(htmlescape::encode_minimal(&s)))
         } else {
             Err(ValidationError::InvalidDescription)
@@ -113,8 +135,19 @@ impl TryFrom<String> for Description {
     }
 }
 
+impl TryFrom<String> for Description {
+    type Error = ValidationError;
+    fn try_from(s: String) -> Result<Self, Self::Error> {
+        Description::try_new_with_policy(s, &ValidationPolicy::default())
+    }
+}
+
 impl_deserialize_with_try_from!(Description);
-impl_serialize!(Description);
+impl_serialize!(Description, escaped);
+impl_schema!(Description => {
+    let policy = ValidationPolicy::default();
+    crate::schema::string().max_length(policy.max_description_len)
+});
 impl_deref_and_as_ref!(Description => str);
 impl_into_inner!(Description => String);
 
@@ -128,10 +161,14 @@ impl Display for Description {
 #[derive(PartialEq, PartialOrd, Eq, Ord, Debug, Clone)]
 pub struct CommentContent(String);
 
-impl TryFrom<String> for CommentContent {
-    type Error = ValidationError;
-    fn try_from(s: String) -> Result<Self, Self::Error> {
-        if s.len() > 4 && s.len() < 80 {
+impl CommentContent {
+    /// Validate a comment with the limits of `policy`
+    pub fn try_new_with_policy(
+        s: impl Into<String>,
+        policy: &ValidationPolicy,
+    ) -> Result<Self, ValidationError> {
+        let s = s.into();
+        if policy.comment_len(s.len()) {
             Ok(CommentContent(htmlescape::encode_minimal(&s)))
         } else {
             Err(ValidationError::InvalidCommentContent)
@@ -139,8 +176,19 @@ impl TryFrom<String> for CommentContent {
     }
 }
 
+impl TryFrom<String> for CommentContent {
+    type Error = ValidationError;
+    fn try_from(s: String) -> Result<Self, Self::Error> {
+        CommentContent::try_new_with_policy(s, &ValidationPolicy::default())
+    }
+}
+
 impl_deserialize_with_try_from!(CommentContent);
-impl_serialize!(CommentContent);
+impl_serialize!(CommentContent, escaped);
+impl_schema!(CommentContent => {
+    let policy = ValidationPolicy::default();
+    crate::schema::string().length(policy.min_comment_len, policy.max_comment_len)
+});
 impl_deref_and_as_ref!(CommentContent => str);
 impl_into_inner!(CommentContent => String);
 
@@ -152,6 +200,8 @@ impl Display for CommentContent {
 
 /// A valid (well formatted) email
 #[derive(PartialEq, PartialOrd, Eq, Ord, Debug, Clone)]
+#[cfg_attr(feature = "diesel", derive(AsExpression, FromSqlRow))]
+#[cfg_attr(feature = "diesel", sql_type = "diesel::sql_types::Text")]
 pub struct Email(String);
 
 impl TryFrom<String> for Email {
@@ -169,7 +219,11 @@ impl TryFrom<String> for Email {
 }
 
 impl_deserialize_with_try_from!(Email);
+impl_from_param_and_form_value!(Email);
 impl_serialize!(Email);
+impl_schema!(Email => crate::schema::string()
+    .format("email")
+    .pattern(EMAIL_REGEX));
 impl_deref_and_as_ref!(Email => str);
 impl_into_inner!(Email => String);
 
@@ -198,7 +252,9 @@ impl TryFrom<String> for QueryStr {
 }
 
 impl_deserialize_with_try_from!(QueryStr);
+impl_from_param_and_form_value!(QueryStr);
 impl_serialize!(QueryStr);
+impl_schema!(QueryStr => crate::schema::string().pattern(SEARCH_QUERY_REGEX));
 impl_deref_and_as_ref!(QueryStr => str);
 impl_into_inner!(QueryStr => String);
 
@@ -208,11 +264,156 @@ impl Display for QueryStr {
     }
 }
 
-impl<'a> FromFormValue<'a> for QueryStr {
-    type Error = <QueryStr as TryFrom<String>>::Error;
-    fn from_form_value(search_str: &'a RawStr) -> Result<Self, Self::Error> {
-        let s = search_str.url_decode().unwrap_or("".to_string()); // Decode string and if not working give empty string back.
-        QueryStr::try_from(s)
+/// The amount of digits in a two-factor code
+const TWO_FACTOR_CODE_LEN: usize = 6;
+
+/// A valid (well formatted) two-factor code
+///
+/// The code is stored as a string so that leading zeros are preserved.
+#[derive(PartialEq, PartialOrd, Eq, Ord, Debug, Clone)]
+pub struct TwoFactorCode(String);
+
+impl TryFrom<String> for TwoFactorCode {
+    type Error = ValidationError;
+    fn try_from(s: String) -> Result<Self, Self::Error> {
+        if s.len() == TWO_FACTOR_CODE_LEN && s.chars().all(|c| c.is_ascii_digit()) {
+            Ok(TwoFactorCode(s))
+        } else {
+            Err(ValidationError::InvalidTwoFactorCode)
+        }
+    }
+}
+
+impl_deserialize_with_try_from!(TwoFactorCode);
+impl_serialize!(TwoFactorCode);
+impl_schema!(TwoFactorCode => crate::schema::string()
+    .length(TWO_FACTOR_CODE_LEN, TWO_FACTOR_CODE_LEN)
+    .pattern("^[0-9]*$"));
+impl_deref_and_as_ref!(TwoFactorCode => str);
+impl_into_inner!(TwoFactorCode => String);
+
+/// The max amount of characters in a display name
+const DISPLAY_NAME_MAX_LEN: usize = 32;
+
+/// A valid (well formatted) display name
+///
+/// Unlike a username, a display name can contain any (non-control)
+/// characters, but it can't be blank. The name is HTML-escaped when it is
+/// validated, and serialized without the escaping (so it is only escaped once
+/// when it is deserialized again).
+#[derive(PartialEq, PartialOrd, Eq, Ord, Debug, Clone)]
+pub struct DisplayName(String);
+
+impl TryFrom<String> for DisplayName {
+    type Error = ValidationError;
+    fn try_from(s: String) -> Result<Self, Self::Error> {
+        let trimmed = s.trim();
+        let len = trimmed.chars().count();
+        if 0 < len && len <= DISPLAY_NAME_MAX_LEN && !trimmed.chars().any(char::is_control) {
+            Ok(DisplayName(htmlescape::encode_minimal(trimmed)))
+        } else {
+            Err(ValidationError::InvalidDisplayName)
+        }
+    }
+}
+
+impl_deserialize_with_try_from!(DisplayName);
+impl_serialize!(DisplayName, escaped);
+impl_schema!(DisplayName => crate::schema::string().length(1, DISPLAY_NAME_MAX_LEN));
+impl_deref_and_as_ref!(DisplayName => str);
+impl_into_inner!(DisplayName => String);
+
+impl Display for DisplayName {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+/// The max amount of bytes in a avatar url
+const AVATAR_URL_MAX_LEN: usize = 2048;
+
+/// A valid (well formatted) url of a avatar image
+///
+/// Only `http` and `https` urls with a host are accepted. The url is not
+/// HTML-escaped, so a query like `?a=1&b=2` is serialized as it is.
+#[derive(PartialEq, PartialOrd, Eq, Ord, Debug, Clone)]
+pub struct AvatarUrl(String);
+
+impl TryFrom<String> for AvatarUrl {
+    type Error = ValidationError;
+    fn try_from(s: String) -> Result<Self, Self::Error> {
+        let rest = if s.starts_with("https://") {
+            &s["https://".len()..]
+        } else if s.starts_with("http://") {
+            &s["http://".len()..]
+        } else {
+            return Err(ValidationError::InvalidUrl);
+        };
+        let host = rest.split(|c: char| c == '/' || c == '?' || c == '#').next();
+        let has_host = host.map_or(false, |host| !host.is_empty());
+        let has_invalid_chars = s.chars().any(|c| c.is_whitespace() || c.is_control());
+
+        if has_host && !has_invalid_chars && s.len() <= AVATAR_URL_MAX_LEN {
+            Ok(AvatarUrl(s))
+        } else {
+            Err(ValidationError::InvalidUrl)
+        }
+    }
+}
+
+impl_deserialize_with_try_from!(AvatarUrl);
+impl_serialize!(AvatarUrl, raw);
+impl_schema!(AvatarUrl => crate::schema::string()
+    .max_length(AVATAR_URL_MAX_LEN)
+    .format("uri")
+    .pattern("^https?://"));
+impl_deref_and_as_ref!(AvatarUrl => str);
+impl_into_inner!(AvatarUrl => String);
+
+impl Display for AvatarUrl {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+/// The lengths of the dash separated groups of a idempotency key
+const IDEMPOTENCY_KEY_GROUPS: [usize; 5] = [8, 4, 4, 4, 12];
+
+/// A valid (well formatted) idempotency key
+///
+/// The key is opaque to the service, but it must be shaped like a UUID (e.g.
+/// `123e4567-e89b-12d3-a456-426655440000`). It is stored in lowercase.
+#[derive(PartialEq, PartialOrd, Eq, Ord, Debug, Clone, Hash)]
+pub struct IdempotencyKey(String);
+
+impl TryFrom<String> for IdempotencyKey {
+    type Error = ValidationError;
+    fn try_from(s: String) -> Result<Self, Self::Error> {
+        let groups: Vec<&str> = s.split('-').collect();
+        let is_valid = groups.len() == IDEMPOTENCY_KEY_GROUPS.len()
+            && groups
+                .iter()
+                .zip(IDEMPOTENCY_KEY_GROUPS.iter())
+                .all(|(group, len)| {
+                    group.len() == *len && group.chars().all(|c| c.is_ascii_hexdigit())
+                });
+        if is_valid {
+            Ok(IdempotencyKey(s.to_ascii_lowercase()))
+        } else {
+            Err(ValidationError::InvalidIdempotencyKey)
+        }
+    }
+}
+
+impl_deserialize_with_try_from!(IdempotencyKey);
+impl_serialize!(IdempotencyKey);
+impl_schema!(IdempotencyKey => crate::schema::string().format("uuid"));
+impl_deref_and_as_ref!(IdempotencyKey => str);
+impl_into_inner!(IdempotencyKey => String);
+
+impl Display for IdempotencyKey {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.0)
     }
 }
 
@@ -262,11 +463,14 @@ mod tests {
 
     doesnt_crash!(username_doesnt_crash, Username);
     doesnt_crash!(plain_password_doesnt_crash, PlainPassword);
-    doesnt_crash!(title_doesnt_crash, Title);
     doesnt_crash!(description_doesnt_crash, Description);
     doesnt_crash!(comment_content_doesnt_crash, CommentContent);
     doesnt_crash!(email_doesnt_crash, Email);
     doesnt_crash!(query_str_doesnt_crash, QueryStr);
+    doesnt_crash!(two_factor_code_doesnt_crash, TwoFactorCode);
+    doesnt_crash!(display_name_doesnt_crash, DisplayName);
+    doesnt_crash!(avatar_url_doesnt_crash, AvatarUrl);
+    doesnt_crash!(idempotency_key_doesnt_crash, IdempotencyKey);
 
     test_input!(valid_usernames, Username, vec!["john", "irene"], true);
     test_input!(
@@ -304,25 +508,101 @@ mod tests {
         false
     );
     test_input!(
-        valid_comments,
-        CommentContent,
-        vec!["Hello everyone!", "Hello", "Hello, I love you all guys."],
+        valid_two_factor_codes,
+        TwoFactorCode,
+        vec!["123456", "000000", "987650"],
         true
     );
     test_input!(
-        valid_title,
-        Title,
-        vec!["Just a question.", "Hello", "Hello, I love you all guys!"],
+        unvalid_two_factor_codes,
+        TwoFactorCode,
+        vec!["12345", "1234567", "12a456", "", "12 456"],
+        false
+    );
+    test_input!(
+        valid_display_names,
+        DisplayName,
+        vec!["J", "John Doe", "Ærlige Åse", "  padded  "],
         true
     );
     test_input!(
-        unvalid_title,
-        Title,
-        vec!["Just a questionyyyyyyyyyyyyyyyyyyyyyyyyyyyyyyyyyyyyyyyyyyyyyyyyyyyyyyyyyyyyyyyyyyyyyyyyyyyyyyyyyyyyyyyyyyyyyyyyy.",
-        "Helloccccccccccccccccccccccccccccccccccccccccccccccccccccccvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvs fgfd fffdfffdfdfddfffffff",
-        "Hello, I love you all guys! Or what Am fthd fgd rg srsrsd weesdef sdfeeeeeeeeeeeeeeeeeeeeef  esfsefsefds dgf  dfd    dfsdf"],
+        unvalid_display_names,
+        DisplayName,
+        vec!["", "   ", "new\nline", "a name which is way too long to be shown"],
         false
     );
+    test_input!(
+        valid_avatar_urls,
+        AvatarUrl,
+        vec![
+            "https://example.com/avatar.png",
+            "http://example.com",
+            "https://cdn.example.com/a.png?size=64"
+        ],
+        true
+    );
+    test_input!(
+        unvalid_avatar_urls,
+        AvatarUrl,
+        vec![
+            "",
+            "example.com/avatar.png",
+            "javascript:alert(1)",
+            "ftp://example.com/avatar.png",
+            "https:///avatar.png",
+            "https://example.com/an avatar.png"
+        ],
+        false
+    );
+
+    #[test]
+    fn display_name_is_escaped_once() {
+        let name = DisplayName::try_from("Tom & Jerry".to_owned()).unwrap();
+        assert_eq!(&*name, "Tom &amp; Jerry");
+        let json = serde_json::to_string(&name).unwrap();
+        assert_eq!(json, r#""Tom & Jerry""#);
+        let decoded: DisplayName = serde_json::from_str(&json).unwrap();
+        assert_eq!(decoded, name);
+    }
+
+    #[test]
+    fn avatar_url_round_trip() {
+        let s = "https://cdn.example.com/a.png?size=64&format=webp";
+        let url = AvatarUrl::try_from(s.to_owned()).unwrap();
+        let json = serde_json::to_string(&url).unwrap();
+        assert_eq!(json, format!(r#""{}""#, s));
+        assert_eq!(serde_json::from_str::<AvatarUrl>(&json).unwrap(), url);
+    }
+
+    test_input!(
+        valid_idempotency_keys,
+        IdempotencyKey,
+        vec![
+            "123e4567-e89b-12d3-a456-426655440000",
+            "00000000-0000-0000-0000-000000000000",
+            "ABCDEF01-2345-6789-ABCD-EF0123456789"
+        ],
+        true
+    );
+    test_input!(
+        unvalid_idempotency_keys,
+        IdempotencyKey,
+        vec![
+            "",
+            "123e4567e89b12d3a456426655440000",
+            "123e4567-e89b-12d3-a456-42665544000",
+            "123e4567-e89b-12d3-a456-4266554400000",
+            "123e4567-e89b-12d3-a456-42665544000g",
+            "123e4567-e89b-12d3-a456-426655440000-0000"
+        ],
+        false
+    );
+    test_input!(
+        valid_comments,
+        CommentContent,
+        vec!["Hello everyone!", "Hello", "Hello, I love you all guys."],
+        true
+    );
     test_input!(
         valid_desc,
         Description,
@@ -334,17 +614,6 @@ mod tests {
         true
     );
 
-    test_escaping!(
-        title_with_quotes,
-        Title,
-        vec![
-            (
-                "A title with \"quoutes\"",
-                "A title with &quot;quoutes&quot;"
-            ),
-            ("A title with <script>", "A title with &lt;script&gt;")
-        ]
-    );
     test_escaping!(
         desc_with_quotes,
         Description,
@@ -371,4 +640,65 @@ mod tests {
             ("A comment with <script>", "A comment with &lt;script&gt;")
         ]
     );
+
+    #[test]
+    fn password_is_not_printed() {
+        let password = PlainPassword::try_from("helloAndWelcome123".to_owned()).unwrap();
+        assert_eq!(format!("{:?}", password), "PlainPassword(..)");
+    }
+
+    #[test]
+    #[cfg(all(feature = "rocket", not(target_arch = "wasm32")))]
+    fn from_param_and_form_value() {
+        use rocket::http::RawStr;
+        use rocket::request::{FromFormValue, FromParam};
+
+        let raw = RawStr::from_str("john_doe");
+        assert_eq!(&*Username::from_param(raw).unwrap(), "john_doe");
+        assert_eq!(&*Username::from_form_value(raw).unwrap(), "john_doe");
+        let raw = RawStr::from_str("john%2Bdoe%40example.com");
+        assert_eq!(&*Email::from_param(raw).unwrap(), "john+doe@example.com");
+        assert_eq!(
+            &*Email::from_form_value(raw).unwrap(),
+            "john+doe@example.com"
+        );
+        let raw = RawStr::from_str("rust%20cats");
+        assert_eq!(&*QueryStr::from_param(raw).unwrap(), "rust cats");
+        assert_eq!(&*QueryStr::from_form_value(raw).unwrap(), "rust cats");
+    }
+
+    #[test]
+    #[cfg(all(feature = "rocket", not(target_arch = "wasm32")))]
+    fn invalid_param_and_form_value() {
+        use rocket::http::RawStr;
+        use rocket::request::{FromFormValue, FromParam};
+
+        // Invalid once decoded, and not utf-8 once decoded
+        for s in &["jo", "john%20doe", "%FF%FF%FF%FF"] {
+            let raw = RawStr::from_str(s);
+            assert_eq!(
+                Username::from_param(raw),
+                Err(ValidationError::InvalidUsername)
+            );
+            assert_eq!(
+                Username::from_form_value(raw),
+                Err(ValidationError::InvalidUsername)
+            );
+        }
+        let raw = RawStr::from_str("john%40doe");
+        assert_eq!(Email::from_param(raw), Err(ValidationError::InvalidEmail));
+        assert_eq!(
+            Email::from_form_value(raw),
+            Err(ValidationError::InvalidEmail)
+        );
+        let raw = RawStr::from_str("a");
+        assert_eq!(
+            QueryStr::from_param(raw),
+            Err(ValidationError::InvalidQuery)
+        );
+        assert_eq!(
+            QueryStr::from_form_value(raw),
+            Err(ValidationError::InvalidQuery)
+        );
+    }
 }
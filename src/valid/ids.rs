@@ -15,24 +15,134 @@ pub trait Id: Copy + Eq + Ord + Hash {
 
 /// A direct referece to a specific category which is stored in the database
 #[derive(Deserialize, Serialize, Clone, Copy, PartialEq, PartialOrd, Eq, Ord, Debug, Hash)]
+#[cfg_attr(feature = "schemars", derive(JsonSchema))]
+#[cfg_attr(feature = "utoipa", derive(ToSchema))]
+#[cfg_attr(feature = "diesel", derive(AsExpression, FromSqlRow))]
+#[cfg_attr(feature = "diesel", sql_type = "diesel::sql_types::Integer")]
+#[cfg_attr(feature = "diesel", sql_type = "diesel::sql_types::BigInt")]
 #[serde(transparent)]
 pub struct CategoryId(u32);
 id_impls!(CategoryId, CategoryId => u32);
 
 /// A direct referece to a specific thread which is stored in the database
 #[derive(Deserialize, Serialize, Clone, Copy, PartialEq, PartialOrd, Eq, Ord, Debug, Hash)]
+#[cfg_attr(feature = "schemars", derive(JsonSchema))]
+#[cfg_attr(feature = "utoipa", derive(ToSchema))]
+#[cfg_attr(feature = "diesel", derive(AsExpression, FromSqlRow))]
+#[cfg_attr(feature = "diesel", sql_type = "diesel::sql_types::Integer")]
+#[cfg_attr(feature = "diesel", sql_type = "diesel::sql_types::BigInt")]
 #[serde(transparent)]
 pub struct ThreadId(u32);
 id_impls!(ThreadId, ThreadId => u32);
 
 /// A direct referece to a specific comment which is stored in the database
 #[derive(Deserialize, Serialize, Clone, Copy, PartialEq, PartialOrd, Eq, Ord, Debug, Hash)]
+#[cfg_attr(feature = "schemars", derive(JsonSchema))]
+#[cfg_attr(feature = "utoipa", derive(ToSchema))]
+#[cfg_attr(feature = "diesel", derive(AsExpression, FromSqlRow))]
+#[cfg_attr(feature = "diesel", sql_type = "diesel::sql_types::Integer")]
+#[cfg_attr(feature = "diesel", sql_type = "diesel::sql_types::BigInt")]
 #[serde(transparent)]
 pub struct CommentId(u32);
 id_impls!(CommentId, CommentId => u32);
 
 /// A direct referece to a specific user which is store in the database
 #[derive(Deserialize, Serialize, Clone, Copy, PartialEq, PartialOrd, Eq, Ord, Debug, Hash)]
+#[cfg_attr(feature = "schemars", derive(JsonSchema))]
+#[cfg_attr(feature = "utoipa", derive(ToSchema))]
+#[cfg_attr(feature = "diesel", derive(AsExpression, FromSqlRow))]
+#[cfg_attr(feature = "diesel", sql_type = "diesel::sql_types::Integer")]
+#[cfg_attr(feature = "diesel", sql_type = "diesel::sql_types::BigInt")]
 #[serde(transparent)]
 pub struct UserId(u32);
 id_impls!(UserId, UserId => u32);
+
+/// A direct referece to a specific draft which is stored in the database
+#[derive(Deserialize, Serialize, Clone, Copy, PartialEq, PartialOrd, Eq, Ord, Debug, Hash)]
+#[cfg_attr(feature = "schemars", derive(JsonSchema))]
+#[cfg_attr(feature = "utoipa", derive(ToSchema))]
+#[cfg_attr(feature = "diesel", derive(AsExpression, FromSqlRow))]
+#[cfg_attr(feature = "diesel", sql_type = "diesel::sql_types::Integer")]
+#[cfg_attr(feature = "diesel", sql_type = "diesel::sql_types::BigInt")]
+#[serde(transparent)]
+pub struct DraftId(u32);
+id_impls!(DraftId, DraftId => u32);
+
+/// A direct referece to a specific attachment which is stored in the database
+#[derive(Deserialize, Serialize, Clone, Copy, PartialEq, PartialOrd, Eq, Ord, Debug, Hash)]
+#[cfg_attr(feature = "schemars", derive(JsonSchema))]
+#[cfg_attr(feature = "utoipa", derive(ToSchema))]
+#[cfg_attr(feature = "diesel", derive(AsExpression, FromSqlRow))]
+#[cfg_attr(feature = "diesel", sql_type = "diesel::sql_types::Integer")]
+#[cfg_attr(feature = "diesel", sql_type = "diesel::sql_types::BigInt")]
+#[serde(transparent)]
+pub struct AttachmentId(u32);
+id_impls!(AttachmentId, AttachmentId => u32);
+
+/// A direct referece to a specific option of a poll which is stored in the
+/// database
+#[derive(Deserialize, Serialize, Clone, Copy, PartialEq, PartialOrd, Eq, Ord, Debug, Hash)]
+#[cfg_attr(feature = "schemars", derive(JsonSchema))]
+#[cfg_attr(feature = "utoipa", derive(ToSchema))]
+#[cfg_attr(feature = "diesel", derive(AsExpression, FromSqlRow))]
+#[cfg_attr(feature = "diesel", sql_type = "diesel::sql_types::Integer")]
+#[cfg_attr(feature = "diesel", sql_type = "diesel::sql_types::BigInt")]
+#[serde(transparent)]
+pub struct PollOptionId(u32);
+id_impls!(PollOptionId, PollOptionId => u32);
+
+/// A list of ids of the same kind
+#[derive(Deserialize, Serialize, Clone, PartialEq, Eq, Debug, Hash)]
+#[cfg_attr(feature = "schemars", derive(JsonSchema))]
+#[cfg_attr(feature = "utoipa", derive(ToSchema))]
+#[serde(transparent)]
+pub struct IdList<I: Id>(Vec<I>);
+
+impl<I: Id> IdList<I> {
+    pub fn new(ids: Vec<I>) -> Self {
+        IdList(ids)
+    }
+
+    #[allow(unused)]
+    pub fn into_inner(self) -> Vec<I> {
+        self.0
+    }
+}
+
+impl<I: Id> From<Vec<I>> for IdList<I> {
+    fn from(ids: Vec<I>) -> Self {
+        IdList(ids)
+    }
+}
+
+impl<I: Id> std::ops::Deref for IdList<I> {
+    type Target = [I];
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    #[cfg(all(feature = "rocket", not(target_arch = "wasm32")))]
+    fn id_from_param_and_form_value() {
+        use crate::valid::ValidationError;
+        use rocket::http::RawStr;
+        use rocket::request::{FromFormValue, FromParam};
+
+        let raw = RawStr::from_str("42");
+        assert_eq!(UserId::from_param(raw), Ok(UserId::from(42)));
+        assert_eq!(ThreadId::from_form_value(raw), Ok(ThreadId::from(42)));
+        for s in &["", "-1", "forty-two", "4294967296"] {
+            let raw = RawStr::from_str(s);
+            assert_eq!(UserId::from_param(raw), Err(ValidationError::InvalidId));
+            assert_eq!(
+                UserId::from_form_value(raw),
+                Err(ValidationError::InvalidId)
+            );
+        }
+    }
+}
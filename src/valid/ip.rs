@@ -0,0 +1,131 @@
+//! Parsing of client IP addresses
+
+use super::ValidationError;
+use std::net::{IpAddr, Ipv6Addr, SocketAddr};
+
+/// Parse the IP address of a client from a `X-Forwarded-For` style string
+///
+/// The string can be a comma separated list of addresses, in which case the
+/// first (the client) is used. A port is stripped from the address, IPv6
+/// addresses with a port must be surrounded with brackets.
+///
+/// # Examples
+///
+/// ```
+/// # use datatypes::valid::ip::parse_client_ip;
+/// let ip = parse_client_ip("203.0.113.7:5678, 10.0.0.1").unwrap();
+/// assert_eq!(ip, "203.0.113.7".parse::<std::net::IpAddr>().unwrap());
+/// ```
+pub fn parse_client_ip(s: &str) -> Result<IpAddr, ValidationError> {
+    let first = s.split(',').next().unwrap_or("").trim();
+
+    if let Ok(ip) = first.parse::<IpAddr>() {
+        return Ok(ip);
+    }
+    if let Ok(addr) = first.parse::<SocketAddr>() {
+        return Ok(addr.ip());
+    }
+    // A bracketed IPv6 address without a port, e.g. `[::1]`
+    if first.starts_with('[') && first.ends_with(']') {
+        if let Ok(ip) = first[1..first.len() - 1].parse::<Ipv6Addr>() {
+            return Ok(IpAddr::V6(ip));
+        }
+    }
+    Err(ValidationError::InvalidIpAddr)
+}
+
+/// (De)serialize a IP address as a string in every format
+///
+/// Serde writes a IP address as a enum of bytes in formats which are not
+/// human readable (like CBOR). Such a enum can't be read back once the payload
+/// has been buffered, which happens when the payload of a tagged request comes
+/// before its type. Use it with
+/// `#[serde(with = "crate::valid::ip::as_string")]`.
+pub mod as_string {
+    use serde::de::{Deserialize, Deserializer, Error};
+    use serde::Serializer;
+    use std::net::IpAddr;
+
+    pub fn serialize<S>(ip: &IpAddr, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.collect_str(ip)
+    }
+
+    pub fn deserialize<'de, D>(deserializer: D) -> Result<IpAddr, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let s = String::deserialize(deserializer)?;
+        s.parse().map_err(D::Error::custom)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ip(s: &str) -> IpAddr {
+        s.parse().unwrap()
+    }
+
+    #[test]
+    fn bare_ip() {
+        assert_eq!(parse_client_ip("1.2.3.4"), Ok(ip("1.2.3.4")));
+        assert_eq!(parse_client_ip("2001:db8::1"), Ok(ip("2001:db8::1")));
+    }
+
+    #[test]
+    fn ip_with_port() {
+        assert_eq!(parse_client_ip("1.2.3.4:5678"), Ok(ip("1.2.3.4")));
+    }
+
+    #[test]
+    fn ipv6_with_brackets() {
+        assert_eq!(parse_client_ip("[2001:db8::1]:443"), Ok(ip("2001:db8::1")));
+        assert_eq!(parse_client_ip("[2001:db8::1]"), Ok(ip("2001:db8::1")));
+    }
+
+    #[test]
+    fn comma_list() {
+        assert_eq!(
+            parse_client_ip(" 1.2.3.4 , 10.0.0.1, 10.0.0.2"),
+            Ok(ip("1.2.3.4"))
+        );
+        assert_eq!(
+            parse_client_ip("[2001:db8::1]:443,10.0.0.1"),
+            Ok(ip("2001:db8::1"))
+        );
+    }
+
+    #[test]
+    fn garbage_is_rejected() {
+        for s in &["", "not an ip", "1.2.3", "1.2.3.4:port", "[1.2.3.4]", ", 1.2.3.4"] {
+            assert_eq!(parse_client_ip(s), Err(ValidationError::InvalidIpAddr), "{}", s);
+        }
+    }
+
+    #[derive(Serialize, Deserialize, PartialEq, Debug)]
+    struct Wrapper {
+        #[serde(with = "as_string")]
+        ip: IpAddr,
+    }
+
+    #[test]
+    fn as_string_is_same_as_default_in_json() {
+        let addr = ip("2001:db8::1");
+        let json = serde_json::to_string(&Wrapper { ip: addr }).unwrap();
+        assert_eq!(json, r#"{"ip":"2001:db8::1"}"#);
+        let json = serde_json::to_string(&addr).unwrap();
+        assert_eq!(json, r#""2001:db8::1""#);
+    }
+
+    #[test]
+    fn as_string_round_trip_in_binary_format() {
+        let value = Wrapper { ip: ip("10.0.0.1") };
+        let bytes = bincode::serialize(&value).unwrap();
+        let decoded: Wrapper = bincode::deserialize(&bytes).unwrap();
+        assert_eq!(decoded, value);
+    }
+}
@@ -2,18 +2,32 @@
 //!
 //! These datatypes can be used to compose requests and responses
 
+use crate::error::ErrorCode;
+use std::fmt;
+
+pub mod attachment;
+pub mod country;
+pub mod duration;
 pub mod fields;
 pub mod ids;
+pub mod ip;
+pub mod phone;
+pub mod policy;
+pub mod title;
 pub mod token;
+#[cfg(feature = "url")]
+pub mod url;
 
 // TODO update regexes or change validation to fit our need
 // I just threw together some regexs to test out the functionality
 
-/// The regex which vertifies that a username is formatted correctly
-const USERNAME_REGEX: &str = "^[a-zA-Z0-9_-]{4,10}$";
+/// The regex which vertifies that a username only contains valid characters
+/// (the length is given by the `ValidationPolicy`)
+const USERNAME_REGEX: &str = "^[a-zA-Z0-9_-]*$";
 
-/// The regex which vertifies that a password is formatted correctly
-const PASSWORD_REGEX: &str = "^[\\w\\d.@%$!]{8,64}$";
+/// The regex which vertifies that a password only contains valid characters
+/// (the length is given by the `ValidationPolicy`)
+const PASSWORD_REGEX: &str = "^[\\w\\d.@%$!]*$";
 
 /// The regex which vertifies that a password is formatted correctly
 const EMAIL_REGEX: &str = "^[A-Za-z0-9._%+-]+@[A-Za-z0-9-]+\\.[A-Za-z]{2,}$";
@@ -21,22 +35,272 @@ const EMAIL_REGEX: &str = "^[A-Za-z0-9._%+-]+@[A-Za-z0-9-]+\\.[A-Za-z]{2,}$";
 /// The regex which vertifies that a search query is formatted correctly
 const SEARCH_QUERY_REGEX: &str = r"^([a-zA-Z0-9_-æøåÆØÅ]|\s){2,30}$";
 
-#[derive(Fail, Serialize, Deserialize, PartialEq, Debug)]
+#[derive(Serialize, Deserialize, PartialEq, Debug)]
+#[cfg_attr(feature = "schemars", derive(JsonSchema))]
+#[cfg_attr(feature = "utoipa", derive(ToSchema))]
+#[non_exhaustive]
 pub enum ValidationError {
-    #[fail(display = "invalid (badly formatted) username")]
     InvalidUsername,
-    #[fail(display = "invalid (badly formatted) password")]
     InvalidPassword,
-    #[fail(display = "invalid (badly formatted) id")]
     InvalidId,
-    #[fail(display = "invalid (badly formatted) title")]
     InvalidTitle,
-    #[fail(display = "invalid (badly formatted) description")]
     InvalidDescription,
-    #[fail(display = "invalid (badly formatted) comment")]
     InvalidCommentContent,
-    #[fail(display = "invalid (badly formatted) email")]
     InvalidEmail,
-    #[fail(display = "invalid (badly formatted) search query")]
     InvalidQuery,
+    InvalidPhoneNumber,
+    InvalidTwoFactorCode,
+    MissingReportDetails,
+    InvalidDraft,
+    InvalidFileName,
+    InvalidMimeType,
+    InvalidDigest,
+    InvalidPoll,
+    InvalidVote,
+    InvalidExcerpt,
+    InvalidIpAddr,
+    InvalidDisplayName,
+    InvalidUrl,
+    InvalidCursor,
+    InvalidPagination,
+    InvalidIdempotencyKey,
+    InvalidDuration,
+    InvalidListLength,
+    InvalidCountryCode,
+    InvalidAttachmentRef,
+}
+
+impl fmt::Display for ValidationError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let message = match self {
+            ValidationError::InvalidUsername => "invalid (badly formatted) username",
+            ValidationError::InvalidPassword => "invalid (badly formatted) password",
+            ValidationError::InvalidId => "invalid (badly formatted) id",
+            ValidationError::InvalidTitle => "invalid (badly formatted) title",
+            ValidationError::InvalidDescription => "invalid (badly formatted) description",
+            ValidationError::InvalidCommentContent => "invalid (badly formatted) comment",
+            ValidationError::InvalidEmail => "invalid (badly formatted) email",
+            ValidationError::InvalidQuery => "invalid (badly formatted) search query",
+            ValidationError::InvalidPhoneNumber => "invalid (badly formatted) phone number",
+            ValidationError::InvalidTwoFactorCode => "invalid (badly formatted) two-factor code",
+            ValidationError::MissingReportDetails => {
+                "reports with the reason other must contain details"
+            }
+            ValidationError::InvalidDraft => "invalid draft",
+            ValidationError::InvalidFileName => "invalid (badly formatted) file name",
+            ValidationError::InvalidMimeType => "invalid (badly formatted) mime type",
+            ValidationError::InvalidDigest => "invalid (badly formatted) digest",
+            ValidationError::InvalidPoll => "polls must have between 2 and 10 options",
+            ValidationError::InvalidVote => "invalid vote for the poll",
+            ValidationError::InvalidExcerpt => "quoted excerpts can be at most 280 characters",
+            ValidationError::InvalidIpAddr => "invalid (badly formatted) ip address",
+            ValidationError::InvalidDisplayName => "invalid (badly formatted) display name",
+            ValidationError::InvalidUrl => "invalid (badly formatted) url",
+            ValidationError::InvalidCursor => "invalid (badly formatted) cursor",
+            ValidationError::InvalidPagination => {
+                "a cursor can't be combined with page or per_page"
+            }
+            ValidationError::InvalidIdempotencyKey => "invalid (badly formatted) idempotency key",
+            ValidationError::InvalidDuration => "invalid (badly formatted) duration",
+            ValidationError::InvalidListLength => "invalid (too short or too long) list",
+            ValidationError::InvalidCountryCode => "invalid (badly formatted) country code",
+            ValidationError::InvalidAttachmentRef => {
+                "invalid (badly formatted) attachment reference"
+            }
+        };
+        f.write_str(message)
+    }
+}
+
+impl std::error::Error for ValidationError {}
+
+impl ValidationError {
+    /// The machine-stable code of the error
+    pub fn code(&self) -> ErrorCode {
+        match self {
+            ValidationError::InvalidUsername => ErrorCode::InvalidUsername,
+            ValidationError::InvalidPassword => ErrorCode::InvalidPassword,
+            ValidationError::InvalidId => ErrorCode::InvalidId,
+            ValidationError::InvalidTitle => ErrorCode::InvalidTitle,
+            ValidationError::InvalidDescription => ErrorCode::InvalidDescription,
+            ValidationError::InvalidCommentContent => ErrorCode::InvalidCommentContent,
+            ValidationError::InvalidEmail => ErrorCode::InvalidEmail,
+            ValidationError::InvalidQuery => ErrorCode::InvalidQuery,
+            ValidationError::InvalidPhoneNumber => ErrorCode::InvalidPhoneNumber,
+            ValidationError::InvalidTwoFactorCode => ErrorCode::InvalidTwoFactorCode,
+            ValidationError::MissingReportDetails => ErrorCode::MissingReportDetails,
+            ValidationError::InvalidDraft => ErrorCode::InvalidDraft,
+            ValidationError::InvalidFileName => ErrorCode::InvalidFileName,
+            ValidationError::InvalidMimeType => ErrorCode::InvalidMimeType,
+            ValidationError::InvalidDigest => ErrorCode::InvalidDigest,
+            ValidationError::InvalidPoll => ErrorCode::InvalidPoll,
+            ValidationError::InvalidVote => ErrorCode::InvalidVote,
+            ValidationError::InvalidExcerpt => ErrorCode::InvalidExcerpt,
+            ValidationError::InvalidIpAddr => ErrorCode::InvalidIpAddr,
+            ValidationError::InvalidDisplayName => ErrorCode::InvalidDisplayName,
+            ValidationError::InvalidUrl => ErrorCode::InvalidUrl,
+            ValidationError::InvalidCursor => ErrorCode::InvalidCursor,
+            ValidationError::InvalidPagination => ErrorCode::InvalidPagination,
+            ValidationError::InvalidIdempotencyKey => ErrorCode::InvalidIdempotencyKey,
+            ValidationError::InvalidDuration => ErrorCode::InvalidDuration,
+            ValidationError::InvalidListLength => ErrorCode::InvalidListLength,
+            ValidationError::InvalidCountryCode => ErrorCode::InvalidCountryCode,
+            ValidationError::InvalidAttachmentRef => ErrorCode::InvalidAttachmentRef,
+        }
+    }
+
+    /// The error which has the given code, if it is a validation error
+    pub fn from_code(code: ErrorCode) -> Option<Self> {
+        match code {
+            ErrorCode::InvalidUsername => Some(ValidationError::InvalidUsername),
+            ErrorCode::InvalidPassword => Some(ValidationError::InvalidPassword),
+            ErrorCode::InvalidId => Some(ValidationError::InvalidId),
+            ErrorCode::InvalidTitle => Some(ValidationError::InvalidTitle),
+            ErrorCode::InvalidDescription => Some(ValidationError::InvalidDescription),
+            ErrorCode::InvalidCommentContent => Some(ValidationError::InvalidCommentContent),
+            ErrorCode::InvalidEmail => Some(ValidationError::InvalidEmail),
+            ErrorCode::InvalidQuery => Some(ValidationError::InvalidQuery),
+            ErrorCode::InvalidPhoneNumber => Some(ValidationError::InvalidPhoneNumber),
+            ErrorCode::InvalidTwoFactorCode => Some(ValidationError::InvalidTwoFactorCode),
+            ErrorCode::MissingReportDetails => Some(ValidationError::MissingReportDetails),
+            ErrorCode::InvalidDraft => Some(ValidationError::InvalidDraft),
+            ErrorCode::InvalidFileName => Some(ValidationError::InvalidFileName),
+            ErrorCode::InvalidMimeType => Some(ValidationError::InvalidMimeType),
+            ErrorCode::InvalidDigest => Some(ValidationError::InvalidDigest),
+            ErrorCode::InvalidPoll => Some(ValidationError::InvalidPoll),
+            ErrorCode::InvalidVote => Some(ValidationError::InvalidVote),
+            ErrorCode::InvalidExcerpt => Some(ValidationError::InvalidExcerpt),
+            ErrorCode::InvalidIpAddr => Some(ValidationError::InvalidIpAddr),
+            ErrorCode::InvalidDisplayName => Some(ValidationError::InvalidDisplayName),
+            ErrorCode::InvalidUrl => Some(ValidationError::InvalidUrl),
+            ErrorCode::InvalidCursor => Some(ValidationError::InvalidCursor),
+            ErrorCode::InvalidPagination => Some(ValidationError::InvalidPagination),
+            ErrorCode::InvalidIdempotencyKey => Some(ValidationError::InvalidIdempotencyKey),
+            ErrorCode::InvalidDuration => Some(ValidationError::InvalidDuration),
+            ErrorCode::InvalidListLength => Some(ValidationError::InvalidListLength),
+            ErrorCode::InvalidCountryCode => Some(ValidationError::InvalidCountryCode),
+            ErrorCode::InvalidAttachmentRef => Some(ValidationError::InvalidAttachmentRef),
+            _ => None,
+        }
+    }
+
+    /// The human readable (English) message of the error, intended for logs
+    pub fn message(&self) -> String {
+        self.to_string()
+    }
+}
+
+/// A payload with constraints between its fields, which can't be checked when
+/// each field is deserialized
+///
+/// A field is validated as it is deserialized, so a payload which has been
+/// deserialized only has to be checked as a whole, e.g. that a cursor isn't
+/// combined with a page.
+pub trait Validate {
+    /// The errors of the payload, which is valid if there are none
+    fn validation_errors(&self) -> Vec<ValidationError>;
+}
+
+/// A field of a request which did not pass validation, together with its name
+/// (see `AuthRequest::validate`)
+///
+/// The error is serialized as its code, like
+/// `{ "field": "username", "error": "INVALID_USERNAME" }`.
+#[derive(Serialize, PartialEq, Debug)]
+pub struct FieldError {
+    /// The name of the field in the payload, like `username`
+    pub field: &'static str,
+    #[serde(serialize_with = "serialize_code")]
+    pub error: ValidationError,
+}
+
+fn serialize_code<S>(error: &ValidationError, serializer: S) -> Result<S::Ok, S::Error>
+where
+    S: serde::Serializer,
+{
+    serde::Serialize::serialize(&error.code(), serializer)
+}
+
+impl fmt::Display for FieldError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}: {}", self.field, self.error)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::valid::fields::Username;
+    use std::convert::TryFrom;
+
+    #[test]
+    fn validation_errors_map_to_stable_codes() {
+        let codes = vec![
+            (ValidationError::InvalidUsername, "INVALID_USERNAME"),
+            (ValidationError::InvalidPassword, "INVALID_PASSWORD"),
+            (ValidationError::InvalidId, "INVALID_ID"),
+            (ValidationError::InvalidTitle, "INVALID_TITLE"),
+            (ValidationError::InvalidDescription, "INVALID_DESCRIPTION"),
+            (
+                ValidationError::InvalidCommentContent,
+                "INVALID_COMMENT_CONTENT",
+            ),
+            (ValidationError::InvalidEmail, "INVALID_EMAIL"),
+            (ValidationError::InvalidQuery, "INVALID_QUERY"),
+            (ValidationError::InvalidPhoneNumber, "INVALID_PHONE_NUMBER"),
+            (
+                ValidationError::InvalidTwoFactorCode,
+                "INVALID_TWO_FACTOR_CODE",
+            ),
+            (
+                ValidationError::MissingReportDetails,
+                "MISSING_REPORT_DETAILS",
+            ),
+            (ValidationError::InvalidDraft, "INVALID_DRAFT"),
+            (ValidationError::InvalidFileName, "INVALID_FILE_NAME"),
+            (ValidationError::InvalidMimeType, "INVALID_MIME_TYPE"),
+            (ValidationError::InvalidDigest, "INVALID_DIGEST"),
+            (ValidationError::InvalidPoll, "INVALID_POLL"),
+            (ValidationError::InvalidVote, "INVALID_VOTE"),
+            (ValidationError::InvalidExcerpt, "INVALID_EXCERPT"),
+            (ValidationError::InvalidIpAddr, "INVALID_IP_ADDR"),
+            (ValidationError::InvalidDisplayName, "INVALID_DISPLAY_NAME"),
+            (ValidationError::InvalidUrl, "INVALID_URL"),
+            (ValidationError::InvalidCursor, "INVALID_CURSOR"),
+            (ValidationError::InvalidPagination, "INVALID_PAGINATION"),
+            (
+                ValidationError::InvalidIdempotencyKey,
+                "INVALID_IDEMPOTENCY_KEY",
+            ),
+            (ValidationError::InvalidDuration, "INVALID_DURATION"),
+            (ValidationError::InvalidListLength, "INVALID_LIST_LENGTH"),
+            (ValidationError::InvalidCountryCode, "INVALID_COUNTRY_CODE"),
+            (
+                ValidationError::InvalidAttachmentRef,
+                "INVALID_ATTACHMENT_REF",
+            ),
+        ];
+        for (error, expt) in codes {
+            assert_eq!(serde_json::to_value(error.code()).unwrap(), expt);
+        }
+    }
+
+    #[test]
+    fn code_is_separate_from_message() {
+        let error = Username::try_from("x".to_owned()).unwrap_err();
+        assert_eq!(error.code(), ErrorCode::InvalidUsername);
+        assert_eq!(error.message(), "invalid (badly formatted) username");
+    }
+
+    #[test]
+    fn field_error_is_serialized_with_the_code() {
+        let error = FieldError {
+            field: "username",
+            error: ValidationError::InvalidUsername,
+        };
+        assert_eq!(
+            serde_json::to_value(&error).unwrap(),
+            serde_json::json!({ "field": "username", "error": "INVALID_USERNAME" })
+        );
+    }
 }
@@ -0,0 +1,115 @@
+//! Validated phone numbers
+
+use super::ValidationError;
+use std::convert::TryFrom;
+use std::fmt::{self, Display};
+
+/// The minimum amount of digits in a phone number (excluding the `+`)
+const MIN_DIGITS: usize = 8;
+
+/// The maximum amount of digits in a phone number as defined by E.164
+const MAX_DIGITS: usize = 15;
+
+/// A valid (well formatted) phone number in E.164 format
+///
+/// Spaces, dashes, dots and parentheses are removed when the number is
+/// validated, so `+47 123 45 678` is stored as `+4712345678`. Only the shape
+/// of the number is validated, not whether it is in use.
+#[derive(PartialEq, PartialOrd, Eq, Ord, Debug, Clone)]
+pub struct PhoneNumber(String);
+
+impl TryFrom<String> for PhoneNumber {
+    type Error = ValidationError;
+    fn try_from(s: String) -> Result<Self, Self::Error> {
+        let normalized: String = s
+            .chars()
+            .filter(|c| !c.is_whitespace() && !"-.()".contains(*c))
+            .collect();
+
+        let valid = normalized.starts_with('+') && {
+            let digits = &normalized[1..];
+            digits.chars().all(|c| c.is_ascii_digit())
+                && !digits.starts_with('0')
+                && MIN_DIGITS <= digits.len()
+                && digits.len() <= MAX_DIGITS
+        };
+
+        if valid {
+            Ok(PhoneNumber(normalized))
+        } else {
+            Err(ValidationError::InvalidPhoneNumber)
+        }
+    }
+}
+
+impl<'a> TryFrom<&'a str> for PhoneNumber {
+    type Error = ValidationError;
+    fn try_from(s: &'a str) -> Result<Self, Self::Error> {
+        PhoneNumber::try_from(s.to_owned())
+    }
+}
+
+impl_deserialize_with_try_from!(PhoneNumber);
+impl_serialize!(PhoneNumber);
+impl_schema!(PhoneNumber => crate::schema::string().pattern(r"^\+"));
+impl_deref_and_as_ref!(PhoneNumber => str);
+impl_into_inner!(PhoneNumber => String);
+
+impl Display for PhoneNumber {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn valid_phone_number() {
+        let number = PhoneNumber::try_from("+4712345678").unwrap();
+        assert_eq!(&*number, "+4712345678");
+    }
+
+    #[test]
+    fn phone_number_is_normalized() {
+        let number = PhoneNumber::try_from("+47 (123) 45-678").unwrap();
+        assert_eq!(&*number, "+4712345678");
+    }
+
+    #[test]
+    fn phone_number_without_plus() {
+        assert_eq!(
+            PhoneNumber::try_from("4712345678"),
+            Err(ValidationError::InvalidPhoneNumber)
+        );
+    }
+
+    #[test]
+    fn phone_number_with_letters() {
+        assert_eq!(
+            PhoneNumber::try_from("+47123abc78"),
+            Err(ValidationError::InvalidPhoneNumber)
+        );
+    }
+
+    #[test]
+    fn phone_number_length_bounds() {
+        assert!(PhoneNumber::try_from("+4712345").is_err());
+        assert!(PhoneNumber::try_from("+4712345678901234").is_err());
+    }
+
+    #[test]
+    fn phone_number_deserialize() {
+        let number: PhoneNumber = serde_json::from_str(r#""+4712345678""#).unwrap();
+        assert_eq!(&*number, "+4712345678");
+        assert!(serde_json::from_str::<PhoneNumber>(r#""12345678""#).is_err());
+    }
+
+    proptest! {
+        #[test]
+        fn phone_number_doesnt_crash(s in "\\PC*") {
+            let _ = PhoneNumber::try_from(s);
+        }
+    }
+}
@@ -0,0 +1,169 @@
+//! Configurable limits of the validated fields
+
+/// The limits which the validated fields are checked against
+///
+/// Different deployments can have different limits, which are given to the
+/// `try_new_with_policy` constructors of the validated fields. The `TryFrom`
+/// implementations (and therefore deserialization) always use
+/// `ValidationPolicy::default()`.
+///
+/// The lengths of usernames, passwords and titles are counted in characters,
+/// the lengths of descriptions and comments in bytes. All the bounds are
+/// inclusive.
+///
+/// # Examples
+///
+/// ```
+/// # use datatypes::valid::fields::Username;
+/// # use datatypes::valid::policy::ValidationPolicy;
+/// let policy = ValidationPolicy {
+///     max_username_len: 16,
+///     ..ValidationPolicy::default()
+/// };
+/// assert!(Username::try_new_with_policy("averylongname", &policy).is_ok());
+/// let default = ValidationPolicy::default();
+/// assert!(Username::try_new_with_policy("averylongname", &default).is_err());
+/// ```
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+pub struct ValidationPolicy {
+    pub min_username_len: usize,
+    pub max_username_len: usize,
+    pub min_password_len: usize,
+    pub max_password_len: usize,
+    pub min_title_len: usize,
+    pub max_title_len: usize,
+    pub max_description_len: usize,
+    pub min_comment_len: usize,
+    pub max_comment_len: usize,
+}
+
+impl ValidationPolicy {
+    pub(crate) fn username_len(&self, len: usize) -> bool {
+        self.min_username_len <= len && len <= self.max_username_len
+    }
+
+    pub(crate) fn password_len(&self, len: usize) -> bool {
+        self.min_password_len <= len && len <= self.max_password_len
+    }
+
+    pub(crate) fn title_len(&self, len: usize) -> bool {
+        self.min_title_len <= len && len <= self.max_title_len
+    }
+
+    pub(crate) fn description_len(&self, len: usize) -> bool {
+        len <= self.max_description_len
+    }
+
+    pub(crate) fn comment_len(&self, len: usize) -> bool {
+        self.min_comment_len <= len && len <= self.max_comment_len
+    }
+}
+
+impl Default for ValidationPolicy {
+    fn default() -> Self {
+        ValidationPolicy {
+            min_username_len: 4,
+            max_username_len: 10,
+            min_password_len: 8,
+            max_password_len: 64,
+            min_title_len: 3,
+            max_title_len: 200,
+            max_description_len: 254,
+            min_comment_len: 5,
+            max_comment_len: 79,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::valid::fields::*;
+    use crate::valid::ValidationError;
+    use std::convert::TryFrom;
+
+    fn lenient() -> ValidationPolicy {
+        ValidationPolicy {
+            min_username_len: 2,
+            max_username_len: 16,
+            min_password_len: 6,
+            max_password_len: 128,
+            min_title_len: 1,
+            max_title_len: 300,
+            max_description_len: 1000,
+            min_comment_len: 1,
+            max_comment_len: 500,
+        }
+    }
+
+    #[test]
+    fn usernames_under_two_policies() {
+        let default = ValidationPolicy::default();
+        for s in &["jo", "averylongname"] {
+            let e = Username::try_new_with_policy(*s, &default).unwrap_err();
+            assert_eq!(e, ValidationError::InvalidUsername);
+            assert!(
+                Username::try_new_with_policy(*s, &lenient()).is_ok(),
+                "{}",
+                s
+            );
+        }
+        // The allowed characters are the same in every policy
+        assert!(Username::try_new_with_policy("jo hn", &lenient()).is_err());
+    }
+
+    #[test]
+    fn passwords_under_two_policies() {
+        let short = "hellO1";
+        let e = PlainPassword::try_new_with_policy(short, &ValidationPolicy::default());
+        assert_eq!(e.err(), Some(ValidationError::InvalidPassword));
+        assert!(PlainPassword::try_new_with_policy(short, &lenient()).is_ok());
+        // The password must still contain lowercase, uppercase and numbers
+        assert!(PlainPassword::try_new_with_policy("hellooo", &lenient()).is_err());
+    }
+
+    #[test]
+    fn titles_under_two_policies() {
+        let default = ValidationPolicy::default();
+        let long = "a".repeat(250);
+        for s in &["Hi", long.as_str()] {
+            let e = Title::try_new_with_policy(s, &default).unwrap_err();
+            assert_eq!(e, ValidationError::InvalidTitle);
+            assert!(Title::try_new_with_policy(s, &lenient()).is_ok());
+        }
+    }
+
+    #[test]
+    fn descriptions_under_two_policies() {
+        let long = "a".repeat(300);
+        let e = Description::try_new_with_policy(long.clone(), &ValidationPolicy::default());
+        assert_eq!(e.err(), Some(ValidationError::InvalidDescription));
+        assert!(Description::try_new_with_policy(long, &lenient()).is_ok());
+    }
+
+    #[test]
+    fn comments_under_two_policies() {
+        let default = ValidationPolicy::default();
+        let long = "a".repeat(200);
+        for s in &["Hi!", long.as_str()] {
+            let e = CommentContent::try_new_with_policy(*s, &default).unwrap_err();
+            assert_eq!(e, ValidationError::InvalidCommentContent);
+            assert!(CommentContent::try_new_with_policy(*s, &lenient()).is_ok());
+        }
+    }
+
+    #[test]
+    fn try_from_uses_the_default_policy() {
+        let default = ValidationPolicy::default();
+        for s in &["jo", "john", "johnjohnjo", "johnjohnjoh"] {
+            let with_policy = Username::try_new_with_policy(*s, &default);
+            assert_eq!(Username::try_from(s.to_string()), with_policy, "{}", s);
+        }
+        let s = "a".repeat(default.max_description_len);
+        assert!(Description::try_from(s.clone()).is_ok());
+        assert!(Description::try_from(s + "a").is_err());
+        let s = "a".repeat(default.max_comment_len);
+        assert!(CommentContent::try_from(s.clone()).is_ok());
+        assert!(CommentContent::try_from(s + "a").is_err());
+    }
+}
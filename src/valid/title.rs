@@ -0,0 +1,192 @@
+//! Validated titles of threads, categories and polls
+
+use super::policy::ValidationPolicy;
+use super::ValidationError;
+use htmlescape::encode_minimal;
+use std::convert::TryFrom;
+use std::fmt::{self, Display};
+
+/// A valid (well formatted) title
+///
+/// Whitespace around the title is removed when it is validated, so
+/// `"  A title "` is stored as `"A title"`. The length is counted in
+/// characters (not bytes) after trimming, and a title can't contain control
+/// characters (like newlines). The title is HTML-escaped after it has been
+/// validated, and serialized without the escaping.
+#[derive(PartialEq, PartialOrd, Eq, Ord, Debug, Clone)]
+#[cfg_attr(feature = "diesel", derive(AsExpression, FromSqlRow))]
+#[cfg_attr(feature = "diesel", sql_type = "diesel::sql_types::Text")]
+pub struct Title(String);
+
+impl Title {
+    /// Validate a title with the limits of `policy`
+    pub fn try_new_with_policy(
+        s: &str,
+        policy: &ValidationPolicy,
+    ) -> Result<Self, ValidationError> {
+        let trimmed = s.trim();
+        let len = trimmed.chars().count();
+
+        if policy.title_len(len) && !trimmed.chars().any(char::is_control) {
+            Ok(Title(encode_minimal(trimmed)))
+        } else {
+            Err(ValidationError::InvalidTitle)
+        }
+    }
+
+    /// Validate a title which is already HTML-escaped, like a stored title
+    ///
+    /// The title is decoded before it is validated, so it isn't escaped twice.
+    pub fn from_escaped(s: &str) -> Result<Self, ValidationError> {
+        let decoded = htmlescape::decode_html(s).map_err(|_| ValidationError::InvalidTitle)?;
+        Title::try_from(decoded)
+    }
+
+    /// The title as it was given (but trimmed), without the HTML-escaping
+    pub fn unescaped(&self) -> String {
+        htmlescape::decode_html(&self.0).unwrap_or_else(|_| self.0.clone())
+    }
+}
+
+impl TryFrom<String> for Title {
+    type Error = ValidationError;
+    fn try_from(s: String) -> Result<Self, Self::Error> {
+        Title::try_from(s.as_str())
+    }
+}
+
+impl<'a> TryFrom<&'a str> for Title {
+    type Error = ValidationError;
+    fn try_from(s: &'a str) -> Result<Self, Self::Error> {
+        Title::try_new_with_policy(s, &ValidationPolicy::default())
+    }
+}
+
+impl_deserialize_with_try_from!(Title);
+impl_serialize!(Title, escaped);
+impl_schema!(Title => {
+    let policy = ValidationPolicy::default();
+    crate::schema::string().length(policy.min_title_len, policy.max_title_len)
+});
+impl_deref_and_as_ref!(Title => str);
+impl_into_inner!(Title => String);
+
+impl Display for Title {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MAX_CHARS: usize = 200;
+
+    #[test]
+    fn max_chars_is_the_default_policy() {
+        assert_eq!(ValidationPolicy::default().max_title_len, MAX_CHARS);
+    }
+
+    #[test]
+    fn valid_titles() {
+        for s in &["Hey", "Just a question.", "Hello, I love you all guys!"] {
+            let title = Title::try_from(*s).unwrap();
+            assert_eq!(&*title, *s);
+        }
+    }
+
+    #[test]
+    fn empty_title() {
+        for s in &["", "   ", "\t\n"] {
+            assert_eq!(
+                Title::try_from(*s),
+                Err(ValidationError::InvalidTitle),
+                "{:?}",
+                s
+            );
+        }
+    }
+
+    #[test]
+    fn title_length_bounds() {
+        assert!(Title::try_from("Hi").is_err());
+        assert!(Title::try_from("a".repeat(MAX_CHARS)).is_ok());
+        assert!(Title::try_from("a".repeat(MAX_CHARS + 1)).is_err());
+        assert!(Title::try_from("a".repeat(5000)).is_err());
+    }
+
+    #[test]
+    fn title_length_is_counted_in_chars() {
+        assert!(Title::try_from("æøå").is_ok());
+        assert!(Title::try_from("å".repeat(MAX_CHARS)).is_ok());
+    }
+
+    #[test]
+    fn title_is_unescaped() {
+        let title = Title::try_from(" Tom & Jerry <3 ").unwrap();
+        assert_eq!(&*title, "Tom &amp; Jerry &lt;3");
+        assert_eq!(title.unescaped(), "Tom & Jerry <3");
+        let title = Title::try_from("Jerry's \"show\" </b>").unwrap();
+        assert_eq!(title.unescaped(), "Jerry's \"show\" </b>");
+        assert_eq!(Title::from_escaped(&title), Ok(title));
+    }
+
+    #[test]
+    fn title_is_trimmed() {
+        let title = Title::try_from("  A title \n").unwrap();
+        assert_eq!(&*title, "A title");
+        // The whitespace doesn't count towards the length
+        assert!(Title::try_from("  Hi  ").is_err());
+    }
+
+    #[test]
+    fn title_with_control_characters() {
+        for s in &["A\ntitle", "A\u{0}title", "A title\u{7f}!"] {
+            assert_eq!(
+                Title::try_from(*s),
+                Err(ValidationError::InvalidTitle),
+                "{:?}",
+                s
+            );
+        }
+    }
+
+    #[test]
+    fn title_is_escaped() {
+        let title = Title::try_from("A title with \"quoutes\"").unwrap();
+        assert_eq!(&*title, "A title with &quot;quoutes&quot;");
+        let title = Title::try_from("A title with <script>").unwrap();
+        assert_eq!(&*title, "A title with &lt;script&gt;");
+    }
+
+    #[test]
+    fn title_from_escaped() {
+        let title = Title::try_from("A title with <script>").unwrap();
+        assert_eq!(Title::from_escaped(&title), Ok(title));
+        assert!(Title::from_escaped("Hi").is_err());
+    }
+
+    #[test]
+    fn title_deserialize() {
+        let title: Title = serde_json::from_str(r#"" A thread ""#).unwrap();
+        assert_eq!(&*title, "A thread");
+        assert!(serde_json::from_str::<Title>(r#""""#).is_err());
+    }
+
+    #[test]
+    fn title_serde_round_trip() {
+        let title = Title::try_from("Tom & Jerry <3").unwrap();
+        let json = serde_json::to_string(&title).unwrap();
+        assert_eq!(json, r#""Tom & Jerry <3""#);
+        let decoded: Title = serde_json::from_str(&json).unwrap();
+        assert_eq!(decoded, title);
+    }
+
+    proptest! {
+        #[test]
+        fn title_doesnt_crash(s in "\\PC*") {
+            let _ = Title::try_from(s);
+        }
+    }
+}
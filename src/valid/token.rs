@@ -1,17 +1,119 @@
+#[cfg(all(feature = "rocket", not(target_arch = "wasm32")))]
 use crate::error::ResponseError;
-use rocket::http::{Cookie, Status};
-use rocket::request::{FromRequest, Outcome as RequestOutcome, Request};
+#[cfg(all(feature = "rocket", not(target_arch = "wasm32")))]
+use rocket::http::{Cookie, RawStr, Status};
+#[cfg(all(feature = "rocket", not(target_arch = "wasm32")))]
+use rocket::request::{FromFormValue, FromParam, FromRequest, Outcome as RequestOutcome, Request};
+#[cfg(all(feature = "rocket", not(target_arch = "wasm32")))]
 use rocket::Outcome;
 use std::convert::{AsRef, From};
 
+/// The name of the private cookie which contains the token of a session in
+/// rocket (see `session::SessionToken`)
 pub const USER_TOKEN_NAME: &str = "user_token";
 
+/// The name of the plain cookie which contains the token of a session, in the
+/// servers which can't read the private cookies of rocket (like actix and
+/// axum), unless a server is configured with another name
+pub const DEFAULT_SESSION_COOKIE: &str = "session";
+
+/// The scheme of a `Authorization` header which contains a token
+const BEARER_PREFIX: &str = "Bearer ";
+
+/// Why a token could not be parsed from a `Authorization` header
+#[derive(Fail, PartialEq, Eq, Clone, Copy, Debug)]
+pub enum TokenParseError {
+    #[fail(display = "authorization header is not a bearer token")]
+    MissingPrefix,
+    #[fail(display = "bearer token is empty")]
+    EmptyToken,
+    #[fail(display = "bearer token contains invalid characters")]
+    InvalidToken,
+}
+
+/// The amount of bytes of a token which are stored inline (without a heap
+/// allocation) when the `small-token` feature is enabled
+pub const INLINE_TOKEN_LEN: usize = 64;
+
+#[cfg(not(feature = "small-token"))]
+type TokenStr = String;
+
+/// Tokens up to `INLINE_TOKEN_LEN` bytes are stored inline, longer tokens
+/// are moved to the heap
+#[cfg(feature = "small-token")]
+type TokenStr = smallstr::SmallString<[u8; INLINE_TOKEN_LEN]>;
+
+/// A token which identifies a user
+///
+/// With the `small-token` feature the token is stored inline, so parsing,
+/// deserializing and cloning a token of a typical length doesn't allocate.
+/// The api and the serialized form are the same with and without the feature.
 #[derive(Serialize, Deserialize, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Clone)]
-pub struct Token(String);
+#[cfg_attr(feature = "diesel", derive(AsExpression, FromSqlRow))]
+#[cfg_attr(feature = "diesel", sql_type = "diesel::sql_types::Text")]
+pub struct Token(TokenStr);
 
 impl Token {
     pub fn new(token: impl Into<String>) -> Self {
-        Token(token.into())
+        Token(TokenStr::from(token.into()))
+    }
+
+    /// The token as a owned string
+    #[cfg(not(feature = "small-token"))]
+    fn into_string(self) -> String {
+        self.0
+    }
+
+    /// The token as a owned string
+    #[cfg(feature = "small-token")]
+    fn into_string(self) -> String {
+        self.0.into_string()
+    }
+
+    /// Parse a token from the value of a `Authorization` header
+    ///
+    /// The `Bearer` scheme is case insensitive, and the token must only
+    /// contain the characters of a `b64token` (RFC 6750).
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use datatypes::valid::token::Token;
+    /// let token = Token::from_bearer("Bearer abc").unwrap();
+    /// assert_eq!(token, Token::new("abc"));
+    /// assert_eq!(token.to_bearer(), "Bearer abc");
+    /// ```
+    pub fn from_bearer(header: &str) -> Result<Token, TokenParseError> {
+        let header = header.trim();
+        let prefix = header.get(..BEARER_PREFIX.len());
+        let has_prefix = prefix.map_or(false, |p| p.eq_ignore_ascii_case(BEARER_PREFIX));
+        // A header with only the scheme has its trailing space trimmed away
+        if !has_prefix && !header.eq_ignore_ascii_case(BEARER_PREFIX.trim_end()) {
+            return Err(TokenParseError::MissingPrefix);
+        }
+
+        let token = header.get(BEARER_PREFIX.len()..).unwrap_or("").trim_start();
+        Token::from_b64token(token)
+    }
+
+    /// Parse a token which must only contain the characters of a `b64token`
+    pub(crate) fn from_b64token(token: &str) -> Result<Token, TokenParseError> {
+        let is_b64token = token
+            .trim_end_matches('=')
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || "-._~+/".contains(c));
+        if token.is_empty() {
+            Err(TokenParseError::EmptyToken)
+        } else if !is_b64token {
+            Err(TokenParseError::InvalidToken)
+        } else {
+            Ok(Token(TokenStr::from(token)))
+        }
+    }
+
+    /// The value of a `Authorization` header which contains the token
+    pub fn to_bearer(&self) -> String {
+        format!("{}{}", BEARER_PREFIX, self.0)
     }
 }
 
@@ -21,24 +123,34 @@ impl AsRef<str> for Token {
     }
 }
 
+// The characters of a `b64token`, see `from_b64token`. A token is only ever
+// sent by a client, so it is write-only
+impl_schema!(Token => crate::schema::string()
+    .min_length(1)
+    .pattern("^[A-Za-z0-9._~+/-]+=*$")
+    .write_only());
+#[cfg(all(feature = "rocket", not(target_arch = "wasm32")))]
 impl<'a> From<&'a Cookie<'a>> for Token {
     fn from(c: &'a Cookie<'a>) -> Self {
-        Token::new(c.value())
+        Token(TokenStr::from(c.value()))
     }
 }
 
+#[cfg(all(feature = "rocket", not(target_arch = "wasm32")))]
 impl<'a> From<Cookie<'a>> for Token {
     fn from(c: Cookie<'a>) -> Self {
-        Token::new(c.value().to_owned())
+        Token(TokenStr::from(c.value()))
     }
 }
 
+#[cfg(all(feature = "rocket", not(target_arch = "wasm32")))]
 impl<'a> Into<Cookie<'a>> for Token {
     fn into(self) -> Cookie<'a> {
-        Cookie::new(USER_TOKEN_NAME, self.0)
+        Cookie::new(USER_TOKEN_NAME, self.into_string())
     }
 }
 
+#[cfg(all(feature = "rocket", not(target_arch = "wasm32")))]
 impl<'a, 'r> FromRequest<'a, 'r> for Token {
     type Error = ResponseError;
 
@@ -51,3 +163,148 @@ impl<'a, 'r> FromRequest<'a, 'r> for Token {
             })
     }
 }
+
+/// A token in a path, like `/sessions/<token>`
+///
+/// The token is url decoded, and must only contain the characters of a
+/// `b64token` like the token of a `Authorization` header.
+///
+/// # Examples
+///
+/// ```
+/// # use datatypes::valid::token::Token;
+/// use rocket::http::RawStr;
+/// use rocket::request::FromParam;
+///
+/// let token = Token::from_param(RawStr::from_str("abc%2B123")).unwrap();
+/// assert_eq!(token, Token::new("abc+123"));
+/// assert!(Token::from_param(RawStr::from_str("abc%20123")).is_err());
+/// ```
+#[cfg(all(feature = "rocket", not(target_arch = "wasm32")))]
+impl<'a> FromParam<'a> for Token {
+    type Error = TokenParseError;
+    fn from_param(s: &'a RawStr) -> Result<Self, Self::Error> {
+        let decoded = s.url_decode().map_err(|_| TokenParseError::InvalidToken)?;
+        Token::from_b64token(&decoded)
+    }
+}
+
+/// A token in a form, which is parsed like a token in a path
+#[cfg(all(feature = "rocket", not(target_arch = "wasm32")))]
+impl<'a> FromFormValue<'a> for Token {
+    type Error = TokenParseError;
+    fn from_form_value(s: &'a RawStr) -> Result<Self, Self::Error> {
+        Token::from_param(s)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bearer_prefix() {
+        assert_eq!(Token::from_bearer("Bearer abc"), Ok(Token::new("abc")));
+    }
+
+    #[test]
+    fn lowercase_bearer_prefix() {
+        assert_eq!(Token::from_bearer("bearer abc"), Ok(Token::new("abc")));
+        assert_eq!(Token::from_bearer("BEARER abc"), Ok(Token::new("abc")));
+    }
+
+    #[test]
+    fn missing_bearer_prefix() {
+        assert_eq!(
+            Token::from_bearer("abc"),
+            Err(TokenParseError::MissingPrefix)
+        );
+        assert_eq!(
+            Token::from_bearer("Basic YWJjOmRlZg=="),
+            Err(TokenParseError::MissingPrefix)
+        );
+    }
+
+    #[test]
+    fn empty_bearer_token() {
+        assert_eq!(
+            Token::from_bearer("Bearer "),
+            Err(TokenParseError::EmptyToken)
+        );
+        assert_eq!(
+            Token::from_bearer("Bearer"),
+            Err(TokenParseError::EmptyToken)
+        );
+    }
+
+    #[test]
+    fn invalid_bearer_token() {
+        assert_eq!(
+            Token::from_bearer("Bearer a b"),
+            Err(TokenParseError::InvalidToken)
+        );
+    }
+
+    #[test]
+    fn to_bearer_round_trip() {
+        let token = Token::new("c2VjcmV0==");
+        assert_eq!(Token::from_bearer(&token.to_bearer()), Ok(token));
+    }
+
+    #[test]
+    fn token_is_serialized_as_a_string() {
+        let token = Token::new("c2VjcmV0");
+        assert_eq!(serde_json::to_string(&token).unwrap(), r#""c2VjcmV0""#);
+        let parsed: Token = serde_json::from_str(r#""c2VjcmV0""#).unwrap();
+        assert_eq!(parsed, token);
+    }
+
+    #[test]
+    #[cfg(feature = "small-token")]
+    fn typical_token_is_inline() {
+        let header = "Bearer q83vEjRWeJq7zN7/ASNFZ4mrze8BI0VniavN7wEjRWc=";
+        let token = Token::from_bearer(header).unwrap();
+        assert!(!token.0.spilled());
+        assert!(!token.clone().0.spilled());
+        let json = serde_json::to_string(&token).unwrap();
+        let parsed: Token = serde_json::from_str(&json).unwrap();
+        assert!(!parsed.0.spilled());
+    }
+
+    #[test]
+    fn long_token() {
+        // Longer than what is stored inline with the `small-token` feature
+        let long = "a".repeat(INLINE_TOKEN_LEN * 2);
+        let token = Token::from_bearer(&format!("Bearer {}", long)).unwrap();
+        assert_eq!(token.as_ref(), long.as_str());
+        assert_eq!(token.clone(), token);
+    }
+
+    #[test]
+    #[cfg(all(feature = "rocket", not(target_arch = "wasm32")))]
+    fn cookie_round_trip() {
+        let token = Token::new("c2VjcmV0");
+        let cookie: Cookie = token.clone().into();
+        assert_eq!(cookie.name(), USER_TOKEN_NAME);
+        assert_eq!(Token::from(cookie), token);
+    }
+
+    #[test]
+    #[cfg(all(feature = "rocket", not(target_arch = "wasm32")))]
+    fn token_from_param_and_form_value() {
+        let raw = RawStr::from_str("c2VjcmV0%3D%3D");
+        assert_eq!(Token::from_param(raw), Ok(Token::new("c2VjcmV0==")));
+        assert_eq!(Token::from_form_value(raw), Ok(Token::new("c2VjcmV0==")));
+
+        let raw = RawStr::from_str("");
+        assert_eq!(Token::from_param(raw), Err(TokenParseError::EmptyToken));
+        for s in &["a%20b", "a;b", "%FF"] {
+            let raw = RawStr::from_str(s);
+            assert_eq!(Token::from_param(raw), Err(TokenParseError::InvalidToken));
+            assert_eq!(
+                Token::from_form_value(raw),
+                Err(TokenParseError::InvalidToken)
+            );
+        }
+    }
+}
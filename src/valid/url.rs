@@ -0,0 +1,106 @@
+//! Validated urls
+
+use super::ValidationError;
+use std::convert::TryFrom;
+use std::fmt::{self, Display};
+
+/// The schemes which a url can have
+const ALLOWED_SCHEMES: &[&str] = &["http", "https"];
+
+/// A valid (well formatted) `http` or `https` url
+///
+/// Other schemes, like `javascript:` or `data:`, are rejected so that a url
+/// can be safely used as a link.
+#[derive(PartialEq, PartialOrd, Eq, Ord, Debug, Clone, Hash)]
+pub struct Url(::url::Url);
+
+impl<'a> TryFrom<&'a str> for Url {
+    type Error = ValidationError;
+    fn try_from(s: &'a str) -> Result<Self, Self::Error> {
+        let url = ::url::Url::parse(s).map_err(|_| ValidationError::InvalidUrl)?;
+        if ALLOWED_SCHEMES.contains(&url.scheme()) && url.has_host() {
+            Ok(Url(url))
+        } else {
+            Err(ValidationError::InvalidUrl)
+        }
+    }
+}
+
+impl TryFrom<String> for Url {
+    type Error = ValidationError;
+    fn try_from(s: String) -> Result<Self, Self::Error> {
+        Url::try_from(s.as_str())
+    }
+}
+
+impl_deserialize_with_try_from!(Url);
+
+impl serde::Serialize for Url {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        serializer.serialize_str(self.0.as_str())
+    }
+}
+
+impl_schema!(Url => crate::schema::string().format("uri"));
+impl_deref_and_as_ref!(Url => ::url::Url);
+impl_into_inner!(Url => ::url::Url);
+
+impl AsRef<str> for Url {
+    fn as_ref(&self) -> &str {
+        self.0.as_str()
+    }
+}
+
+impl Display for Url {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn https_url_is_valid() {
+        let url = Url::try_from("https://example.com/avatar.png?size=64").unwrap();
+        assert_eq!(url.scheme(), "https");
+        assert_eq!(url.host_str(), Some("example.com"));
+    }
+
+    #[test]
+    fn http_url_is_valid() {
+        assert!(Url::try_from("http://example.com").is_ok());
+    }
+
+    #[test]
+    fn javascript_url_is_rejected() {
+        assert_eq!(
+            Url::try_from("javascript:alert(1)"),
+            Err(ValidationError::InvalidUrl)
+        );
+        assert_eq!(
+            Url::try_from("ftp://example.com/file"),
+            Err(ValidationError::InvalidUrl)
+        );
+    }
+
+    #[test]
+    fn malformed_url_is_rejected() {
+        for s in &["", "example.com", "https://", "http://exa mple.com"] {
+            assert_eq!(Url::try_from(*s), Err(ValidationError::InvalidUrl), "{}", s);
+        }
+    }
+
+    #[test]
+    fn url_serde_round_trip() {
+        let url = Url::try_from("https://example.com/a").unwrap();
+        let json = serde_json::to_string(&url).unwrap();
+        assert_eq!(json, r#""https://example.com/a""#);
+        assert_eq!(serde_json::from_str::<Url>(&json).unwrap(), url);
+        assert!(serde_json::from_str::<Url>(r#""javascript:alert(1)""#).is_err());
+    }
+}
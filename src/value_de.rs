@@ -0,0 +1,216 @@
+//! A deserializer of a json value, where a error tells if a field was missing
+//! (see `payloads::parse_request`)
+//!
+//! serde_json only tells the kind of a data error in its message. serde hands
+//! a missing field to the error of the deserializer (`Error::missing_field`),
+//! so a deserializer with its own error can keep the field instead of the
+//! message. The value is deserialized just as `serde_json::from_value` does,
+//! and a value which serde buffers (like a flattened struct) is deserialized
+//! with the same error.
+
+use serde::de::value::{MapDeserializer, SeqDeserializer};
+use serde::de::{
+    self, DeserializeSeed, Deserializer, EnumAccess, IntoDeserializer, VariantAccess, Visitor,
+};
+use serde_json::{Map, Value};
+use std::fmt;
+
+/// The error of a `ValueDeserializer`
+#[derive(PartialEq, Clone, Debug)]
+pub(crate) enum DeError {
+    /// A field (of the struct at the path of the error) was missing
+    MissingField(&'static str),
+    /// Any other error, by its message
+    Custom(String),
+}
+
+impl fmt::Display for DeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            DeError::MissingField(field) => write!(f, "missing field `{}`", field),
+            DeError::Custom(message) => f.write_str(message),
+        }
+    }
+}
+
+impl std::error::Error for DeError {}
+
+impl de::Error for DeError {
+    fn custom<T: fmt::Display>(msg: T) -> Self {
+        DeError::Custom(msg.to_string())
+    }
+
+    fn missing_field(field: &'static str) -> Self {
+        DeError::MissingField(field)
+    }
+}
+
+/// A deserializer of a json value
+pub(crate) struct ValueDeserializer(Value);
+
+impl ValueDeserializer {
+    pub(crate) fn new(value: Value) -> Self {
+        ValueDeserializer(value)
+    }
+}
+
+impl<'de> IntoDeserializer<'de, DeError> for ValueDeserializer {
+    type Deserializer = Self;
+    fn into_deserializer(self) -> Self {
+        self
+    }
+}
+
+fn visit_array<'de, V>(array: Vec<Value>, visitor: V) -> Result<V::Value, DeError>
+where
+    V: Visitor<'de>,
+{
+    let mut seq = SeqDeserializer::<_, DeError>::new(array.into_iter().map(ValueDeserializer));
+    let value = visitor.visit_seq(&mut seq)?;
+    seq.end()?;
+    Ok(value)
+}
+
+fn visit_object<'de, V>(object: Map<String, Value>, visitor: V) -> Result<V::Value, DeError>
+where
+    V: Visitor<'de>,
+{
+    let entries = object
+        .into_iter()
+        .map(|(key, value)| (key, ValueDeserializer(value)));
+    let mut map = MapDeserializer::<_, DeError>::new(entries);
+    let value = visitor.visit_map(&mut map)?;
+    map.end()?;
+    Ok(value)
+}
+
+impl<'de> Deserializer<'de> for ValueDeserializer {
+    type Error = DeError;
+
+    fn deserialize_any<V>(self, visitor: V) -> Result<V::Value, DeError>
+    where
+        V: Visitor<'de>,
+    {
+        match self.0 {
+            Value::Null => visitor.visit_unit(),
+            Value::Bool(b) => visitor.visit_bool(b),
+            Value::Number(n) => match (n.as_u64(), n.as_i64(), n.as_f64()) {
+                (Some(n), _, _) => visitor.visit_u64(n),
+                (_, Some(n), _) => visitor.visit_i64(n),
+                (_, _, Some(n)) => visitor.visit_f64(n),
+                _ => Err(de::Error::custom(format!("invalid number {}", n))),
+            },
+            Value::String(s) => visitor.visit_string(s),
+            Value::Array(array) => visit_array(array, visitor),
+            Value::Object(object) => visit_object(object, visitor),
+        }
+    }
+
+    fn deserialize_option<V>(self, visitor: V) -> Result<V::Value, DeError>
+    where
+        V: Visitor<'de>,
+    {
+        match self.0 {
+            Value::Null => visitor.visit_none(),
+            _ => visitor.visit_some(self),
+        }
+    }
+
+    fn deserialize_newtype_struct<V>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, DeError>
+    where
+        V: Visitor<'de>,
+    {
+        visitor.visit_newtype_struct(self)
+    }
+
+    /// A enum is either the name of a unit variant, or a object with the name
+    /// of the variant as its only key
+    fn deserialize_enum<V>(
+        self,
+        _name: &'static str,
+        _variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, DeError>
+    where
+        V: Visitor<'de>,
+    {
+        match self.0 {
+            Value::String(variant) => visitor.visit_enum(Variant {
+                variant,
+                value: Value::Null,
+            }),
+            Value::Object(object) => {
+                let mut entries = object.into_iter();
+                match (entries.next(), entries.next()) {
+                    (Some((variant, value)), None) => {
+                        visitor.visit_enum(Variant { variant, value })
+                    }
+                    _ => Err(de::Error::custom("expected a object with a single key")),
+                }
+            }
+            other => ValueDeserializer(other).deserialize_any(visitor),
+        }
+    }
+
+    serde::forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 u8 u16 u32 u64 f32 f64 char str string bytes
+        byte_buf unit unit_struct seq tuple tuple_struct map struct identifier
+        ignored_any
+    }
+}
+
+/// A variant of a enum, by its name and its value
+struct Variant {
+    variant: String,
+    value: Value,
+}
+
+impl<'de> EnumAccess<'de> for Variant {
+    type Error = DeError;
+    type Variant = ValueDeserializer;
+
+    fn variant_seed<V>(self, seed: V) -> Result<(V::Value, Self::Variant), DeError>
+    where
+        V: DeserializeSeed<'de>,
+    {
+        let name: de::value::StringDeserializer<DeError> = self.variant.into_deserializer();
+        Ok((seed.deserialize(name)?, ValueDeserializer(self.value)))
+    }
+}
+
+impl<'de> VariantAccess<'de> for ValueDeserializer {
+    type Error = DeError;
+
+    fn unit_variant(self) -> Result<(), DeError> {
+        de::Deserialize::deserialize(self)
+    }
+
+    fn newtype_variant_seed<T>(self, seed: T) -> Result<T::Value, DeError>
+    where
+        T: DeserializeSeed<'de>,
+    {
+        seed.deserialize(self)
+    }
+
+    fn tuple_variant<V>(self, _len: usize, visitor: V) -> Result<V::Value, DeError>
+    where
+        V: Visitor<'de>,
+    {
+        self.deserialize_seq(visitor)
+    }
+
+    fn struct_variant<V>(
+        self,
+        _fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, DeError>
+    where
+        V: Visitor<'de>,
+    {
+        self.deserialize_map(visitor)
+    }
+}
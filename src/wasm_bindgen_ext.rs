@@ -0,0 +1,183 @@
+//! Bindings of the validation for JavaScript, for a frontend which is built
+//! with wasm-bindgen
+//!
+//! | function             | JavaScript         | error                             |
+//! |----------------------|--------------------|-----------------------------------|
+//! | `validate_username`  | `validateUsername` | the code, like `INVALID_USERNAME` |
+//! | `validate_email`     | `validateEmail`    | the code, like `INVALID_EMAIL`    |
+//! | `parse_auth_request` | `parseAuthRequest` | the `ParseError` as a object      |
+//!
+//! The fields are validated exactly like they are by the services, with the
+//! default `ValidationPolicy`. A request which is parsed is given back as a
+//! object in its serialized form (with its `type` and `payload`), but with the
+//! text of its fields as it was given, and not HTML-escaped like in json.
+//!
+//! The default features which need a server (`rocket` and `tarpc`) are left
+//! out on wasm32, so the crate is built for the browser with:
+//!
+//! ```text
+//! cargo build --target wasm32-unknown-unknown --features wasm-bindgen
+//! ```
+
+use crate::auth::requests::AuthRequest;
+use crate::error::ParseError;
+use crate::valid::fields::{Email, Username};
+use crate::valid::ValidationError;
+use std::convert::TryFrom;
+use wasm_bindgen::prelude::*;
+
+fn check_username(s: &str) -> Result<(), ValidationError> {
+    Username::try_from(s.to_owned()).map(|_| ())
+}
+
+fn check_email(s: &str) -> Result<(), ValidationError> {
+    Email::try_from(s.to_owned()).map(|_| ())
+}
+
+fn parse(json: &str) -> Result<AuthRequest, ParseError> {
+    AuthRequest::parse_json(json)
+}
+
+/// The code of a `ValidationError` as a JavaScript string
+fn code(e: ValidationError) -> JsValue {
+    JsValue::from_str(e.code().as_str())
+}
+
+/// A value as a JavaScript object
+fn to_js<T: serde::Serialize>(value: &T) -> Result<JsValue, JsValue> {
+    serde_wasm_bindgen::to_value(value).map_err(JsValue::from)
+}
+
+/// A request in its serialized form, with every string unescaped
+///
+/// The fields which are serialized as they are (like a token) never contain
+/// a HTML entity, so only the escaped fields are changed.
+fn unescaped(request: &AuthRequest) -> serde_json::Value {
+    let mut value = serde_json::to_value(request).expect("a request is always serializable");
+    unescape(&mut value);
+    value
+}
+
+fn unescape(value: &mut serde_json::Value) {
+    use serde_json::Value;
+    match value {
+        Value::String(s) => {
+            if let Ok(decoded) = htmlescape::decode_html(s) {
+                *s = decoded;
+            }
+        }
+        Value::Array(items) => items.iter_mut().for_each(unescape),
+        Value::Object(map) => map.values_mut().for_each(unescape),
+        Value::Null | Value::Bool(_) | Value::Number(_) => {}
+    }
+}
+
+/// Check a username, where a error is its code
+#[wasm_bindgen(js_name = validateUsername)]
+pub fn validate_username(s: &str) -> Result<(), JsValue> {
+    check_username(s).map_err(code)
+}
+
+/// Check a email, where a error is its code
+#[wasm_bindgen(js_name = validateEmail)]
+pub fn validate_email(s: &str) -> Result<(), JsValue> {
+    check_email(s).map_err(code)
+}
+
+/// Parse a auth request from json, where a error is the `ParseError` as a
+/// object (like `{ request_type, field, kind }`)
+#[wasm_bindgen(js_name = parseAuthRequest)]
+pub fn parse_auth_request(json: &str) -> Result<JsValue, JsValue> {
+    match parse(json) {
+        Ok(request) => {
+            // A json map is a JavaScript object (and not a `Map`)
+            let serializer = serde_wasm_bindgen::Serializer::json_compatible();
+            serde::Serialize::serialize(&unescaped(&request), &serializer).map_err(JsValue::from)
+        }
+        Err(e) => Err(to_js(&e)?),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::ParseErrorKind;
+
+    #[test]
+    fn usernames() {
+        assert_eq!(check_username("john"), Ok(()));
+        assert_eq!(check_username("j"), Err(ValidationError::InvalidUsername));
+        assert_eq!(
+            check_username("jo hn"),
+            Err(ValidationError::InvalidUsername)
+        );
+    }
+
+    #[test]
+    fn emails() {
+        assert_eq!(check_email("john@doe.com"), Ok(()));
+        assert_eq!(check_email("john"), Err(ValidationError::InvalidEmail));
+    }
+
+    #[test]
+    fn auth_requests() {
+        let json = r#"{ "type": "AUTHENTICATE", "payload": { "username": "john", "password": "helloAndWelcome123" } }"#;
+        match parse(json).unwrap() {
+            AuthRequest::Authenticate(payload) => assert_eq!(&*payload.username, "john"),
+            request => panic!("{:?}", request),
+        }
+
+        let json = r#"{ "type": "AUTHENTICATE", "payload": { "password": "helloAndWelcome123" } }"#;
+        let e = parse(json).unwrap_err();
+        assert_eq!(e.kind, ParseErrorKind::MissingField);
+        assert_eq!(e.field.as_ref().map(String::as_str), Some("username"));
+    }
+
+    #[test]
+    fn requests_are_unescaped() {
+        let json = r#"{ "type": "AUTHENTICATE", "payload": { "username": "john", "password": "helloAndWelcome123" } }"#;
+        let value = unescaped(&parse(json).unwrap());
+        assert_eq!(
+            value,
+            serde_json::from_str::<serde_json::Value>(json).unwrap()
+        );
+
+        let mut value = serde_json::json!({ "a": ["&lt;b&gt; &amp; &#x27;c&#x27;", 1], "d": "e" });
+        unescape(&mut value);
+        assert_eq!(
+            value,
+            serde_json::json!({ "a": ["<b> & 'c'", 1], "d": "e" })
+        );
+    }
+}
+
+/// The bindings themselves, which only run in a JavaScript runtime (with
+/// `wasm-pack test --node -- --features wasm-bindgen`)
+#[cfg(all(test, target_arch = "wasm32"))]
+mod wasm_tests {
+    use super::*;
+    use wasm_bindgen_test::wasm_bindgen_test;
+
+    #[wasm_bindgen_test]
+    fn validate_username_binding() {
+        assert!(validate_username("john").is_ok());
+        let e = validate_username("j").unwrap_err();
+        assert_eq!(
+            e.as_string().as_ref().map(String::as_str),
+            Some("INVALID_USERNAME")
+        );
+    }
+
+    #[wasm_bindgen_test]
+    fn validate_email_binding() {
+        assert!(validate_email("john@doe.com").is_ok());
+        assert!(validate_email("john").is_err());
+    }
+
+    #[wasm_bindgen_test]
+    fn parse_auth_request_binding() {
+        let json = r#"{ "type": "WHO_AM_I" }"#;
+        assert!(parse_auth_request(json).unwrap().is_object());
+        assert!(parse_auth_request("{").unwrap_err().is_object());
+    }
+}
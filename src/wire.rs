@@ -0,0 +1,1167 @@
+//! Golden tests of the serialized errors and payloads
+//!
+//! # Stability
+//!
+//! The serialized form of the errors (the variant tags and the field names)
+//! is a part of the public API, which clients like the mobile app depend on.
+//! A serialized error must never change, variants can only be added. The
+//! fixtures in this module pin every variant, which means that a rename makes
+//! the tests fail.
+//!
+//! All the payloads use `snake_case` field names (`#[serde(rename_all =
+//! "snake_case")]`), which integrations depend on. The field names of every
+//! payload are pinned here in the same way.
+//!
+//! Optional fields can be left out by clients, and are left out (instead of
+//! being serialized as `null`) when they are `None`. The minimal JSON of every
+//! payload with optional fields is pinned as well.
+//!
+//! The type tags of the requests are pinned as well, clients in other
+//! languages match on the exact strings (like `"AUTHENTICATE"`).
+//!
+//! The error enums are `#[non_exhaustive]` so that adding a variant is not a
+//! breaking change for crates that match on them.
+
+use crate::admin::requests::AdminRequest;
+use crate::admin::responses::AdminError;
+use crate::auth::requests::AuthRequest;
+use crate::auth::responses::AuthError;
+use crate::content::requests::ContentRequest;
+use crate::content::responses::ContentError;
+use crate::error::{ParseError, ParseErrorKind, ResponseError};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use std::fmt::Debug;
+
+/// Assert that `value` serializes to `fixture`, and that `fixture`
+/// deserializes to `value`
+fn golden<T>(value: T, fixture: &str)
+where
+    T: Serialize + DeserializeOwned + PartialEq + Debug,
+{
+    let expt: serde_json::Value = serde_json::from_str(fixture).unwrap();
+    assert_eq!(serde_json::to_value(&value).unwrap(), expt, "{:?}", value);
+    assert_eq!(
+        serde_json::from_str::<T>(fixture).unwrap(),
+        value,
+        "{}",
+        fixture
+    );
+}
+
+#[test]
+fn auth_errors() {
+    golden(AuthError::InvalidToken, r#""InvalidToken""#);
+    golden(AuthError::InvalidUsername, r#""InvalidUsername""#);
+    golden(AuthError::InvalidPassword, r#""InvalidPassword""#);
+    golden(AuthError::InvalidCredentials, r#""InvalidCredentials""#);
+    golden(AuthError::ExistingUser, r#""ExistingUser""#);
+    golden(AuthError::InvalidTwoFactorCode, r#""InvalidTwoFactorCode""#);
+    golden(AuthError::InsufficientRole, r#""InsufficientRole""#);
+    golden(
+        AuthError::RateLimited { retry_after: 30 },
+        r#"{ "RateLimited": { "retry_after": 30 } }"#,
+    );
+    golden(AuthError::InternalServerError, r#""InternalServerError""#);
+}
+
+#[test]
+fn admin_errors() {
+    golden(AdminError::InternalError, r#"{ "type": "INTERNAL_ERROR" }"#);
+}
+
+#[test]
+fn content_errors() {
+    golden(ContentError::MissingContent, r#""MissingContent""#);
+    golden(ContentError::HiddenContent, r#""HiddenContent""#);
+    golden(
+        ContentError::InternalServerError,
+        r#""InternalServerError""#,
+    );
+    golden(ContentError::InvalidSearchQuery, r#""InvalidSearchQuery""#);
+    golden(ContentError::InvalidId, r#""InvalidId""#);
+    golden(ContentError::InvalidContent, r#""InvalidContent""#);
+    golden(ContentError::ReplyTargetMissing, r#""ReplyTargetMissing""#);
+}
+
+#[test]
+fn response_errors() {
+    golden(
+        ResponseError::AuthRequestError(AuthError::InvalidCredentials),
+        r#"{ "type": "AUTH_REQUEST_ERROR", "payload": "InvalidCredentials" }"#,
+    );
+    golden(
+        ResponseError::ContentRequestError(ContentError::MissingContent),
+        r#"{ "type": "CONTENT_REQUEST_ERROR", "payload": "MissingContent" }"#,
+    );
+    golden(
+        ResponseError::AdminRequestError(AdminError::InternalError),
+        r#"{ "type": "ADMIN_REQUEST_ERROR", "payload": { "type": "INTERNAL_ERROR" } }"#,
+    );
+    golden(
+        ResponseError::Unauthenticated,
+        r#"{ "type": "UNAUTHENTICATED" }"#,
+    );
+    golden(ResponseError::Unauthorized, r#"{ "type": "UNAUTHORIZED" }"#);
+    golden(
+        ResponseError::InvalidPayload(crate::valid::ValidationError::InvalidTitle.into()),
+        r#"{
+            "type": "INVALID_PAYLOAD",
+            "payload": {
+                "code": "INVALID_PAYLOAD",
+                "message": "invalid (badly formatted) title",
+                "reason": "INVALID_TITLE"
+            }
+        }"#,
+    );
+    golden(
+        ResponseError::InternalServerError,
+        r#"{ "type": "INTERNAL_SERVER_ERROR" }"#,
+    );
+}
+
+#[test]
+fn parse_errors() {
+    golden(
+        ParseError {
+            request_type: None,
+            field: None,
+            kind: ParseErrorKind::MissingTag,
+        },
+        r#"{ "kind": { "type": "MISSING_TAG" } }"#,
+    );
+    golden(
+        ParseError {
+            request_type: Some("BAN".to_owned()),
+            field: None,
+            kind: ParseErrorKind::UnknownTag {
+                expected: vec!["BAN_IP".to_owned()],
+            },
+        },
+        r#"{
+            "request_type": "BAN",
+            "kind": { "type": "UNKNOWN_TAG", "payload": { "expected": ["BAN_IP"] } }
+        }"#,
+    );
+    golden(
+        ParseError {
+            request_type: Some("BAN_IP".to_owned()),
+            field: Some("ip".to_owned()),
+            kind: ParseErrorKind::MissingField,
+        },
+        r#"{ "request_type": "BAN_IP", "field": "ip", "kind": { "type": "MISSING_FIELD" } }"#,
+    );
+    golden(
+        ParseError {
+            request_type: Some("BAN_IP".to_owned()),
+            field: None,
+            kind: ParseErrorKind::MissingPayload,
+        },
+        r#"{ "request_type": "BAN_IP", "kind": { "type": "MISSING_PAYLOAD" } }"#,
+    );
+    golden(
+        ParseError {
+            request_type: Some("BAN_USER".to_owned()),
+            field: Some("id".to_owned()),
+            kind: ParseErrorKind::InvalidValue {
+                message: "invalid type".to_owned(),
+            },
+        },
+        r#"{
+            "request_type": "BAN_USER",
+            "field": "id",
+            "kind": { "type": "INVALID_VALUE", "payload": { "message": "invalid type" } }
+        }"#,
+    );
+    golden(
+        ParseError {
+            request_type: None,
+            field: None,
+            kind: ParseErrorKind::InvalidJson {
+                message: "EOF".to_owned(),
+            },
+        },
+        r#"{ "kind": { "type": "INVALID_JSON", "payload": { "message": "EOF" } } }"#,
+    );
+}
+
+/// Assert that `fixture` deserializes to a request which is serialized with
+/// the type tag `pinned` gives it, and that the tag is one of `tags`
+fn request_tag<T>(fixture: &str, pinned: fn(&T) -> &'static str, tags: &[&str])
+where
+    T: Serialize + DeserializeOwned,
+{
+    let request: T = serde_json::from_str(fixture).unwrap_or_else(|e| panic!("{}: {}", e, fixture));
+    let value = serde_json::to_value(&request).unwrap();
+    assert_eq!(value["type"], pinned(&request), "{}", fixture);
+    assert!(tags.contains(&pinned(&request)), "{}", fixture);
+}
+
+// The type tags of the requests are matched without a wildcard, which means
+// that a new variant doesn't compile until its tag is pinned here. Add it to
+// the `TAGS` of the request, and a fixture of it to the test below as well.
+
+fn auth_request_tag(request: &AuthRequest) -> &'static str {
+    match request {
+        AuthRequest::Authenticate(_) => "AUTHENTICATE",
+        AuthRequest::Deauthenticate(_) => "DEAUTHENTICATE",
+        AuthRequest::RegisterUser(_) => "REGISTER",
+        AuthRequest::SubmitTwoFactor(_) => "SUBMIT_TWO_FACTOR",
+        AuthRequest::WhoAmI(_) => "WHO_AM_I",
+    }
+}
+
+fn admin_request_tag(request: &AdminRequest) -> &'static str {
+    match request {
+        AdminRequest::BanIp(_) => "BAN_IP",
+        AdminRequest::UnbanIp(_) => "UNBAN_IP",
+        AdminRequest::BanUser(_) => "BAN_USER",
+        AdminRequest::UnbanUser(_) => "UNBAN_USER",
+        AdminRequest::SetUserRole(_) => "SET_USER_ROLE",
+        AdminRequest::ListBans(_) => "LIST_BANS",
+        AdminRequest::ListReports(_) => "LIST_REPORTS",
+    }
+}
+
+fn content_request_tag(request: &ContentRequest) -> &'static str {
+    match request {
+        ContentRequest::AddUser(_) => "ADD_USER",
+        ContentRequest::EditUser(_) => "EDIT_USER",
+        ContentRequest::UpdateProfile(_) => "UPDATE_PROFILE",
+        ContentRequest::AddCategory(_) => "ADD_CATEGORY",
+        ContentRequest::EditCategory(_) => "EDIT_CATEGORY",
+        ContentRequest::HideCategory(_) => "HIDE_CATEGORY",
+        ContentRequest::AddThread(_) => "ADD_THREAD",
+        ContentRequest::EditThread(_) => "EDIT_THREAD",
+        ContentRequest::HideThread(_) => "HIDE_THREAD",
+        ContentRequest::SetThreadPinned(_) => "SET_THREAD_PINNED",
+        ContentRequest::AddComment(_) => "ADD_COMMENT",
+        ContentRequest::EditComment(_) => "EDIT_COMMENT",
+        ContentRequest::HideComment(_) => "HIDE_COMMENT",
+        ContentRequest::AttachToComment(_) => "ATTACH_TO_COMMENT",
+        ContentRequest::React(_) => "REACT",
+        ContentRequest::RemoveReaction(_) => "REMOVE_REACTION",
+        ContentRequest::Report(_) => "REPORT",
+        ContentRequest::BulkDelete(_) => "BULK_DELETE",
+        ContentRequest::Subscribe(_) => "SUBSCRIBE",
+        ContentRequest::Unsubscribe(_) => "UNSUBSCRIBE",
+        ContentRequest::ListSubscriptions(_) => "LIST_SUBSCRIPTIONS",
+        ContentRequest::Bookmark(_) => "BOOKMARK",
+        ContentRequest::Unbookmark(_) => "UNBOOKMARK",
+        ContentRequest::ListBookmarks(_) => "LIST_BOOKMARKS",
+        ContentRequest::SaveDraft(_) => "SAVE_DRAFT",
+        ContentRequest::ListDrafts(_) => "LIST_DRAFTS",
+        ContentRequest::DeleteDraft(_) => "DELETE_DRAFT",
+        ContentRequest::GetEditHistory(_) => "GET_EDIT_HISTORY",
+        ContentRequest::CreatePoll(_) => "CREATE_POLL",
+        ContentRequest::Vote(_) => "VOTE",
+        ContentRequest::FetchThread(_) => "FETCH_THREAD",
+    }
+}
+
+#[test]
+fn auth_request_tags() {
+    let fixtures = [
+        r#"{
+            "type": "AUTHENTICATE",
+            "payload": { "username": "john", "password": "helloAndWelcome123" }
+        }"#,
+        r#"{ "type": "DEAUTHENTICATE", "payload": {} }"#,
+        r#"{
+            "type": "REGISTER",
+            "payload": {
+                "username": "john",
+                "password": "helloAndWelcome123",
+                "email": "john@doe.com"
+            }
+        }"#,
+        r#"{
+            "type": "SUBMIT_TWO_FACTOR",
+            "payload": { "challenge": "pending-challenge", "code": "012345" }
+        }"#,
+        r#"{ "type": "WHO_AM_I", "payload": {} }"#,
+    ];
+    assert_eq!(fixtures.len(), AuthRequest::TAGS.len());
+    for fixture in &fixtures {
+        request_tag(fixture, auth_request_tag, AuthRequest::TAGS);
+    }
+}
+
+#[test]
+fn admin_request_tags() {
+    let fixtures = [
+        r#"{ "type": "BAN_IP", "payload": { "ip": "10.0.0.1" } }"#,
+        r#"{ "type": "UNBAN_IP", "payload": { "ip": "10.0.0.1" } }"#,
+        r#"{ "type": "BAN_USER", "payload": { "id": 3 } }"#,
+        r#"{ "type": "UNBAN_USER", "payload": { "id": 3 } }"#,
+        r#"{ "type": "SET_USER_ROLE", "payload": { "id": 3, "role": "moderator" } }"#,
+        r#"{ "type": "LIST_BANS", "payload": {} }"#,
+        r#"{ "type": "LIST_REPORTS", "payload": {} }"#,
+    ];
+    assert_eq!(fixtures.len(), AdminRequest::TAGS.len());
+    for fixture in &fixtures {
+        request_tag(fixture, admin_request_tag, AdminRequest::TAGS);
+    }
+}
+
+#[test]
+fn content_request_tags() {
+    let fixtures = [
+        r#"{ "type": "ADD_USER", "payload": { "id": 1, "username": "john" } }"#,
+        r#"{ "type": "EDIT_USER", "payload": {} }"#,
+        r#"{ "type": "UPDATE_PROFILE", "payload": {} }"#,
+        r#"{
+            "type": "ADD_CATEGORY",
+            "payload": { "title": "A category", "description": "About something" }
+        }"#,
+        r#"{ "type": "EDIT_CATEGORY", "payload": { "id": 1 } }"#,
+        r#"{ "type": "HIDE_CATEGORY", "payload": { "id": 1, "hide": true } }"#,
+        r#"{
+            "type": "ADD_THREAD",
+            "payload": { "category_id": 1, "title": "A thread", "description": "About something" }
+        }"#,
+        r#"{ "type": "EDIT_THREAD", "payload": { "id": 1 } }"#,
+        r#"{ "type": "HIDE_THREAD", "payload": { "id": 1, "hide": true } }"#,
+        r#"{ "type": "SET_THREAD_PINNED", "payload": { "thread_id": 1, "pinned": true } }"#,
+        r#"{ "type": "ADD_COMMENT", "payload": { "thread_id": 1, "content": "A comment" } }"#,
+        r#"{ "type": "EDIT_COMMENT", "payload": { "id": 1, "content": "A comment" } }"#,
+        r#"{ "type": "HIDE_COMMENT", "payload": { "id": 1, "hide": true } }"#,
+        r#"{ "type": "ATTACH_TO_COMMENT", "payload": { "comment_id": 1, "attachment_id": 2 } }"#,
+        r#"{
+            "type": "REACT",
+            "payload": { "target": { "type": "THREAD", "id": 1 }, "kind": "LIKE" }
+        }"#,
+        r#"{
+            "type": "REMOVE_REACTION",
+            "payload": { "target": { "type": "THREAD", "id": 1 }, "kind": "LIKE" }
+        }"#,
+        r#"{
+            "type": "REPORT",
+            "payload": { "target": { "type": "COMMENT", "id": 1 }, "reason": "SPAM" }
+        }"#,
+        r#"{ "type": "BULK_DELETE", "payload": { "targets": [{ "type": "THREAD", "id": 1 }] } }"#,
+        r#"{ "type": "SUBSCRIBE", "payload": { "thread_id": 1 } }"#,
+        r#"{ "type": "UNSUBSCRIBE", "payload": { "thread_id": 1 } }"#,
+        r#"{ "type": "LIST_SUBSCRIPTIONS", "payload": {} }"#,
+        r#"{ "type": "BOOKMARK", "payload": { "thread_id": 1 } }"#,
+        r#"{ "type": "UNBOOKMARK", "payload": { "thread_id": 1 } }"#,
+        r#"{ "type": "LIST_BOOKMARKS", "payload": {} }"#,
+        r#"{
+            "type": "SAVE_DRAFT",
+            "payload": {
+                "target": { "type": "REPLY_TO", "payload": { "thread_id": 1 } },
+                "content": "A comment",
+                "updated_at": 1540000000
+            }
+        }"#,
+        r#"{ "type": "LIST_DRAFTS", "payload": {} }"#,
+        r#"{ "type": "DELETE_DRAFT", "payload": { "draft_id": 1 } }"#,
+        r#"{
+            "type": "GET_EDIT_HISTORY",
+            "payload": { "target": { "type": "COMMENT", "id": 1 } }
+        }"#,
+        r#"{
+            "type": "CREATE_POLL",
+            "payload": {
+                "thread_id": 1,
+                "question": "Which option?",
+                "options": ["First option", "Second option"],
+                "multiple_choice": false
+            }
+        }"#,
+        r#"{ "type": "VOTE", "payload": { "thread_id": 1, "option_ids": [1] } }"#,
+        r#"{ "type": "FETCH_THREAD", "payload": { "thread_id": 1, "include_hidden": false } }"#,
+    ];
+    assert_eq!(fixtures.len(), ContentRequest::TAGS.len());
+    for fixture in &fixtures {
+        request_tag(fixture, content_request_tag, ContentRequest::TAGS);
+    }
+}
+
+/// Assert that `fixture` deserializes to a `T` which serializes with exactly
+/// the field names `expt`
+///
+/// The fixture must contain every field, as fields which are `None` can be
+/// left out when serializing.
+fn fields<T>(fixture: &str, expt: &[&str])
+where
+    T: Serialize + DeserializeOwned,
+{
+    let payload: T = serde_json::from_str(fixture).unwrap_or_else(|e| panic!("{}: {}", e, fixture));
+    let value = serde_json::to_value(&payload).unwrap();
+    assert_field_names(&value, expt, fixture);
+}
+
+/// Assert that the object `value` has exactly the field names `expt`
+fn assert_field_names(value: &serde_json::Value, expt: &[&str], fixture: &str) {
+    let mut names: Vec<&str> = value
+        .as_object()
+        .expect("payloads are serialized as objects")
+        .keys()
+        .map(|k| k.as_str())
+        .collect();
+    names.sort();
+    let mut expt = expt.to_vec();
+    expt.sort();
+    assert_eq!(names, expt, "{}", fixture);
+}
+
+const THREAD: &str = r#"{
+    "id": 1,
+    "category_id": 2,
+    "user_id": 3,
+    "title": "A thread",
+    "description": "About something",
+    "format": "MARKDOWN",
+    "timestamp": "2018-10-20T12:00:00",
+    "hidden": false,
+    "pinned": true,
+    "reactions": [{ "kind": "LIKE", "count": 2 }],
+    "edited_at": 1540000000,
+    "poll": {
+        "question": "Which option?",
+        "options": [
+            { "id": 1, "text": "First option", "votes": 0 },
+            { "id": 2, "text": "Second option", "votes": 3 }
+        ],
+        "multiple_choice": false,
+        "closes_at": 1540000000
+    }
+}"#;
+
+/// The fields of `THREAD`
+const THREAD_FIELDS: &[&str] = &[
+    "id",
+    "category_id",
+    "user_id",
+    "title",
+    "description",
+    "format",
+    "timestamp",
+    "hidden",
+    "pinned",
+    "reactions",
+    "edited_at",
+    "poll",
+];
+
+const COMMENT: &str = r#"{
+    "id": 1,
+    "thread_id": 2,
+    "parent_id": 3,
+    "user_id": 4,
+    "content": "A comment",
+    "format": "MARKDOWN",
+    "timestamp": "2018-10-20T12:00:00",
+    "hidden": false,
+    "reactions": [],
+    "edited_at": 1540000000,
+    "attachments": [],
+    "reply_to": { "comment_id": 3, "excerpt": "Quoted" }
+}"#;
+
+/// The fields of `COMMENT`
+const COMMENT_FIELDS: &[&str] = &[
+    "id",
+    "thread_id",
+    "parent_id",
+    "user_id",
+    "content",
+    "format",
+    "timestamp",
+    "hidden",
+    "reactions",
+    "edited_at",
+    "attachments",
+    "reply_to",
+];
+
+#[test]
+fn auth_payload_fields() {
+    use crate::auth::requests::*;
+    use crate::auth::responses::{TwoFactorChallengePayload, WhoAmIResponse};
+
+    fields::<AuthPayload>(
+        r#"{ "username": "john", "password": "helloAndWelcome123" }"#,
+        &["username", "password"],
+    );
+    fields::<RegisterUserPayload>(
+        r#"{ "username": "john", "password": "helloAndWelcome123", "email": "john@doe.com" }"#,
+        &["username", "password", "email"],
+    );
+    fields::<TwoFactorPayload>(
+        r#"{ "challenge": "pending-challenge", "code": "012345" }"#,
+        &["challenge", "code"],
+    );
+    fields::<SetUserRolePayload>(r#"{ "id": 1, "role": "moderator" }"#, &["id", "role"]);
+    fields::<TwoFactorChallengePayload>(r#"{ "challenge": "pending-challenge" }"#, &["challenge"]);
+    fields::<WhoAmIResponse>(
+        r#"{ "id": 1, "username": "john", "role": "user" }"#,
+        &["id", "username", "role"],
+    );
+}
+
+#[test]
+fn admin_payload_fields() {
+    use crate::admin::moderation::ModAction;
+    use crate::admin::requests::*;
+    use crate::admin::responses::{BanEntry, ReportEntry};
+
+    fields::<IpAddrPayload>(r#"{ "ip": "10.0.0.1" }"#, &["ip"]);
+    fields::<UserIdRefPayload>(r#"{ "id": 1 }"#, &["id"]);
+    fields::<BanIpPayload>(
+        r#"{ "ip": "10.0.0.1", "duration": "2h", "country": "NO" }"#,
+        &["ip", "duration", "country"],
+    );
+    fields::<BanUserPayload>(
+        r#"{ "id": 1, "duration": 3600, "country": "NO" }"#,
+        &["id", "duration", "country"],
+    );
+    fields::<ListBansPayload>(
+        r#"{
+            "pagination": { "page": 1, "per_page": 20 },
+            "filter": { "type": "IP_FAMILY", "payload": "V6" }
+        }"#,
+        &["pagination", "filter"],
+    );
+    fields::<BanEntry>(
+        r#"{
+            "subject": { "type": "USER", "payload": 1 },
+            "banned_at": "2018-10-20T12:00:00",
+            "country": "NO"
+        }"#,
+        &["subject", "banned_at", "country"],
+    );
+    fields::<ReportEntry>(
+        r#"{
+            "reporter": 1,
+            "target": { "type": "THREAD", "id": 2 },
+            "reason": "SPAM",
+            "timestamp": "2018-10-20T12:00:00",
+            "status": "OPEN"
+        }"#,
+        &["reporter", "target", "reason", "timestamp", "status"],
+    );
+    fields::<ModAction>(
+        r#"{
+            "actor": 1,
+            "target": { "type": "USER", "payload": 2 },
+            "kind": "BAN_USER",
+            "at": "2018-10-20T12:00:00Z",
+            "reason": "spam"
+        }"#,
+        &["actor", "target", "kind", "at", "reason"],
+    );
+}
+
+#[test]
+fn content_request_payload_fields() {
+    use crate::content::requests::*;
+
+    let key = "123e4567-e89b-12d3-a456-426655440000";
+    fields::<ContentRefPayload>(
+        r#"{ "target": { "type": "THREAD", "id": 1 } }"#,
+        &["target"],
+    );
+    fields::<GetUserPayload>(r#"{ "id": 1 }"#, &["id"]);
+    fields::<AddUserPayload>(r#"{ "id": 1, "username": "john" }"#, &["id", "username"]);
+    fields::<EditUserPayload>(
+        r#"{ "id": 1, "description": "About me", "avatar": "cat.png" }"#,
+        &["id", "description", "avatar"],
+    );
+    fields::<UpdateProfilePayload>(
+        r#"{
+            "id": 1,
+            "display_name": "John",
+            "bio": "About me",
+            "avatar_url": "https://example.com/cat.png"
+        }"#,
+        &["id", "display_name", "bio", "avatar_url"],
+    );
+    fields::<GetCategoryPayload>(
+        r#"{ "id": 1, "include_hidden": true }"#,
+        &["id", "include_hidden"],
+    );
+    fields::<AddCategoryPayload>(
+        &format!(
+            r#"{{
+                "title": "A category",
+                "description": "About something",
+                "format": "MARKDOWN",
+                "idempotency_key": "{}"
+            }}"#,
+            key
+        ),
+        &["title", "description", "format", "idempotency_key"],
+    );
+    fields::<EditCategoryPayload>(
+        r#"{ "id": 1, "title": "A category", "description": "About something", "format": "PLAIN_TEXT" }"#,
+        &["id", "title", "description", "format"],
+    );
+    fields::<HideCategoryPayload>(r#"{ "id": 1, "hide": true }"#, &["id", "hide"]);
+    fields::<GetThreadPayload>(
+        r#"{ "id": 1, "include_hidden": true }"#,
+        &["id", "include_hidden"],
+    );
+    fields::<GetThreadsPayload>(
+        r#"{
+            "id": 1,
+            "include_hidden": false,
+            "sort": "OLDEST_FIRST",
+            "author": 2,
+            "page": 1,
+            "per_page": 20,
+            "after": "YWJj"
+        }"#,
+        &[
+            "id",
+            "include_hidden",
+            "sort",
+            "author",
+            "page",
+            "per_page",
+            "after",
+        ],
+    );
+    fields::<AddThreadPayload>(
+        &format!(
+            r#"{{
+                "category_id": 1,
+                "user_id": 2,
+                "title": "A thread",
+                "description": "About something",
+                "format": "MARKDOWN",
+                "idempotency_key": "{}"
+            }}"#,
+            key
+        ),
+        &[
+            "category_id",
+            "user_id",
+            "title",
+            "description",
+            "format",
+            "idempotency_key",
+        ],
+    );
+    fields::<EditThreadPayload>(
+        r#"{
+            "id": 1,
+            "user_id": 2,
+            "title": "A thread",
+            "description": "About something",
+            "format": "MARKDOWN"
+        }"#,
+        &["id", "user_id", "title", "description", "format"],
+    );
+    fields::<HideThreadPayload>(
+        r#"{ "id": 1, "user_id": 2, "hide": true }"#,
+        &["id", "user_id", "hide"],
+    );
+    fields::<FetchThreadPayload>(
+        r#"{ "thread_id": 1, "include_hidden": false, "comments": { "page": 2, "per_page": 10 } }"#,
+        &["thread_id", "include_hidden", "comments"],
+    );
+    fields::<ThreadRefPayload>(r#"{ "thread_id": 1 }"#, &["thread_id"]);
+    fields::<PinThreadPayload>(
+        r#"{ "thread_id": 1, "pinned": true, "scope": "GLOBAL" }"#,
+        &["thread_id", "pinned", "scope"],
+    );
+    fields::<GetCommentPayload>(
+        r#"{ "id": 1, "include_hidden": true }"#,
+        &["id", "include_hidden"],
+    );
+    fields::<GetCommentsPayload>(
+        r#"{ "id": 1, "include_hidden": true, "sort": "TOP_VOTED", "author": 2 }"#,
+        &["id", "include_hidden", "sort", "author"],
+    );
+    fields::<AddCommentPayload>(
+        &format!(
+            r#"{{
+                "thread_id": 1,
+                "user_id": 2,
+                "parent_id": 3,
+                "content": "A comment",
+                "format": "MARKDOWN",
+                "reply_to": {{ "comment_id": 3, "excerpt": "Quoted" }},
+                "idempotency_key": "{}",
+                "attachments": ["q2-_Xw8AAAB9aBcDeFgHiJ"]
+            }}"#,
+            key
+        ),
+        &[
+            "thread_id",
+            "user_id",
+            "parent_id",
+            "content",
+            "format",
+            "reply_to",
+            "idempotency_key",
+            "attachments",
+        ],
+    );
+    fields::<ReplyRef>(
+        r#"{ "comment_id": 3, "excerpt": "Quoted" }"#,
+        &["comment_id", "excerpt"],
+    );
+    fields::<EditCommentPayload>(
+        r#"{ "id": 1, "user_id": 2, "content": "A comment", "format": "PLAIN_TEXT" }"#,
+        &["id", "user_id", "content", "format"],
+    );
+    fields::<HideCommentPayload>(
+        r#"{ "id": 1, "user_id": 2, "hide": true }"#,
+        &["id", "user_id", "hide"],
+    );
+    fields::<AttachPayload>(
+        r#"{ "comment_id": 1, "attachment_id": 2 }"#,
+        &["comment_id", "attachment_id"],
+    );
+    fields::<ReactPayload>(
+        r#"{ "target": { "type": "COMMENT", "id": 1 }, "kind": "HEART" }"#,
+        &["target", "kind"],
+    );
+    fields::<ReportPayload>(
+        r#"{ "target": { "type": "COMMENT", "id": 1 }, "reason": "OTHER", "details": "Something" }"#,
+        &["target", "reason", "details"],
+    );
+    fields::<BulkDeletePayload>(
+        r#"{ "targets": [{ "type": "THREAD", "id": 1 }, { "type": "COMMENT", "id": 2 }] }"#,
+        &["targets"],
+    );
+    fields::<DraftPayload>(
+        r#"{
+            "draft_id": 1,
+            "target": { "type": "NEW_THREAD", "payload": { "category_id": 1 } },
+            "title": "A thread",
+            "content": "A comment",
+            "updated_at": 1540000000
+        }"#,
+        &["draft_id", "target", "title", "content", "updated_at"],
+    );
+    fields::<DraftRefPayload>(r#"{ "draft_id": 1 }"#, &["draft_id"]);
+    fields::<NewPollPayload>(
+        r#"{
+            "thread_id": 1,
+            "question": "Which option?",
+            "options": ["First option", "Second option"],
+            "multiple_choice": true,
+            "closes_at": 1540000000
+        }"#,
+        &[
+            "thread_id",
+            "question",
+            "options",
+            "multiple_choice",
+            "closes_at",
+        ],
+    );
+    fields::<VotePayload>(
+        r#"{ "thread_id": 1, "option_ids": [1, 2] }"#,
+        &["thread_id", "option_ids"],
+    );
+    fields::<SearchPayload>(
+        r#"{ "query": "cats", "include_hidden": false, "sort": "NEWEST_FIRST", "author": 2 }"#,
+        &["query", "include_hidden", "sort", "author"],
+    );
+    fields::<GetHiddenPayload>(r#"{ "include_hidden": true }"#, &["include_hidden"]);
+}
+
+#[test]
+fn content_response_payload_fields() {
+    use crate::content::responses::*;
+
+    fields::<UserPayload>(
+        r#"{ "id": 1, "username": "john", "description": "About me", "avatar": "cat.png" }"#,
+        &["id", "username", "description", "avatar"],
+    );
+    fields::<CategoryPayload>(
+        r#"{
+            "id": 1,
+            "title": "A category",
+            "description": "About something",
+            "format": "MARKDOWN",
+            "hidden": false
+        }"#,
+        &["id", "title", "description", "format", "hidden"],
+    );
+    fields::<ThreadPayload>(THREAD, THREAD_FIELDS);
+    fields::<PollOption>(
+        r#"{ "id": 1, "text": "First option", "votes": 0 }"#,
+        &["id", "text", "votes"],
+    );
+    fields::<CommentPayload>(COMMENT, COMMENT_FIELDS);
+    fields::<AttachmentMeta>(
+        &format!(
+            r#"{{ "id": 1, "filename": "cat.png", "mime": "image/png", "size_bytes": 1024, "sha256": "{}" }}"#,
+            "ab".repeat(32)
+        ),
+        &["id", "filename", "mime", "size_bytes", "sha256"],
+    );
+    fields::<ReactionCount>(r#"{ "kind": "LAUGH", "count": 4 }"#, &["kind", "count"]);
+    fields::<ThreadPagePayload>(
+        &format!(
+            r#"{{
+                "thread": {},
+                "comments": {{ "items": [{}], "page": 1, "per_page": 20, "total": 1 }}
+            }}"#,
+            THREAD, COMMENT
+        ),
+        &["thread", "comments"],
+    );
+    fields::<SubscriptionPayload>(
+        &format!(r#"{{ "thread": {}, "subscribed_at": 1540000000 }}"#, THREAD),
+        &["thread", "subscribed_at"],
+    );
+    fields::<BookmarkPayload>(
+        &format!(r#"{{ "thread": {}, "bookmarked_at": 1540000000 }}"#, THREAD),
+        &["thread", "bookmarked_at"],
+    );
+    fields::<EditHistoryPayload>(
+        r#"{
+            "target": { "type": "COMMENT", "id": 1 },
+            "revisions": [{ "revision": 1, "edited_by": 2, "edited_at": 1540000000, "content": "A comment" }]
+        }"#,
+        &["target", "revisions"],
+    );
+    fields::<RevisionPayload>(
+        r#"{ "revision": 1, "edited_by": 2, "edited_at": 1540000000, "content": "A comment" }"#,
+        &["revision", "edited_by", "edited_at", "content"],
+    );
+}
+
+/// A fetch of a thread or comment (or a page of them) is answered with every
+/// field of the thread or comment, inside of its state
+#[test]
+fn fetch_response_fields() {
+    use crate::content::responses::ContentSuccess;
+
+    let live = |content: &str| format!(r#"{{ "type": "LIVE", "payload": {} }}"#, content);
+    let page = |item: &str| {
+        format!(
+            r#"{{ "items": [{}], "page": 1, "per_page": 20, "total": 1 }}"#,
+            live(item)
+        )
+    };
+    let responses = vec![
+        ("THREAD", live(THREAD), THREAD_FIELDS),
+        ("THREADS", page(THREAD), THREAD_FIELDS),
+        ("COMMENT", live(COMMENT), COMMENT_FIELDS),
+        ("COMMENTS", page(COMMENT), COMMENT_FIELDS),
+    ];
+    for (tag, payload, expt) in responses {
+        let fixture = format!(r#"{{ "type": "{}", "payload": {} }}"#, tag, payload);
+        let response: ContentSuccess =
+            serde_json::from_str(&fixture).unwrap_or_else(|e| panic!("{}: {}", e, fixture));
+        let value = serde_json::to_value(&response).unwrap();
+        let payload = &value["payload"];
+        let state = payload.get("items").map_or(payload, |items| &items[0]);
+        assert_eq!(state["type"], "LIVE", "{}", fixture);
+        assert_field_names(&state["payload"], expt, &fixture);
+    }
+}
+
+#[test]
+fn content_model_fields() {
+    use crate::content::models::*;
+
+    fields::<Thread>(
+        r#"{
+            "id": 1,
+            "category_id": 2,
+            "author": 3,
+            "title": "A thread",
+            "body": "About something",
+            "format": "MARKDOWN",
+            "created_at": "2018-10-20T12:00:00Z",
+            "updated_at": "2018-10-20T13:00:00Z",
+            "votes": 12
+        }"#,
+        &[
+            "id",
+            "category_id",
+            "author",
+            "title",
+            "body",
+            "format",
+            "created_at",
+            "updated_at",
+            "votes",
+        ],
+    );
+    fields::<Comment>(
+        r#"{
+            "id": 4,
+            "thread_id": 1,
+            "parent_id": 2,
+            "author": 3,
+            "body": "A comment",
+            "format": "MARKDOWN",
+            "created_at": "2018-10-20T12:00:00Z",
+            "updated_at": "2018-10-20T13:00:00Z",
+            "votes": -2
+        }"#,
+        &[
+            "id",
+            "thread_id",
+            "parent_id",
+            "author",
+            "body",
+            "format",
+            "created_at",
+            "updated_at",
+            "votes",
+        ],
+    );
+    fields::<Tombstone>(
+        r#"{
+            "id": { "type": "COMMENT", "id": 4 },
+            "deleted_by": 5,
+            "deleted_at": "2018-10-20T12:00:00Z",
+            "reason": "spam"
+        }"#,
+        &["id", "deleted_by", "deleted_at", "reason"],
+    );
+    fields::<ThreadState>(
+        r#"{ "type": "DELETED", "payload": { "id": { "type": "THREAD", "id": 1 }, "deleted_by": 5, "deleted_at": "2018-10-20T12:00:00Z" } }"#,
+        &["type", "payload"],
+    );
+}
+
+#[test]
+fn common_payload_fields() {
+    use crate::error::{ErrorPayload, WireError};
+    use crate::payloads::*;
+    use crate::valid::ids::UserId;
+
+    fields::<PaginationPayload>(r#"{ "page": 1, "per_page": 20 }"#, &["page", "per_page"]);
+    fields::<Paginated<UserId>>(
+        r#"{ "items": [1, 2], "page": 1, "per_page": 20, "total": 2, "next": "bmV4dA" }"#,
+        &["items", "page", "per_page", "total", "next"],
+    );
+    fields::<CursorPage<UserId>>(
+        r#"{ "items": [1, 2], "next": "bmV4dA", "prev": "cHJldg" }"#,
+        &["items", "next", "prev"],
+    );
+    fields::<TokenPayload<PaginationPayload>>(
+        r#"{ "token": "random-token", "page": 1, "per_page": 20 }"#,
+        &["token", "page", "per_page"],
+    );
+    fields::<UserIdPayload<PaginationPayload>>(
+        r#"{ "id": 1, "page": 1, "per_page": 20 }"#,
+        &["id", "page", "per_page"],
+    );
+    fields::<TokenPayloadCompact<PaginationPayload>>(
+        r#"{ "token": "random-token", "inner": { "page": 1, "per_page": 20 } }"#,
+        &["token", "inner"],
+    );
+    fields::<UserIdPayloadCompact<PaginationPayload>>(
+        r#"{ "id": 1, "inner": { "page": 1, "per_page": 20 } }"#,
+        &["id", "inner"],
+    );
+    fields::<ErrorPayload>(
+        r#"{ "code": "INVALID_PAYLOAD", "message": "invalid title", "reason": "INVALID_TITLE" }"#,
+        &["code", "message", "reason"],
+    );
+    fields::<WireError>(
+        r#"{ "code": "AUTH_RATE_LIMITED", "message": "too many attempts", "details": { "retry_after": 30 } }"#,
+        &["code", "message", "details"],
+    );
+}
+
+/// Assert that `fixture` deserializes to a `T` which serializes back to
+/// exactly `fixture`, and that no value of it is `null`
+///
+/// The fixture leaves out every optional field, which means that it is the
+/// smallest JSON of the payload. Fields which have a default (like `format`)
+/// are always serialized, and are therefore part of it.
+fn assert_minimal_json<T>(fixture: &str)
+where
+    T: Serialize + DeserializeOwned,
+{
+    let payload: T = serde_json::from_str(fixture).unwrap_or_else(|e| panic!("{}: {}", e, fixture));
+    let value = serde_json::to_value(&payload).unwrap();
+    assert_no_nulls(&value, fixture);
+    let expt: serde_json::Value = serde_json::from_str(fixture).unwrap();
+    assert_eq!(value, expt, "{}", fixture);
+}
+
+fn assert_no_nulls(value: &serde_json::Value, fixture: &str) {
+    use serde_json::Value;
+
+    match value {
+        Value::Null => panic!("a null was serialized: {}", fixture),
+        Value::Array(values) => {
+            for value in values {
+                assert_no_nulls(value, fixture);
+            }
+        }
+        Value::Object(map) => {
+            for value in map.values() {
+                assert_no_nulls(value, fixture);
+            }
+        }
+        _ => (),
+    }
+}
+
+#[test]
+fn minimal_admin_payloads() {
+    use crate::admin::moderation::ModAction;
+    use crate::admin::requests::*;
+
+    assert_minimal_json::<BanIpPayload>(r#"{ "ip": "10.0.0.1" }"#);
+    assert_minimal_json::<BanUserPayload>(r#"{ "id": 1 }"#);
+    assert_minimal_json::<ListBansPayload>(r#"{ "pagination": { "page": 1, "per_page": 20 } }"#);
+    assert_minimal_json::<ModAction>(
+        r#"{
+            "actor": 1,
+            "target": { "type": "USER", "payload": 2 },
+            "kind": "BAN_USER",
+            "at": "2018-10-20T12:00:00Z"
+        }"#,
+    );
+}
+
+#[test]
+fn minimal_content_request_payloads() {
+    use crate::content::requests::*;
+
+    assert_minimal_json::<EditUserPayload>("{}");
+    assert_minimal_json::<UpdateProfilePayload>("{}");
+    assert_minimal_json::<AddCategoryPayload>(
+        r#"{ "title": "A category", "description": "About something", "format": "PLAIN_TEXT" }"#,
+    );
+    assert_minimal_json::<EditCategoryPayload>(r#"{ "id": 1 }"#);
+    assert_minimal_json::<GetThreadsPayload>(
+        r#"{ "id": 1, "include_hidden": false, "sort": "NEWEST_FIRST" }"#,
+    );
+    assert_minimal_json::<AddThreadPayload>(
+        r#"{
+            "category_id": 1,
+            "title": "A thread",
+            "description": "About something",
+            "format": "PLAIN_TEXT"
+        }"#,
+    );
+    assert_minimal_json::<EditThreadPayload>(r#"{ "id": 1 }"#);
+    assert_minimal_json::<HideThreadPayload>(r#"{ "id": 1, "hide": true }"#);
+    assert_minimal_json::<GetCommentsPayload>(
+        r#"{ "id": 1, "include_hidden": true, "sort": "OLDEST_FIRST" }"#,
+    );
+    assert_minimal_json::<AddCommentPayload>(
+        r#"{ "thread_id": 1, "content": "A comment", "format": "PLAIN_TEXT" }"#,
+    );
+    assert_minimal_json::<ReplyRef>(r#"{ "comment_id": 3 }"#);
+    assert_minimal_json::<EditCommentPayload>(r#"{ "id": 1, "content": "A comment" }"#);
+    assert_minimal_json::<HideCommentPayload>(r#"{ "id": 1, "hide": true }"#);
+    assert_minimal_json::<ReportPayload>(
+        r#"{ "target": { "type": "COMMENT", "id": 1 }, "reason": "SPAM" }"#,
+    );
+    assert_minimal_json::<DraftPayload>(
+        r#"{
+            "target": { "type": "REPLY_TO", "payload": { "thread_id": 1 } },
+            "content": "A comment",
+            "updated_at": 1540000000
+        }"#,
+    );
+    assert_minimal_json::<NewPollPayload>(
+        r#"{
+            "thread_id": 1,
+            "question": "Which option?",
+            "options": ["First option", "Second option"],
+            "multiple_choice": false
+        }"#,
+    );
+    assert_minimal_json::<SearchPayload>(
+        r#"{ "query": "cats", "include_hidden": false, "sort": "NEWEST_FIRST" }"#,
+    );
+}
+
+#[test]
+fn minimal_content_response_payloads() {
+    use crate::content::models::*;
+    use crate::content::responses::*;
+
+    assert_minimal_json::<UserPayload>(r#"{ "id": 1, "username": "john" }"#);
+    assert_minimal_json::<ThreadPayload>(
+        r#"{
+            "id": 1,
+            "category_id": 2,
+            "user_id": 3,
+            "title": "A thread",
+            "description": "About something",
+            "timestamp": "2018-10-20T12:00:00",
+            "hidden": false,
+            "pinned": false,
+            "reactions": []
+        }"#,
+    );
+    assert_minimal_json::<PollPayload>(
+        r#"{
+            "question": "Which option?",
+            "options": [
+                { "id": 1, "text": "First option", "votes": 0 },
+                { "id": 2, "text": "Second option", "votes": 3 }
+            ],
+            "multiple_choice": false
+        }"#,
+    );
+    assert_minimal_json::<CommentPayload>(
+        r#"{
+            "id": 1,
+            "thread_id": 2,
+            "user_id": 4,
+            "content": "A comment",
+            "format": "PLAIN_TEXT",
+            "timestamp": "2018-10-20T12:00:00",
+            "hidden": false,
+            "reactions": [],
+            "attachments": []
+        }"#,
+    );
+    assert_minimal_json::<Thread>(
+        r#"{
+            "id": 1,
+            "category_id": 2,
+            "author": 3,
+            "title": "A thread",
+            "body": "About something",
+            "format": "PLAIN_TEXT",
+            "created_at": "2018-10-20T12:00:00Z",
+            "votes": 0
+        }"#,
+    );
+    assert_minimal_json::<Comment>(
+        r#"{
+            "id": 4,
+            "thread_id": 1,
+            "author": 3,
+            "body": "A comment",
+            "format": "PLAIN_TEXT",
+            "created_at": "2018-10-20T12:00:00Z",
+            "votes": 0
+        }"#,
+    );
+    assert_minimal_json::<Tombstone>(
+        r#"{
+            "id": { "type": "COMMENT", "id": 4 },
+            "deleted_by": 5,
+            "deleted_at": "2018-10-20T12:00:00Z"
+        }"#,
+    );
+}
+
+#[test]
+fn minimal_common_payloads() {
+    use crate::error::{ErrorPayload, WireError};
+    use crate::payloads::CursorPage;
+    use crate::valid::ids::UserId;
+
+    assert_minimal_json::<CursorPage<UserId>>(r#"{ "items": [1, 2] }"#);
+    assert_minimal_json::<ErrorPayload>(
+        r#"{ "code": "INVALID_PAYLOAD", "message": "invalid title" }"#,
+    );
+    assert_minimal_json::<WireError>(
+        r#"{ "code": "AUTH_RATE_LIMITED", "message": "too many attempts" }"#,
+    );
+}
@@ -0,0 +1,118 @@
+//! Requests captured from older clients, which must keep deserializing
+//!
+//! Every time a variant or a field is renamed, the old name is kept as a
+//! `#[serde(alias = "...")]` (and listed in `auth::requests::LEGACY_TAGS` or
+//! `LEGACY_FIELDS`), and a request which uses the old name is captured here.
+//! The fixtures are never changed or removed, a failing test means that a
+//! change strands old clients.
+
+use crate::auth::requests::{AuthRequest, LEGACY_FIELDS, LEGACY_TAGS};
+
+/// A registration sent by clients from before `REGISTER_USER` was renamed to
+/// `REGISTER`, and from before `username` was renamed from `raw_username`
+const REGISTER_USER_RAW_USERNAME: &str = r#"{
+    "type": "REGISTER_USER",
+    "payload": {
+        "raw_username": "john",
+        "password": "helloAndWelcome123",
+        "email": "john@doe.com"
+    }
+}"#;
+
+/// A registration sent by clients from when the field was named `user`
+const REGISTER_USER_USER: &str = r#"{
+    "type": "REGISTER_USER",
+    "payload": {
+        "user": "john",
+        "password": "helloAndWelcome123",
+        "email": "john@doe.com"
+    }
+}"#;
+
+/// A registration sent by clients from before the rename to `REGISTER`
+const REGISTER_USER: &str = r#"{
+    "type": "REGISTER_USER",
+    "payload": {
+        "username": "john",
+        "password": "helloAndWelcome123",
+        "email": "john@doe.com"
+    }
+}"#;
+
+/// A login sent by clients from before `username` was renamed from
+/// `raw_username`
+const AUTHENTICATE_RAW_USERNAME: &str = r#"{
+    "type": "AUTHENTICATE",
+    "payload": { "raw_username": "john", "password": "helloAndWelcome123" }
+}"#;
+
+/// A login sent by clients from when the field was named `user`
+const AUTHENTICATE_USER: &str = r#"{
+    "type": "AUTHENTICATE",
+    "payload": { "user": "john", "password": "helloAndWelcome123" }
+}"#;
+
+fn parse(fixture: &str) -> AuthRequest {
+    serde_json::from_str(fixture).unwrap_or_else(|e| panic!("{}: {}", e, fixture))
+}
+
+#[test]
+fn old_registrations() {
+    for fixture in &[
+        REGISTER_USER_RAW_USERNAME,
+        REGISTER_USER_USER,
+        REGISTER_USER,
+    ] {
+        match parse(fixture) {
+            AuthRequest::RegisterUser(payload) => {
+                assert_eq!(&*payload.username, "john");
+                assert_eq!(&*payload.email, "john@doe.com");
+            }
+            _ => panic!("expected a registration: {}", fixture),
+        }
+    }
+}
+
+#[test]
+fn old_logins() {
+    for fixture in &[AUTHENTICATE_RAW_USERNAME, AUTHENTICATE_USER] {
+        match parse(fixture) {
+            AuthRequest::Authenticate(payload) => assert_eq!(&*payload.username, "john"),
+            _ => panic!("expected a login: {}", fixture),
+        }
+    }
+}
+
+#[test]
+fn old_requests_are_serialized_with_the_current_names() {
+    let json = serde_json::to_value(&parse(REGISTER_USER_RAW_USERNAME)).unwrap();
+    let expt = serde_json::json!({
+        "type": "REGISTER",
+        "payload": {
+            "username": "john",
+            "password": "helloAndWelcome123",
+            "email": "john@doe.com"
+        }
+    });
+    assert_eq!(json, expt);
+}
+
+#[test]
+fn every_alias_is_captured() {
+    let fixtures = [
+        REGISTER_USER_RAW_USERNAME,
+        REGISTER_USER_USER,
+        REGISTER_USER,
+        AUTHENTICATE_RAW_USERNAME,
+        AUTHENTICATE_USER,
+    ];
+    let names = LEGACY_TAGS.iter().chain(LEGACY_FIELDS).map(|(old, _)| old);
+    for name in names {
+        let quoted = format!("\"{}\"", name);
+        assert!(
+            fixtures.iter().any(|fixture| fixture.contains(&quoted)),
+            "no captured request uses '{}'",
+            name
+        );
+    }
+}